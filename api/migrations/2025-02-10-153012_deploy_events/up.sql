@@ -0,0 +1,14 @@
+-- Your SQL goes here
+create table deploy_events (
+    id uuid primary key default uuid_generate_v4(),
+    organization_id uuid not null references organizations(id) on delete cascade,
+    user_id uuid not null references users(id) on delete cascade,
+    deploy_hash text not null,
+    model_count integer not null,
+    success_count integer not null,
+    failure_count integer not null,
+    pruned_count integer not null default 0,
+    created_at timestamp with time zone not null default now()
+);
+
+create index deploy_events_organization_id_created_at_idx on deploy_events (organization_id, created_at desc);
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE datasets ADD COLUMN refresh_schedule JSONB NULL;
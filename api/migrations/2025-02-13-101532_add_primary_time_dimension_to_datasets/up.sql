@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE datasets ADD COLUMN primary_time_dimension TEXT NULL;
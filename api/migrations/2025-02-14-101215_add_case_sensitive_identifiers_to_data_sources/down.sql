@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE data_sources DROP COLUMN case_sensitive_identifiers;
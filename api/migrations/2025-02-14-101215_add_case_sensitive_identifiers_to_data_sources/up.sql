@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE data_sources ADD COLUMN case_sensitive_identifiers BOOLEAN NULL;
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE dataset_columns ADD COLUMN hidden BOOLEAN NOT NULL DEFAULT false;
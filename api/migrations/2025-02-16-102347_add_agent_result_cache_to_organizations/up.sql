@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE organizations ADD COLUMN agent_result_cache_enabled BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,14 @@
+-- This file should undo anything in `up.sql`
+ALTER TYPE stored_values_status_enum RENAME TO stored_values_status_enum_old;
+
+CREATE TYPE stored_values_status_enum AS ENUM ('syncing', 'success', 'failed');
+
+ALTER TABLE dataset_columns
+    ALTER COLUMN stored_values_status TYPE stored_values_status_enum
+    USING CASE
+        WHEN stored_values_status::text = 'truncated' THEN 'success'::stored_values_status_enum
+        WHEN stored_values_status::text = 'skipped' THEN 'failed'::stored_values_status_enum
+        ELSE stored_values_status::text::stored_values_status_enum
+    END;
+
+DROP TYPE stored_values_status_enum_old;
@@ -0,0 +1,16 @@
+-- Your SQL goes here
+ALTER TYPE stored_values_status_enum RENAME TO stored_values_status_enum_old;
+
+CREATE TYPE stored_values_status_enum AS ENUM (
+    'syncing',
+    'success',
+    'failed',
+    'truncated',
+    'skipped'
+);
+
+ALTER TABLE dataset_columns
+    ALTER COLUMN stored_values_status TYPE stored_values_status_enum
+    USING stored_values_status::text::stored_values_status_enum;
+
+DROP TYPE stored_values_status_enum_old;
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE dataset_columns DROP COLUMN label;
+ALTER TABLE datasets DROP COLUMN label;
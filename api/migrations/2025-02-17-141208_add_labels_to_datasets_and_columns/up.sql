@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE datasets ADD COLUMN label TEXT;
+ALTER TABLE dataset_columns ADD COLUMN label TEXT;
@@ -174,6 +174,10 @@ pub enum StoredValuesStatus {
     Syncing,
     Success,
     Failed,
+    /// Synced up to the cap on values stored per column
+    Truncated,
+    /// Not synced, since the column has too many distinct values
+    Skipped,
 }
 
 impl ToSql<sql_types::StoredValuesStatusEnum, Pg> for StoredValuesStatus {
@@ -182,6 +186,8 @@ impl ToSql<sql_types::StoredValuesStatusEnum, Pg> for StoredValuesStatus {
             StoredValuesStatus::Syncing => out.write_all(b"syncing")?,
             StoredValuesStatus::Success => out.write_all(b"success")?,
             StoredValuesStatus::Failed => out.write_all(b"failed")?,
+            StoredValuesStatus::Truncated => out.write_all(b"truncated")?,
+            StoredValuesStatus::Skipped => out.write_all(b"skipped")?,
         }
         Ok(IsNull::No)
     }
@@ -193,6 +199,8 @@ impl FromSql<sql_types::StoredValuesStatusEnum, Pg> for StoredValuesStatus {
             b"syncing" => Ok(StoredValuesStatus::Syncing),
             b"success" => Ok(StoredValuesStatus::Success),
             b"failed" => Ok(StoredValuesStatus::Failed),
+            b"truncated" => Ok(StoredValuesStatus::Truncated),
+            b"skipped" => Ok(StoredValuesStatus::Skipped),
             _ => Err("Unrecognized enum variant".into()),
         }
     }
@@ -348,7 +356,7 @@ impl DatasetType {
         match s {
             "table" => Some(DatasetType::Table),
             "view" => Some(DatasetType::View),
-            "materializedView" => Some(DatasetType::MaterializedView),
+            "materializedView" | "materialized_view" => Some(DatasetType::MaterializedView),
             _ => None,
         }
     }
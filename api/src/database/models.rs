@@ -113,6 +113,9 @@ pub struct DataSource {
     pub updated_at: DateTime<Utc>,
     pub deleted_at: Option<DateTime<Utc>>,
     pub env: String,
+    /// Whether schema and table names must match the warehouse's casing exactly; `None`
+    /// uses the dialect's default
+    pub case_sensitive_identifiers: Option<bool>,
 }
 
 #[derive(
@@ -147,6 +150,11 @@ pub struct DatasetColumn {
     pub semantic_type: Option<String>,
     pub dim_type: Option<String>,
     pub expr: Option<String>,
+    pub ordinal: Option<i32>,
+    /// Modeled for joins and lineage but left out of the fields shown to end users
+    pub hidden: bool,
+    /// Display name shown in place of `name`; never used in SQL
+    pub label: Option<String>,
 }
 
 #[derive(
@@ -190,6 +198,11 @@ pub struct Dataset {
     pub model: Option<String>,
     pub yml_file: Option<String>,
     pub database_identifier: Option<String>,
+    pub refresh_schedule: Option<Value>,
+    pub primary_time_dimension: Option<String>,
+    pub grain: Option<Value>,
+    /// Display name shown in place of `name`; never used in SQL
+    pub label: Option<String>,
 }
 
 #[derive(Insertable, Queryable, Associations, Debug)]
@@ -230,6 +243,8 @@ pub struct Organization {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub deleted_at: Option<DateTime<Utc>>,
+    /// Whether answers to repeated questions are served from the agent result cache
+    pub agent_result_cache_enabled: bool,
 }
 
 #[derive(
@@ -514,6 +529,20 @@ pub struct SqlEvaluation {
     pub deleted_at: Option<DateTime<Utc>>,
 }
 
+#[derive(Queryable, Insertable, Debug, Serialize)]
+#[diesel(table_name = deploy_events)]
+pub struct DeployEvent {
+    pub id: Uuid,
+    pub organization_id: Uuid,
+    pub user_id: Uuid,
+    pub deploy_hash: String,
+    pub model_count: i32,
+    pub success_count: i32,
+    pub failure_count: i32,
+    pub pruned_count: i32,
+    pub created_at: DateTime<Utc>,
+}
+
 #[derive(Queryable, Insertable, Debug)]
 #[diesel(table_name = entity_relationship)]
 pub struct EntityRelationship {
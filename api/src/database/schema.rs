@@ -161,6 +161,7 @@ diesel::table! {
         updated_at -> Timestamptz,
         deleted_at -> Nullable<Timestamptz>,
         env -> Varchar,
+        case_sensitive_identifiers -> Nullable<Bool>,
     }
 }
 
@@ -187,6 +188,9 @@ diesel::table! {
         semantic_type -> Nullable<Text>,
         dim_type -> Nullable<Text>,
         expr -> Nullable<Text>,
+        ordinal -> Nullable<Int4>,
+        hidden -> Bool,
+        label -> Nullable<Text>,
     }
 }
 
@@ -253,6 +257,10 @@ diesel::table! {
         model -> Nullable<Text>,
         yml_file -> Nullable<Text>,
         database_identifier -> Nullable<Text>,
+        refresh_schedule -> Nullable<Jsonb>,
+        primary_time_dimension -> Nullable<Text>,
+        grain -> Nullable<Jsonb>,
+        label -> Nullable<Text>,
     }
 }
 
@@ -276,6 +284,20 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    deploy_events (id) {
+        id -> Uuid,
+        organization_id -> Uuid,
+        user_id -> Uuid,
+        deploy_hash -> Text,
+        model_count -> Int4,
+        success_count -> Int4,
+        failure_count -> Int4,
+        pruned_count -> Int4,
+        created_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     entity_relationship (primary_dataset_id, foreign_dataset_id) {
         primary_dataset_id -> Uuid,
@@ -324,6 +346,7 @@ diesel::table! {
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
         deleted_at -> Nullable<Timestamptz>,
+        agent_result_cache_enabled -> Bool,
     }
 }
 
@@ -532,6 +555,8 @@ diesel::joinable!(datasets_to_dataset_groups -> dataset_groups (dataset_group_id
 diesel::joinable!(datasets_to_dataset_groups -> datasets (dataset_id));
 diesel::joinable!(datasets_to_permission_groups -> datasets (dataset_id));
 diesel::joinable!(datasets_to_permission_groups -> permission_groups (permission_group_id));
+diesel::joinable!(deploy_events -> organizations (organization_id));
+diesel::joinable!(deploy_events -> users (user_id));
 diesel::joinable!(messages -> datasets (dataset_id));
 diesel::joinable!(messages -> threads (thread_id));
 diesel::joinable!(messages -> users (sent_by));
@@ -567,6 +592,7 @@ diesel::allow_tables_to_appear_in_same_query!(
     datasets,
     datasets_to_dataset_groups,
     datasets_to_permission_groups,
+    deploy_events,
     entity_relationship,
     messages,
     organizations,
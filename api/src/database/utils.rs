@@ -1,5 +1,5 @@
 use anyhow::{anyhow, Result};
-use diesel::{BoolExpressionMethods, ExpressionMethods, JoinOnDsl, QueryDsl};
+use diesel::{dsl::count_star, BoolExpressionMethods, ExpressionMethods, JoinOnDsl, QueryDsl};
 use diesel_async::RunQueryDsl;
 use uuid::Uuid;
 
@@ -93,6 +93,7 @@ impl DataSource {
                 data_sources::updated_at,
                 data_sources::deleted_at,
                 data_sources::env,
+                data_sources::case_sensitive_identifiers,
             ))
             .first::<DataSource>(&mut conn)
             .await
@@ -134,6 +135,7 @@ impl DataSource {
                 data_sources::updated_at,
                 data_sources::deleted_at,
                 data_sources::env,
+                data_sources::case_sensitive_identifiers,
             ))
             .first::<DataSource>(&mut conn)
             .await
@@ -148,6 +150,43 @@ impl DataSource {
 
         Ok(data_source)
     }
+
+    /// Active data sources that share a `(name, env, organization_id)`. Deploys look data
+    /// sources up by name and env, so each entry is a data issue that has to be fixed by
+    /// hand. Returns `(name, env, organization_id, count)`.
+    pub async fn find_duplicates() -> Result<Vec<(String, String, Uuid, i64)>> {
+        let mut conn = match get_pg_pool().get().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                tracing::error!("Error getting connection from pool: {}", e);
+                return Err(anyhow!("Error getting connection: {}", e));
+            }
+        };
+
+        match data_sources::table
+            .filter(data_sources::deleted_at.is_null())
+            .group_by((
+                data_sources::name,
+                data_sources::env,
+                data_sources::organization_id,
+            ))
+            .having(count_star().gt(1))
+            .select((
+                data_sources::name,
+                data_sources::env,
+                data_sources::organization_id,
+                count_star(),
+            ))
+            .load::<(String, String, Uuid, i64)>(&mut conn)
+            .await
+        {
+            Ok(duplicates) => Ok(duplicates),
+            Err(e) => {
+                tracing::error!("Error querying duplicate data sources: {}", e);
+                Err(anyhow!("Error querying duplicate data sources: {}", e))
+            }
+        }
+    }
 }
 
 impl Term {
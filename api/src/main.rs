@@ -10,6 +10,7 @@ use std::sync::Arc;
 use axum::{middleware, Extension, Router};
 use buster_middleware::{auth::auth, cors::cors};
 use database::lib::get_pg_pool;
+use database::models::DataSource;
 use diesel::{Connection, PgConnection};
 use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
 use dotenv::dotenv;
@@ -45,6 +46,12 @@ async fn main() {
         .with(tracing_subscriber::fmt::layer())
         .init();
 
+    // Agents can't run without a working LLM, so check its configuration up front
+    if let Err(e) = utils::clients::ai::llm_config::init_llm_config().await {
+        tracing::error!("Invalid LLM configuration: {}", e);
+        return;
+    }
+
     // Initialize global pools
     if let Err(e) = database::lib::init_pools().await {
         tracing::error!("Failed to initialize global pools: {}", e);
@@ -60,6 +67,22 @@ async fn main() {
 
     tracing::info!("Successfully ran database migrations");
 
+    // Deploys can't tell duplicate data sources apart, so surface them at startup
+    match DataSource::find_duplicates().await {
+        Ok(duplicates) => {
+            for (name, env, organization_id, count) in duplicates {
+                tracing::warn!(
+                    "{} active data sources named '{}' in env '{}' for organization {}",
+                    count,
+                    name,
+                    env,
+                    organization_id
+                );
+            }
+        }
+        Err(e) => tracing::error!("Failed to check for duplicate data sources: {}", e),
+    }
+
     let protected_router = Router::new().nest("/api/v1", routes::protected_router());
     let public_router = Router::new().route("/health", axum::routing::get(|| async { "OK" }));
 
@@ -0,0 +1,90 @@
+use anyhow::Result;
+use axum::Extension;
+use diesel::{ExpressionMethods, QueryDsl};
+use diesel_async::RunQueryDsl;
+use reqwest::StatusCode;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{
+    database::{enums::DataSourceType, lib::get_pg_pool, models::User, schema::data_sources},
+    routes::rest::ApiResponse,
+    utils::{
+        security::checks::is_user_workspace_admin_or_data_admin,
+        user::user_info::get_user_organization_id,
+    },
+};
+
+/// A data source as the CLI sees it, for checking deploy targets before writing anything.
+#[derive(Serialize)]
+pub struct DataSourceSummary {
+    pub id: Uuid,
+    pub name: String,
+    pub env: String,
+    #[serde(rename = "type")]
+    pub type_: DataSourceType,
+}
+
+pub async fn list_data_sources(
+    Extension(user): Extension<User>,
+) -> Result<ApiResponse<Vec<DataSourceSummary>>, (StatusCode, String)> {
+    let organization_id = match get_user_organization_id(&user.id).await {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::error!("Error getting user organization id: {:?}", e);
+            return Err((e.status_code(), e.public_message().to_string()));
+        }
+    };
+
+    match is_user_workspace_admin_or_data_admin(&user, &organization_id).await {
+        Ok(true) => (),
+        Ok(false) => {
+            return Err((
+                StatusCode::FORBIDDEN,
+                "Insufficient permissions".to_string(),
+            ))
+        }
+        Err(e) => {
+            tracing::error!("Error checking user permissions: {:?}", e);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()));
+        }
+    }
+
+    match list_data_sources_handler(&organization_id).await {
+        Ok(data_sources) => Ok(ApiResponse::JsonData(data_sources)),
+        Err(e) => {
+            tracing::error!("Error listing data sources: {:?}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Error listing data sources".to_string(),
+            ))
+        }
+    }
+}
+
+async fn list_data_sources_handler(organization_id: &Uuid) -> Result<Vec<DataSourceSummary>> {
+    let mut conn = get_pg_pool().get().await?;
+
+    let rows = data_sources::table
+        .filter(data_sources::organization_id.eq(organization_id))
+        .filter(data_sources::deleted_at.is_null())
+        .select((
+            data_sources::id,
+            data_sources::name,
+            data_sources::env,
+            data_sources::type_,
+        ))
+        .order((data_sources::name, data_sources::env))
+        .load::<(Uuid, String, String, DataSourceType)>(&mut conn)
+        .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(id, name, env, type_)| DataSourceSummary {
+            id,
+            name,
+            env,
+            type_,
+        })
+        .collect())
+}
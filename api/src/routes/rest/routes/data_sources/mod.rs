@@ -1,8 +1,13 @@
+mod list_data_sources;
 mod post_data_sources;
 
-use axum::{routing::post, Router};
+use axum::{
+    routing::{get, post},
+    Router,
+};
 
 pub fn router() -> Router {
     Router::new()
+        .route("/", get(list_data_sources::list_data_sources))
         .route("/", post(post_data_sources::post_data_sources))
 }
@@ -29,6 +29,11 @@ use crate::utils::query_engine::credentials::Credential;
 pub struct CreateDataSourceRequest {
     pub name: String,
     pub env: String,
+    /// Match schema and table names exactly, for warehouses using quoted mixed-case
+    /// identifiers. When unset, a data source that already exists keeps its setting and a
+    /// new one uses the dialect's default
+    #[serde(default)]
+    pub case_sensitive_identifiers: Option<bool>,
     #[serde(flatten)]
     pub credential: Credential,
 }
@@ -123,6 +128,7 @@ async fn post_data_sources_handler(
                 onboarding_status: DataSourceOnboardingStatus::NotStarted,
                 onboarding_error: None,
                 env: request.env.clone(),
+                case_sensitive_identifiers: request.case_sensitive_identifiers,
             }
         })
         .collect::<Vec<DataSource>>();
@@ -142,6 +148,13 @@ async fn post_data_sources_handler(
             data_sources::updated_at.eq(chrono::Utc::now()),
             data_sources::deleted_at.eq(Option::<DateTime<Utc>>::None),
             data_sources::env.eq(excluded(data_sources::env)),
+            // Re-posting without the override keeps the one already stored
+            data_sources::case_sensitive_identifiers.eq(diesel::dsl::sql::<
+                diesel::sql_types::Nullable<diesel::sql_types::Bool>,
+            >(
+                "COALESCE(excluded.case_sensitive_identifiers, \
+                 data_sources.case_sensitive_identifiers)",
+            )),
         ))
         .execute(&mut conn)
         .await
@@ -1,33 +1,67 @@
 use anyhow::{anyhow, Result};
-use axum::{extract::Json, Extension};
+use axum::{
+    body::Body,
+    extract::{rejection::JsonRejection, Json, Query},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
+    Extension,
+};
 use chrono::{DateTime, Utc};
-use diesel::{upsert::excluded, ExpressionMethods, QueryDsl, SelectableHelper};
-use diesel_async::RunQueryDsl;
+use diesel::{upsert::excluded, ExpressionMethods, OptionalExtension, QueryDsl, SelectableHelper};
+use diesel_async::{scoped_futures::ScopedFutureExt, AsyncConnection, AsyncPgConnection, RunQueryDsl};
+use futures::stream::{self, StreamExt};
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use serde_yaml;
+use sha2::{Digest, Sha256};
 use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
 use uuid::Uuid;
 
 use crate::{
     database::{
-        enums::DatasetType,
+        enums::{DataSourceType, DatasetType},
         lib::get_pg_pool,
-        models::{DataSource, Dataset, DatasetColumn, EntityRelationship, User},
-        schema::{data_sources, dataset_columns, datasets, entity_relationship},
+        models::{DataSource, Dataset, DatasetColumn, DeployEvent, EntityRelationship, User},
+        schema::{data_sources, dataset_columns, datasets, deploy_events, entity_relationship},
     },
     routes::rest::ApiResponse,
     utils::{
         dataset::column_management::{get_column_types, update_dataset_columns},
         query_engine::{
-            credentials::get_data_source_credentials,
-            import_dataset_columns::{retrieve_dataset_columns, retrieve_dataset_columns_batch},
+            credentials::{get_data_source_credentials, Credential},
+            dialect_detection::resolve_data_source_type,
+            column_comments::comment_statements,
+            grain_uniqueness::grain_is_unique,
+            sample_values::distinct_values_exceed,
+            similar_tables::{schema_tables, similar_tables},
+            import_dataset_columns::{
+                retrieve_dataset_columns, retrieve_dataset_columns_batch, DatasetColumnRecord,
+                DatasetObjectType,
+            },
+            table_references::default_sql_definition,
+            warehouse_errors::{is_warehouse_auth_error, warehouse_timeout},
             write_query_engine::write_query_engine,
         },
-        security::checks::is_user_workspace_admin_or_data_admin,
-        stored_values::{process_stored_values_background, store_column_values, StoredValueColumn},
+        security::{
+            checks::is_user_workspace_admin_or_data_admin,
+            rate_limit::{rate_limited_response, DEPLOY_RATE_LIMITER},
+        },
+        stored_values::{
+            delete_column_values, process_stored_values_background, store_column_values,
+            StoredValueColumn,
+        },
         user::user_info::get_user_organization_id,
-        validation::{dataset_validation::validate_model, ValidationError, ValidationResult},
+        validation::{
+            dataset_validation::validate_model, find_relationship_cycles, has_unclosed_code_fence,
+            identifiers_match, measure_agg_mismatch, sanitize_description, undeclared_columns,
+            validate_entity_expr, validate_measure_filter, StandardType, ValidationError,
+            ValidationErrorContext, ValidationResult,
+        },
         ColumnUpdate, ValidationErrorType,
     },
 };
@@ -38,11 +72,13 @@ pub struct BusterConfig {
     pub schema: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct DeployDatasetsRequest {
     pub id: Option<Uuid>,
     pub data_source_name: String,
-    pub env: String,
+    /// Omitted when neither the model, buster.yml nor `--env` sets one, which rejects the model
+    #[serde(default)]
+    pub env: Option<String>,
     #[serde(rename = "type")]
     pub type_: String,
     pub name: String,
@@ -55,9 +91,106 @@ pub struct DeployDatasetsRequest {
     pub columns: Vec<DeployDatasetsColumnsRequest>,
     pub yml_file: Option<String>,
     pub database_identifier: Option<String>,
+    /// Detect the warehouse dialect from its version banner instead of trusting the recorded type
+    #[serde(default)]
+    pub dialect_autodetect: bool,
+    /// Refresh schedule for datasets backed by a materialized view
+    #[serde(default)]
+    pub refresh: Option<DeployDatasetsRefreshRequest>,
+    /// Timestamp dimension used by default when filtering the dataset by time
+    #[serde(default)]
+    pub primary_time_dimension: Option<String>,
+    /// Dimensions or entities that together identify a single row
+    #[serde(default)]
+    pub grain: Option<Vec<String>>,
+    /// Query the warehouse to confirm the grain has no duplicate rows
+    #[serde(default)]
+    pub verify_grain: bool,
+    /// Warehouse table backing the model, when it differs from the model name
+    #[serde(default)]
+    pub database_name: Option<String>,
+    /// Disabled datasets stay deployed but are hidden from users
+    #[serde(default = "default_dataset_enabled")]
+    pub enabled: bool,
+    /// Report warehouse columns the model doesn't declare
+    #[serde(default)]
+    pub strict_schema: bool,
+    /// When the table isn't found, list the schema's tables named most like it
+    #[serde(default)]
+    pub explain_matching: bool,
+    /// Reject the model instead of warning when a check like `strict_schema` finds something
+    #[serde(default)]
+    pub fail_on_warning: bool,
+    /// After deploying, write the model and column descriptions to the warehouse as comments
+    #[serde(default)]
+    pub write_comments: bool,
+    /// Check descriptions and `when_to_use` render cleanly as markdown, removing HTML that
+    /// could run in the UI
+    #[serde(default)]
+    pub lint_descriptions: bool,
+    /// Reject the model instead of warning when `lint_descriptions` finds markdown problems
+    #[serde(default)]
+    pub fail_on_markdown_issues: bool,
+    /// Dataset types the project allows; any type is allowed when unset
+    #[serde(default)]
+    pub allowed_dataset_types: Option<Vec<String>>,
+    /// Tells agents when to use the model; the description is used when unset
+    #[serde(default)]
+    pub when_to_use: Option<String>,
+    /// `version` of the model file the model came from; 0 when the file doesn't set one
+    #[serde(default)]
+    pub version: i32,
+    /// What happens to columns removed from the model
+    #[serde(default)]
+    pub column_delete_mode: ColumnDeleteMode,
+    /// With hard deletes, also delete removed columns that have stored values
+    #[serde(default)]
+    pub force: bool,
+    /// Only create the dataset if it doesn't exist yet; an existing one is left untouched
+    #[serde(default)]
+    pub only_new: bool,
+    /// Display name shown in place of `name`
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Columns left out of this deploy; deployed ones are kept as they are, not deleted
+    #[serde(default)]
+    pub skipped_columns: Vec<String>,
 }
 
-#[derive(Debug, Deserialize)]
+/// Soft-deleted columns keep their row with `deleted_at` set, so a column added back
+/// later is restored as it was; hard-deleted ones are removed along with their stored
+/// values.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnDeleteMode {
+    #[default]
+    Soft,
+    Hard,
+}
+
+impl DeployDatasetsRequest {
+    /// The physical table name, falling back to the model name.
+    fn table_name(&self) -> &str {
+        self.database_name.as_deref().unwrap_or(&self.name)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DeployDatasetsRefreshRequest {
+    pub cron: String,
+    #[serde(default = "default_refresh_enabled")]
+    pub enabled: bool,
+}
+
+fn default_refresh_enabled() -> bool {
+    true
+}
+
+fn default_dataset_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize, Serialize)]
 pub struct DeployDatasetsColumnsRequest {
     pub name: String,
     pub description: String,
@@ -68,9 +201,34 @@ pub struct DeployDatasetsColumnsRequest {
     pub agg: Option<String>,
     #[serde(default)]
     pub stored_values: bool,
+    /// The CLI sends a dimension's `searchable` flag, which enrolls it in stored values
+    /// the same as `stored_values`
+    #[serde(default)]
+    pub searchable: bool,
+    /// Quantile for `agg: percentile` measures, in (0, 1]
+    #[serde(default)]
+    pub percentile: Option<f64>,
+    /// Boolean expression restricting the rows a measure aggregates
+    #[serde(default)]
+    pub filters: Option<String>,
+    /// Keep the column out of the fields shown to end users
+    #[serde(default)]
+    pub hidden: bool,
+    /// Display name shown in place of `name`
+    #[serde(default)]
+    pub label: Option<String>,
+    /// The measures an `agg: ratio` measure divides, once both are aggregated
+    #[serde(default)]
+    pub ratio: Option<DeployDatasetsRatioRequest>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DeployDatasetsRatioRequest {
+    pub numerator: String,
+    pub denominator: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
 pub struct DeployDatasetsEntityRelationshipsRequest {
     pub name: String,
     pub expr: String,
@@ -89,8 +247,51 @@ pub struct DeploymentSummary {
     pub total_models: usize,
     pub successful_models: usize,
     pub failed_models: usize,
+    pub pruned_count: usize,
+    /// Previously disabled models this deploy enabled
+    pub enabled: Vec<String>,
+    /// Previously enabled models this deploy disabled
+    pub disabled: Vec<String>,
+    /// Existing models left untouched because they were deployed with `only_new`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<String>,
     pub successes: Vec<DeploymentSuccess>,
     pub failures: Vec<DeploymentFailure>,
+    pub timings: DeployTimings,
+}
+
+/// Where a deploy spent its time, to find the models and data sources that slow it down.
+#[derive(Debug, Default, Serialize)]
+pub struct DeployTimings {
+    /// Models that took longest to validate, slowest first
+    pub slowest_models: Vec<ModelTiming>,
+    /// Column fetch and write time of each data source group, in the order they were written
+    pub groups: Vec<GroupTiming>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelTiming {
+    pub model_name: String,
+    pub data_source_name: String,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GroupTiming {
+    pub data_source_name: String,
+    pub database: Option<String>,
+    pub models: usize,
+    /// Fetching the group's warehouse columns
+    pub fetch_ms: u64,
+    /// Writing the group's datasets, columns and warehouse comments
+    pub write_ms: u64,
+}
+
+/// Models listed in a deploy summary's `slowest_models`.
+const SLOWEST_MODELS_REPORTED: usize = 10;
+
+fn duration_ms(duration: Duration) -> u64 {
+    duration.as_millis().try_into().unwrap_or(u64::MAX)
 }
 
 #[derive(Serialize)]
@@ -98,6 +299,9 @@ pub struct DeploymentSuccess {
     pub model_name: String,
     pub data_source_name: String,
     pub schema: String,
+    /// Findings the model deployed with, e.g. schema drift
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<ValidationError>,
 }
 
 #[derive(Serialize)]
@@ -106,6 +310,8 @@ pub struct DeploymentFailure {
     pub data_source_name: String,
     pub schema: String,
     pub errors: Vec<ValidationError>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<ValidationError>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -146,6 +352,8 @@ pub struct Dimension {
     pub dimension_type: String,
     pub description: String,
     pub searchable: bool,
+    #[serde(default)]
+    pub hidden: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -193,19 +401,57 @@ pub struct DatasetValidationFailure {
     pub errors: Vec<ValidationError>,
 }
 
+const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// Data source groups validated against their warehouses at the same time, unless the
+/// client asks for fewer (e.g. `?concurrency=1` for rate-limited warehouses).
+const DEFAULT_DEPLOY_CONCURRENCY: usize = 4;
+const MAX_DEPLOY_CONCURRENCY: usize = 16;
+
+/// Postgres rejects statements with more bind parameters than this.
+const MAX_BIND_PARAMS: usize = 65_535;
+/// Rows per bulk upsert, unless `DEPLOY_UPSERT_BATCH_SIZE` says otherwise.
+const DEFAULT_UPSERT_BATCH_SIZE: usize = 1000;
+/// Bind parameters each upserted row takes: one per field of `Dataset` and
+/// `DatasetColumn`.
+const DATASET_BIND_PARAMS: usize = 24;
+const DATASET_COLUMN_BIND_PARAMS: usize = 20;
+
+/// Header the CLI sends its version in.
+const CLI_VERSION_HEADER: &str = "x-buster-cli-version";
+/// Oldest CLI whose deploy requests this server reads.
+const MIN_CLI_VERSION: &str = "0.0.3";
+/// Newest model file `version` this server reads. dbt-style files declare `version: 2`,
+/// which reads the same as 1.
+const MAX_MODEL_VERSION: i32 = 2;
+
+#[derive(Debug, Deserialize)]
+pub struct DeployDatasetsQuery {
+    pub concurrency: Option<usize>,
+}
+
+/// One line of the streamed deploy response: a result per model as each data source
+/// group completes, then a final summary (or error) line.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum DeployStreamEvent {
+    Result(ValidationResult),
+    Summary(DeploymentSummary),
+    Error { message: String },
+}
+
 // Main API endpoint function
 pub async fn deploy_datasets(
     Extension(user): Extension<User>,
-    Json(request): Json<Vec<DeployDatasetsRequest>>,
-) -> Result<ApiResponse<DeployDatasetsResponse>, (StatusCode, String)> {
+    headers: HeaderMap,
+    Query(query): Query<DeployDatasetsQuery>,
+    payload: Result<Json<Vec<DeployDatasetsRequest>>, JsonRejection>,
+) -> Result<Response, (StatusCode, String)> {
     let organization_id = match get_user_organization_id(&user.id).await {
         Ok(id) => id,
         Err(e) => {
             tracing::error!("Error getting user organization id: {:?}", e);
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Error getting user organization id".to_string(),
-            ));
+            return Err((e.status_code(), e.public_message().to_string()));
         }
     };
 
@@ -224,9 +470,56 @@ pub async fn deploy_datasets(
         }
     }
 
+    if let Err(retry_after) = DEPLOY_RATE_LIMITER.check(&organization_id) {
+        tracing::warn!(
+            "Rate limited deploy for organization {}, retry after {:?}",
+            organization_id,
+            retry_after
+        );
+        return Ok(rate_limited_response(retry_after));
+    }
+
+    // Version drift is reported before the payload is read, since a payload from an
+    // outdated CLI may not deserialize at all
+    let cli_version = headers
+        .get(CLI_VERSION_HEADER)
+        .and_then(|value| value.to_str().ok());
+    if let Err(message) = check_cli_version(cli_version) {
+        return Err((StatusCode::UPGRADE_REQUIRED, message));
+    }
+    let request = match payload {
+        Ok(Json(request)) => request,
+        Err(JsonRejection::JsonDataError(e)) => {
+            tracing::warn!(
+                "Unreadable deploy request from CLI {:?}: {}",
+                cli_version,
+                e.body_text()
+            );
+            return Err(unreadable_request_error(cli_version, &e.body_text()));
+        }
+        Err(rejection) => return Err((rejection.status(), rejection.body_text())),
+    };
+    if let Err(message) = check_model_versions(&request) {
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, message));
+    }
+
+    let wants_ndjson = headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .map_or(false, |accept| accept.contains(NDJSON_CONTENT_TYPE));
+
+    let concurrency = query
+        .concurrency
+        .unwrap_or(DEFAULT_DEPLOY_CONCURRENCY)
+        .clamp(1, MAX_DEPLOY_CONCURRENCY);
+
+    if wants_ndjson {
+        return Ok(stream_deploy_datasets(user.id, organization_id, request, concurrency));
+    }
+
     // Call handler function
-    match handle_deploy_datasets(&user.id, request).await {
-        Ok(result) => Ok(ApiResponse::JsonData(result)),
+    match handle_deploy_datasets(&user.id, &organization_id, request, concurrency, None).await {
+        Ok(result) => Ok(ApiResponse::JsonData(result).into_response()),
         Err(e) => {
             tracing::error!("Error in deploy_datasets: {:?}", e);
             Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
@@ -234,168 +527,434 @@ pub async fn deploy_datasets(
     }
 }
 
+/// Rejects CLIs older than `MIN_CLI_VERSION`. Clients that don't send a version are let
+/// through and only fail if their payload can't be read.
+fn check_cli_version(cli_version: Option<&str>) -> Result<(), String> {
+    let Some(cli_version) = cli_version else {
+        return Ok(());
+    };
+    match (parse_version(cli_version), parse_version(MIN_CLI_VERSION)) {
+        (Some(version), Some(min_version)) if version < min_version => Err(format!(
+            "Buster CLI {} is no longer supported; this server needs {} or newer",
+            cli_version, MIN_CLI_VERSION
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// `major.minor.patch`, ignoring a leading `v` and any pre-release or build suffix.
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let version = version.trim().trim_start_matches('v');
+    let mut parts = version
+        .split(['-', '+'])
+        .next()?
+        .split('.')
+        .map(|part| part.parse::<u64>().ok());
+    Some((
+        parts.next()??,
+        parts.next().flatten().unwrap_or(0),
+        parts.next().flatten().unwrap_or(0),
+    ))
+}
+
+/// The error for a deploy request that doesn't deserialize, naming the side that's behind.
+/// A missing field is one an older CLI doesn't send, so it gets `426 Upgrade Required`;
+/// anything else is a value from a newer CLI this server doesn't know yet.
+fn unreadable_request_error(cli_version: Option<&str>, error: &str) -> (StatusCode, String) {
+    let cli_version = cli_version.unwrap_or("unknown version");
+    if error.contains("missing field") {
+        (
+            StatusCode::UPGRADE_REQUIRED,
+            format!(
+                "The CLI ({}) is older than this server and left out a field it needs: {}",
+                cli_version, error
+            ),
+        )
+    } else {
+        (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!(
+                "This server is older than the CLI ({}) and can't read its deploy request; \
+                 upgrade the Buster server: {}",
+                cli_version, error
+            ),
+        )
+    }
+}
+
+/// Rejects models whose file `version` is newer than this server reads.
+fn check_model_versions(requests: &[DeployDatasetsRequest]) -> Result<(), String> {
+    let unsupported: Vec<String> = requests
+        .iter()
+        .filter(|req| req.version > MAX_MODEL_VERSION)
+        .map(|req| format!("{} (version {})", req.name, req.version))
+        .collect();
+    if unsupported.is_empty() {
+        return Ok(());
+    }
+
+    Err(format!(
+        "Unsupported model version for {}; this server is older than those files and reads \
+         model files up to version {}, so upgrade the Buster server or lower the version",
+        unsupported.join(", "),
+        MAX_MODEL_VERSION
+    ))
+}
+
+/// Runs the deploy in the background and streams its progress back as NDJSON.
+fn stream_deploy_datasets(
+    user_id: Uuid,
+    organization_id: Uuid,
+    requests: Vec<DeployDatasetsRequest>,
+    concurrency: usize,
+) -> Response {
+    let (result_tx, mut result_rx) = mpsc::channel::<ValidationResult>(100);
+    let (line_tx, line_rx) = mpsc::channel::<Result<String, Infallible>>(100);
+
+    let deploy = tokio::spawn(async move {
+        handle_deploy_datasets(&user_id, &organization_id, requests, concurrency, Some(result_tx))
+            .await
+    });
+
+    tokio::spawn(async move {
+        // The results channel closes once the deploy finishes and drops its sender
+        while let Some(result) = result_rx.recv().await {
+            if line_tx
+                .send(Ok(ndjson_line(&DeployStreamEvent::Result(result))))
+                .await
+                .is_err()
+            {
+                return;
+            }
+        }
+
+        let last_event = match deploy.await {
+            Ok(Ok(response)) => DeployStreamEvent::Summary(response.summary),
+            Ok(Err(e)) => {
+                tracing::error!("Error in deploy_datasets: {:?}", e);
+                DeployStreamEvent::Error {
+                    message: e.to_string(),
+                }
+            }
+            Err(e) => {
+                tracing::error!("Deploy task failed: {:?}", e);
+                DeployStreamEvent::Error {
+                    message: "Deploy task failed".to_string(),
+                }
+            }
+        };
+        let _ = line_tx.send(Ok(ndjson_line(&last_event))).await;
+    });
+
+    (
+        [(header::CONTENT_TYPE, NDJSON_CONTENT_TYPE)],
+        Body::from_stream(ReceiverStream::new(line_rx)),
+    )
+        .into_response()
+}
+
+fn ndjson_line(event: &DeployStreamEvent) -> String {
+    let mut line = serde_json::to_string(event).unwrap_or_else(|e| {
+        format!(r#"{{"type":"error","message":"Failed to serialize deploy event: {}"}}"#, e)
+    });
+    line.push('\n');
+    line
+}
+
 // Main handler function that contains all business logic
 async fn handle_deploy_datasets(
     user_id: &Uuid,
+    organization_id: &Uuid,
     requests: Vec<DeployDatasetsRequest>,
+    concurrency: usize,
+    progress: Option<mpsc::Sender<ValidationResult>>,
 ) -> Result<DeployDatasetsResponse> {
-    let results = deploy_datasets_handler(user_id, requests, false).await?;
+    let deploy_hash = compute_deploy_hash(&requests)?;
+    let (results, outcome) =
+        deploy_datasets_handler(user_id, requests, false, concurrency, progress.as_ref()).await?;
+
+    let summary = outcome.summary(&results);
+
+    // The deploy itself has already been written at this point, so a failure to record
+    // the audit event is logged rather than surfaced to the caller.
+    if let Err(e) =
+        record_deploy_event(user_id, organization_id, deploy_hash, &summary).await
+    {
+        tracing::error!("Error recording deploy event: {:?}", e);
+    }
 
-    let successful_models = results.iter().filter(|r| r.success).count();
-    let failed_models = results.iter().filter(|r| !r.success).count();
+    Ok(DeployDatasetsResponse { results, summary })
+}
 
-    let summary = DeploymentSummary {
-        total_models: results.len(),
-        successful_models,
-        failed_models,
-        successes: results
-            .iter()
-            .filter(|r| r.success)
-            .map(|r| DeploymentSuccess {
-                model_name: r.model_name.clone(),
-                data_source_name: r.data_source_name.clone(),
-                schema: r.schema.clone(),
-            })
-            .collect(),
-        failures: results
-            .iter()
-            .filter(|r| !r.success)
-            .map(|r| DeploymentFailure {
-                model_name: r.model_name.clone(),
-                data_source_name: r.data_source_name.clone(),
-                schema: r.schema.clone(),
-                errors: r.errors.clone(),
-            })
-            .collect(),
+/// Hashes the full deploy payload so audit entries can be matched back to the exact
+/// set of models that was deployed.
+fn compute_deploy_hash(requests: &[DeployDatasetsRequest]) -> Result<String> {
+    let payload = serde_json::to_vec(requests)?;
+    Ok(format!("{:x}", Sha256::digest(&payload)))
+}
+
+async fn record_deploy_event(
+    user_id: &Uuid,
+    organization_id: &Uuid,
+    deploy_hash: String,
+    summary: &DeploymentSummary,
+) -> Result<()> {
+    let mut conn = get_pg_pool().get().await?;
+
+    let deploy_event = DeployEvent {
+        id: Uuid::new_v4(),
+        organization_id: *organization_id,
+        user_id: *user_id,
+        deploy_hash,
+        model_count: summary.total_models as i32,
+        success_count: summary.successful_models as i32,
+        failure_count: summary.failed_models as i32,
+        pruned_count: summary.pruned_count as i32,
+        created_at: Utc::now(),
     };
 
-    Ok(DeployDatasetsResponse { results, summary })
+    diesel::insert_into(deploy_events::table)
+        .values(&deploy_event)
+        .execute(&mut conn)
+        .await?;
+
+    Ok(())
+}
+
+/// What a deploy's writes changed beyond the datasets' definitions.
+#[derive(Debug, Default)]
+struct DeployWriteOutcome {
+    /// Columns pruned because they were removed from their model
+    pruned_count: usize,
+    /// Existing datasets whose `enabled` flag changed, with the new value
+    enabled_changes: Vec<(String, bool)>,
+    /// Existing datasets `only_new` left untouched
+    skipped: Vec<String>,
+    /// Ids of the written datasets, keyed by table name
+    dataset_ids: HashMap<String, Uuid>,
+    /// How long each model took to validate, in validation order
+    model_timings: Vec<ModelTiming>,
+    group_timings: Vec<GroupTiming>,
+}
+
+impl DeployWriteOutcome {
+    /// Models `only_new` skipped validated but weren't written, so they count as neither
+    /// successes nor failures.
+    fn summary(&self, results: &[ValidationResult]) -> DeploymentSummary {
+        let deployed =
+            |r: &&ValidationResult| r.success && !self.skipped.contains(&r.model_name);
+
+        DeploymentSummary {
+            total_models: results.len(),
+            successful_models: results.iter().filter(deployed).count(),
+            failed_models: results.iter().filter(|r| !r.success).count(),
+            pruned_count: self.pruned_count,
+            enabled: self.toggled(true),
+            disabled: self.toggled(false),
+            skipped: self.skipped.clone(),
+            successes: results
+                .iter()
+                .filter(deployed)
+                .map(|r| DeploymentSuccess {
+                    model_name: r.model_name.clone(),
+                    data_source_name: r.data_source_name.clone(),
+                    schema: r.schema.clone(),
+                    warnings: r.warnings.clone(),
+                })
+                .collect(),
+            failures: results
+                .iter()
+                .filter(|r| !r.success)
+                .map(|r| DeploymentFailure {
+                    model_name: r.model_name.clone(),
+                    data_source_name: r.data_source_name.clone(),
+                    schema: r.schema.clone(),
+                    errors: r.errors.clone(),
+                    warnings: r.warnings.clone(),
+                })
+                .collect(),
+            timings: self.timings(),
+        }
+    }
+
+    /// The datasets of `valid_datasets` that were written, leaving out those `only_new`
+    /// skipped.
+    fn written<'a>(
+        &self,
+        valid_datasets: &[&'a DeployDatasetsRequest],
+    ) -> Vec<&'a DeployDatasetsRequest> {
+        valid_datasets
+            .iter()
+            .copied()
+            .filter(|req| !self.skipped.contains(&req.name))
+            .collect()
+    }
+
+    fn timings(&self) -> DeployTimings {
+        let mut slowest_models = self.model_timings.clone();
+        slowest_models.sort_by(|a, b| b.duration_ms.cmp(&a.duration_ms));
+        slowest_models.truncate(SLOWEST_MODELS_REPORTED);
+
+        DeployTimings {
+            slowest_models,
+            groups: self.group_timings.clone(),
+        }
+    }
+
+    fn toggled(&self, enabled: bool) -> Vec<String> {
+        self.enabled_changes
+            .iter()
+            .filter(|(_, now_enabled)| *now_enabled == enabled)
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
 }
 
-// Handler function that contains all the business logic
+// Handler function that contains all the business logic. Returns the per-model validation
+// results along with what the writes changed (pruned columns, enable/disable transitions).
 async fn deploy_datasets_handler(
     user_id: &Uuid,
-    requests: Vec<DeployDatasetsRequest>,
+    mut requests: Vec<DeployDatasetsRequest>,
     is_simple: bool,
-) -> Result<Vec<ValidationResult>> {
+    concurrency: usize,
+    progress: Option<&mpsc::Sender<ValidationResult>>,
+) -> Result<(Vec<ValidationResult>, DeployWriteOutcome)> {
     let organization_id = get_user_organization_id(user_id).await?;
     let mut conn = get_pg_pool().get().await?;
     let mut results = Vec::new();
+    let mut streamed = 0;
+    let mut outcome = DeployWriteOutcome::default();
+
+    let description_issues: HashMap<String, Vec<ValidationError>> = requests
+        .iter_mut()
+        .filter(|req| req.lint_descriptions)
+        .map(|req| (req.name.clone(), lint_description_markdown(req)))
+        .filter(|(_, issues)| !issues.is_empty())
+        .collect();
 
-    // Group requests by data source and database for efficient validation
-    let mut data_source_groups: HashMap<(String, Option<String>), Vec<&DeployDatasetsRequest>> = HashMap::new();
+    // Group requests by data source, env and database for efficient validation
+    let mut data_source_groups: HashMap<
+        (String, String, Option<String>),
+        Vec<&DeployDatasetsRequest>,
+    > = HashMap::new();
+    let configured_envs = std::env::var("DEPLOY_ALLOWED_ENVS").ok();
+    // Envs of the data sources models without an env name, looked up once per name
+    let mut data_source_envs: HashMap<&str, Vec<String>> = HashMap::new();
     for req in &requests {
-        data_source_groups
-            .entry((req.data_source_name.clone(), req.database.clone()))
-            .or_default()
-            .push(req);
-    }
-
-    // Process each data source group
-    for ((data_source_name, database), group) in data_source_groups {
-
-        // Get data source
-        let data_source = match data_sources::table
-            .filter(data_sources::name.eq(&data_source_name))
-            .filter(data_sources::env.eq(&group[0].env))
-            .filter(data_sources::organization_id.eq(&organization_id))
-            .filter(data_sources::deleted_at.is_null())
-            .select(data_sources::all_columns)
-            .first::<DataSource>(&mut conn)
-            .await
-        {
-            Ok(ds) => ds,
-            Err(_) => {
-                for req in group {
-                    let mut validation = ValidationResult::new(
-                        req.name.clone(),
-                        req.data_source_name.clone(),
-                        req.schema.clone(),
-                    );
-                    validation.add_error(ValidationError::data_source_error(format!(
-                        "Data source '{}' not found",
-                        data_source_name
-                    )));
-                    results.push(validation);
+        let env = match &req.env {
+            Some(env) => Ok(env.clone()),
+            None => {
+                if !data_source_envs.contains_key(req.data_source_name.as_str()) {
+                    let envs =
+                        data_source_envs_of(&mut conn, &organization_id, &req.data_source_name)
+                            .await?;
+                    data_source_envs.insert(&req.data_source_name, envs);
                 }
-                continue;
+                let envs = &data_source_envs[req.data_source_name.as_str()];
+                Err(missing_env(&req.data_source_name, envs))
             }
         };
 
-        // Get credentials for the data source
-        let credentials = match get_data_source_credentials(&data_source.secret_id, &data_source.type_, false).await {
-            Ok(creds) => creds,
-            Err(e) => {
-                for req in group {
-                    let mut validation = ValidationResult::new(
-                        req.name.clone(),
-                        req.data_source_name.clone(),
-                        req.schema.clone(),
-                    );
-                    validation.add_error(ValidationError::data_source_error(format!(
-                        "Failed to get data source credentials: {}",
-                        e
-                    )));
-                    results.push(validation);
-                }
+        // Models without columns or with an unknown env are rejected before any warehouse
+        // lookups
+        let env = if req.columns.is_empty() {
+            Err(ValidationError::empty_model(&req.name))
+        } else {
+            env.and_then(|env| match validate_env(&env, configured_envs.as_deref()) {
+                Some(error) => Err(error),
+                None => Ok(env),
+            })
+        };
+        let env = match env {
+            Ok(env) => env,
+            Err(error) => {
+                let mut validation = ValidationResult::new(
+                    req.name.clone(),
+                    req.data_source_name.clone(),
+                    req.schema.clone(),
+                );
+                validation.add_error(error);
+                results.push(validation);
                 continue;
             }
         };
 
-        // Prepare tables for batch validation
-        let tables_to_validate: Vec<(String, String)> = group
-            .iter()
-            .map(|req| (req.name.clone(), req.schema.clone()))
-            .collect();
+        data_source_groups
+            .entry((req.data_source_name.clone(), env, req.database.clone()))
+            .or_default()
+            .push(req);
+    }
 
-        tracing::info!(
-            "Validating tables for data source '{:?}.{:?}': {:?}",
-            data_source_name,
-            database,
-            tables_to_validate
-        );
+    // Warehouse lookups are the slow part of a deploy, so groups are fetched
+    // `concurrency` at a time; the writes below still run one group at a time.
+    let fetched_groups: Vec<_> = stream::iter(data_source_groups)
+        .map(|((data_source_name, env, database), group)| async move {
+            let started = Instant::now();
+            let fetched =
+                fetch_group_columns(&organization_id, &data_source_name, &env, database, &group)
+                    .await;
+            (group, fetched, started.elapsed())
+        })
+        .buffer_unordered(concurrency)
+        .collect()
+        .await;
 
-        // Get all columns in one batch - this acts as our validation
-        let ds_columns = match retrieve_dataset_columns_batch(&tables_to_validate, &credentials, database).await {
-            Ok(cols) => {
-                // Add debug logging
-                tracing::info!(
-                    "Retrieved {} columns for data source '{}'. Tables found: {:?}",
-                    cols.len(),
-                    data_source_name,
-                    cols.iter()
-                        .map(|c| format!("{}.{}", c.schema_name, c.dataset_name))
-                        .collect::<HashSet<_>>()
-                );
-                cols
-            },
-            Err(e) => {
-                tracing::error!(
-                    "Error retrieving columns for data source '{}': {:?}",
-                    data_source_name,
-                    e
-                );
-                for req in group {
+    // Process each data source group
+    for (group, fetched, fetch_time) in fetched_groups {
+        // Results from the previous group are final once its writes are done
+        stream_results(progress, &results, &mut streamed).await;
+        outcome.group_timings.push(GroupTiming {
+            data_source_name: group[0].data_source_name.clone(),
+            database: group[0].database.clone(),
+            models: group.len(),
+            fetch_ms: duration_ms(fetch_time),
+            write_ms: 0,
+        });
+
+        let (data_source, data_source_type, ds_columns, timed_out) = match fetched {
+            Ok(fetched) => fetched,
+            Err(error) => {
+                // Rejected credentials are a problem with the data source, not its models,
+                // so the full error and its guidance are only reported once per group
+                let repeated_error = match error.error_type {
+                    ValidationErrorType::WarehouseAuthFailed => {
+                        ValidationError::data_source_error(format!(
+                            "Not validated: the warehouse rejected the credentials for data source '{}'",
+                            group[0].data_source_name
+                        ))
+                    }
+                    _ => error.clone(),
+                };
+
+                for (i, req) in group.iter().enumerate() {
                     let mut validation = ValidationResult::new(
                         req.name.clone(),
                         req.data_source_name.clone(),
                         req.schema.clone(),
                     );
-                    validation.add_error(ValidationError::data_source_error(format!(
-                        "Failed to get columns from data source: {}",
-                        e
-                    )));
+                    validation.add_error(if i == 0 {
+                        error.clone()
+                    } else {
+                        repeated_error.clone()
+                    });
                     results.push(validation);
                 }
                 continue;
             }
         };
 
+        let relationship_cycles =
+            relationship_cycles_by_model(&mut conn, &data_source, &requests).await?;
+        let foreign_ids = foreign_dataset_ids(&mut conn, &organization_id, &group).await?;
+        let case_sensitive = identifiers_case_sensitive(&data_source, &data_source_type);
+        let columns_case_sensitive = column_identifiers_case_sensitive(&data_source);
+
         // Create a map of valid datasets and their columns
         let mut valid_datasets = Vec::new();
         let mut dataset_columns_map: HashMap<String, Vec<_>> = HashMap::new();
         
         for req in group {
+            let validation_started = Instant::now();
             let mut validation = ValidationResult::new(
                 req.name.clone(),
                 req.data_source_name.clone(),
@@ -406,8 +965,10 @@ async fn deploy_datasets_handler(
             let columns: Vec<_> = ds_columns
                 .iter()
                 .filter(|col| {
-                    let name_match = col.dataset_name.to_lowercase() == req.name.to_lowercase();
-                    let schema_match = col.schema_name.to_lowercase() == req.schema.to_lowercase();
+                    let name_match =
+                        identifiers_match(&col.dataset_name, req.table_name(), case_sensitive);
+                    let schema_match =
+                        identifiers_match(&col.schema_name, &req.schema, case_sensitive);
                     
                     // Add detailed debug logging for column matching
                     tracing::info!(
@@ -417,31 +978,153 @@ async fn deploy_datasets_handler(
                         name_match,
                         schema_match,
                         req.schema,
-                        req.name
+                        req.table_name()
                     );
                     
                     name_match && schema_match
                 })
                 .collect();
 
-            if columns.is_empty() {
+            let deployed_searchable =
+                deployed_searchable_columns(&mut conn, &data_source, req).await?;
+            let changed_searchable = changed_searchable_columns(req, &deployed_searchable);
+
+            // Reported alongside whatever else is wrong with the model, not instead of it
+            let entity_errors = if columns.is_empty() {
+                Vec::new()
+            } else {
+                validate_entity_columns(req, &columns, columns_case_sensitive)
+            };
+            let entities_valid = entity_errors.is_empty();
+            for error in entity_errors {
+                validation.add_error(error);
+            }
+
+            let timeout = timed_out.get(&(req.table_name().to_string(), req.schema.clone()));
+            if let Some(id) = req.id.filter(|id| foreign_ids.contains(id)) {
+                validation.add_error(ValidationError::dataset_not_found(&id.to_string()));
+            } else if let Some(after) = timeout {
+                validation.add_error(ValidationError::table_timeout(
+                    &format!("{}.{}", req.schema, req.table_name()),
+                    *after,
+                ));
+            } else if columns.is_empty() {
                 tracing::warn!(
                     "No columns found for dataset '{}' in schema '{}'. Available tables:\n{}",
-                    req.name,
+                    req.table_name(),
                     req.schema,
                     ds_columns
                         .iter()
-                        .map(|c| format!("  - {}.{}", c.schema_name, c.dataset_name))
+                        .map(|c| format!(
+                            "  - {}.{} ({:?})",
+                            c.schema_name,
+                            c.dataset_name,
+                            c.object_type()
+                        ))
                         .collect::<Vec<_>>()
                         .join("\n")
                 );
-                validation.add_error(ValidationError::table_not_found(&format!(
+                let mut error = ValidationError::table_not_found(&format!(
                     "{}.{}",
                     req.schema,
-                    req.name
-                )));
+                    req.table_name()
+                ));
+                // On case-sensitive data sources the likely mistake is the casing
+                error.suggestion = ds_columns
+                    .iter()
+                    .find(|col| {
+                        identifiers_match(&col.dataset_name, req.table_name(), false)
+                            && identifiers_match(&col.schema_name, &req.schema, false)
+                    })
+                    .filter(|_| case_sensitive)
+                    .map(|col| {
+                        format!(
+                            "Identifiers are case-sensitive on this data source; did you mean \
+                             '{}.{}'?",
+                            col.schema_name, col.dataset_name
+                        )
+                    });
+                if req.explain_matching {
+                    match schema_tables(&data_source, &req.schema).await {
+                        Ok(tables) => {
+                            error.context = Some(ValidationErrorContext {
+                                similar_tables: similar_tables(req.table_name(), &tables),
+                            })
+                        }
+                        Err(e) => tracing::warn!(
+                            "Unable to list tables of schema '{}' to explain matching: {:?}",
+                            req.schema,
+                            e
+                        ),
+                    }
+                }
+                validation.add_error(error);
                 validation.success = false;
-            } else {
+            } else if let Some(errors) = validate_measure_columns(req, &data_source_type) {
+                for error in errors {
+                    validation.add_error(error);
+                }
+            } else if let Some(errors) =
+                validate_measure_filters(req, &columns, &data_source_type, columns_case_sensitive)
+            {
+                for error in errors {
+                    validation.add_error(error);
+                }
+            } else if let Some(error) = validate_refresh_schedule(req) {
+                validation.add_error(error);
+            } else if let Some(error) = validate_primary_time_dimension(req) {
+                validation.add_error(error);
+            } else if let Some(error) = validate_dataset_type(req) {
+                validation.add_error(error);
+            } else if let Some(error) =
+                validate_searchable_columns(
+                    &data_source,
+                    &changed_searchable,
+                    &columns,
+                    columns_case_sensitive,
+                )
+                .await
+            {
+                validation.add_error(error);
+            } else if let Some(errors) =
+                removed_stored_values_columns(&mut conn, &data_source, req).await?
+            {
+                for error in errors {
+                    validation.add_error(error);
+                }
+            } else if let Some(cycle) = relationship_cycles.get(&req.name.to_lowercase()) {
+                validation.add_error(ValidationError::relationship_cycle(cycle));
+            } else if let Err(errors) = resolve_grain(req, &columns) {
+                for error in errors {
+                    validation.add_error(error);
+                }
+            } else if let Some(error) = verify_grain(&data_source, req, &columns).await {
+                validation.add_error(error);
+            } else if let Some(drift) = schema_drift(req, &columns).filter(|_| req.fail_on_warning)
+            {
+                validation.add_error(drift);
+            } else if let Some(issues) = description_issues
+                .get(&req.name)
+                .filter(|_| req.fail_on_markdown_issues)
+            {
+                for issue in issues {
+                    validation.add_error(issue.clone());
+                }
+            } else if entities_valid {
+                if let Some(drift) = schema_drift(req, &columns) {
+                    validation.add_warning(drift);
+                }
+                for warning in defaulted_column_types(req, &columns) {
+                    validation.add_warning(warning);
+                }
+                for warning in
+                    searchable_enrollment_warnings(&changed_searchable, &deployed_searchable)
+                {
+                    validation.add_warning(warning);
+                }
+                for issue in description_issues.get(&req.name).into_iter().flatten() {
+                    validation.add_warning(issue.clone());
+                }
                 tracing::info!(
                     "✅ Found {} columns for dataset '{}.{}'",
                     columns.len(),
@@ -453,171 +1136,1699 @@ async fn deploy_datasets_handler(
                 dataset_columns_map.insert(req.name.clone(), columns);
             }
 
+            outcome.model_timings.push(ModelTiming {
+                model_name: req.name.clone(),
+                data_source_name: req.data_source_name.clone(),
+                duration_ms: duration_ms(validation_started.elapsed()),
+            });
             results.push(validation);
         }
 
-        // Bulk upsert valid datasets
+        // Each group's writes are applied atomically, so a failed deploy never leaves a
+        // dataset upserted without its columns
         if !valid_datasets.is_empty() {
-            let now = Utc::now();
-            
-            // Get existing dataset IDs for this data source
-            let existing_datasets: HashSet<String> = datasets::table
-                .filter(datasets::data_source_id.eq(&data_source.id))
-                .filter(datasets::deleted_at.is_null())
-                .select(datasets::name)
-                .load::<String>(&mut conn)
-                .await?
-                .into_iter()
-                .collect();
+            let write_started = Instant::now();
+            let group_outcome = conn
+                .transaction::<_, anyhow::Error, _>(|conn| {
+                    write_group_datasets(
+                        conn,
+                        user_id,
+                        &organization_id,
+                        &data_source,
+                        &data_source_type,
+                        &valid_datasets,
+                        &dataset_columns_map,
+                    )
+                    .scope_boxed()
+                })
+                .await?;
+            let written = group_outcome.written(&valid_datasets);
+            outcome.pruned_count += group_outcome.pruned_count;
+            outcome.enabled_changes.extend(group_outcome.enabled_changes);
+            outcome.skipped.extend(group_outcome.skipped);
+
+            write_warehouse_comments(
+                &data_source_type,
+                columns_case_sensitive,
+                &written,
+                &group_outcome.dataset_ids,
+                &dataset_columns_map,
+                &mut results,
+            )
+            .await;
+
+            if let Some(timing) = outcome.group_timings.last_mut() {
+                timing.write_ms = duration_ms(write_started.elapsed());
+            }
+        }
+    }
+
+    stream_results(progress, &results, &mut streamed).await;
+
+    Ok((results, outcome))
+}
+
+/// Maps each model on an entity relationship cycle to the cycle's members. Cycles are
+/// looked for across the data source's whole graph: its stored relationships, with each
+/// model being deployed to it contributing its foreign entities in place of its stored edges.
+async fn relationship_cycles_by_model(
+    conn: &mut AsyncPgConnection,
+    data_source: &DataSource,
+    requests: &[DeployDatasetsRequest],
+) -> Result<HashMap<String, Vec<String>>> {
+    let dataset_names: HashMap<Uuid, String> = datasets::table
+        .filter(datasets::data_source_id.eq(data_source.id))
+        .filter(datasets::deleted_at.is_null())
+        .select((datasets::id, datasets::name))
+        .load::<(Uuid, String)>(conn)
+        .await?
+        .into_iter()
+        .map(|(id, name)| (id, name.to_lowercase()))
+        .collect();
+    let dataset_ids: Vec<Uuid> = dataset_names.keys().copied().collect();
+
+    // A stored relationship points from the dataset holding the foreign key to the one it
+    // references
+    let stored = entity_relationship::table
+        .filter(entity_relationship::foreign_dataset_id.eq_any(&dataset_ids))
+        .select((
+            entity_relationship::foreign_dataset_id,
+            entity_relationship::primary_dataset_id,
+        ))
+        .load::<(Uuid, Uuid)>(conn)
+        .await?;
+
+    let mut edges: HashMap<String, Vec<String>> = HashMap::new();
+    for (from, to) in stored {
+        if let (Some(from), Some(to)) = (dataset_names.get(&from), dataset_names.get(&to)) {
+            edges.entry(from.clone()).or_default().push(to.clone());
+        }
+    }
+
+    for req in requests
+        .iter()
+        .filter(|req| req.data_source_name == data_source.name)
+    {
+        let targets = req
+            .entity_relationships
+            .iter()
+            .flatten()
+            .filter(|entity| entity.type_ == "foreign")
+            .map(|entity| entity.name.to_lowercase())
+            .collect();
+        edges.insert(req.name.to_lowercase(), targets);
+    }
+
+    let mut cycles_by_model = HashMap::new();
+    for cycle in find_relationship_cycles(&edges) {
+        for member in &cycle {
+            cycles_by_model
+                .entry(member.clone())
+                .or_insert_with(|| cycle.clone());
+        }
+    }
+    Ok(cycles_by_model)
+}
+
+/// Pinned dataset ids that belong to another organization. Upserting one would collide
+/// with that organization's dataset, so they're reported like ids that don't exist.
+async fn foreign_dataset_ids(
+    conn: &mut AsyncPgConnection,
+    organization_id: &Uuid,
+    group: &[&DeployDatasetsRequest],
+) -> Result<HashSet<Uuid>> {
+    let ids: Vec<Uuid> = group.iter().filter_map(|req| req.id).collect();
+    if ids.is_empty() {
+        return Ok(HashSet::new());
+    }
+
+    Ok(datasets::table
+        .filter(datasets::id.eq_any(&ids))
+        .filter(datasets::organization_id.ne(organization_id))
+        .select(datasets::id)
+        .load::<Uuid>(conn)
+        .await?
+        .into_iter()
+        .collect())
+}
+
+/// Upserts a group's validated datasets and their columns. Runs inside the group's
+/// transaction; the id read-back must use the same connection to see the upserted rows.
+/// Returns the columns pruned and the existing datasets whose `enabled` flag changed.
+async fn write_group_datasets(
+    conn: &mut AsyncPgConnection,
+    user_id: &Uuid,
+    organization_id: &Uuid,
+    data_source: &DataSource,
+    data_source_type: &DataSourceType,
+    valid_datasets: &[&DeployDatasetsRequest],
+    dataset_columns_map: &HashMap<String, Vec<&DatasetColumnRecord>>,
+) -> Result<DeployWriteOutcome> {
+    let mut pruned_count = 0;
+    let now = Utc::now();
+
+    // Current `enabled` state of this data source's datasets, keyed by table
+    let existing_enabled: HashMap<String, bool> = datasets::table
+        .filter(datasets::data_source_id.eq(&data_source.id))
+        .filter(datasets::deleted_at.is_null())
+        .select((datasets::database_name, datasets::enabled))
+        .load::<(String, bool)>(conn)
+        .await?
+        .into_iter()
+        .collect();
+
+    // `only_new` datasets never change an existing one
+    let enabled_changes: Vec<(String, bool)> = valid_datasets
+        .iter()
+        .filter(|req| !req.only_new)
+        .filter(|req| {
+            existing_enabled
+                .get(req.table_name())
+                .is_some_and(|enabled| *enabled != req.enabled)
+        })
+        .map(|req| (req.name.clone(), req.enabled))
+        .collect();
+
+    // Prepare datasets for upsert
+    let datasets_to_upsert: Vec<Dataset> = valid_datasets
+        .iter()
+        .map(|req| Dataset {
+            id: req.id.unwrap_or_else(Uuid::new_v4),
+            name: req.name.clone(),
+            data_source_id: data_source.id,
+            created_at: now,
+            updated_at: now,
+            database_name: req.table_name().to_string(),
+            when_to_use: Some(req.when_to_use.as_ref().unwrap_or(&req.description))
+                .map(|when_to_use| when_to_use.trim().to_string())
+                .filter(|when_to_use| !when_to_use.is_empty()),
+            when_not_to_use: None,
+            type_: DatasetType::from_str(&req.type_).unwrap_or(DatasetType::View),
+            definition: req.sql_definition.clone().unwrap_or_else(|| {
+                // Models without SQL read their table, named exactly as the warehouse stores it
+                let (schema, table) = dataset_columns_map
+                    .get(&req.name)
+                    .and_then(|columns| columns.first())
+                    .map(|col| (col.schema_name.as_str(), col.dataset_name.as_str()))
+                    .unwrap_or((req.schema.as_str(), req.table_name()));
+                default_sql_definition(data_source_type, req.database.as_deref(), schema, table)
+            }),
+            schema: req.schema.clone(),
+            enabled: req.enabled,
+            created_by: user_id.clone(),
+            updated_by: user_id.clone(),
+            deleted_at: None,
+            imported: false,
+            organization_id: organization_id.clone(),
+            model: req.model.clone(),
+            yml_file: req.yml_file.clone(),
+            database_identifier: req.database.clone(),
+            refresh_schedule: req.refresh.as_ref().map(|refresh| {
+                json!({ "cron": refresh.cron.trim(), "enabled": refresh.enabled })
+            }),
+            primary_time_dimension: req.primary_time_dimension.clone(),
+            grain: dataset_columns_map
+                .get(&req.name)
+                .and_then(|columns| resolve_grain(req, columns).ok())
+                .filter(|grain| !grain.is_empty())
+                .map(|grain| json!(grain)),
+            label: req.label.clone(),
+        })
+        .collect();
+
+    // `only_new` datasets are inserted with conflicts left to the database, so two deploys
+    // creating the same model can't both write it
+    let only_new_tables: HashSet<&str> = valid_datasets
+        .iter()
+        .filter(|req| req.only_new)
+        .map(|req| req.table_name())
+        .collect();
+    let (only_new, mut datasets_to_upsert): (Vec<Dataset>, Vec<Dataset>) = datasets_to_upsert
+        .into_iter()
+        .partition(|dataset| only_new_tables.contains(dataset.database_name.as_str()));
+
+    let mut created: HashSet<String> = HashSet::new();
+    for batch in only_new.chunks(upsert_batch_size(DATASET_BIND_PARAMS)) {
+        let inserted: Vec<String> = diesel::insert_into(datasets::table)
+            .values(batch)
+            .on_conflict((datasets::database_name, datasets::data_source_id))
+            .do_nothing()
+            .returning(datasets::database_name)
+            .get_results(conn)
+            .await?;
+        created.extend(inserted);
+    }
+
+    // A soft-deleted dataset conflicts too, but is restored like any new model. Its row is
+    // locked so a concurrent deploy can't restore it in between.
+    let conflicting: Vec<&str> = only_new
+        .iter()
+        .map(|dataset| dataset.database_name.as_str())
+        .filter(|table| !created.contains(*table))
+        .collect();
+    let deleted: HashSet<String> = if conflicting.is_empty() {
+        HashSet::new()
+    } else {
+        datasets::table
+            .filter(datasets::data_source_id.eq(&data_source.id))
+            .filter(datasets::database_name.eq_any(conflicting))
+            .filter(datasets::deleted_at.is_not_null())
+            .select(datasets::database_name)
+            .for_update()
+            .load::<String>(conn)
+            .await?
+            .into_iter()
+            .collect()
+    };
+    let (restored, existing): (Vec<Dataset>, Vec<Dataset>) = only_new
+        .into_iter()
+        .filter(|dataset| !created.contains(&dataset.database_name))
+        .partition(|dataset| deleted.contains(&dataset.database_name));
+    datasets_to_upsert.extend(restored);
+
+    // `only_new` datasets that already exist aren't written at all, not even their columns
+    let (skipped, valid_datasets): (Vec<&DeployDatasetsRequest>, Vec<&DeployDatasetsRequest>) =
+        valid_datasets.iter().copied().partition(|req| {
+            existing
+                .iter()
+                .any(|dataset| dataset.database_name == req.table_name())
+        });
+    let valid_datasets = valid_datasets.as_slice();
+
+    // Bulk upsert datasets, in batches that stay under the bind parameter limit
+    for batch in datasets_to_upsert.chunks(upsert_batch_size(DATASET_BIND_PARAMS)) {
+        diesel::insert_into(datasets::table)
+            .values(batch)
+            .on_conflict((datasets::database_name, datasets::data_source_id))
+            .do_update()
+            .set((
+                datasets::updated_at.eq(excluded(datasets::updated_at)),
+                datasets::updated_by.eq(excluded(datasets::updated_by)),
+                datasets::definition.eq(excluded(datasets::definition)),
+                datasets::when_to_use.eq(excluded(datasets::when_to_use)),
+                datasets::model.eq(excluded(datasets::model)),
+                datasets::yml_file.eq(excluded(datasets::yml_file)),
+                datasets::database_identifier.eq(excluded(datasets::database_identifier)),
+                datasets::refresh_schedule.eq(excluded(datasets::refresh_schedule)),
+                datasets::primary_time_dimension.eq(excluded(datasets::primary_time_dimension)),
+                datasets::grain.eq(excluded(datasets::grain)),
+                datasets::label.eq(excluded(datasets::label)),
+                datasets::type_.eq(excluded(datasets::type_)),
+                datasets::schema.eq(excluded(datasets::schema)),
+                datasets::name.eq(excluded(datasets::name)),
+                datasets::enabled.eq(excluded(datasets::enabled)),
+                datasets::deleted_at.eq(None::<DateTime<Utc>>),
+            ))
+            .execute(conn)
+            .await?;
+    }
+
+    // Get the dataset IDs after upsert for column operations
+    let dataset_ids: HashMap<String, Uuid> = datasets::table
+        .filter(datasets::data_source_id.eq(&data_source.id))
+        .filter(datasets::database_name.eq_any(valid_datasets.iter().map(|req| req.table_name())))
+        .filter(datasets::deleted_at.is_null())
+        .select((datasets::database_name, datasets::id))
+        .load::<(String, Uuid)>(conn)
+        .await?
+        .into_iter()
+        .collect();
+
+    // Bulk upsert columns for each dataset
+    for req in valid_datasets {
+        let dataset_id = match dataset_ids.get(req.table_name()) {
+            Some(id) => *id,
+            None => {
+                tracing::error!(
+                    "Dataset ID not found after upsert for {}.{}",
+                    req.schema,
+                    req.name
+                );
+                continue;
+            }
+        };
+
+        // Warehouse positions, keyed by lowercased column name
+        let ordinals: HashMap<String, i32> = dataset_columns_map
+            .get(&req.name)
+            .map(|cols| {
+                cols.iter()
+                    .map(|col| (col.name.to_lowercase(), col.ordinal))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        pruned_count += upsert_dataset_columns(
+            conn,
+            organization_id,
+            dataset_id,
+            req.column_delete_mode,
+            &req.columns,
+            &req.skipped_columns,
+            &ordinals,
+            data_source_type,
+            now,
+        )
+        .await?;
+    }
+
+    Ok(DeployWriteOutcome {
+        pruned_count,
+        enabled_changes,
+        skipped: skipped.iter().map(|req| req.name.clone()).collect(),
+        dataset_ids,
+        ..Default::default()
+    })
+}
+
+/// Comment statements sent to a warehouse at once with `write_comments`.
+const COMMENT_WRITE_CONCURRENCY: usize = 4;
+
+/// With `write_comments`, writes each deployed model's descriptions to its warehouse table
+/// and columns as comments. The datasets are already written, so a failure only adds a
+/// warning to the model's result.
+async fn write_warehouse_comments(
+    data_source_type: &DataSourceType,
+    case_sensitive: bool,
+    valid_datasets: &[&DeployDatasetsRequest],
+    dataset_ids: &HashMap<String, Uuid>,
+    dataset_columns_map: &HashMap<String, Vec<&DatasetColumnRecord>>,
+    results: &mut [ValidationResult],
+) {
+    for req in valid_datasets.iter().filter(|req| req.write_comments) {
+        let Some(dataset_id) = dataset_ids.get(req.table_name()) else {
+            continue;
+        };
+        let warehouse_columns = dataset_columns_map
+            .get(&req.name)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        let (schema, table) = warehouse_columns
+            .first()
+            .map(|col| (col.schema_name.as_str(), col.dataset_name.as_str()))
+            .unwrap_or((req.schema.as_str(), req.table_name()));
+        let object_type = warehouse_columns
+            .first()
+            .map_or(DatasetObjectType::Table, |col| col.object_type());
+        let column_descriptions = comment_columns(&req.columns, warehouse_columns, case_sensitive);
+
+        let table_name = format!("{}.{}", schema, table);
+        let failure = match comment_statements(
+            data_source_type,
+            req.database.as_deref(),
+            schema,
+            table,
+            object_type,
+            &req.description,
+            &column_descriptions,
+        ) {
+            None => Some(format!("{:?} doesn't support comments", data_source_type)),
+            Some(statements) => {
+                let failures: Vec<String> = stream::iter(statements)
+                    .map(|sql| async move {
+                        write_query_engine(dataset_id, &sql)
+                            .await
+                            .err()
+                            .map(|e| e.to_string())
+                    })
+                    .buffer_unordered(COMMENT_WRITE_CONCURRENCY)
+                    .filter_map(|failure| async move { failure })
+                    .collect()
+                    .await;
+                let mut reasons: Vec<String> = Vec::new();
+                for failure in failures {
+                    if !reasons.contains(&failure) {
+                        reasons.push(failure);
+                    }
+                }
+                (!reasons.is_empty()).then(|| reasons.join("; "))
+            }
+        };
+
+        // Each model's own table gets its own warning, even when models share a name
+        if let Some(reason) = failure {
+            tracing::warn!("Failed to write comments to '{}': {}", table_name, reason);
+            if let Some(validation) = results.iter_mut().rev().find(|validation| {
+                validation.model_name == req.name
+                    && validation.data_source_name == req.data_source_name
+                    && validation.schema == req.schema
+            }) {
+                validation.add_warning(ValidationError::comment_write_failed(&table_name, &reason));
+            }
+        }
+    }
+}
+
+/// The warehouse columns to comment, named as the warehouse stores them, with their model
+/// description. Only columns whose `expr` is the bare (or quoted) column name map to one,
+/// the first such model column wins, and comments the warehouse already has are left alone.
+fn comment_columns<'a>(
+    columns: &'a [DeployDatasetsColumnsRequest],
+    warehouse_columns: &'a [&DatasetColumnRecord],
+    case_sensitive: bool,
+) -> Vec<(&'a str, &'a str)> {
+    let mut commented: Vec<(&str, &str)> = Vec::new();
+    for col in columns {
+        let Some(warehouse_column) =
+            expr_warehouse_column(col, warehouse_columns, case_sensitive)
+        else {
+            continue;
+        };
+        if commented.iter().any(|(name, _)| *name == warehouse_column.name) {
+            continue;
+        }
+        let unchanged = warehouse_column.comment.as_deref().map(str::trim)
+            == Some(col.description.trim());
+        if !unchanged {
+            commented.push((warehouse_column.name.as_str(), col.description.as_str()));
+        }
+    }
+    commented
+}
+
+/// The warehouse column a model column reads, when its `expr` (or, without one, its name)
+/// is the bare or quoted column name.
+fn expr_warehouse_column<'a>(
+    col: &DeployDatasetsColumnsRequest,
+    warehouse_columns: &[&'a DatasetColumnRecord],
+    case_sensitive: bool,
+) -> Option<&'a DatasetColumnRecord> {
+    let column_name = col
+        .expr
+        .as_deref()
+        .unwrap_or(&col.name)
+        .trim()
+        .trim_matches(|c| c == '"' || c == '`');
+    warehouse_columns
+        .iter()
+        .find(|wc| identifiers_match(&wc.name, column_name, case_sensitive))
+        .copied()
+}
+
+/// Attempts at retrieving a group's warehouse columns before giving up, waiting 1s, then
+/// 2s between them.
+const COLUMN_FETCH_ATTEMPTS: u32 = 3;
+
+/// Whether schema and table names must match the warehouse's casing exactly. Most
+/// warehouses fold unquoted identifiers, so names are compared case-insensitively, but
+/// BigQuery table names are case-sensitive. A data source's `case_sensitive_identifiers`
+/// overrides the dialect, for teams using quoted mixed-case identifiers.
+fn identifiers_case_sensitive(
+    data_source: &DataSource,
+    data_source_type: &DataSourceType,
+) -> bool {
+    data_source
+        .case_sensitive_identifiers
+        .unwrap_or(matches!(data_source_type, DataSourceType::BigQuery))
+}
+
+/// Whether column names in expressions must match the warehouse's casing exactly. Only
+/// when the data source says so: BigQuery's column names are case-insensitive even though
+/// its table names aren't.
+fn column_identifiers_case_sensitive(data_source: &DataSource) -> bool {
+    data_source.case_sensitive_identifiers.unwrap_or(false)
+}
+
+/// Tables whose metadata query timed out when retried on their own, keyed by
+/// `(table, schema)` as the models name them, with the deadline they ran past.
+type TimedOutTables = HashMap<(String, String), Duration>;
+
+/// Looks up a group's data source and retrieves the warehouse columns for all of its
+/// tables in one batch. The error is reported against every model in the group.
+async fn fetch_group_columns(
+    organization_id: &Uuid,
+    data_source_name: &str,
+    env: &str,
+    database: Option<String>,
+    group: &[&DeployDatasetsRequest],
+) -> Result<
+    (DataSource, DataSourceType, Vec<DatasetColumnRecord>, TimedOutTables),
+    ValidationError,
+> {
+    let mut conn = get_pg_pool().get().await.map_err(|e| {
+        ValidationError::data_source_error(format!("Failed to get database connection: {}", e))
+    })?;
+
+    // Get data source. Names are expected to be unique per env, but nothing enforces it,
+    // so more than one match is reported instead of picking one arbitrarily.
+    let mut matching_data_sources = match data_sources::table
+        .filter(data_sources::name.eq(data_source_name))
+        .filter(data_sources::env.eq(env))
+        .filter(data_sources::organization_id.eq(organization_id))
+        .filter(data_sources::deleted_at.is_null())
+        .select(data_sources::all_columns)
+        .load::<DataSource>(&mut conn)
+        .await
+    {
+        Ok(data_sources) => data_sources,
+        Err(e) => {
+            return Err(ValidationError::data_source_error(format!(
+                "Failed to look up data source '{}': {}",
+                data_source_name, e
+            )))
+        }
+    };
+
+    let data_source = match matching_data_sources.len() {
+        0 => {
+            return Err(ValidationError::data_source_error(format!(
+                "Data source '{}' not found",
+                data_source_name
+            )))
+        }
+        1 => matching_data_sources.remove(0),
+        _ => {
+            let ids: Vec<Uuid> = matching_data_sources.iter().map(|ds| ds.id).collect();
+            tracing::error!(
+                "Ambiguous data source '{}' in env '{}': {:?}",
+                data_source_name,
+                env,
+                ids
+            );
+            return Err(ValidationError::ambiguous_data_source(data_source_name, env, &ids));
+        }
+    };
+
+    // Get credentials for the data source
+    let autodetect = group.iter().any(|req| req.dialect_autodetect);
+    let data_source_type = resolve_data_source_type(&data_source, autodetect).await;
+    let credentials =
+        match get_data_source_credentials(&data_source, &data_source_type, false).await {
+            Ok(creds) => creds,
+            Err(e) => {
+                return Err(ValidationError::data_source_error(format!(
+                    "Failed to get data source credentials: {}",
+                    e
+                )))
+            }
+        };
+
+    // Prepare tables for batch validation
+    let tables_to_validate: Vec<(String, String)> = group
+        .iter()
+        .map(|req| (req.table_name().to_string(), req.schema.clone()))
+        .collect();
+
+    tracing::info!(
+        "Validating tables for data source '{:?}.{:?}': {:?}",
+        data_source_name,
+        database,
+        tables_to_validate
+    );
+
+    // Get all columns in one batch - this acts as our validation. Connection problems are
+    // often transient, so they're retried with backoff; rejected credentials and timeouts
+    // are not.
+    let mut attempt = 1;
+    let retrieved = loop {
+        match retrieve_dataset_columns_batch(&tables_to_validate, &credentials, database.clone())
+            .await
+        {
+            Err(e)
+                if attempt < COLUMN_FETCH_ATTEMPTS
+                    && !is_warehouse_auth_error(&e)
+                    && warehouse_timeout(&e).is_none() =>
+            {
+                let delay = Duration::from_secs(1 << (attempt - 1));
+                tracing::warn!(
+                    "Attempt {} to retrieve columns for data source '{}' failed, retrying in {:?}: {:?}",
+                    attempt,
+                    data_source_name,
+                    delay,
+                    e
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            result => break result,
+        }
+    };
+
+    // One bad table name can fail the whole batch query, so each table is retried on its
+    // own. Tables that still fail are left out and reported as not found, or as timed out
+    // when that's why they failed.
+    let mut timed_out = TimedOutTables::new();
+    let retrieved = match retrieved {
+        Err(e)
+            if tables_to_validate.len() > 1
+                && !is_warehouse_auth_error(&e)
+                && warehouse_timeout(&e).is_none() =>
+        {
+            tracing::warn!(
+                "Batch column retrieval for data source '{}' failed, retrying each table on its own: {:?}",
+                data_source_name,
+                e
+            );
+            retrieve_columns_per_table(
+                &tables_to_validate,
+                &credentials,
+                database.clone(),
+                &mut timed_out,
+            )
+            .await
+            .ok_or(e)
+        }
+        retrieved => retrieved,
+    };
+
+    match retrieved {
+        Ok(cols) => {
+            // Add debug logging
+            tracing::info!(
+                "Retrieved {} columns for data source '{}'. Tables found: {:?}",
+                cols.len(),
+                data_source_name,
+                cols.iter()
+                    .map(|c| format!("{}.{}", c.schema_name, c.dataset_name))
+                    .collect::<HashSet<_>>()
+            );
+            Ok((data_source, data_source_type, cols, timed_out))
+        }
+        Err(e) if is_warehouse_auth_error(&e) => {
+            tracing::error!(
+                "Warehouse rejected credentials for data source '{}': {:?}",
+                data_source_name,
+                e
+            );
+            Err(ValidationError::warehouse_auth_failed(data_source_name, &e.to_string()))
+        }
+        Err(e) => {
+            if let Some(timeout) = warehouse_timeout(&e) {
+                tracing::error!(
+                    "Timed out retrieving columns for data source '{}': {:?}",
+                    data_source_name,
+                    e
+                );
+                return Err(ValidationError::warehouse_timeout(data_source_name, timeout.after));
+            }
+            tracing::error!(
+                "Error retrieving columns for data source '{}': {:?}",
+                data_source_name,
+                e
+            );
+            Err(ValidationError::data_source_error(format!(
+                "Failed to get columns from data source: {}",
+                e
+            )))
+        }
+    }
+}
+
+/// Retrieves columns one table at a time, skipping tables whose query fails and recording
+/// those that timed out in `timed_out`. `None` when every table fails, since then the data
+/// source itself is the problem.
+async fn retrieve_columns_per_table(
+    tables: &[(String, String)],
+    credentials: &Credential,
+    database: Option<String>,
+    timed_out: &mut TimedOutTables,
+) -> Option<Vec<DatasetColumnRecord>> {
+    let mut columns = Vec::new();
+    let mut any_succeeded = false;
+
+    for table in tables {
+        match retrieve_dataset_columns_batch(
+            std::slice::from_ref(table),
+            credentials,
+            database.clone(),
+        )
+        .await
+        {
+            Ok(table_columns) => {
+                any_succeeded = true;
+                columns.extend(table_columns);
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to retrieve columns for table '{}.{}': {:?}",
+                    table.1,
+                    table.0,
+                    e
+                );
+                if let Some(timeout) = warehouse_timeout(&e) {
+                    timed_out.insert(table.clone(), timeout.after);
+                }
+            }
+        }
+    }
+
+    any_succeeded.then_some(columns)
+}
+
+/// Sends any results not yet streamed to the progress channel, if there is one.
+async fn stream_results(
+    progress: Option<&mpsc::Sender<ValidationResult>>,
+    results: &[ValidationResult],
+    streamed: &mut usize,
+) {
+    if let Some(tx) = progress {
+        for result in &results[*streamed..] {
+            // A dropped receiver only means the client went away; the deploy still completes
+            let _ = tx.send(result.clone()).await;
+        }
+    }
+    *streamed = results.len();
+}
+
+/// Deletes columns that are no longer in the request and upserts the rest, returning how
+/// many columns were pruned. Hard deletes also clear out columns soft-deleted by earlier
+/// deploys, and the stored values of every column they remove.
+///
+/// Rows are matched on `(dataset_id, name)`, so the fresh ids generated here are only
+/// used for brand-new columns. The update branch deliberately leaves the
+/// `stored_values*` fields alone: an unchanged searchable column keeps its synced
+/// values, status and count across re-deploys.
+///
+/// Columns whose expression (or name) is a physical column get that column's warehouse
+/// position as their `ordinal`; computed columns are left unordered.
+async fn upsert_dataset_columns(
+    conn: &mut AsyncPgConnection,
+    organization_id: &Uuid,
+    dataset_id: Uuid,
+    delete_mode: ColumnDeleteMode,
+    requested_columns: &[DeployDatasetsColumnsRequest],
+    skipped_columns: &[String],
+    ordinals: &HashMap<String, i32>,
+    data_source_type: &DataSourceType,
+    now: DateTime<Utc>,
+) -> Result<usize> {
+    let columns: Vec<DatasetColumn> = requested_columns
+        .iter()
+        .map(|col| DatasetColumn {
+            id: Uuid::new_v4(),
+            dataset_id,
+            name: col.name.clone(),
+            type_: col.type_.clone().unwrap_or_else(|| "text".to_string()),
+            description: Some(col.description.clone()),
+            nullable: true,
+            created_at: now,
+            updated_at: now,
+            deleted_at: None,
+            stored_values: None,
+            stored_values_status: None,
+            stored_values_error: None,
+            stored_values_count: None,
+            stored_values_last_synced: None,
+            semantic_type: col.semantic_type.clone(),
+            dim_type: col.type_.clone(),
+            expr: column_expr(col, requested_columns, data_source_type),
+            ordinal: col
+                .expr
+                .as_deref()
+                .and_then(|expr| ordinals.get(&expr.trim().to_lowercase()))
+                .or_else(|| ordinals.get(&col.name.to_lowercase()))
+                .copied(),
+            hidden: col.hidden,
+            label: col.label.clone(),
+        })
+        .collect();
+
+    // Get current column names
+    let current_column_names: HashSet<String> = dataset_columns::table
+        .filter(dataset_columns::dataset_id.eq(dataset_id))
+        .filter(dataset_columns::deleted_at.is_null())
+        .select(dataset_columns::name)
+        .load::<String>(conn)
+        .await?
+        .into_iter()
+        .collect();
+
+    // Get new column names; skipped columns count as kept so they aren't deleted
+    let new_column_names: HashSet<String> = columns
+        .iter()
+        .map(|c| c.name.clone())
+        .chain(skipped_columns.iter().cloned())
+        .collect();
+
+    // Delete removed columns
+    let columns_to_delete: Vec<String> = current_column_names
+        .difference(&new_column_names)
+        .cloned()
+        .collect();
+
+    let mut pruned_count = 0;
+    match delete_mode {
+        ColumnDeleteMode::Soft if !columns_to_delete.is_empty() => {
+            pruned_count = diesel::update(dataset_columns::table)
+                .filter(dataset_columns::dataset_id.eq(dataset_id))
+                .filter(dataset_columns::name.eq_any(&columns_to_delete))
+                .filter(dataset_columns::deleted_at.is_null())
+                .set(dataset_columns::deleted_at.eq(now))
+                .execute(conn)
+                .await?;
+        }
+        ColumnDeleteMode::Soft => (),
+        ColumnDeleteMode::Hard => {
+            let removed: Vec<(Uuid, Option<bool>)> = dataset_columns::table
+                .filter(dataset_columns::dataset_id.eq(dataset_id))
+                .filter(dataset_columns::name.ne_all(&new_column_names))
+                .select((dataset_columns::id, dataset_columns::stored_values))
+                .load(conn)
+                .await?;
+            let with_values: Vec<Uuid> = removed
+                .iter()
+                .filter(|(_, stored_values)| *stored_values == Some(true))
+                .map(|(id, _)| *id)
+                .collect();
+            if !with_values.is_empty() {
+                delete_column_values(conn, organization_id, &with_values).await?;
+            }
+
+            diesel::delete(dataset_columns::table)
+                .filter(dataset_columns::id.eq_any(removed.iter().map(|(id, _)| *id)))
+                .execute(conn)
+                .await?;
+            pruned_count = columns_to_delete.len();
+        }
+    }
+
+    // Bulk upsert columns, in batches that stay under the bind parameter limit
+    for batch in columns.chunks(upsert_batch_size(DATASET_COLUMN_BIND_PARAMS)) {
+        diesel::insert_into(dataset_columns::table)
+            .values(batch)
+            .on_conflict((dataset_columns::dataset_id, dataset_columns::name))
+            .do_update()
+            .set((
+                dataset_columns::type_.eq(excluded(dataset_columns::type_)),
+                dataset_columns::description.eq(excluded(dataset_columns::description)),
+                dataset_columns::semantic_type.eq(excluded(dataset_columns::semantic_type)),
+                dataset_columns::dim_type.eq(excluded(dataset_columns::dim_type)),
+                dataset_columns::expr.eq(excluded(dataset_columns::expr)),
+                dataset_columns::ordinal.eq(excluded(dataset_columns::ordinal)),
+                dataset_columns::hidden.eq(excluded(dataset_columns::hidden)),
+                dataset_columns::label.eq(excluded(dataset_columns::label)),
+                dataset_columns::updated_at.eq(now),
+                dataset_columns::deleted_at.eq(None::<DateTime<Utc>>),
+            ))
+            .execute(conn)
+            .await?;
+    }
+
+    Ok(pruned_count)
+}
+
+/// Rows per bulk upsert statement for rows of `params_per_row` values. Set with
+/// `DEPLOY_UPSERT_BATCH_SIZE`; capped so a batch never exceeds Postgres's bind parameter
+/// limit.
+fn upsert_batch_size(params_per_row: usize) -> usize {
+    batch_size(
+        std::env::var("DEPLOY_UPSERT_BATCH_SIZE").ok().as_deref(),
+        params_per_row,
+    )
+}
+
+fn batch_size(configured: Option<&str>, params_per_row: usize) -> usize {
+    configured
+        .and_then(|size| size.trim().parse::<usize>().ok())
+        .filter(|size| *size > 0)
+        .unwrap_or(DEFAULT_UPSERT_BATCH_SIZE)
+        .min(MAX_BIND_PARAMS / params_per_row)
+}
+
+/// The expression stored for a column. Measure filters are applied inside the aggregate
+/// as `CASE WHEN filter THEN expr END`, which every dialect supports, and percentile
+/// measures are expanded into the dialect's quantile aggregate, so the query engine can
+/// use them as-is. Ratio measures are built from the aggregates of the `columns` they
+/// divide.
+fn column_expr(
+    col: &DeployDatasetsColumnsRequest,
+    columns: &[DeployDatasetsColumnsRequest],
+    data_source_type: &DataSourceType,
+) -> Option<String> {
+    if col.agg.as_deref() == Some("ratio") {
+        let ratio = col.ratio.as_ref()?;
+        let aggregate = |name: &str| {
+            let measure = columns.iter().find(|measure| measure.name == name)?;
+            aggregate_expr(measure, data_source_type)
+        };
+        return Some(format!(
+            "{} / NULLIF({}, 0)",
+            aggregate(&ratio.numerator)?,
+            aggregate(&ratio.denominator)?
+        ));
+    }
+
+    let expr = match (col.filters.as_deref().map(str::trim), col.expr.as_deref()) {
+        (Some(filter), Some(expr)) if !filter.is_empty() => {
+            Some(format!("CASE WHEN ({}) THEN {} END", filter, expr))
+        }
+        _ => col.expr.clone(),
+    };
+
+    match (col.agg.as_deref(), col.percentile, expr.as_deref()) {
+        (Some("percentile"), Some(percentile), Some(filtered)) => {
+            percentile_expr(data_source_type, filtered, percentile).or(expr)
+        }
+        _ => expr,
+    }
+}
+
+/// A measure's aggregate with its filter applied, as a ratio measure divides it, or `None`
+/// for measures without an aggregation.
+fn aggregate_expr(
+    col: &DeployDatasetsColumnsRequest,
+    data_source_type: &DataSourceType,
+) -> Option<String> {
+    let expr = column_expr(col, &[], data_source_type)?;
+    let aggregate = match col.agg.as_deref()?.to_lowercase().as_str() {
+        "sum" => format!("SUM({})", expr),
+        "avg" => format!("AVG({})", expr),
+        "count" => format!("COUNT({})", expr),
+        "count_distinct" => format!("COUNT(DISTINCT {})", expr),
+        "min" => format!("MIN({})", expr),
+        "max" => format!("MAX({})", expr),
+        // Already expanded into the dialect's quantile aggregate
+        "percentile" => expr,
+        _ => return None,
+    };
+    Some(aggregate)
+}
+
+/// Maps a percentile aggregate onto the dialect's quantile function, or `None` when the
+/// warehouse has no aggregate (non-window) form of it.
+fn percentile_expr(data_source_type: &DataSourceType, expr: &str, percentile: f64) -> Option<String> {
+    match data_source_type {
+        DataSourceType::Postgres | DataSourceType::Supabase | DataSourceType::Redshift => Some(
+            format!("PERCENTILE_CONT({}) WITHIN GROUP (ORDER BY {})", percentile, expr),
+        ),
+        DataSourceType::Snowflake => Some(format!("APPROX_PERCENTILE({}, {})", expr, percentile)),
+        DataSourceType::Databricks => Some(format!("PERCENTILE_APPROX({}, {})", expr, percentile)),
+        DataSourceType::BigQuery => Some(format!(
+            "APPROX_QUANTILES({}, 1000)[OFFSET({})]",
+            expr,
+            (percentile * 1000.0).round() as i64
+        )),
+        DataSourceType::MySql | DataSourceType::Mariadb | DataSourceType::SqlServer => None,
+    }
+}
+
+/// Checks that a dataset's refresh schedule, if any, is a parseable cron expression.
+fn validate_refresh_schedule(req: &DeployDatasetsRequest) -> Option<ValidationError> {
+    let refresh = req.refresh.as_ref()?;
+    let cron = refresh.cron.trim();
+
+    if cron.is_empty() {
+        return Some(ValidationError::invalid_refresh_schedule(
+            cron,
+            "expression is empty",
+        ));
+    }
+
+    match croner::Cron::new(cron).parse() {
+        Ok(_) => None,
+        Err(e) => Some(ValidationError::invalid_refresh_schedule(
+            cron,
+            &e.to_string(),
+        )),
+    }
+}
+
+/// Checks that a dataset's primary time dimension names one of its dimensions, and that
+/// the dimension holds dates or timestamps.
+fn validate_primary_time_dimension(req: &DeployDatasetsRequest) -> Option<ValidationError> {
+    let name = req.primary_time_dimension.as_deref()?;
+
+    let Some(dimension) = req
+        .columns
+        .iter()
+        .filter(|col| col.semantic_type.as_deref() == Some("dimension"))
+        .find(|col| col.name == name)
+    else {
+        return Some(ValidationError::column_not_found(name));
+    };
+
+    let type_ = dimension.type_.as_deref().unwrap_or_default();
+    if is_time_type(type_) {
+        None
+    } else {
+        Some(ValidationError::type_mismatch(name, "timestamp", type_))
+    }
+}
+
+/// Distinct values a numeric dimension may have and still be `searchable`.
+const SEARCHABLE_MAX_NUMERIC_VALUES: usize = 1000;
+
+/// A deployed column's `expr` and whether it's enrolled in stored values.
+type DeployedSearchable = (Option<String>, bool);
+
+/// The model's previously deployed columns by name, or none when it hasn't been deployed.
+async fn deployed_searchable_columns(
+    conn: &mut AsyncPgConnection,
+    data_source: &DataSource,
+    req: &DeployDatasetsRequest,
+) -> Result<HashMap<String, DeployedSearchable>> {
+    let dataset_id = datasets::table
+        .filter(datasets::data_source_id.eq(&data_source.id))
+        .filter(datasets::database_name.eq(req.table_name()))
+        .filter(datasets::deleted_at.is_null())
+        .select(datasets::id)
+        .first::<Uuid>(conn)
+        .await
+        .optional()?;
+    let Some(dataset_id) = dataset_id else {
+        return Ok(HashMap::new());
+    };
+
+    let columns: Vec<(String, Option<String>, Option<bool>)> = dataset_columns::table
+        .filter(dataset_columns::dataset_id.eq(dataset_id))
+        .filter(dataset_columns::deleted_at.is_null())
+        .select((
+            dataset_columns::name,
+            dataset_columns::expr,
+            dataset_columns::stored_values,
+        ))
+        .load(conn)
+        .await?;
+
+    Ok(columns
+        .into_iter()
+        .map(|(name, expr, stored_values)| (name, (expr, stored_values == Some(true))))
+        .collect())
+}
+
+/// The searchable dimensions that weren't enrolled when the model was last deployed, or
+/// whose `expr` has changed since. Unchanged ones were checked by an earlier deploy, so
+/// their values aren't counted again.
+fn changed_searchable_columns<'a>(
+    req: &'a DeployDatasetsRequest,
+    deployed: &HashMap<String, DeployedSearchable>,
+) -> Vec<&'a DeployDatasetsColumnsRequest> {
+    req.columns
+        .iter()
+        .filter(|col| {
+            (col.stored_values || col.searchable)
+                && col.semantic_type.as_deref() == Some("dimension")
+        })
+        .filter(|col| match deployed.get(&col.name) {
+            Some((expr, enrolled)) => !enrolled || expr.as_deref() != col.expr.as_deref(),
+            None => true,
+        })
+        .collect()
+}
+
+/// A warning for each changed dimension that only its `searchable` flag enrolls in stored
+/// values, since that syncs every one of its distinct values from the warehouse.
+fn searchable_enrollment_warnings(
+    changed: &[&DeployDatasetsColumnsRequest],
+    deployed: &HashMap<String, DeployedSearchable>,
+) -> Vec<ValidationError> {
+    changed
+        .iter()
+        .filter(|col| col.searchable && !col.stored_values)
+        .filter(|col| !deployed.get(&col.name).is_some_and(|(_, enrolled)| *enrolled))
+        .map(|col| ValidationError::searchable_enrolls_stored_values(&col.name))
+        .collect()
+}
+
+/// Checks that `searchable` is only set on dimensions stored values can enumerate: strings,
+/// booleans, and numerics with at most `SEARCHABLE_MAX_NUMERIC_VALUES` distinct values.
+/// Dates, timestamps and JSON are rejected outright; numerics, and types that aren't
+/// recognized, are counted in the warehouse column their `expr` reads.
+async fn validate_searchable_columns(
+    data_source: &DataSource,
+    searchable: &[&DeployDatasetsColumnsRequest],
+    warehouse_columns: &[&DatasetColumnRecord],
+    case_sensitive: bool,
+) -> Option<ValidationError> {
+    for col in searchable {
+        let type_ = col.type_.as_deref().unwrap_or_default();
+        let base_type = type_.split('(').next().unwrap_or_default().trim();
+        match StandardType::from_str(base_type) {
+            StandardType::Text | StandardType::Boolean => continue,
+            StandardType::Date | StandardType::Timestamp | StandardType::Json => {
+                return Some(ValidationError::searchable_not_supported(
+                    &col.name,
+                    &format!("it is a {} dimension", type_),
+                ));
+            }
+            _ => (),
+        }
+
+        let Some(column) = expr_warehouse_column(col, warehouse_columns, case_sensitive) else {
+            return Some(ValidationError::searchable_not_supported(
+                &col.name,
+                "it isn't a string or boolean, nor a table column whose values can be counted",
+            ));
+        };
+
+        match distinct_values_exceed(
+            data_source,
+            &column.schema_name,
+            &column.dataset_name,
+            &column.name,
+            SEARCHABLE_MAX_NUMERIC_VALUES,
+        )
+        .await
+        {
+            Ok(false) => (),
+            Ok(true) => {
+                return Some(ValidationError::searchable_not_supported(
+                    &col.name,
+                    &format!(
+                        "it has more than {} distinct values",
+                        SEARCHABLE_MAX_NUMERIC_VALUES
+                    ),
+                ))
+            }
+            Err(e) => {
+                return Some(ValidationError::data_source_error(format!(
+                    "Failed to count the values of searchable column '{}': {}",
+                    col.name, e
+                )))
+            }
+        }
+    }
+
+    None
+}
+
+/// With hard deletes and without `force`, rejects a model that drops columns with stored
+/// values, since deleting them also deletes their values. Soft-deleted columns count too,
+/// as hard deletes clear them out along with the rest.
+async fn removed_stored_values_columns(
+    conn: &mut AsyncPgConnection,
+    data_source: &DataSource,
+    req: &DeployDatasetsRequest,
+) -> Result<Option<Vec<ValidationError>>> {
+    if req.column_delete_mode != ColumnDeleteMode::Hard || req.force {
+        return Ok(None);
+    }
+
+    let dataset_id = datasets::table
+        .filter(datasets::data_source_id.eq(&data_source.id))
+        .filter(datasets::database_name.eq(req.table_name()))
+        .filter(datasets::deleted_at.is_null())
+        .select(datasets::id)
+        .first::<Uuid>(conn)
+        .await
+        .optional()?;
+    let Some(dataset_id) = dataset_id else {
+        return Ok(None);
+    };
+
+    let removed: Vec<String> = dataset_columns::table
+        .filter(dataset_columns::dataset_id.eq(dataset_id))
+        .filter(dataset_columns::stored_values.eq(Some(true)))
+        .filter(
+            dataset_columns::name.ne_all(
+                req.columns
+                    .iter()
+                    .map(|col| col.name.as_str())
+                    .chain(req.skipped_columns.iter().map(String::as_str)),
+            ),
+        )
+        .select(dataset_columns::name)
+        .order(dataset_columns::name)
+        .load(conn)
+        .await?;
 
-            // Prepare datasets for upsert
-            let datasets_to_upsert: Vec<Dataset> = valid_datasets
-                .iter()
-                .map(|req| Dataset {
-                    id: req.id.unwrap_or_else(Uuid::new_v4),
-                    name: req.name.clone(),
-                    data_source_id: data_source.id,
-                    created_at: now,
-                    updated_at: now,
-                    database_name: req.name.clone(),
-                    when_to_use: Some(req.description.clone()),
-                    when_not_to_use: None,
-                    type_: DatasetType::View,
-                    definition: req.sql_definition.clone().unwrap_or_default(),
-                    schema: req.schema.clone(),
-                    enabled: true,
-                    created_by: user_id.clone(),
-                    updated_by: user_id.clone(),
-                    deleted_at: None,
-                    imported: false,
-                    organization_id: organization_id.clone(),
-                    model: req.model.clone(),
-                    yml_file: req.yml_file.clone(),
-                    database_identifier: req.database.clone(),
-                })
-                .collect();
+    Ok((!removed.is_empty()).then(|| {
+        removed
+            .iter()
+            .map(|name| ValidationError::stored_values_column_removed(name))
+            .collect()
+    }))
+}
 
-            // Bulk upsert datasets
-            diesel::insert_into(datasets::table)
-                .values(&datasets_to_upsert)
-                .on_conflict((datasets::database_name, datasets::data_source_id))
-                .do_update()
-                .set((
-                    datasets::updated_at.eq(excluded(datasets::updated_at)),
-                    datasets::updated_by.eq(excluded(datasets::updated_by)),
-                    datasets::definition.eq(excluded(datasets::definition)),
-                    datasets::when_to_use.eq(excluded(datasets::when_to_use)),
-                    datasets::model.eq(excluded(datasets::model)),
-                    datasets::yml_file.eq(excluded(datasets::yml_file)),
-                    datasets::schema.eq(excluded(datasets::schema)),
-                    datasets::name.eq(excluded(datasets::name)),
-                    datasets::deleted_at.eq(None::<DateTime<Utc>>),
-                ))
-                .execute(&mut conn)
-                .await?;
+/// Checks a dataset's type is one deploy can write and, when the project restricts them,
+/// one of its allowed types.
+fn validate_dataset_type(req: &DeployDatasetsRequest) -> Option<ValidationError> {
+    let known = ["table", "view", "materialized_view"].map(str::to_string);
+    let allowed = req.allowed_dataset_types.as_deref().unwrap_or(&known);
 
-            // Get the dataset IDs after upsert for column operations
-            let dataset_ids: HashMap<String, Uuid> = datasets::table
-                .filter(datasets::data_source_id.eq(&data_source.id))
-                .filter(datasets::database_name.eq_any(valid_datasets.iter().map(|req| &req.name)))
-                .filter(datasets::deleted_at.is_null())
-                .select((datasets::database_name, datasets::id))
-                .load::<(String, Uuid)>(&mut conn)
-                .await?
-                .into_iter()
-                .collect();
+    let is_allowed = DatasetType::from_str(&req.type_).is_some_and(|type_| {
+        allowed
+            .iter()
+            .any(|allowed| DatasetType::from_str(allowed) == Some(type_))
+    });
+    (!is_allowed).then(|| ValidationError::disallowed_dataset_type(&req.name, &req.type_, allowed))
+}
 
-            // Bulk upsert columns for each dataset
-            for req in valid_datasets {
-                let dataset_id = match dataset_ids.get(&req.name) {
-                    Some(id) => *id,
-                    None => {
-                        tracing::error!(
-                            "Dataset ID not found after upsert for {}.{}",
-                            req.schema,
-                            req.name
-                        );
-                        continue;
-                    }
-                };
+/// Envs every organization can deploy to, besides those listed in `DEPLOY_ALLOWED_ENVS`.
+const KNOWN_ENVS: [&str; 3] = ["dev", "staging", "prod"];
+
+/// Rejects an env outside the known ones and the comma-separated `configured` ones, which
+/// would otherwise only surface as a data source that can't be found.
+fn validate_env(env: &str, configured: Option<&str>) -> Option<ValidationError> {
+    let allowed: Vec<String> = KNOWN_ENVS
+        .into_iter()
+        .chain(configured.unwrap_or_default().split(','))
+        .map(str::trim)
+        .filter(|allowed| !allowed.is_empty())
+        .map(str::to_string)
+        .collect();
 
-                let columns: Vec<DatasetColumn> = req
-                    .columns
-                    .iter()
-                    .map(|col| DatasetColumn {
-                        id: Uuid::new_v4(),
-                        dataset_id,
-                        name: col.name.clone(),
-                        type_: col.type_.clone().unwrap_or_else(|| "text".to_string()),
-                        description: Some(col.description.clone()),
-                        nullable: true,
-                        created_at: now,
-                        updated_at: now,
-                        deleted_at: None,
-                        stored_values: None,
-                        stored_values_status: None,
-                        stored_values_error: None,
-                        stored_values_count: None,
-                        stored_values_last_synced: None,
-                        semantic_type: col.semantic_type.clone(),
-                        dim_type: col.type_.clone(),
-                        expr: col.expr.clone(),
-                    })
-                    .collect();
-
-                // Get current column names
-                let current_column_names: HashSet<String> = dataset_columns::table
-                    .filter(dataset_columns::dataset_id.eq(dataset_id))
-                    .filter(dataset_columns::deleted_at.is_null())
-                    .select(dataset_columns::name)
-                    .load::<String>(&mut conn)
-                    .await?
-                    .into_iter()
-                    .collect();
-
-                // Get new column names
-                let new_column_names: HashSet<String> = columns
+    (!allowed.iter().any(|allowed| allowed == env))
+        .then(|| ValidationError::invalid_env(env, &allowed))
+}
+
+/// Envs of the organization's active data sources named `data_source_name`.
+async fn data_source_envs_of(
+    conn: &mut AsyncPgConnection,
+    organization_id: &Uuid,
+    data_source_name: &str,
+) -> Result<Vec<String>> {
+    let mut envs = data_sources::table
+        .filter(data_sources::name.eq(data_source_name))
+        .filter(data_sources::organization_id.eq(organization_id))
+        .filter(data_sources::deleted_at.is_null())
+        .select(data_sources::env)
+        .load::<String>(conn)
+        .await
+        .map_err(|e| anyhow!("Error looking up envs of data source {}: {}", data_source_name, e))?;
+    envs.sort();
+    envs.dedup();
+    Ok(envs)
+}
+
+/// The error for a model whose env none of the model, buster.yml or `--env` set. The
+/// server doesn't pick one, but names the envs the data source exists in.
+fn missing_env(data_source_name: &str, envs: &[String]) -> ValidationError {
+    match envs {
+        [] => ValidationError::data_source_error(format!(
+            "Data source '{}' not found",
+            data_source_name
+        )),
+        envs => ValidationError::env_required(data_source_name, envs),
+    }
+}
+
+fn is_time_type(type_: &str) -> bool {
+    let type_ = type_.trim().to_lowercase();
+    type_.starts_with("timestamp") || type_ == "datetime" || type_ == "date"
+}
+
+/// Resolves a dataset's grain, a list of dimension or entity names, to the warehouse
+/// columns those fields are built on. Each component must be a plain column of the table
+/// and may appear once. A dataset without a grain resolves to no columns.
+fn resolve_grain(
+    req: &DeployDatasetsRequest,
+    warehouse_columns: &[&DatasetColumnRecord],
+) -> Result<Vec<String>, Vec<ValidationError>> {
+    let Some(grain) = &req.grain else {
+        return Ok(Vec::new());
+    };
+
+    if grain.is_empty() {
+        return Err(vec![ValidationError::invalid_grain(
+            None,
+            "grain must list at least one dimension or entity",
+        )]);
+    }
+
+    let mut errors = Vec::new();
+    let mut resolved: Vec<String> = Vec::new();
+
+    for component in grain {
+        let expr = req
+            .columns
+            .iter()
+            .filter(|col| col.semantic_type.as_deref() == Some("dimension"))
+            .find(|col| &col.name == component)
+            .and_then(|col| col.expr.as_deref())
+            .or_else(|| {
+                req.entity_relationships
                     .iter()
-                    .map(|c| c.name.clone())
-                    .collect();
-
-                // Soft delete removed columns
-                let columns_to_delete: Vec<String> = current_column_names
-                    .difference(&new_column_names)
-                    .cloned()
-                    .collect();
-
-                if !columns_to_delete.is_empty() {
-                    diesel::update(dataset_columns::table)
-                        .filter(dataset_columns::dataset_id.eq(dataset_id))
-                        .filter(dataset_columns::name.eq_any(&columns_to_delete))
-                        .filter(dataset_columns::deleted_at.is_null())
-                        .set(dataset_columns::deleted_at.eq(now))
-                        .execute(&mut conn)
-                        .await?;
+                    .flatten()
+                    .find(|entity| &entity.name == component)
+                    .map(|entity| entity.expr.as_str())
+            });
+
+        let Some(expr) = expr else {
+            errors.push(ValidationError::invalid_grain(
+                Some(component.as_str()),
+                &format!("'{}' is not a dimension or entity of the model", component),
+            ));
+            continue;
+        };
+
+        let column_name = expr.trim().trim_matches('"');
+        match warehouse_columns
+            .iter()
+            .find(|col| col.name.eq_ignore_ascii_case(column_name))
+        {
+            Some(col) if resolved.contains(&col.name) => {
+                errors.push(ValidationError::invalid_grain(
+                    Some(component.as_str()),
+                    &format!("'{}' repeats column '{}'", component, col.name),
+                ));
+            }
+            Some(col) => resolved.push(col.name.clone()),
+            None => errors.push(ValidationError::invalid_grain(
+                Some(component.as_str()),
+                &format!(
+                    "'{}' ({}) does not resolve to a column of {}.{}",
+                    component, expr, req.schema, req.table_name()
+                ),
+            )),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(resolved)
+    } else {
+        Err(errors)
+    }
+}
+
+/// When the deploy opted in, checks the warehouse for rows sharing the same grain.
+async fn verify_grain(
+    data_source: &DataSource,
+    req: &DeployDatasetsRequest,
+    warehouse_columns: &[&DatasetColumnRecord],
+) -> Option<ValidationError> {
+    if !req.verify_grain {
+        return None;
+    }
+
+    let grain = resolve_grain(req, warehouse_columns).ok()?;
+    if grain.is_empty() {
+        return None;
+    }
+
+    match grain_is_unique(data_source, &req.schema, req.table_name(), &grain).await {
+        Ok(true) => None,
+        Ok(false) => Some(ValidationError::invalid_grain(
+            None,
+            &format!(
+                "{}.{} has more than one row for some combination of ({})",
+                req.schema,
+                req.table_name(),
+                grain.join(", ")
+            ),
+        )),
+        Err(e) => Some(ValidationError::data_source_error(format!(
+            "Failed to verify the grain of {}.{}: {}",
+            req.schema, req.table_name(), e
+        ))),
+    }
+}
+
+/// With `strict_schema`, reports the warehouse columns of a dataset's table that the
+/// model doesn't read, so models can be kept in step with their tables.
+fn schema_drift(
+    req: &DeployDatasetsRequest,
+    warehouse_columns: &[&DatasetColumnRecord],
+) -> Option<ValidationError> {
+    if !req.strict_schema {
+        return None;
+    }
+
+    let expressions = req
+        .columns
+        .iter()
+        .flat_map(|col| {
+            [col.expr.as_deref().unwrap_or(&col.name)]
+                .into_iter()
+                .chain(col.filters.as_deref())
+        })
+        .chain(
+            req.entity_relationships
+                .iter()
+                .flatten()
+                .map(|rel| rel.expr.as_str()),
+        )
+        .chain(req.skipped_columns.iter().map(String::as_str));
+
+    let undeclared: Vec<&str> = undeclared_columns(expressions, warehouse_columns)
+        .into_iter()
+        .map(|col| col.name.as_str())
+        .collect();
+
+    if undeclared.is_empty() {
+        None
+    } else {
+        Some(ValidationError::schema_drift(req.table_name(), &undeclared))
+    }
+}
+
+/// Checks a model's descriptions and `when_to_use` render cleanly as markdown: code fences
+/// must be closed, and HTML that could run in the UI is removed from what's stored. Done
+/// here rather than in the CLI, so every client deploying with `lint_descriptions` gets it.
+fn lint_description_markdown(req: &mut DeployDatasetsRequest) -> Vec<ValidationError> {
+    let model_name = req.name.clone();
+    let descriptions = std::iter::once((None, "description", &mut req.description))
+        .chain(
+            req.when_to_use
+                .as_mut()
+                .map(|when_to_use| (None, "when_to_use", when_to_use)),
+        )
+        .chain(
+            req.columns
+                .iter_mut()
+                .map(|col| (Some(col.name.as_str()), "description", &mut col.description)),
+        );
+
+    let mut issues = Vec::new();
+    for (column_name, field, description) in descriptions {
+        if has_unclosed_code_fence(description) {
+            issues.push(ValidationError::malformed_description(
+                column_name,
+                &model_name,
+                field,
+                "has an unclosed code fence",
+            ));
+        }
+        if let Some(sanitized) = sanitize_description(description) {
+            *description = sanitized;
+            issues.push(ValidationError::malformed_description(
+                column_name,
+                &model_name,
+                field,
+                "contained HTML that could run in the UI, which was removed",
+            ));
+        }
+    }
+    issues
+}
+
+/// Warnings for columns deployed without a `type`, which are stored as text whatever the
+/// warehouse holds. Each names the warehouse's own type for the column, or `unknown` for
+/// expressions that aren't a plain column.
+fn defaulted_column_types(
+    req: &DeployDatasetsRequest,
+    warehouse_columns: &[&DatasetColumnRecord],
+) -> Vec<ValidationError> {
+    req.columns
+        .iter()
+        .filter(|col| col.type_.as_deref().map_or(true, |type_| type_.trim().is_empty()))
+        .map(|col| {
+            let source = col.expr.as_deref().unwrap_or(&col.name).trim();
+            let warehouse_type = warehouse_columns
+                .iter()
+                .find(|warehouse_col| warehouse_col.name.eq_ignore_ascii_case(source))
+                .map_or("unknown", |warehouse_col| warehouse_col.type_.as_str());
+            ValidationError::defaulted_column_type(&col.name, warehouse_type)
+        })
+        .collect()
+}
+
+/// Checks measures whose aggregation needs more than an `expr`: ratio measures divide two
+/// other aggregated measures of the dataset, so both must exist, aggregate, and not be
+/// ratios themselves. Percentile measures need a quantile in (0, 1] and a warehouse that
+/// can compute it, and sums and averages need a numeric `type`.
+fn validate_measure_columns(
+    req: &DeployDatasetsRequest,
+    data_source_type: &DataSourceType,
+) -> Option<Vec<ValidationError>> {
+    let mut errors = validate_percentile_measures(req, data_source_type);
+
+    errors.extend(req
+        .columns
+        .iter()
+        .filter(|col| col.agg.as_deref() == Some("ratio"))
+        .flat_map(|col| validate_ratio_measure(col, &req.columns, data_source_type)));
+
+    errors.extend(req
+        .columns
+        .iter()
+        .filter(|col| col.semantic_type.as_deref() == Some("measure"))
+        .filter_map(|col| {
+            let reason = measure_agg_mismatch(col.type_.as_deref()?, col.agg.as_deref()?)?;
+            Some(ValidationError::expression_error(
+                &col.name,
+                col.expr.as_deref().unwrap_or_default(),
+                &reason,
+            ))
+        }));
+
+    if errors.is_empty() {
+        None
+    } else {
+        Some(errors)
+    }
+}
+
+fn validate_ratio_measure(
+    col: &DeployDatasetsColumnsRequest,
+    columns: &[DeployDatasetsColumnsRequest],
+    data_source_type: &DataSourceType,
+) -> Vec<ValidationError> {
+    let expr = match &col.ratio {
+        Some(ratio) => format!("{} / {}", ratio.numerator, ratio.denominator),
+        None => col.expr.clone().unwrap_or_default(),
+    };
+    let error = |message: &str| ValidationError::expression_error(&col.name, &expr, message);
+
+    if col.semantic_type.as_deref() != Some("measure") {
+        return vec![error("ratio aggregation is only valid on measures")];
+    }
+    let Some(ratio) = &col.ratio else {
+        return vec![error("ratio measures need a `ratio` naming the measures they divide")];
+    };
+
+    [&ratio.numerator, &ratio.denominator]
+        .into_iter()
+        .filter_map(|name| {
+            let measure = columns.iter().find(|measure| {
+                measure.name == *name && measure.semantic_type.as_deref() == Some("measure")
+            });
+            match measure {
+                None => Some(error(&format!("ratio references unknown measure '{}'", name))),
+                Some(measure) if measure.agg.as_deref() == Some("ratio") => Some(error(&format!(
+                    "ratio references ratio measure '{}'; ratios must divide aggregated measures",
+                    name
+                ))),
+                Some(measure) if measure.agg.as_deref() == Some("percentile") => {
+                    Some(error(&format!(
+                        "ratio references percentile measure '{}', which is not supported",
+                        name
+                    )))
+                }
+                Some(measure) if aggregate_expr(measure, data_source_type).is_none() => {
+                    Some(error(&format!("ratio references measure '{}', which has no agg", name)))
                 }
+                Some(_) => None,
+            }
+        })
+        .collect()
+}
+
+/// Checks each entity's `expr` only names columns that exist in the warehouse or
+/// dimensions the model declares.
+fn validate_entity_columns(
+    req: &DeployDatasetsRequest,
+    warehouse_columns: &[&DatasetColumnRecord],
+    case_sensitive: bool,
+) -> Vec<ValidationError> {
+    let dimensions: Vec<&str> = req
+        .columns
+        .iter()
+        .filter(|col| col.semantic_type.as_deref() == Some("dimension"))
+        .map(|col| col.name.as_str())
+        .collect();
+
+    req.entity_relationships
+        .iter()
+        .flatten()
+        .flat_map(|entity| {
+            validate_entity_expr(
+                &entity.name,
+                &entity.expr,
+                warehouse_columns,
+                &dimensions,
+                case_sensitive,
+            )
+        })
+        .collect()
+}
+
+/// Checks measure filters: they only apply to row-level measures, and must name columns
+/// that exist in the warehouse.
+fn validate_measure_filters(
+    req: &DeployDatasetsRequest,
+    warehouse_columns: &[&DatasetColumnRecord],
+    data_source_type: &DataSourceType,
+    case_sensitive: bool,
+) -> Option<Vec<ValidationError>> {
+    let mut errors = Vec::new();
+
+    for col in &req.columns {
+        let Some(filter) = col.filters.as_deref() else {
+            continue;
+        };
+        let filter = filter.trim();
+
+        if col.semantic_type.as_deref() != Some("measure") {
+            errors.push(ValidationError::expression_error(
+                &col.name,
+                filter,
+                "filters are only valid on measures",
+            ));
+        } else if col.agg.as_deref() == Some("ratio") {
+            errors.push(ValidationError::expression_error(
+                &col.name,
+                filter,
+                "ratio measures cannot be filtered; filter the measures they reference",
+            ));
+        } else if filter.is_empty() {
+            errors.push(ValidationError::expression_error(
+                &col.name,
+                filter,
+                "filters must not be empty",
+            ));
+        } else {
+            errors.extend(validate_measure_filter(
+                &col.name,
+                filter,
+                warehouse_columns,
+                data_source_type,
+                case_sensitive,
+            ));
+        }
+    }
+
+    if errors.is_empty() {
+        None
+    } else {
+        Some(errors)
+    }
+}
 
-                // Bulk upsert columns
-                diesel::insert_into(dataset_columns::table)
-                    .values(&columns)
-                    .on_conflict((dataset_columns::dataset_id, dataset_columns::name))
-                    .do_update()
-                    .set((
-                        dataset_columns::type_.eq(excluded(dataset_columns::type_)),
-                        dataset_columns::description.eq(excluded(dataset_columns::description)),
-                        dataset_columns::semantic_type.eq(excluded(dataset_columns::semantic_type)),
-                        dataset_columns::dim_type.eq(excluded(dataset_columns::dim_type)),
-                        dataset_columns::expr.eq(excluded(dataset_columns::expr)),
-                        dataset_columns::updated_at.eq(now),
-                        dataset_columns::deleted_at.eq(None::<DateTime<Utc>>),
-                    ))
-                    .execute(&mut conn)
-                    .await?;
+fn validate_percentile_measures(
+    req: &DeployDatasetsRequest,
+    data_source_type: &DataSourceType,
+) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+
+    for col in &req.columns {
+        let expr = col.expr.as_deref().unwrap_or_default();
+        match (col.agg.as_deref(), col.percentile) {
+            (Some("percentile"), None) => errors.push(ValidationError::expression_error(
+                &col.name,
+                expr,
+                "percentile measures require a `percentile` value",
+            )),
+            (Some("percentile"), Some(p)) if !(p > 0.0 && p <= 1.0) => {
+                errors.push(ValidationError::expression_error(
+                    &col.name,
+                    expr,
+                    &format!("percentile must be in (0, 1], got {}", p),
+                ))
+            }
+            (Some("percentile"), Some(p)) => {
+                if percentile_expr(data_source_type, expr, p).is_none() {
+                    errors.push(ValidationError::expression_error(
+                        &col.name,
+                        expr,
+                        &format!(
+                            "{} does not support percentile aggregation",
+                            data_source_type.to_string()
+                        ),
+                    ));
+                }
             }
+            (_, Some(_)) => errors.push(ValidationError::expression_error(
+                &col.name,
+                expr,
+                "`percentile` is only valid with `agg: percentile`",
+            )),
+            _ => {}
         }
     }
 
-    Ok(results)
+    errors
 }
 
 async fn batch_validate_datasets(
@@ -685,7 +2896,7 @@ async fn batch_validate_datasets(
 
         // Get credentials
         let credentials =
-            match get_data_source_credentials(&data_source.secret_id, &data_source.type_, false)
+            match get_data_source_credentials(&data_source, &data_source.type_, false)
                 .await
             {
                 Ok(creds) => creds,
@@ -711,16 +2922,22 @@ async fn batch_validate_datasets(
             match retrieve_dataset_columns_batch(&tables_to_validate, &credentials, database).await {
                 Ok(cols) => cols,
                 Err(e) => {
+                    let error = match warehouse_timeout(&e) {
+                        Some(timeout) => {
+                            ValidationError::warehouse_timeout(&data_source_name, timeout.after)
+                        }
+                        None => ValidationError::data_source_error(format!(
+                            "Failed to get columns from data source: {}",
+                            e
+                        )),
+                    };
                     for (request, _) in group {
                         failures.push(DatasetValidationFailure {
                             dataset_id: request.dataset_id,
                             name: request.name.clone(),
                             schema: request.schema.clone(),
                             data_source_name: request.data_source_name.clone(),
-                            errors: vec![ValidationError::data_source_error(format!(
-                                "Failed to get columns from data source: {}",
-                                e
-                            ))],
+                            errors: vec![error.clone()],
                         });
                     }
                     continue;
@@ -748,6 +2965,22 @@ async fn batch_validate_datasets(
                 }
             }
 
+            // A pinned id must name a live dataset of the caller's organization, or the
+            // update below would silently touch nothing
+            if let Some(id) = request.dataset_id {
+                let exists = diesel::select(diesel::dsl::exists(
+                    datasets::table
+                        .filter(datasets::id.eq(id))
+                        .filter(datasets::organization_id.eq(organization_id))
+                        .filter(datasets::deleted_at.is_null()),
+                ))
+                .get_result::<bool>(&mut conn)
+                .await?;
+                if !exists {
+                    validation_errors.push(ValidationError::dataset_not_found(&id.to_string()));
+                }
+            }
+
             if validation_errors.is_empty() {
                 // Create or update dataset
                 match create_or_update_dataset(request, &organization_id, user_id).await {
@@ -801,8 +3034,10 @@ async fn create_or_update_dataset(
     let dataset_id = match request.dataset_id {
         Some(id) => {
             // Update existing dataset
-            diesel::update(datasets::table)
+            let updated = diesel::update(datasets::table)
                 .filter(datasets::id.eq(id))
+                .filter(datasets::organization_id.eq(organization_id))
+                .filter(datasets::deleted_at.is_null())
                 .set((
                     datasets::name.eq(&request.name),
                     datasets::updated_at.eq(now),
@@ -810,6 +3045,9 @@ async fn create_or_update_dataset(
                 ))
                 .execute(&mut conn)
                 .await?;
+            if updated == 0 {
+                return Err(anyhow!("Dataset {} not found", id));
+            }
             id
         }
         None => {
@@ -823,7 +3061,7 @@ async fn create_or_update_dataset(
                 database_name: request.name.clone(),
                 when_to_use: None,
                 when_not_to_use: None,
-                type_: DatasetType::View,
+                type_: DatasetType::from_str(&request.type_).unwrap_or(DatasetType::View),
                 definition: String::new(),
                 schema: request.schema.clone(),
                 enabled: false,
@@ -835,6 +3073,10 @@ async fn create_or_update_dataset(
                 yml_file: None,
                 model: None,
                 database_identifier: None,
+                refresh_schedule: None,
+                primary_time_dimension: None,
+                grain: None,
+                label: None,
             };
 
             diesel::insert_into(datasets::table)
@@ -868,6 +3110,9 @@ async fn create_or_update_dataset(
             semantic_type: col.semantic_type.clone(),
             dim_type: None,
             expr: col.expr.clone(),
+            ordinal: None,
+            hidden: col.hidden,
+            label: col.label.clone(),
         })
         .collect();
 
@@ -890,10 +3135,737 @@ async fn create_or_update_dataset(
         .await?;
 
     // Insert new columns
-    diesel::insert_into(dataset_columns::table)
-        .values(&new_columns)
+    for batch in new_columns.chunks(upsert_batch_size(DATASET_COLUMN_BIND_PARAMS)) {
+        diesel::insert_into(dataset_columns::table)
+            .values(batch)
+            .execute(&mut conn)
+            .await?;
+    }
+
+    Ok(dataset_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{enums::StoredValuesStatus, lib::establish_diesel_connection};
+    use crate::utils::query_engine::import_dataset_columns::column;
+    use dotenv::dotenv;
+
+    fn searchable_column(name: &str) -> DeployDatasetsColumnsRequest {
+        DeployDatasetsColumnsRequest {
+            name: name.to_string(),
+            description: format!("{} column", name),
+            semantic_type: Some("dimension".to_string()),
+            expr: Some(name.to_string()),
+            type_: Some("string".to_string()),
+            agg: None,
+            stored_values: false,
+            searchable: true,
+            percentile: None,
+            filters: None,
+            hidden: false,
+            label: None,
+            ratio: None,
+        }
+    }
+
+    #[test]
+    fn test_percentile_expr_per_dialect() {
+        assert_eq!(
+            percentile_expr(&DataSourceType::Postgres, "latency_ms", 0.9).as_deref(),
+            Some("PERCENTILE_CONT(0.9) WITHIN GROUP (ORDER BY latency_ms)")
+        );
+        assert_eq!(
+            percentile_expr(&DataSourceType::Snowflake, "latency_ms", 0.5).as_deref(),
+            Some("APPROX_PERCENTILE(latency_ms, 0.5)")
+        );
+        assert_eq!(
+            percentile_expr(&DataSourceType::BigQuery, "latency_ms", 0.95).as_deref(),
+            Some("APPROX_QUANTILES(latency_ms, 1000)[OFFSET(950)]")
+        );
+        assert!(percentile_expr(&DataSourceType::MySql, "latency_ms", 0.9).is_none());
+    }
+
+    #[test]
+    fn test_comment_columns_match_expr() {
+        let warehouse_column = |name: &str, comment: Option<&str>| DatasetColumnRecord {
+            comment: comment.map(str::to_string),
+            ..column(name, "text")
+        };
+        let warehouse = [
+            warehouse_column("OrderStatus", None),
+            warehouse_column("amount", None),
+            warehouse_column("region", Some("Sales region")),
+        ];
+        let warehouse: Vec<&DatasetColumnRecord> = warehouse.iter().collect();
+
+        let model_column = |name: &str, expr: &str| DeployDatasetsColumnsRequest {
+            expr: Some(expr.to_string()),
+            ..searchable_column(name)
+        };
+        let columns = vec![
+            // Renamed in the model, so only its expr names the warehouse column
+            model_column("status", "\"OrderStatus\""),
+            model_column("total_amount", "amount"),
+            model_column("amount_copy", "amount"),
+            model_column("region", "region"),
+            model_column("doubled", "amount * 2"),
+            // A model column named like a warehouse column it doesn't read
+            model_column("OrderStatus", "upper(status)"),
+        ];
+
+        assert_eq!(
+            comment_columns(&columns, &warehouse, false),
+            vec![
+                ("OrderStatus", "status column"),
+                ("amount", "total_amount column"),
+                ("region", "region column"),
+            ]
+        );
+
+        let unchanged = [warehouse_column("region", Some("region column"))];
+        let unchanged: Vec<&DatasetColumnRecord> = unchanged.iter().collect();
+        assert!(comment_columns(&columns, &unchanged, false).is_empty());
+
+        // With case-sensitive identifiers, the expr must match the warehouse's casing
+        let columns = vec![model_column("status", "orderstatus"), model_column("total", "amount")];
+        assert_eq!(
+            comment_columns(&columns, &warehouse, true),
+            vec![("amount", "total column")]
+        );
+    }
+
+    #[test]
+    fn test_batch_size() {
+        assert_eq!(batch_size(None, DATASET_COLUMN_BIND_PARAMS), DEFAULT_UPSERT_BATCH_SIZE);
+        assert_eq!(batch_size(Some(" 250 "), DATASET_COLUMN_BIND_PARAMS), 250);
+        assert_eq!(batch_size(Some("0"), DATASET_BIND_PARAMS), DEFAULT_UPSERT_BATCH_SIZE);
+        assert_eq!(batch_size(Some("many"), DATASET_BIND_PARAMS), DEFAULT_UPSERT_BATCH_SIZE);
+
+        // Capped at the bind parameter limit however large the configured size
+        let capped = batch_size(Some("100000"), DATASET_COLUMN_BIND_PARAMS);
+        assert_eq!(capped, 3276);
+        assert!(capped * DATASET_COLUMN_BIND_PARAMS <= MAX_BIND_PARAMS);
+    }
+
+    #[test]
+    fn test_column_expr_applies_filters() {
+        let mut col = searchable_column("completed_revenue");
+        col.semantic_type = Some("measure".to_string());
+        col.expr = Some("amount".to_string());
+        col.agg = Some("sum".to_string());
+        col.filters = Some("status = 'completed'".to_string());
+        assert_eq!(
+            column_expr(&col, &[], &DataSourceType::Postgres).as_deref(),
+            Some("CASE WHEN (status = 'completed') THEN amount END")
+        );
+
+        col.agg = Some("percentile".to_string());
+        col.percentile = Some(0.5);
+        assert_eq!(
+            column_expr(&col, &[], &DataSourceType::Snowflake).as_deref(),
+            Some("APPROX_PERCENTILE(CASE WHEN (status = 'completed') THEN amount END, 0.5)")
+        );
+    }
+
+    #[test]
+    fn test_ratio_measures() {
+        let req: DeployDatasetsRequest = serde_json::from_value(json!({
+            "data_source_name": "warehouse",
+            "env": "dev",
+            "type": "view",
+            "name": "orders",
+            "schema": "public",
+            "description": "Orders",
+            "columns": [
+                { "name": "revenue", "description": "Revenue", "semantic_type": "measure",
+                  "expr": "amount", "type": "number", "agg": "sum",
+                  "filters": "status = 'paid'" },
+                { "name": "customers", "description": "Customers", "semantic_type": "measure",
+                  "expr": "customer_id", "type": "string", "agg": "count_distinct" },
+                { "name": "revenue_per_customer", "description": "Revenue per customer",
+                  "semantic_type": "measure", "type": "number", "agg": "ratio",
+                  "ratio": { "numerator": "revenue", "denominator": "customers" } },
+                { "name": "broken", "description": "Broken", "semantic_type": "measure",
+                  "type": "number", "agg": "ratio",
+                  "ratio": { "numerator": "revenue_per_customer", "denominator": "refunds" } },
+                { "name": "p90_latency", "description": "P90 latency", "semantic_type": "measure",
+                  "expr": "latency_ms", "type": "number", "agg": "percentile", "percentile": 0.9 },
+                { "name": "p90_per_customer", "description": "P90 per customer",
+                  "semantic_type": "measure", "type": "number", "agg": "ratio",
+                  "ratio": { "numerator": "p90_latency", "denominator": "customers" } }
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(
+            column_expr(&req.columns[2], &req.columns, &DataSourceType::Postgres).as_deref(),
+            Some(
+                "SUM(CASE WHEN (status = 'paid') THEN amount END) / \
+                 NULLIF(COUNT(DISTINCT customer_id), 0)"
+            )
+        );
+
+        let errors = validate_measure_columns(&req, &DataSourceType::Postgres).unwrap();
+        let messages: Vec<_> = errors.iter().map(|error| error.message.as_str()).collect();
+        assert_eq!(messages.len(), 3);
+        assert!(messages[0].contains("ratio references ratio measure 'revenue_per_customer'"));
+        assert!(messages[1].contains("ratio references unknown measure 'refunds'"));
+        assert!(messages[2].contains("ratio references percentile measure 'p90_latency'"));
+    }
+
+    #[test]
+    fn test_validate_measure_columns_checks_agg_type() {
+        let req: DeployDatasetsRequest = serde_json::from_value(json!({
+            "data_source_name": "warehouse",
+            "env": "dev",
+            "type": "view",
+            "name": "orders",
+            "schema": "public",
+            "description": "Orders",
+            "columns": [
+                { "name": "revenue", "description": "Revenue", "semantic_type": "measure",
+                  "expr": "amount", "type": "number", "agg": "sum" },
+                { "name": "statuses", "description": "Statuses", "semantic_type": "measure",
+                  "expr": "status", "type": "string", "agg": "count_distinct" },
+                { "name": "status_total", "description": "Nonsense", "semantic_type": "measure",
+                  "expr": "status", "type": "string", "agg": "sum" }
+            ]
+        }))
+        .unwrap();
+
+        let errors = validate_measure_columns(&req, &DataSourceType::Postgres).unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].column_name.as_deref(), Some("status_total"));
+        assert!(errors[0].message.contains("`agg: sum` needs a numeric measure"));
+    }
+
+    #[test]
+    fn test_changed_searchable_columns() {
+        let req: DeployDatasetsRequest = serde_json::from_value(json!({
+            "data_source_name": "warehouse",
+            "env": "dev",
+            "type": "view",
+            "name": "orders",
+            "schema": "public",
+            "description": "Orders",
+            "columns": [
+                { "name": "status", "description": "Status", "semantic_type": "dimension",
+                  "expr": "status", "type": "string", "searchable": true },
+                { "name": "region", "description": "Region", "semantic_type": "dimension",
+                  "expr": "lower(region)", "type": "string", "stored_values": true },
+                { "name": "channel", "description": "Channel", "semantic_type": "dimension",
+                  "expr": "channel", "type": "string", "searchable": true },
+                { "name": "country", "description": "Country", "semantic_type": "dimension",
+                  "expr": "country", "type": "string", "searchable": true },
+                { "name": "city", "description": "City", "semantic_type": "dimension",
+                  "expr": "city", "type": "string" }
+            ]
+        }))
+        .unwrap();
+        let deployed: HashMap<String, DeployedSearchable> = [
+            ("status", "status", true),
+            ("region", "region", true),
+            ("channel", "channel", false),
+            ("city", "city", true),
+        ]
+        .into_iter()
+        .map(|(name, expr, enrolled)| (name.to_string(), (Some(expr.to_string()), enrolled)))
+        .collect();
+
+        // Unchanged enrolled columns aren't checked again
+        let changed = changed_searchable_columns(&req, &deployed);
+        let names: Vec<&str> = changed.iter().map(|col| col.name.as_str()).collect();
+        assert_eq!(names, vec!["region", "channel", "country"]);
+
+        // Only columns `searchable` newly enrolls are warned about
+        let warnings = searchable_enrollment_warnings(&changed, &deployed);
+        let warned: Vec<Option<&str>> =
+            warnings.iter().map(|w| w.column_name.as_deref()).collect();
+        assert_eq!(warned, vec![Some("channel"), Some("country")]);
+        assert!(warnings
+            .iter()
+            .all(|w| w.error_type == ValidationErrorType::SearchableEnrollsStoredValues));
+    }
+
+    #[test]
+    fn test_identifiers_match_per_dialect() {
+        assert!(identifiers_match("ORDERS", "orders", false));
+        assert!(!identifiers_match("Orders", "orders", true));
+        assert!(identifiers_match("Orders", "Orders", true));
+    }
+
+    #[test]
+    fn test_resolve_grain() {
+        let mut req: DeployDatasetsRequest = serde_json::from_value(json!({
+            "data_source_name": "warehouse",
+            "env": "dev",
+            "type": "view",
+            "name": "order_lines",
+            "schema": "public",
+            "description": "Order lines",
+            "entity_relationships": [{ "name": "order", "expr": "order_id", "type": "foreign" }],
+            "columns": [{
+                "name": "line",
+                "description": "Line number",
+                "semantic_type": "dimension",
+                "expr": "line_number",
+                "type": "integer",
+                "agg": null
+            }],
+            "grain": ["order", "line"]
+        }))
+        .unwrap();
+
+        let order_id = column("order_id", "integer");
+        let line_number = column("LINE_NUMBER", "integer");
+        let columns = vec![&order_id, &line_number];
+
+        assert_eq!(
+            resolve_grain(&req, &columns).unwrap(),
+            vec!["order_id", "LINE_NUMBER"]
+        );
+
+        req.grain = Some(vec!["order".to_string(), "sku".to_string()]);
+        let errors = resolve_grain(&req, &columns).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].column_name.as_deref(), Some("sku"));
+
+        assert!(resolve_grain(&req, &[&order_id]).is_err());
+        req.grain = None;
+        assert!(resolve_grain(&req, &columns).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_defaulted_column_types() {
+        let req: DeployDatasetsRequest = serde_json::from_value(json!({
+            "data_source_name": "warehouse",
+            "env": "dev",
+            "type": "view",
+            "name": "stores",
+            "schema": "public",
+            "description": "Stores",
+            "columns": [
+                { "name": "name", "description": "Name", "semantic_type": "dimension",
+                  "expr": "name", "type": "string", "agg": null },
+                { "name": "location", "description": "Location", "semantic_type": "dimension",
+                  "expr": "LOCATION", "type": null, "agg": null },
+                { "name": "label", "description": "Label", "semantic_type": "dimension",
+                  "expr": "name || ' store'", "type": "", "agg": null }
+            ]
+        }))
+        .unwrap();
+        let location = column("location", "geography");
+
+        let warnings = defaulted_column_types(&req, &[&location]);
+        assert_eq!(
+            warnings
+                .iter()
+                .map(|warning| warning.message.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "Column 'location' has no type and was deployed as text (warehouse type \
+                 'geography')",
+                "Column 'label' has no type and was deployed as text (warehouse type 'unknown')",
+            ]
+        );
+        assert_eq!(warnings[0].error_type, ValidationErrorType::DefaultedColumnType);
+    }
+
+    #[test]
+    fn test_validate_dataset_type() {
+        let mut req: DeployDatasetsRequest = serde_json::from_value(json!({
+            "data_source_name": "warehouse",
+            "env": "dev",
+            "type": "materialized_view",
+            "name": "daily_orders",
+            "schema": "public",
+            "description": "Orders per day",
+            "columns": []
+        }))
+        .unwrap();
+        assert!(validate_dataset_type(&req).is_none());
+
+        req.allowed_dataset_types = Some(vec!["table".to_string(), "view".to_string()]);
+        let error = validate_dataset_type(&req).unwrap();
+        assert_eq!(error.error_type, ValidationErrorType::DisallowedDatasetType);
+        assert!(error.message.contains("'daily_orders'"));
+        assert!(error.message.contains("'materialized_view'"));
+
+        req.type_ = "view".to_string();
+        assert!(validate_dataset_type(&req).is_none());
+
+        req.allowed_dataset_types = None;
+        req.type_ = "snapshot".to_string();
+        assert!(validate_dataset_type(&req).is_some());
+    }
+
+    #[test]
+    fn test_validate_env() {
+        assert!(validate_env("prod", None).is_none());
+        assert!(validate_env("qa", Some(" qa, sandbox ")).is_none());
+
+        let error = validate_env("production", Some("qa")).unwrap();
+        assert_eq!(error.error_type, ValidationErrorType::InvalidEnv);
+        assert_eq!(error.message, "Unknown env 'production'");
+        assert_eq!(error.suggestion.as_deref(), Some("Use one of: dev, staging, prod, qa"));
+    }
+
+    #[test]
+    fn test_missing_env() {
+        // Even a data source in a single env isn't guessed
+        let single = missing_env("warehouse", &["prod".to_string()]);
+        assert_eq!(single.error_type, ValidationErrorType::RequiredFieldMissing);
+        assert_eq!(
+            single.message,
+            "Data source 'warehouse' exists in envs prod; the model must set env"
+        );
+
+        let missing = missing_env("warehouse", &[]);
+        assert_eq!(missing.message, "Data source 'warehouse' not found");
+
+        let ambiguous = missing_env("warehouse", &["dev".to_string(), "prod".to_string()]);
+        assert_eq!(
+            ambiguous.message,
+            "Data source 'warehouse' exists in envs dev, prod; the model must set env"
+        );
+    }
+
+    #[test]
+    fn test_deploy_timings_lists_slowest_models() {
+        let outcome = DeployWriteOutcome {
+            model_timings: (0..12)
+                .map(|i| ModelTiming {
+                    model_name: format!("model_{}", i),
+                    data_source_name: "warehouse".to_string(),
+                    duration_ms: (i * 7 % 12) as u64,
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        let timings = outcome.timings();
+        assert_eq!(timings.slowest_models.len(), SLOWEST_MODELS_REPORTED);
+        assert_eq!(timings.slowest_models[0].duration_ms, 11);
+        assert!(timings
+            .slowest_models
+            .windows(2)
+            .all(|pair| pair[0].duration_ms >= pair[1].duration_ms));
+        assert_eq!(duration_ms(Duration::from_secs(2)), 2000);
+    }
+
+    #[test]
+    fn test_only_new_skipped_models_are_not_deployed() {
+        let request = |name: &str| -> DeployDatasetsRequest {
+            serde_json::from_value(json!({
+                "data_source_name": "warehouse",
+                "env": "dev",
+                "type": "view",
+                "name": name,
+                "schema": "public",
+                "description": "",
+                "columns": []
+            }))
+            .unwrap()
+        };
+        let (orders, customers) = (request("orders"), request("customers"));
+        let outcome = DeployWriteOutcome {
+            skipped: vec!["customers".to_string()],
+            ..Default::default()
+        };
+
+        // Only written datasets get warehouse comments
+        let written = outcome.written(&[&orders, &customers]);
+        assert_eq!(written.len(), 1);
+        assert_eq!(written[0].name, "orders");
+
+        let result = |name: &str| {
+            ValidationResult::new(name.to_string(), "warehouse".to_string(), "public".to_string())
+        };
+        let mut failed = result("regions");
+        failed.add_error(missing_env("warehouse", &[]));
+        let summary = outcome.summary(&[result("orders"), result("customers"), failed]);
+        assert_eq!(summary.total_models, 3);
+        assert_eq!(summary.successful_models, 1);
+        assert_eq!(summary.failed_models, 1);
+        assert_eq!(summary.skipped, vec!["customers"]);
+        let successes: Vec<&str> =
+            summary.successes.iter().map(|s| s.model_name.as_str()).collect();
+        assert_eq!(successes, vec!["orders"]);
+    }
+
+    #[test]
+    fn test_deploy_version_compatibility() {
+        assert_eq!(parse_version("0.0.3"), Some((0, 0, 3)));
+        assert_eq!(parse_version("v1.2"), Some((1, 2, 0)));
+        assert_eq!(parse_version("0.1.0-beta.1"), Some((0, 1, 0)));
+        assert_eq!(parse_version("dev"), None);
+
+        assert!(check_cli_version(None).is_ok());
+        assert!(check_cli_version(Some(MIN_CLI_VERSION)).is_ok());
+        assert!(check_cli_version(Some("dev")).is_ok());
+        let error = check_cli_version(Some("0.0.1")).unwrap_err();
+        assert!(error.contains("Buster CLI 0.0.1 is no longer supported"));
+
+        let mut req: DeployDatasetsRequest = serde_json::from_value(json!({
+            "data_source_name": "warehouse",
+            "env": "dev",
+            "type": "view",
+            "name": "orders",
+            "schema": "public",
+            "description": "Orders",
+            "columns": []
+        }))
+        .unwrap();
+        assert_eq!(req.version, 0);
+        assert!(check_model_versions(std::slice::from_ref(&req)).is_ok());
+
+        // dbt-style model files
+        req.version = 2;
+        assert!(check_model_versions(std::slice::from_ref(&req)).is_ok());
+
+        req.version = MAX_MODEL_VERSION + 1;
+        let error = check_model_versions(&[req]).unwrap_err();
+        assert!(error.contains("orders (version 3)"));
+        assert!(error.contains("upgrade the Buster server"));
+
+        let (status, message) =
+            unreadable_request_error(Some("0.0.3"), "missing field `env` at line 1");
+        assert_eq!(status, StatusCode::UPGRADE_REQUIRED);
+        assert!(message.contains("The CLI (0.0.3) is older than this server"));
+        let (status, message) =
+            unreadable_request_error(None, "unknown variant `ratio` at line 1");
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(message.contains("This server is older than the CLI (unknown version)"));
+    }
+
+    #[tokio::test]
+    async fn test_redeploy_preserves_stored_values() -> Result<()> {
+        dotenv().ok();
+
+        let pool = establish_diesel_connection().await?;
+        let mut conn = pool.get().await?;
+
+        let organization_id = Uuid::new_v4();
+        let user_id = Uuid::new_v4();
+        let data_source_id = Uuid::new_v4();
+        let dataset_id = Uuid::new_v4();
+
+        diesel::sql_query(format!(
+            "INSERT INTO organizations (id, name) VALUES ('{organization_id}', 'deploy-test-{organization_id}')"
+        ))
+        .execute(&mut conn)
+        .await?;
+        diesel::sql_query(format!(
+            "INSERT INTO users (id, email) VALUES ('{user_id}', 'deploy-test-{user_id}@buster.so')"
+        ))
+        .execute(&mut conn)
+        .await?;
+        diesel::sql_query(format!(
+            "INSERT INTO data_sources (id, name, type, secret_id, organization_id, created_by, updated_by)
+             VALUES ('{data_source_id}', 'deploy_test', 'postgres', '{}', '{organization_id}', '{user_id}', '{user_id}')",
+            Uuid::new_v4()
+        ))
+        .execute(&mut conn)
+        .await?;
+        diesel::sql_query(format!(
+            "INSERT INTO datasets (id, name, database_name, type, definition, schema, data_source_id, organization_id, created_by, updated_by)
+             VALUES ('{dataset_id}', 'orders', 'orders', 'view', 'select 1', 'public', '{data_source_id}', '{organization_id}', '{user_id}', '{user_id}')"
+        ))
         .execute(&mut conn)
         .await?;
 
-    Ok(dataset_id)
+        let columns = vec![searchable_column("status")];
+        upsert_dataset_columns(
+            &mut conn,
+            &organization_id,
+            dataset_id,
+            ColumnDeleteMode::Soft,
+            &columns,
+            &[],
+            &HashMap::new(),
+            &DataSourceType::Postgres,
+            Utc::now(),
+        )
+        .await?;
+
+        // Simulate a completed stored-values sync for the column
+        diesel::update(dataset_columns::table)
+            .filter(dataset_columns::dataset_id.eq(dataset_id))
+            .filter(dataset_columns::name.eq("status"))
+            .set((
+                dataset_columns::stored_values.eq(Some(true)),
+                dataset_columns::stored_values_status.eq(Some(StoredValuesStatus::Success)),
+                dataset_columns::stored_values_count.eq(Some(42_i64)),
+            ))
+            .execute(&mut conn)
+            .await?;
+
+        // Deploy the same column again
+        upsert_dataset_columns(
+            &mut conn,
+            &organization_id,
+            dataset_id,
+            ColumnDeleteMode::Soft,
+            &columns,
+            &[],
+            &HashMap::new(),
+            &DataSourceType::Postgres,
+            Utc::now(),
+        )
+        .await?;
+
+        let (stored_values, status, count) = dataset_columns::table
+            .filter(dataset_columns::dataset_id.eq(dataset_id))
+            .filter(dataset_columns::name.eq("status"))
+            .select((
+                dataset_columns::stored_values,
+                dataset_columns::stored_values_status,
+                dataset_columns::stored_values_count,
+            ))
+            .first::<(Option<bool>, Option<StoredValuesStatus>, Option<i64>)>(&mut conn)
+            .await?;
+
+        // Cleanup cascades from the organization and user
+        diesel::sql_query(format!("DELETE FROM organizations WHERE id = '{organization_id}'"))
+            .execute(&mut conn)
+            .await?;
+        diesel::sql_query(format!("DELETE FROM users WHERE id = '{user_id}'"))
+            .execute(&mut conn)
+            .await?;
+
+        assert_eq!(stored_values, Some(true));
+        assert_eq!(status, Some(StoredValuesStatus::Success));
+        assert_eq!(count, Some(42));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_hard_delete_removes_soft_deleted_columns() -> Result<()> {
+        dotenv().ok();
+
+        let pool = establish_diesel_connection().await?;
+        let mut conn = pool.get().await?;
+
+        let organization_id = Uuid::new_v4();
+        let user_id = Uuid::new_v4();
+        let data_source_id = Uuid::new_v4();
+        let dataset_id = Uuid::new_v4();
+
+        diesel::sql_query(format!(
+            "INSERT INTO organizations (id, name) VALUES ('{organization_id}', 'deploy-test-{organization_id}')"
+        ))
+        .execute(&mut conn)
+        .await?;
+        diesel::sql_query(format!(
+            "INSERT INTO users (id, email) VALUES ('{user_id}', 'deploy-test-{user_id}@buster.so')"
+        ))
+        .execute(&mut conn)
+        .await?;
+        diesel::sql_query(format!(
+            "INSERT INTO data_sources (id, name, type, secret_id, organization_id, created_by, updated_by)
+             VALUES ('{data_source_id}', 'deploy_test', 'postgres', '{}', '{organization_id}', '{user_id}', '{user_id}')",
+            Uuid::new_v4()
+        ))
+        .execute(&mut conn)
+        .await?;
+        diesel::sql_query(format!(
+            "INSERT INTO datasets (id, name, database_name, type, definition, schema, data_source_id, organization_id, created_by, updated_by)
+             VALUES ('{dataset_id}', 'orders', 'orders', 'view', 'select 1', 'public', '{data_source_id}', '{organization_id}', '{user_id}', '{user_id}')"
+        ))
+        .execute(&mut conn)
+        .await?;
+        let data_source = data_sources::table
+            .filter(data_sources::id.eq(data_source_id))
+            .first::<DataSource>(&mut conn)
+            .await?;
+
+        let kept = vec![searchable_column("status")];
+        upsert_dataset_columns(
+            &mut conn,
+            &organization_id,
+            dataset_id,
+            ColumnDeleteMode::Soft,
+            &[searchable_column("status"), searchable_column("region")],
+            &[],
+            &HashMap::new(),
+            &DataSourceType::Postgres,
+            Utc::now(),
+        )
+        .await?;
+        diesel::update(dataset_columns::table)
+            .filter(dataset_columns::dataset_id.eq(dataset_id))
+            .filter(dataset_columns::name.eq("region"))
+            .set(dataset_columns::stored_values.eq(Some(true)))
+            .execute(&mut conn)
+            .await?;
+
+        // Soft-delete the searchable column first
+        upsert_dataset_columns(
+            &mut conn,
+            &organization_id,
+            dataset_id,
+            ColumnDeleteMode::Soft,
+            &kept,
+            &[],
+            &HashMap::new(),
+            &DataSourceType::Postgres,
+            Utc::now(),
+        )
+        .await?;
+
+        let mut req: DeployDatasetsRequest = serde_json::from_value(json!({
+            "data_source_name": "deploy_test",
+            "env": "dev",
+            "type": "view",
+            "name": "orders",
+            "schema": "public",
+            "description": "Orders",
+            "column_delete_mode": "hard",
+            "columns": [
+                { "name": "status", "description": "Status", "semantic_type": "dimension",
+                  "expr": "status", "type": "string" }
+            ]
+        }))?;
+        let errors = removed_stored_values_columns(&mut conn, &data_source, &req)
+            .await?
+            .unwrap_or_default();
+        req.force = true;
+        let forced = removed_stored_values_columns(&mut conn, &data_source, &req).await?;
+
+        upsert_dataset_columns(
+            &mut conn,
+            &organization_id,
+            dataset_id,
+            ColumnDeleteMode::Hard,
+            &kept,
+            &[],
+            &HashMap::new(),
+            &DataSourceType::Postgres,
+            Utc::now(),
+        )
+        .await?;
+        let remaining: Vec<String> = dataset_columns::table
+            .filter(dataset_columns::dataset_id.eq(dataset_id))
+            .select(dataset_columns::name)
+            .load(&mut conn)
+            .await?;
+
+        // Cleanup cascades from the organization and user
+        diesel::sql_query(format!("DELETE FROM organizations WHERE id = '{organization_id}'"))
+            .execute(&mut conn)
+            .await?;
+        diesel::sql_query(format!("DELETE FROM users WHERE id = '{user_id}'"))
+            .execute(&mut conn)
+            .await?;
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].column_name.as_deref(), Some("region"));
+        assert!(forced.is_none());
+        assert_eq!(remaining, vec!["status".to_string()]);
+
+        Ok(())
+    }
 }
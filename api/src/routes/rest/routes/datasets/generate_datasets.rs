@@ -1,11 +1,16 @@
 use anyhow::{anyhow, Result};
-use axum::{extract::Json, Extension};
+use axum::{
+    extract::Json,
+    response::{IntoResponse, Response},
+    Extension,
+};
 use diesel::{ExpressionMethods, JoinOnDsl, QueryDsl};
 use diesel_async::RunQueryDsl;
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use serde_yaml;
 use std::collections::HashMap;
+use std::sync::Arc;
 use uuid::Uuid;
 use regex::Regex;
 use tokio::task::JoinSet;
@@ -13,17 +18,28 @@ use tokio::task::JoinSet;
 use crate::{
     database::{
         lib::get_pg_pool,
+        enums::DataSourceType,
         models::{Dataset, DataSource, User},
         schema::{data_sources, datasets},
     },
     routes::rest::ApiResponse,
     utils::{
-        security::checks::is_user_workspace_admin_or_data_admin,
+        security::{
+            checks::is_user_workspace_admin_or_data_admin,
+            rate_limit::{rate_limited_response, GENERATE_RATE_LIMITER},
+        },
         user::user_info::get_user_organization_id,
         query_engine::{
             credentials::get_data_source_credentials,
-            import_dataset_columns::{retrieve_dataset_columns_batch, DatasetColumnRecord},
+            dialect_detection::resolve_data_source_type,
+            foreign_keys::{reads_foreign_keys, table_keys, ForeignKey, TableKeys},
+            import_dataset_columns::{
+                dataset_columns_batch_sql, retrieve_dataset_columns_batch, DatasetColumnRecord,
+            },
+            data_types::DataType,
+            sample_values::{format_sample_values, quote_identifier, sample_column_values},
         },
+        validation::type_mapping::{measure_agg_mismatch, normalize_type},
         clients::ai::{
             openai::{OpenAiChatModel, OpenAiChatRole, OpenAiChatContent, OpenAiChatMessage},
             llm_router::{llm_chat, LlmModel, LlmMessage},
@@ -37,12 +53,215 @@ pub struct GenerateDatasetRequest {
     pub schema: String,
     pub database: Option<String>,
     pub model_names: Vec<String>,
+    #[serde(default)]
+    pub identifier_case: IdentifierCase,
+    /// Detect the warehouse dialect from its version banner instead of trusting the recorded type
+    #[serde(default)]
+    pub dialect_autodetect: bool,
+    /// Append a few distinct example values to string column descriptions. Queries the
+    /// warehouse once per string column, so it is off unless asked for.
+    #[serde(default)]
+    pub sample_values: bool,
+    /// `searchable` set on generated dimensions
+    #[serde(default)]
+    pub default_searchable: bool,
+    /// `agg` set on generated measures
+    #[serde(default)]
+    pub default_agg: MeasureAgg,
+    /// Only list the columns generate would skip as unsupported, without generating YAML
+    #[serde(default)]
+    pub report_unsupported: bool,
+    /// Columns and descriptions of each model as recorded by a catalog such as dbt's
+    /// `catalog.json`. When set, the warehouse isn't queried for columns.
+    #[serde(default)]
+    pub catalog: Option<Vec<CatalogModel>>,
+    /// Fields to extract from semi-structured columns, which are otherwise skipped
+    #[serde(default)]
+    pub flatten_hints: Vec<FlattenHint>,
+    /// Emit entities from the primary and foreign keys declared in the warehouse
+    #[serde(default)]
+    pub emit_entities_from_fk: bool,
+    /// Only classify each model's columns, without generating YAML
+    #[serde(default)]
+    pub check: bool,
+    /// Only return the metadata queries columns would be read with, without running them
+    #[serde(default)]
+    pub dry_run_sql: bool,
+}
+
+/// A field to extract from an ARRAY, OBJECT or VARIANT column.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FlattenHint {
+    /// Table the column belongs to
+    pub model_name: String,
+    /// Column and path to the field in Snowflake syntax, e.g. `payload:customer.id` or
+    /// `items[0]:sku`
+    pub path: String,
+    /// Name of the generated field; defaults to the path after the column, e.g. `customer_id`
+    #[serde(default)]
+    pub name: Option<String>,
+    /// `string` (default), `number`, `boolean` or `timestamp`. Numbers become measures.
+    #[serde(default, rename = "type")]
+    pub type_: Option<String>,
+}
+
+impl FlattenHint {
+    /// The column and the path into it, split where the path starts.
+    fn column_and_path(&self) -> (&str, &str) {
+        let path = self.path.trim();
+        let start = path.find([':', '[']).unwrap_or(path.len());
+        (path[..start].trim(), &path[start..])
+    }
+
+    fn column(&self) -> &str {
+        self.column_and_path().0
+    }
+
+    fn applies_to(&self, col: &DatasetColumnRecord) -> bool {
+        self.model_name.eq_ignore_ascii_case(&col.dataset_name)
+            && self.column().eq_ignore_ascii_case(&col.name)
+    }
+}
+
+/// A model's columns as recorded in an external catalog.
+#[derive(Debug, Deserialize)]
+pub struct CatalogModel {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub columns: Vec<CatalogColumn>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CatalogColumn {
+    pub name: String,
+    /// Warehouse type as the catalog reports it, e.g. `character varying` or `NUMBER`
+    #[serde(rename = "type")]
+    pub type_: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Casing applied to generated `name` identifiers; `expr` always keeps the warehouse's.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum IdentifierCase {
+    Lower,
+    Upper,
+    #[default]
+    Preserve,
+}
+
+impl IdentifierCase {
+    fn apply(&self, identifier: &str) -> String {
+        match self {
+            IdentifierCase::Lower => identifier.to_lowercase(),
+            IdentifierCase::Upper => identifier.to_uppercase(),
+            IdentifierCase::Preserve => identifier.to_string(),
+        }
+    }
+}
+
+/// Aggregations a generated measure can default to.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MeasureAgg {
+    #[default]
+    Sum,
+    Avg,
+    Count,
+    CountDistinct,
+    Min,
+    Max,
+}
+
+impl MeasureAgg {
+    fn as_str(&self) -> &'static str {
+        match self {
+            MeasureAgg::Sum => "sum",
+            MeasureAgg::Avg => "avg",
+            MeasureAgg::Count => "count",
+            MeasureAgg::CountDistinct => "count_distinct",
+            MeasureAgg::Min => "min",
+            MeasureAgg::Max => "max",
+        }
+    }
+}
+
+/// Reserved words that are also common column names, which only work quoted.
+const RESERVED_COLUMN_NAMES: &[&str] = &[
+    "all", "and", "as", "by", "case", "check", "column", "default", "desc", "distinct", "end",
+    "from", "group", "having", "in", "limit", "not", "null", "on", "or", "order", "select",
+    "table", "to", "union", "user", "when", "where",
+];
+
+/// A warehouse column as a model `expr`, in its original case. It's quoted only when the
+/// warehouse wouldn't read it bare: it has characters a bare identifier can't, is a
+/// reserved word, or has case the warehouse folds unquoted identifiers away from.
+fn column_expr(data_source_type: DataSourceType, column: &str) -> String {
+    let bare = column
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && column.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !RESERVED_COLUMN_NAMES.contains(&column.to_lowercase().as_str());
+    let folded_away = match data_source_type {
+        DataSourceType::Snowflake => column.chars().any(|c| c.is_ascii_lowercase()),
+        DataSourceType::Postgres | DataSourceType::Supabase | DataSourceType::Redshift => {
+            column.chars().any(|c| c.is_ascii_uppercase())
+        }
+        _ => false,
+    };
+
+    if bare && !folded_away {
+        column.to_string()
+    } else {
+        quote_identifier(&data_source_type, column)
+    }
 }
 
 #[derive(Debug, Serialize)]
 pub struct GenerateDatasetResponse {
     pub yml_contents: HashMap<String, String>,
     pub errors: HashMap<String, String>,
+    pub unsupported_columns: Vec<UnsupportedColumn>,
+    /// Options that couldn't be applied, such as foreign keys on a warehouse without them
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// How each model's columns would map, when only checking
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub model_checks: Vec<ModelCheck>,
+    /// Metadata queries that would read the columns, with `dry_run_sql`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub metadata_queries: Vec<String>,
+}
+
+/// How many of a model's columns generate would turn into dimensions and measures, how
+/// many it would skip as unsupported, and which have a type it doesn't know and would
+/// default to a string dimension.
+#[derive(Debug, Serialize)]
+pub struct ModelCheck {
+    pub model_name: String,
+    pub entities: usize,
+    pub dimensions: usize,
+    pub measures: usize,
+    pub unsupported: usize,
+    pub defaulted_columns: Vec<DefaultedColumn>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DefaultedColumn {
+    pub column_name: String,
+    pub data_type: String,
+}
+
+/// A column generate leaves out of its model because the warehouse type has no
+/// dimension or measure mapping (ARRAY, OBJECT, VARIANT) and no flatten hint names it.
+#[derive(Debug, Serialize)]
+pub struct UnsupportedColumn {
+    pub model_name: String,
+    pub column_name: String,
+    pub data_type: String,
 }
 
 #[derive(Debug, Serialize)]
@@ -53,30 +272,56 @@ struct ModelConfig {
 #[derive(Debug, Serialize)]
 struct Model {
     name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
     description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    when_to_use: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    entities: Vec<Entity>,
     dimensions: Vec<Dimension>,
     measures: Vec<Measure>,
 }
 
+#[derive(Debug, Serialize, PartialEq)]
+struct Entity {
+    name: String,
+    /// The referenced model, when the entity isn't named after it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ref_: Option<String>,
+    expr: String,
+    #[serde(rename = "type")]
+    type_: String,
+    description: String,
+}
+
 #[derive(Debug, Serialize)]
 struct Dimension {
     name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
     expr: String,
     #[serde(rename = "type")]
     type_: String,
     description: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     searchable: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hidden: Option<bool>,
 }
 
 #[derive(Debug, Serialize)]
 struct Measure {
     name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
     expr: String,
     #[serde(rename = "type")]
     type_: String,
     agg: Option<String>,
     description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hidden: Option<bool>,
 }
 
 // Add type mapping enum
@@ -87,11 +332,12 @@ enum ColumnMappingType {
     Unsupported,
 }
 
-fn map_snowflake_type(type_str: &str) -> ColumnMappingType {
+/// Maps a Snowflake type name, or `None` for a type generate doesn't know.
+fn known_snowflake_type(type_str: &str) -> Option<ColumnMappingType> {
     // Convert to uppercase for consistent matching
     let type_upper = type_str.to_uppercase();
     
-    match type_upper.as_str() {
+    let mapping = match type_upper.as_str() {
         // Numeric types that should be measures
         "NUMBER" | "DECIMAL" | "NUMERIC" | "FLOAT" | "REAL" | "DOUBLE" | "INT" | "INTEGER" | 
         "BIGINT" | "SMALLINT" | "TINYINT" | "BYTEINT" => ColumnMappingType::Measure("number".to_string()),
@@ -109,27 +355,382 @@ fn map_snowflake_type(type_str: &str) -> ColumnMappingType {
         // Unsupported types
         "ARRAY" | "OBJECT" | "VARIANT" => ColumnMappingType::Unsupported,
         
-        // Default to dimension for unknown types
+        _ => return None,
+    };
+    Some(mapping)
+}
+
+/// A field a flatten hint extracts from a semi-structured column.
+#[derive(Debug)]
+struct FlattenedField {
+    name: String,
+    expr: String,
+    mapping: ColumnMappingType,
+}
+
+/// Turns a hint into the field it extracts, cast to its type in the warehouse's dialect.
+fn flatten_field(hint: &FlattenHint, data_source_type: DataSourceType) -> Result<FlattenedField> {
+    let (column, path) = hint.column_and_path();
+    if column.is_empty() || path.trim_start_matches([':', '[']).is_empty() {
+        return Err(anyhow!(
+            "Flatten hint '{}' must be a column and a path, e.g. payload:customer.id",
+            hint.path
+        ));
+    }
+
+    let type_ = hint.type_.as_deref().unwrap_or("string").trim().to_lowercase();
+    let mapping = match type_.as_str() {
+        "string" | "boolean" | "timestamp" => ColumnMappingType::Dimension(type_.clone()),
+        "number" => ColumnMappingType::Measure(type_.clone()),
+        other => {
+            return Err(anyhow!(
+                "Flatten hint '{}' has unknown type '{}'; expected string, number, boolean \
+                 or timestamp",
+                hint.path,
+                other
+            ))
+        }
+    };
+
+    // Names can't start with a digit, so array paths like `items[0]:sku` keep the column
+    let name = match hint.name.as_deref().map(str::trim) {
+        Some(name) if !name.is_empty() => name.to_string(),
         _ => {
-            tracing::warn!("Unknown Snowflake type: {}, defaulting to string dimension", type_str);
+            let name = path
+                .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .filter(|part| !part.is_empty())
+                .collect::<Vec<_>>()
+                .join("_");
+            if name.starts_with(|c: char| c.is_ascii_digit()) {
+                format!("{}_{}", column, name)
+            } else {
+                name
+            }
+        }
+    };
+
+    Ok(FlattenedField {
+        name,
+        expr: flatten_expr(data_source_type, column, path, &type_)?,
+        mapping,
+    })
+}
+
+/// SQL extracting `path` from a semi-structured `column` as `type_`: Snowflake's
+/// `column:path::TYPE`, Postgres' `#>>` and BigQuery's `JSON_VALUE`.
+fn flatten_expr(
+    data_source_type: DataSourceType,
+    column: &str,
+    path: &str,
+    type_: &str,
+) -> Result<String> {
+    let segments: Vec<&str> = path
+        .split([':', '.', '[', ']'])
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    let cast = |snowflake, postgres, bigquery| match data_source_type {
+        DataSourceType::Postgres | DataSourceType::Supabase => postgres,
+        DataSourceType::BigQuery => bigquery,
+        _ => snowflake,
+    };
+    let cast = match type_ {
+        "string" => cast("VARCHAR", "TEXT", "STRING"),
+        "boolean" => cast("BOOLEAN", "BOOLEAN", "BOOL"),
+        "timestamp" => cast("TIMESTAMP", "TIMESTAMP", "TIMESTAMP"),
+        _ => cast("FLOAT", "DOUBLE PRECISION", "FLOAT64"),
+    };
+
+    match data_source_type {
+        DataSourceType::Snowflake => Ok(format!("{}{}::{}", column, path, cast)),
+        DataSourceType::Postgres | DataSourceType::Supabase => Ok(format!(
+            "({} #>> '{{{}}}')::{}",
+            column,
+            segments.join(","),
+            cast
+        )),
+        DataSourceType::BigQuery => {
+            let json_path: String = segments
+                .iter()
+                .map(|segment| match segment.parse::<usize>() {
+                    Ok(index) => format!("[{}]", index),
+                    Err(_) => format!(".{}", segment),
+                })
+                .collect();
+            Ok(format!(
+                "CAST(JSON_VALUE({}, '${}') AS {})",
+                column, json_path, cast
+            ))
+        }
+        other => Err(anyhow!(
+            "Flatten hints aren't supported on {} yet",
+            other.to_string()
+        )),
+    }
+}
+
+/// Maps a column type through the data source's type map, falling back to the Snowflake
+/// names generate has always used when the dialect doesn't list it. `None` for a type
+/// generate doesn't know.
+fn known_column_type(
+    data_source_type: DataSourceType,
+    type_str: &str,
+) -> Option<ColumnMappingType> {
+    let mapping = match normalize_type(data_source_type, type_str.trim()) {
+        DataType::Text(_) | DataType::Char(_) | DataType::Uuid(_) => {
             ColumnMappingType::Dimension("string".to_string())
         }
+        DataType::Bool(_) => ColumnMappingType::Dimension("boolean".to_string()),
+        DataType::Date(_)
+        | DataType::Time(_)
+        | DataType::Timestamp(_)
+        | DataType::Timestamptz(_) => ColumnMappingType::Dimension("timestamp".to_string()),
+        DataType::Int2(_)
+        | DataType::Int4(_)
+        | DataType::Int8(_)
+        | DataType::Float4(_)
+        | DataType::Float8(_)
+        | DataType::Decimal(_) => ColumnMappingType::Measure("number".to_string()),
+        DataType::Json(_) | DataType::Bytea(_) => ColumnMappingType::Unsupported,
+        _ => return known_snowflake_type(type_str),
+    };
+    Some(mapping)
+}
+
+/// Maps a column type, defaulting unknown types to a string dimension.
+fn map_column_type(data_source_type: DataSourceType, type_str: &str) -> ColumnMappingType {
+    known_column_type(data_source_type, type_str).unwrap_or_else(|| {
+        tracing::warn!("Unknown type: {}, defaulting to string dimension", type_str);
+        ColumnMappingType::Dimension("string".to_string())
+    })
+}
+
+/// What generate models a warehouse column as.
+#[derive(Debug)]
+enum ColumnClass {
+    /// One of the model's entities
+    Entity,
+    /// A dimension or measure; `defaulted` when its type is unknown
+    Field {
+        mapping: ColumnMappingType,
+        defaulted: bool,
+    },
+    /// The fields flatten hints extract from it
+    Flattened(Vec<FlattenedField>),
+    /// Left out, with no mapping and no flatten hint
+    Unsupported,
+}
+
+/// Classifies a column for both `build_model` and `check_model`, so a check reports
+/// what generate writes. Entity columns are modeled as entities, and key columns are
+/// grouped on as dimensions whatever their type.
+fn classify_column(
+    col: &DatasetColumnRecord,
+    data_source_type: DataSourceType,
+    entity_exprs: &[&str],
+    key_columns: &[String],
+    flatten_hints: &[FlattenHint],
+) -> Result<ColumnClass> {
+    if entity_exprs.contains(&column_expr(data_source_type, &col.name).as_str()) {
+        return Ok(ColumnClass::Entity);
     }
+
+    let class = match known_column_type(data_source_type, &col.type_) {
+        None => ColumnClass::Field {
+            mapping: map_column_type(data_source_type, &col.type_),
+            defaulted: true,
+        },
+        Some(ColumnMappingType::Unsupported) => {
+            let hints: Vec<_> = flatten_hints.iter().filter(|h| h.applies_to(col)).collect();
+            if hints.is_empty() {
+                ColumnClass::Unsupported
+            } else {
+                ColumnClass::Flattened(
+                    hints
+                        .into_iter()
+                        .map(|hint| flatten_field(hint, data_source_type))
+                        .collect::<Result<_>>()?,
+                )
+            }
+        }
+        Some(ColumnMappingType::Measure(type_))
+            if key_columns.contains(&col.name.to_lowercase()) =>
+        {
+            ColumnClass::Field {
+                mapping: ColumnMappingType::Dimension(type_),
+                defaulted: false,
+            }
+        }
+        Some(mapping) => ColumnClass::Field {
+            mapping,
+            defaulted: false,
+        },
+    };
+    Ok(class)
+}
+
+/// A warning for each flatten hint that extracts nothing: one naming no column of the
+/// models being generated, or a column generate models as is because its type is supported.
+fn unmatched_flatten_hints(
+    flatten_hints: &[FlattenHint],
+    ds_columns: &[DatasetColumnRecord],
+    data_source_type: DataSourceType,
+) -> Vec<String> {
+    flatten_hints
+        .iter()
+        .filter_map(|hint| {
+            let column = ds_columns.iter().find(|col| hint.applies_to(col));
+            match column {
+                None => Some(format!(
+                    "Flatten hint '{}' on model '{}' matches no column",
+                    hint.path, hint.model_name
+                )),
+                Some(col)
+                    if !matches!(
+                        known_column_type(data_source_type, &col.type_),
+                        Some(ColumnMappingType::Unsupported)
+                    ) =>
+                {
+                    Some(format!(
+                        "Flatten hint '{}' on model '{}' was ignored: column '{}' is a {} column, \
+                         not an ARRAY, OBJECT or VARIANT",
+                        hint.path, hint.model_name, col.name, col.type_
+                    ))
+                }
+                Some(_) => None,
+            }
+        })
+        .collect()
+}
+
+/// Catalog models as column records, numbered in catalog order.
+fn catalog_columns(catalog: &[CatalogModel], schema: &str) -> Vec<DatasetColumnRecord> {
+    catalog
+        .iter()
+        .flat_map(|model| {
+            model
+                .columns
+                .iter()
+                .enumerate()
+                .map(move |(index, column)| DatasetColumnRecord {
+                    dataset_name: model.name.clone(),
+                    schema_name: schema.to_string(),
+                    name: column.name.clone(),
+                    type_: column.type_.clone(),
+                    nullable: true,
+                    comment: column.description.clone(),
+                    source_type: "catalog".to_string(),
+                    ordinal: index as i32 + 1,
+                })
+        })
+        .collect()
+}
+
+/// Seeds `when_to_use` from the model's description and the measures and dimensions it
+/// can be analyzed with. Only models whose description came with the columns (e.g. from
+/// dbt) are seeded; without one, column names alone don't say when to use the model.
+fn seed_when_to_use(
+    description: Option<&str>,
+    dimensions: &[Dimension],
+    measures: &[Measure],
+) -> Option<String> {
+    let description = description
+        .map(str::trim)
+        .filter(|description| !description.is_empty())?;
+    let description = if description.ends_with('.') {
+        description.to_string()
+    } else {
+        format!("{}.", description)
+    };
+
+    const MAX_LISTED: usize = 5;
+    let list = |names: Vec<&str>| {
+        if names.len() > MAX_LISTED {
+            format!("{} and {} more", names[..MAX_LISTED].join(", "), names.len() - MAX_LISTED)
+        } else {
+            match names.split_last() {
+                Some((last, [])) => last.to_string(),
+                Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+                None => String::new(),
+            }
+        }
+    };
+    // Hidden columns aren't what end users query by
+    let dimensions = list(
+        dimensions
+            .iter()
+            .filter(|d| d.hidden != Some(true))
+            .map(|d| d.name.as_str())
+            .collect(),
+    );
+    let measures = list(
+        measures
+            .iter()
+            .filter(|m| m.hidden != Some(true))
+            .map(|m| m.name.as_str())
+            .collect(),
+    );
+
+    let usage = match (measures.is_empty(), dimensions.is_empty()) {
+        (false, false) => Some(format!(
+            "Use this model to analyze {} by {}.",
+            measures, dimensions
+        )),
+        (false, true) => Some(format!("Use this model to analyze {}.", measures)),
+        (true, false) => Some(format!("Use this model to look up records by {}.", dimensions)),
+        (true, true) => None,
+    };
+
+    Some(match usage {
+        Some(usage) => format!("{} {}", description, usage),
+        None => description,
+    })
+}
+
+/// Whether a column is a surrogate key or audit timestamp, which are modeled for joins and
+/// lineage but hidden from end users by default.
+fn is_technical_column(name: &str) -> bool {
+    let name = name.to_lowercase();
+    name.ends_with("_id") || name == "created_at" || name == "updated_at"
+}
+
+/// Display label derived from a snake_case name, e.g. `total_revenue` becomes
+/// "Total Revenue".
+fn title_case_label(name: &str) -> String {
+    name.split(|c: char| c == '_' || c.is_whitespace())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first
+                    .to_uppercase()
+                    .chain(chars.flat_map(char::to_lowercase))
+                    .collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Whether a warehouse column type holds text. Sampling is limited to these so long
+/// numeric or binary values don't end up in descriptions.
+fn is_string_type(type_str: &str) -> bool {
+    let type_lower = type_str.to_lowercase();
+    ["char", "text", "string"]
+        .iter()
+        .any(|t| type_lower.contains(t))
 }
 
 pub async fn generate_datasets(
     Extension(user): Extension<User>,
     Json(request): Json<GenerateDatasetRequest>,
-) -> Result<ApiResponse<GenerateDatasetResponse>, (StatusCode, String)> {
+) -> Result<Response, (StatusCode, String)> {
     // Check if user is workspace admin or data admin
     let organization_id = match get_user_organization_id(&user.id).await {
         Ok(id) => id,
         Err(e) => {
             tracing::error!("Error getting user organization id: {:?}", e);
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Error getting user organization id".to_string(),
-            ));
+            return Err((e.status_code(), e.public_message().to_string()));
         }
     };
 
@@ -150,8 +751,17 @@ pub async fn generate_datasets(
         }
     }
 
+    if let Err(retry_after) = GENERATE_RATE_LIMITER.check(&organization_id) {
+        tracing::warn!(
+            "Rate limited generate for organization {}, retry after {:?}",
+            organization_id,
+            retry_after
+        );
+        return Ok(rate_limited_response(retry_after));
+    }
+
     match generate_datasets_handler(&request, &organization_id).await {
-        Ok(response) => Ok(ApiResponse::JsonData(response)),
+        Ok(response) => Ok(ApiResponse::JsonData(response).into_response()),
         Err(e) => {
             tracing::error!("Error generating datasets: {:?}", e);
             Err((
@@ -210,17 +820,36 @@ async fn enhance_yaml_with_descriptions(yaml: String) -> Result<String> {
     Ok(yaml)
 }
 
-async fn generate_model_yaml(
+/// What building every model of a request shares, set up once per request.
+struct GenerateContext {
+    ds_columns: Vec<DatasetColumnRecord>,
+    schema: String,
+    identifier_case: IdentifierCase,
+    /// The data source string dimensions are sampled from, when sampling
+    sample_source: Option<DataSource>,
+    default_searchable: bool,
+    default_agg: MeasureAgg,
+    data_source_type: DataSourceType,
+    /// The columns came from a dbt catalog, whose descriptions are kept
+    from_catalog: bool,
+    flatten_hints: Vec<FlattenHint>,
+}
+
+/// Builds a model from its warehouse columns, before descriptions are written for it.
+async fn build_model(
+    context: &GenerateContext,
     model_name: &str,
-    ds_columns: &[DatasetColumnRecord],
-    schema: &str,
-) -> Result<String> {
-    // Filter columns for this model
-    let model_columns: Vec<_> = ds_columns
+    model_description: Option<&str>,
+    entities: Vec<Entity>,
+    key_columns: &[String],
+) -> Result<Model> {
+    // Filter columns for this model, in table order so regenerating gives stable YAML
+    let mut model_columns: Vec<_> = context
+        .ds_columns
         .iter()
         .filter(|col| {
             col.dataset_name.to_lowercase() == model_name.to_lowercase()
-                && col.schema_name.to_lowercase() == schema.to_lowercase()
+                && col.schema_name.to_lowercase() == context.schema.to_lowercase()
         })
         .collect();
 
@@ -228,47 +857,166 @@ async fn generate_model_yaml(
         return Err(anyhow!("No columns found for model"));
     }
 
+    model_columns.sort_by_key(|col| col.ordinal);
+
     let mut dimensions = Vec::new();
     let mut measures = Vec::new();
 
+    // Catalog descriptions are kept; everything else is left for the description pass
+    let column_description = |col: &DatasetColumnRecord| {
+        col.comment
+            .as_deref()
+            .map(str::trim)
+            .filter(|comment| context.from_catalog && !comment.is_empty())
+            .unwrap_or("{NEED DESCRIPTION HERE}")
+            .to_string()
+    };
+
+    // Key columns are modeled as entities rather than summed or grouped on
+    let entity_exprs: Vec<&str> = entities.iter().map(|entity| entity.expr.as_str()).collect();
+
     // Process each column and categorize as dimension or measure
     for col in model_columns {
-        match map_snowflake_type(&col.type_) {
-            ColumnMappingType::Dimension(semantic_type) => {
+        let class = classify_column(
+            col,
+            context.data_source_type,
+            &entity_exprs,
+            key_columns,
+            &context.flatten_hints,
+        )?;
+
+        match class {
+            ColumnClass::Entity => (),
+            ColumnClass::Field {
+                mapping: ColumnMappingType::Dimension(semantic_type),
+                ..
+            } => {
+                let mut description = column_description(col);
+
+                // Examples sit outside the placeholder so they survive the description pass
+                if let Some(data_source) = context
+                    .sample_source
+                    .as_ref()
+                    .filter(|_| is_string_type(&col.type_))
+                {
+                    let sampled = sample_column_values(
+                        data_source,
+                        &col.schema_name,
+                        &col.dataset_name,
+                        &col.name,
+                    )
+                    .await;
+
+                    match sampled {
+                        Ok(values) => {
+                            if let Some(examples) = format_sample_values(&values) {
+                                description = format!("{} ({})", description, examples);
+                            }
+                        }
+                        Err(e) => tracing::warn!(
+                            "Unable to sample values for column {}.{}: {:?}",
+                            col.dataset_name,
+                            col.name,
+                            e
+                        ),
+                    }
+                }
+
                 dimensions.push(Dimension {
-                    name: col.name.clone(),
-                    expr: col.name.clone(),
+                    name: context.identifier_case.apply(&col.name),
+                    label: Some(title_case_label(&col.name)),
+                    expr: column_expr(context.data_source_type, &col.name),
                     type_: semantic_type,
-                    description: "{NEED DESCRIPTION HERE}".to_string(),
-                    searchable: Some(false),
+                    description,
+                    searchable: Some(context.default_searchable),
+                    hidden: is_technical_column(&col.name).then_some(true),
                 });
             }
-            ColumnMappingType::Measure(measure_type) => {
+            ColumnClass::Field {
+                mapping: ColumnMappingType::Measure(measure_type),
+                ..
+            } => {
                 measures.push(Measure {
-                    name: col.name.clone(),
-                    expr: col.name.clone(),
+                    name: context.identifier_case.apply(&col.name),
+                    label: Some(title_case_label(&col.name)),
+                    expr: column_expr(context.data_source_type, &col.name),
                     type_: measure_type,
-                    agg: Some("sum".to_string()),
-                    description: "{NEED DESCRIPTION HERE}".to_string(),
+                    agg: Some(context.default_agg.as_str().to_string()),
+                    description: column_description(col),
+                    hidden: is_technical_column(&col.name).then_some(true),
                 });
             }
-            ColumnMappingType::Unsupported => {
+            ColumnClass::Field {
+                mapping: ColumnMappingType::Unsupported,
+                ..
+            }
+            | ColumnClass::Unsupported => {
                 tracing::warn!(
                     "Skipping unsupported column type: {} for column: {}",
                     col.type_,
                     col.name
                 );
             }
+            ColumnClass::Flattened(fields) => {
+                for field in fields {
+                    match field.mapping {
+                        ColumnMappingType::Dimension(semantic_type) => {
+                            dimensions.push(Dimension {
+                                name: context.identifier_case.apply(&field.name),
+                                label: Some(title_case_label(&field.name)),
+                                expr: field.expr,
+                                type_: semantic_type,
+                                description: "{NEED DESCRIPTION HERE}".to_string(),
+                                searchable: Some(context.default_searchable),
+                                hidden: None,
+                            })
+                        }
+                        ColumnMappingType::Measure(measure_type) => measures.push(Measure {
+                            name: context.identifier_case.apply(&field.name),
+                            label: Some(title_case_label(&field.name)),
+                            expr: field.expr,
+                            type_: measure_type,
+                            agg: Some(context.default_agg.as_str().to_string()),
+                            description: "{NEED DESCRIPTION HERE}".to_string(),
+                            hidden: None,
+                        }),
+                        ColumnMappingType::Unsupported => {}
+                    }
+                }
+            }
+        }
+    }
+
+    // Guards against type mapping or the default agg producing measures that can't aggregate
+    for measure in &measures {
+        if let Some(reason) = measure
+            .agg
+            .as_deref()
+            .and_then(|agg| measure_agg_mismatch(&measure.type_, agg))
+        {
+            return Err(anyhow!("Measure '{}': {}", measure.name, reason));
         }
     }
 
     let model = Model {
         name: model_name.to_string(),
-        description: format!("Generated model for {}", model_name),
+        label: Some(title_case_label(model_name)),
+        when_to_use: seed_when_to_use(model_description, &dimensions, &measures),
+        description: model_description
+            .map(str::trim)
+            .filter(|description| !description.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("Generated model for {}", model_name)),
+        entities,
         dimensions,
         measures,
     };
 
+    Ok(model)
+}
+
+/// A model's YAML, with descriptions written for the fields generate couldn't describe.
+async fn generate_model_yaml(model: Model) -> Result<String> {
     let config = ModelConfig {
         models: vec![model],
     };
@@ -299,32 +1047,176 @@ async fn generate_datasets_handler(
         Err(e) => return Err(anyhow!("Data source not found: {}", e)),
     };
 
-    // Get credentials
-    let credentials = get_data_source_credentials(&data_source.secret_id, &data_source.type_, false).await?;
+    let data_source_type = match &request.catalog {
+        Some(_) => data_source.type_,
+        None => resolve_data_source_type(&data_source, request.dialect_autodetect).await,
+    };
+
+    // Catalog columns stand in for the warehouse, typed with the recorded dialect
+    let (ds_columns, from_catalog) = match &request.catalog {
+        Some(catalog) => (catalog_columns(catalog, &request.schema), true),
+        None => {
+            // Get credentials
+            let credentials =
+                get_data_source_credentials(&data_source, &data_source_type, false).await?;
+
+            // Prepare tables for batch validation
+            let tables_to_validate: Vec<(String, String)> = request
+                .model_names
+                .iter()
+                .map(|name| (name.clone(), request.schema.clone()))
+                .collect();
+
+            if request.dry_run_sql {
+                return Ok(GenerateDatasetResponse {
+                    yml_contents: HashMap::new(),
+                    errors: HashMap::new(),
+                    unsupported_columns: Vec::new(),
+                    warnings: Vec::new(),
+                    model_checks: Vec::new(),
+                    metadata_queries: dataset_columns_batch_sql(
+                        &tables_to_validate,
+                        &credentials,
+                        request.database.as_deref(),
+                    )?,
+                });
+            }
 
-    // Prepare tables for batch validation
-    let tables_to_validate: Vec<(String, String)> = request
-        .model_names
+            // Get all columns in one batch
+            let ds_columns = match retrieve_dataset_columns_batch(&tables_to_validate, &credentials, request.database.clone()).await {
+                Ok(cols) => cols,
+                Err(e) => return Err(anyhow!("Failed to get columns from data source: {}", e)),
+            };
+            (ds_columns, false)
+        }
+    };
+    let model_descriptions: HashMap<String, String> = request
+        .catalog
         .iter()
-        .map(|name| (name.clone(), request.schema.clone()))
+        .flatten()
+        .filter_map(|model| Some((model.name.to_lowercase(), model.description.clone()?)))
         .collect();
 
-    // Get all columns in one batch
-    let ds_columns = match retrieve_dataset_columns_batch(&tables_to_validate, &credentials, request.database.clone()).await {
-        Ok(cols) => cols,
-        Err(e) => return Err(anyhow!("Failed to get columns from data source: {}", e)),
+    if request.report_unsupported {
+        return Ok(GenerateDatasetResponse {
+            yml_contents: HashMap::new(),
+            errors: HashMap::new(),
+            unsupported_columns: unsupported_columns(
+                &request.model_names,
+                &request.schema,
+                &ds_columns,
+                data_source_type,
+                &request.flatten_hints,
+            ),
+            warnings: Vec::new(),
+            model_checks: Vec::new(),
+            metadata_queries: Vec::new(),
+        });
+    }
+
+    // Read before checking too, so a check classifies key columns as generate does
+    let mut warnings =
+        unmatched_flatten_hints(&request.flatten_hints, &ds_columns, data_source_type);
+    let keys = if !request.emit_entities_from_fk || from_catalog {
+        TableKeys::default()
+    } else if !reads_foreign_keys(&data_source_type) {
+        warnings.push(format!(
+            "No entities were generated from foreign keys: {} doesn't enforce them",
+            data_source_type.to_string()
+        ));
+        TableKeys::default()
+    } else {
+        match table_keys(&data_source, &request.schema).await {
+            Ok(keys) => keys,
+            Err(e) => {
+                tracing::warn!("Unable to read keys of schema {}: {:?}", request.schema, e);
+                warnings.push(format!(
+                    "No entities were generated from foreign keys: reading them failed: {}",
+                    e
+                ));
+                TableKeys::default()
+            }
+        }
     };
 
+    if request.check {
+        let mut model_checks = Vec::new();
+        let mut errors = HashMap::new();
+        for model_name in &request.model_names {
+            let entities = model_entities(
+                model_name,
+                &keys,
+                &request.model_names,
+                request.identifier_case,
+                data_source_type,
+            );
+            match check_model(
+                model_name,
+                &ds_columns,
+                &request.schema,
+                data_source_type,
+                &request.flatten_hints,
+                &entities,
+                &model_key_columns(model_name, &keys),
+            ) {
+                Ok(check) => model_checks.push(check),
+                Err(e) => {
+                    errors.insert(model_name.clone(), e.to_string());
+                }
+            }
+        }
+
+        return Ok(GenerateDatasetResponse {
+            yml_contents: HashMap::new(),
+            errors,
+            unsupported_columns: Vec::new(),
+            warnings,
+            model_checks,
+            metadata_queries: Vec::new(),
+        });
+    }
+
     // Process models concurrently
     let mut join_set = JoinSet::new();
     
+    let context = Arc::new(GenerateContext {
+        ds_columns,
+        schema: request.schema.clone(),
+        identifier_case: request.identifier_case,
+        sample_source: (request.sample_values && !from_catalog).then(|| data_source.clone()),
+        default_searchable: request.default_searchable,
+        default_agg: request.default_agg,
+        data_source_type,
+        from_catalog,
+        flatten_hints: request.flatten_hints.clone(),
+    });
+
     for model_name in &request.model_names {
         let model_name = model_name.clone();
-        let schema = request.schema.clone();
-        let ds_columns = ds_columns.clone();
+        let context = Arc::clone(&context);
+        let model_description = model_descriptions.get(&model_name.to_lowercase()).cloned();
+        let entities = model_entities(
+            &model_name,
+            &keys,
+            &request.model_names,
+            request.identifier_case,
+            data_source_type,
+        );
+        let key_columns = model_key_columns(&model_name, &keys);
         
         join_set.spawn(async move {
-            let result = generate_model_yaml(&model_name, &ds_columns, &schema).await;
+            let model = build_model(
+                &context,
+                &model_name,
+                model_description.as_deref(),
+                entities,
+                &key_columns,
+            )
+            .await;
+            let result = match model {
+                Ok(model) => generate_model_yaml(model).await,
+                Err(e) => Err(e),
+            };
             (model_name, result)
         });
     }
@@ -350,5 +1242,520 @@ async fn generate_datasets_handler(
     Ok(GenerateDatasetResponse {
         yml_contents,
         errors,
+        unsupported_columns: Vec::new(),
+        warnings,
+        model_checks: Vec::new(),
+        metadata_queries: Vec::new(),
     })
-} 
\ No newline at end of file
+}
+
+/// Counts how `build_model` would model a model's columns, through the same
+/// `classify_column`, including the fields flatten hints extract from unsupported columns.
+fn check_model(
+    model_name: &str,
+    ds_columns: &[DatasetColumnRecord],
+    schema: &str,
+    data_source_type: DataSourceType,
+    flatten_hints: &[FlattenHint],
+    entities: &[Entity],
+    key_columns: &[String],
+) -> Result<ModelCheck> {
+    let mut model_columns: Vec<_> = ds_columns
+        .iter()
+        .filter(|col| {
+            col.dataset_name.to_lowercase() == model_name.to_lowercase()
+                && col.schema_name.to_lowercase() == schema.to_lowercase()
+        })
+        .collect();
+
+    if model_columns.is_empty() {
+        return Err(anyhow!("No columns found for model"));
+    }
+
+    model_columns.sort_by_key(|col| col.ordinal);
+
+    let mut check = ModelCheck {
+        model_name: model_name.to_string(),
+        entities: entities.len(),
+        dimensions: 0,
+        measures: 0,
+        unsupported: 0,
+        defaulted_columns: Vec::new(),
+    };
+    let entity_exprs: Vec<&str> = entities.iter().map(|entity| entity.expr.as_str()).collect();
+    for col in model_columns {
+        let mappings = match classify_column(
+            col,
+            data_source_type,
+            &entity_exprs,
+            key_columns,
+            flatten_hints,
+        )? {
+            ColumnClass::Entity => Vec::new(),
+            ColumnClass::Field { mapping, defaulted } => {
+                if defaulted {
+                    check.defaulted_columns.push(DefaultedColumn {
+                        column_name: col.name.clone(),
+                        data_type: col.type_.clone(),
+                    });
+                }
+                vec![mapping]
+            }
+            ColumnClass::Flattened(fields) => {
+                fields.into_iter().map(|field| field.mapping).collect()
+            }
+            ColumnClass::Unsupported => vec![ColumnMappingType::Unsupported],
+        };
+
+        for mapping in mappings {
+            match mapping {
+                ColumnMappingType::Dimension(_) => check.dimensions += 1,
+                ColumnMappingType::Measure(_) => check.measures += 1,
+                ColumnMappingType::Unsupported => check.unsupported += 1,
+            }
+        }
+    }
+
+    Ok(check)
+}
+
+/// Entities for a model's declared keys: a primary entity for its primary key, and a
+/// foreign entity named after the referenced model for each foreign key pointing at a
+/// model generated alongside it. When several keys point at the same model, each is named
+/// after its column instead, with `ref_` naming the model.
+fn model_entities(
+    model_name: &str,
+    keys: &TableKeys,
+    model_names: &[String],
+    identifier_case: IdentifierCase,
+    data_source_type: DataSourceType,
+) -> Vec<Entity> {
+    let primary = keys
+        .primary_keys
+        .get(&model_name.to_lowercase())
+        .map(|column| Entity {
+            name: identifier_case.apply(column),
+            ref_: None,
+            expr: column_expr(data_source_type, column),
+            type_: "primary".to_string(),
+            description: format!("Primary key of {}", model_name),
+        });
+
+    let foreign_keys: Vec<(&ForeignKey, &String)> = keys
+        .foreign_keys
+        .iter()
+        .filter(|fk| fk.table.eq_ignore_ascii_case(model_name))
+        .filter_map(|fk| {
+            let referenced_model = model_names
+                .iter()
+                .find(|name| name.eq_ignore_ascii_case(&fk.referenced_table))?;
+            Some((fk, referenced_model))
+        })
+        .collect();
+    let foreign = foreign_keys.iter().map(|(fk, referenced_model)| {
+        let shared = foreign_keys
+            .iter()
+            .filter(|(_, other)| other == referenced_model)
+            .count()
+            > 1;
+        Entity {
+            name: if shared {
+                identifier_case.apply(&fk.column)
+            } else {
+                referenced_model.to_string()
+            },
+            ref_: shared.then(|| referenced_model.to_string()),
+            expr: column_expr(data_source_type, &fk.column),
+            type_: "foreign".to_string(),
+            description: format!(
+                "References {}.{}",
+                fk.referenced_table, fk.referenced_column
+            ),
+        }
+    });
+
+    primary.into_iter().chain(foreign).collect()
+}
+
+/// Lowercased columns of a model that take part in a declared key: its primary key, its
+/// foreign keys, whether or not the referenced model is generated, and columns other
+/// tables' foreign keys reference.
+fn model_key_columns(model_name: &str, keys: &TableKeys) -> Vec<String> {
+    let primary = keys.primary_keys.get(&model_name.to_lowercase()).cloned();
+    let foreign = keys.foreign_keys.iter().flat_map(|fk| {
+        let column = fk
+            .table
+            .eq_ignore_ascii_case(model_name)
+            .then(|| fk.column.clone());
+        let referenced = fk
+            .referenced_table
+            .eq_ignore_ascii_case(model_name)
+            .then(|| fk.referenced_column.clone());
+        column.into_iter().chain(referenced)
+    });
+
+    let mut columns: Vec<String> = primary
+        .into_iter()
+        .chain(foreign)
+        .map(|column| column.to_lowercase())
+        .collect();
+    columns.sort();
+    columns.dedup();
+    columns
+}
+
+/// Columns of the requested models that `build_model` skips as unsupported, in
+/// model and table order. Columns a flatten hint names are left out, since generate
+/// extracts fields from them.
+fn unsupported_columns(
+    model_names: &[String],
+    schema: &str,
+    ds_columns: &[DatasetColumnRecord],
+    data_source_type: DataSourceType,
+    flatten_hints: &[FlattenHint],
+) -> Vec<UnsupportedColumn> {
+    let mut columns: Vec<_> = ds_columns
+        .iter()
+        .filter(|col| col.schema_name.to_lowercase() == schema.to_lowercase())
+        .filter(|col| {
+            model_names
+                .iter()
+                .any(|name| name.to_lowercase() == col.dataset_name.to_lowercase())
+        })
+        .filter(|col| {
+            matches!(
+                map_column_type(data_source_type, &col.type_),
+                ColumnMappingType::Unsupported
+            )
+        })
+        .filter(|col| !flatten_hints.iter().any(|hint| hint.applies_to(col)))
+        .collect();
+    columns.sort_by_key(|col| (col.dataset_name.to_lowercase(), col.ordinal));
+
+    columns
+        .into_iter()
+        .map(|col| UnsupportedColumn {
+            model_name: col.dataset_name.clone(),
+            column_name: col.name.clone(),
+            data_type: col.type_.clone(),
+        })
+        .collect()
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::query_engine::import_dataset_columns::column;
+
+    #[test]
+    fn test_title_case_label() {
+        assert_eq!(title_case_label("total_revenue"), "Total Revenue");
+        assert_eq!(title_case_label("ORDER_COUNT"), "Order Count");
+        assert_eq!(title_case_label("_loaded__at"), "Loaded At");
+    }
+
+    #[test]
+    fn test_seed_when_to_use() {
+        let dimension = |name: &str, hidden: bool| Dimension {
+            name: name.to_string(),
+            label: None,
+            expr: name.to_string(),
+            type_: "string".to_string(),
+            description: String::new(),
+            searchable: None,
+            hidden: hidden.then_some(true),
+        };
+        let measure = |name: &str| Measure {
+            name: name.to_string(),
+            label: None,
+            expr: name.to_string(),
+            type_: "number".to_string(),
+            agg: Some("sum".to_string()),
+            description: String::new(),
+            hidden: None,
+        };
+        let dimensions = vec![
+            dimension("status", false),
+            dimension("region", false),
+            dimension("loaded_at", true),
+        ];
+        let measures = vec![measure("amount")];
+
+        assert_eq!(
+            seed_when_to_use(Some(" One row per order "), &dimensions, &measures).as_deref(),
+            Some("One row per order. Use this model to analyze amount by status and region.")
+        );
+        assert_eq!(
+            seed_when_to_use(Some("Orders."), &[], &measures).as_deref(),
+            Some("Orders. Use this model to analyze amount.")
+        );
+        assert_eq!(
+            seed_when_to_use(Some("Orders"), &dimensions[..1], &[]).as_deref(),
+            Some("Orders. Use this model to look up records by status.")
+        );
+        assert_eq!(seed_when_to_use(Some("Orders"), &[], &[]).as_deref(), Some("Orders."));
+
+        // Without a description there's nothing to seed it from
+        assert_eq!(seed_when_to_use(None, &dimensions, &measures), None);
+        assert_eq!(seed_when_to_use(Some("  "), &dimensions, &measures), None);
+
+        let many: Vec<Dimension> = ["a", "b", "c", "d", "e", "f", "g"]
+            .into_iter()
+            .map(|name| dimension(name, false))
+            .collect();
+        assert_eq!(
+            seed_when_to_use(Some("Wide"), &many, &[]).as_deref(),
+            Some("Wide. Use this model to look up records by a, b, c, d, e and 2 more.")
+        );
+    }
+
+    #[test]
+    fn test_column_expr() {
+        // Case the warehouse folds to, or doesn't care about, stays bare
+        assert_eq!(column_expr(DataSourceType::Postgres, "order_id"), "order_id");
+        assert_eq!(column_expr(DataSourceType::Snowflake, "ORDER_ID"), "ORDER_ID");
+        assert_eq!(column_expr(DataSourceType::BigQuery, "OrderId"), "OrderId");
+
+        // Mixed case keeps its case, quoted where unquoted names are folded
+        assert_eq!(column_expr(DataSourceType::Postgres, "OrderId"), "\"OrderId\"");
+        assert_eq!(column_expr(DataSourceType::Snowflake, "order_id"), "\"order_id\"");
+        assert_eq!(column_expr(DataSourceType::BigQuery, "order id"), "`order id`");
+        assert_eq!(column_expr(DataSourceType::MySql, "order"), "`order`");
+        assert_eq!(column_expr(DataSourceType::SqlServer, "2nd_line"), "[2nd_line]");
+    }
+
+    #[test]
+    fn test_model_key_columns() {
+        let foreign_key = |table: &str, column: &str, referenced: (&str, &str)| ForeignKey {
+            table: table.to_string(),
+            column: column.to_string(),
+            referenced_table: referenced.0.to_string(),
+            referenced_column: referenced.1.to_string(),
+        };
+        let keys = TableKeys {
+            primary_keys: [("orders".to_string(), "ID".to_string())].into(),
+            foreign_keys: vec![
+                foreign_key("orders", "customer_id", ("customers", "id")),
+                foreign_key("ORDERS", "warehouse_id", ("warehouses", "id")),
+                foreign_key("order_lines", "order_number", ("orders", "number")),
+                foreign_key("refunds", "order_id", ("orders", "id")),
+            ],
+        };
+
+        assert_eq!(
+            model_key_columns("orders", &keys),
+            vec!["customer_id", "id", "number", "warehouse_id"]
+        );
+        assert_eq!(model_key_columns("customers", &keys), vec!["id"]);
+        assert!(model_key_columns("payments", &keys).is_empty());
+    }
+
+    #[test]
+    fn test_model_entities() {
+        let foreign_key = |column: &str, referenced: &str| ForeignKey {
+            table: "orders".to_string(),
+            column: column.to_string(),
+            referenced_table: referenced.to_string(),
+            referenced_column: "id".to_string(),
+        };
+        let keys = TableKeys {
+            primary_keys: [("orders".to_string(), "ID".to_string())].into(),
+            foreign_keys: vec![
+                foreign_key("customer_id", "customers"),
+                foreign_key("Billing_Address_Id", "ADDRESSES"),
+                foreign_key("shipping_address_id", "addresses"),
+                foreign_key("warehouse_id", "warehouses"),
+            ],
+        };
+        let model_names = vec![
+            "orders".to_string(),
+            "customers".to_string(),
+            "addresses".to_string(),
+        ];
+
+        let entities = model_entities(
+            "orders",
+            &keys,
+            &model_names,
+            IdentifierCase::Lower,
+            DataSourceType::Postgres,
+        );
+        let summary: Vec<(&str, Option<&str>, &str, &str)> = entities
+            .iter()
+            .map(|entity| {
+                (
+                    entity.name.as_str(),
+                    entity.ref_.as_deref(),
+                    entity.expr.as_str(),
+                    entity.type_.as_str(),
+                )
+            })
+            .collect();
+        // Keys to a model that isn't generated (warehouses) get no entity
+        assert_eq!(
+            summary,
+            vec![
+                ("id", None, "\"ID\"", "primary"),
+                ("customers", None, "customer_id", "foreign"),
+                ("billing_address_id", Some("addresses"), "\"Billing_Address_Id\"", "foreign"),
+                ("shipping_address_id", Some("addresses"), "shipping_address_id", "foreign"),
+            ]
+        );
+        assert_eq!(entities[1].description, "References customers.id");
+
+        // Only models referenced more than once carry `ref_`
+        let yaml = serde_yaml::to_string(&entities).unwrap();
+        assert_eq!(yaml.matches("ref_: addresses").count(), 2);
+        assert!(!yaml.contains("ref_: customers"));
+
+        assert!(model_entities(
+            "customers",
+            &keys,
+            &model_names,
+            IdentifierCase::Lower,
+            DataSourceType::Postgres,
+        )
+        .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_check_agrees_with_generate_on_key_columns() -> Result<()> {
+        let column_at = |name: &str, type_: &str, ordinal: i32| DatasetColumnRecord {
+            ordinal,
+            ..column(name, type_)
+        };
+        let ds_columns = vec![
+            column_at("id", "integer", 1),
+            column_at("customer_id", "integer", 2),
+            column_at("warehouse_id", "integer", 3),
+            column_at("amount", "numeric", 4),
+            column_at("status", "text", 5),
+        ];
+        let keys = TableKeys {
+            primary_keys: [("orders".to_string(), "id".to_string())].into(),
+            foreign_keys: vec![
+                ForeignKey {
+                    table: "orders".to_string(),
+                    column: "customer_id".to_string(),
+                    referenced_table: "customers".to_string(),
+                    referenced_column: "id".to_string(),
+                },
+                // Not generated alongside, so a key column but no entity
+                ForeignKey {
+                    table: "orders".to_string(),
+                    column: "warehouse_id".to_string(),
+                    referenced_table: "warehouses".to_string(),
+                    referenced_column: "id".to_string(),
+                },
+            ],
+        };
+        let model_names = vec!["orders".to_string(), "customers".to_string()];
+        let entities = || {
+            model_entities(
+                "orders",
+                &keys,
+                &model_names,
+                IdentifierCase::Preserve,
+                DataSourceType::Postgres,
+            )
+        };
+        let key_columns = model_key_columns("orders", &keys);
+
+        let check = check_model(
+            "orders",
+            &ds_columns,
+            "public",
+            DataSourceType::Postgres,
+            &[],
+            &entities(),
+            &key_columns,
+        )?;
+        let context = GenerateContext {
+            ds_columns,
+            schema: "public".to_string(),
+            identifier_case: IdentifierCase::Preserve,
+            sample_source: None,
+            default_searchable: false,
+            default_agg: MeasureAgg::Sum,
+            data_source_type: DataSourceType::Postgres,
+            from_catalog: false,
+            flatten_hints: Vec::new(),
+        };
+        let model = build_model(&context, "orders", None, entities(), &key_columns).await?;
+
+        // Numeric keys are grouped on, not summed, in both
+        let dimensions: Vec<&str> = model.dimensions.iter().map(|d| d.name.as_str()).collect();
+        let measures: Vec<&str> = model.measures.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(dimensions, vec!["warehouse_id", "status"]);
+        assert_eq!(measures, vec!["amount"]);
+        assert_eq!(
+            (check.entities, check.dimensions, check.measures, check.unsupported),
+            (model.entities.len(), model.dimensions.len(), model.measures.len(), 0)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_column_types_follow_dialect() {
+        // BigQuery's INT64 isn't a Snowflake type, so it used to default to a string
+        assert!(matches!(
+            map_column_type(DataSourceType::BigQuery, "INT64"),
+            ColumnMappingType::Measure(type_) if type_ == "number"
+        ));
+        assert!(matches!(
+            map_column_type(DataSourceType::Postgres, "jsonb"),
+            ColumnMappingType::Unsupported
+        ));
+        // Names the dialect doesn't list still map by their Snowflake names
+        assert!(matches!(
+            map_column_type(DataSourceType::Redshift, "varchar"),
+            ColumnMappingType::Dimension(type_) if type_ == "string"
+        ));
+    }
+
+    #[test]
+    fn test_flatten_expr() -> Result<()> {
+        assert_eq!(
+            flatten_expr(DataSourceType::Snowflake, "payload", ":customer.id", "number")?,
+            "payload:customer.id::FLOAT"
+        );
+        assert_eq!(
+            flatten_expr(DataSourceType::Postgres, "items", "[0]:sku", "string")?,
+            "(items #>> '{0,sku}')::TEXT"
+        );
+        assert_eq!(
+            flatten_expr(DataSourceType::BigQuery, "items", "[0]:sku", "string")?,
+            "CAST(JSON_VALUE(items, '$[0].sku') AS STRING)"
+        );
+        assert!(flatten_expr(DataSourceType::MySql, "payload", ":id", "string").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_unmatched_flatten_hints() {
+        let ds_columns = vec![column("payload", "jsonb"), column("status", "text")];
+        let hint = |model_name: &str, path: &str| FlattenHint {
+            model_name: model_name.to_string(),
+            path: path.to_string(),
+            name: None,
+            type_: None,
+        };
+        let hints = [
+            hint("orders", "payload:customer.id"),
+            hint("orders", "status:code"),
+            hint("orders", "items[0]:sku"),
+            hint("customers", "payload:email"),
+        ];
+
+        assert_eq!(
+            unmatched_flatten_hints(&hints, &ds_columns, DataSourceType::Postgres),
+            vec![
+                "Flatten hint 'status:code' on model 'orders' was ignored: column 'status' is a \
+                 text column, not an ARRAY, OBJECT or VARIANT",
+                "Flatten hint 'items[0]:sku' on model 'orders' matches no column",
+                "Flatten hint 'payload:email' on model 'customers' matches no column",
+            ]
+        );
+    }
+}
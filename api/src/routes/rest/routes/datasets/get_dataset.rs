@@ -3,6 +3,7 @@ use axum::{extract::Path, Extension};
 use diesel::{ExpressionMethods, JoinOnDsl, QueryDsl};
 use diesel_async::RunQueryDsl;
 use serde::Serialize;
+use serde_json::Value;
 use uuid::Uuid;
 
 use crate::{
@@ -36,6 +37,9 @@ pub struct GetDatasetResponse {
     pub name: String,
     pub sql: Option<String>,
     pub yml_file: Option<String>,
+    pub refresh_schedule: Option<Value>,
+    pub primary_time_dimension: Option<String>,
+    pub grain: Option<Value>,
     pub data_source_name: String,
     pub data_source_type: String,
     pub data_source_id: Uuid,
@@ -98,6 +102,9 @@ async fn get_dataset_handler(dataset_id: &Uuid, user: &User) -> Result<GetDatase
         sql,
         when_to_use,
         yml_file,
+        refresh_schedule,
+        primary_time_dimension,
+        grain,
         data_source_name,
         data_source_type,
         data_source_id,
@@ -111,6 +118,9 @@ async fn get_dataset_handler(dataset_id: &Uuid, user: &User) -> Result<GetDatase
             datasets::definition,
             datasets::when_to_use,
             datasets::yml_file,
+            datasets::refresh_schedule,
+            datasets::primary_time_dimension,
+            datasets::grain,
             data_sources::name,
             data_sources::type_,
             data_sources::id,
@@ -121,6 +131,9 @@ async fn get_dataset_handler(dataset_id: &Uuid, user: &User) -> Result<GetDatase
             String,
             Option<String>,
             Option<String>,
+            Option<Value>,
+            Option<String>,
+            Option<Value>,
             String,
             String,
             Uuid,
@@ -137,6 +150,9 @@ async fn get_dataset_handler(dataset_id: &Uuid, user: &User) -> Result<GetDatase
         sql: Some(sql),
         when_to_use,
         yml_file,
+        refresh_schedule,
+        primary_time_dimension,
+        grain,
         data_source_name,
         data_source_type,
         data_source_id,
@@ -0,0 +1,209 @@
+use anyhow::Result;
+use axum::{extract::Query, Extension};
+use diesel::{ExpressionMethods, QueryDsl};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use reqwest::StatusCode;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    database::{
+        lib::get_pg_pool,
+        models::{DeployEvent, User},
+        schema::deploy_events,
+    },
+    routes::rest::ApiResponse,
+    utils::{
+        security::checks::is_user_workspace_admin_or_data_admin,
+        user::user_info::get_user_organization_id,
+    },
+};
+
+const DEFAULT_PAGE_SIZE: i64 = 25;
+/// Larger page sizes are clamped to this, so one request can't read the whole audit log.
+const MAX_PAGE_SIZE: i64 = 100;
+
+#[derive(Deserialize)]
+pub struct ListDeployEventsQuery {
+    pub page: Option<i64>,
+    pub page_size: Option<i64>,
+}
+
+pub async fn list_deploy_events(
+    Extension(user): Extension<User>,
+    Query(query): Query<ListDeployEventsQuery>,
+) -> Result<ApiResponse<Vec<DeployEvent>>, (StatusCode, String)> {
+    let (offset, page_size) = match page_bounds(query.page, query.page_size) {
+        Ok(bounds) => bounds,
+        Err(message) => return Err((StatusCode::BAD_REQUEST, message)),
+    };
+
+    let organization_id = match get_user_organization_id(&user.id).await {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::error!("Error getting user organization id: {:?}", e);
+            return Err((e.status_code(), e.public_message().to_string()));
+        }
+    };
+
+    match is_user_workspace_admin_or_data_admin(&user, &organization_id).await {
+        Ok(true) => (),
+        Ok(false) => {
+            return Err((
+                StatusCode::FORBIDDEN,
+                "Insufficient permissions".to_string(),
+            ))
+        }
+        Err(e) => {
+            tracing::error!("Error checking user permissions: {:?}", e);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()));
+        }
+    }
+
+    let mut conn = match get_pg_pool().get().await {
+        Ok(conn) => conn,
+        Err(e) => {
+            tracing::error!("Error getting database connection: {:?}", e);
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Error listing deploy events".to_string(),
+            ));
+        }
+    };
+
+    match list_deploy_events_handler(&mut conn, &organization_id, offset, page_size).await {
+        Ok(events) => Ok(ApiResponse::JsonData(events)),
+        Err(e) => {
+            tracing::error!("Error listing deploy events: {:?}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Error listing deploy events".to_string(),
+            ))
+        }
+    }
+}
+
+/// The offset and page size to read, with the page size clamped to `1..=MAX_PAGE_SIZE`.
+/// Pages too far out to have an offset are rejected rather than overflowing.
+fn page_bounds(page: Option<i64>, page_size: Option<i64>) -> Result<(i64, i64), String> {
+    let page = page.unwrap_or(0);
+    if page < 0 {
+        return Err(format!("page must be 0 or greater, got {}", page));
+    }
+    let page_size = page_size.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let offset = page
+        .checked_mul(page_size)
+        .ok_or_else(|| format!("page {} is out of range", page))?;
+    Ok((offset, page_size))
+}
+
+async fn list_deploy_events_handler(
+    conn: &mut AsyncPgConnection,
+    organization_id: &Uuid,
+    offset: i64,
+    page_size: i64,
+) -> Result<Vec<DeployEvent>> {
+    let events = deploy_events::table
+        .filter(deploy_events::organization_id.eq(organization_id))
+        .order(deploy_events::created_at.desc())
+        .limit(page_size)
+        .offset(offset)
+        .load::<DeployEvent>(conn)
+        .await?;
+
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::lib::establish_diesel_connection;
+    use chrono::{Duration, Utc};
+    use dotenv::dotenv;
+
+    #[test]
+    fn test_page_bounds() {
+        assert_eq!(page_bounds(None, None), Ok((0, DEFAULT_PAGE_SIZE)));
+        assert_eq!(page_bounds(Some(3), Some(10)), Ok((30, 10)));
+        assert_eq!(page_bounds(Some(0), Some(10_000)), Ok((0, MAX_PAGE_SIZE)));
+        assert_eq!(page_bounds(Some(0), Some(0)), Ok((0, 1)));
+        assert_eq!(page_bounds(Some(0), Some(-5)), Ok((0, 1)));
+        assert_eq!(
+            page_bounds(Some(-1), None),
+            Err("page must be 0 or greater, got -1".to_string())
+        );
+        assert_eq!(
+            page_bounds(Some(i64::MAX), None),
+            Err(format!("page {} is out of range", i64::MAX))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_deploy_events_pages_newest_first() -> Result<()> {
+        dotenv().ok();
+
+        let pool = establish_diesel_connection().await?;
+        let mut conn = pool.get().await?;
+
+        let organization_id = Uuid::new_v4();
+        let other_organization_id = Uuid::new_v4();
+        let user_id = Uuid::new_v4();
+
+        for id in [organization_id, other_organization_id] {
+            diesel::sql_query(format!(
+                "INSERT INTO organizations (id, name) VALUES ('{id}', 'deploy-events-test-{id}')"
+            ))
+            .execute(&mut conn)
+            .await?;
+        }
+        diesel::sql_query(format!(
+            "INSERT INTO users (id, email) VALUES ('{user_id}', 'deploy-events-test-{user_id}@buster.so')"
+        ))
+        .execute(&mut conn)
+        .await?;
+
+        let now = Utc::now();
+        let event = |organization_id: Uuid, model_count: i32| DeployEvent {
+            id: Uuid::new_v4(),
+            organization_id,
+            user_id,
+            deploy_hash: format!("hash-{}", model_count),
+            model_count,
+            success_count: model_count,
+            failure_count: 0,
+            pruned_count: 0,
+            created_at: now + Duration::seconds(model_count as i64),
+        };
+        let events: Vec<DeployEvent> = (1..=3)
+            .map(|model_count| event(organization_id, model_count))
+            .chain([event(other_organization_id, 10)])
+            .collect();
+        diesel::insert_into(deploy_events::table)
+            .values(&events)
+            .execute(&mut conn)
+            .await?;
+
+        let model_counts = |events: Vec<DeployEvent>| -> Vec<i32> {
+            events.iter().map(|event| event.model_count).collect()
+        };
+        let first = list_deploy_events_handler(&mut conn, &organization_id, 0, 2).await?;
+        let second = list_deploy_events_handler(&mut conn, &organization_id, 2, 2).await?;
+        let past_end = list_deploy_events_handler(&mut conn, &organization_id, 10, 2).await?;
+
+        // Cleanup cascades from the organizations and user
+        for id in [organization_id, other_organization_id] {
+            diesel::sql_query(format!("DELETE FROM organizations WHERE id = '{id}'"))
+                .execute(&mut conn)
+                .await?;
+        }
+        diesel::sql_query(format!("DELETE FROM users WHERE id = '{user_id}'"))
+            .execute(&mut conn)
+            .await?;
+
+        assert_eq!(model_counts(first), vec![3, 2]);
+        assert_eq!(model_counts(second), vec![1]);
+        assert!(past_end.is_empty());
+
+        Ok(())
+    }
+}
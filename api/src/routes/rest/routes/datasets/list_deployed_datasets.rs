@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use axum::{extract::Query, Extension};
+use chrono::{DateTime, Utc};
+use diesel::{dsl::count_star, ExpressionMethods, JoinOnDsl, QueryDsl};
+use diesel_async::RunQueryDsl;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    database::{
+        enums::DatasetType,
+        lib::get_pg_pool,
+        models::User,
+        schema::{data_sources, dataset_columns, datasets},
+    },
+    routes::rest::ApiResponse,
+    utils::{
+        security::checks::is_user_workspace_admin_or_data_admin,
+        user::user_info::get_user_organization_id,
+    },
+};
+
+#[derive(Deserialize)]
+pub struct ListDeployedDatasetsQuery {
+    pub data_source_name: Option<String>,
+    pub schema: Option<String>,
+    pub env: Option<String>,
+    /// Include each dataset's deployed model file, for `buster import`
+    #[serde(default)]
+    pub include_yml: bool,
+}
+
+/// A deployed dataset as the CLI sees it, for comparing against local model files.
+#[derive(Serialize)]
+pub struct DeployedDataset {
+    pub id: Uuid,
+    pub name: String,
+    pub database_name: String,
+    pub schema: String,
+    pub data_source_name: String,
+    /// Env of the data source the dataset was deployed to
+    pub env: String,
+    #[serde(rename = "type")]
+    pub type_: DatasetType,
+    pub enabled: bool,
+    pub column_count: i64,
+    pub updated_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub yml_file: Option<String>,
+}
+
+pub async fn list_deployed_datasets(
+    Extension(user): Extension<User>,
+    Query(query): Query<ListDeployedDatasetsQuery>,
+) -> Result<ApiResponse<Vec<DeployedDataset>>, (StatusCode, String)> {
+    let organization_id = match get_user_organization_id(&user.id).await {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::error!("Error getting user organization id: {:?}", e);
+            return Err((e.status_code(), e.public_message().to_string()));
+        }
+    };
+
+    match is_user_workspace_admin_or_data_admin(&user, &organization_id).await {
+        Ok(true) => (),
+        Ok(false) => {
+            return Err((
+                StatusCode::FORBIDDEN,
+                "Insufficient permissions".to_string(),
+            ))
+        }
+        Err(e) => {
+            tracing::error!("Error checking user permissions: {:?}", e);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()));
+        }
+    }
+
+    match list_deployed_datasets_handler(&organization_id, query).await {
+        Ok(datasets) => Ok(ApiResponse::JsonData(datasets)),
+        Err(e) => {
+            tracing::error!("Error listing deployed datasets: {:?}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Error listing deployed datasets".to_string(),
+            ))
+        }
+    }
+}
+
+async fn list_deployed_datasets_handler(
+    organization_id: &Uuid,
+    query: ListDeployedDatasetsQuery,
+) -> Result<Vec<DeployedDataset>> {
+    let mut conn = get_pg_pool().get().await?;
+
+    let mut datasets_query = datasets::table
+        .inner_join(data_sources::table.on(datasets::data_source_id.eq(data_sources::id)))
+        .filter(datasets::organization_id.eq(organization_id))
+        .filter(datasets::deleted_at.is_null())
+        .filter(data_sources::deleted_at.is_null())
+        .select((
+            datasets::id,
+            datasets::name,
+            datasets::database_name,
+            datasets::schema,
+            data_sources::name,
+            data_sources::env,
+            datasets::type_,
+            datasets::enabled,
+            datasets::updated_at,
+            datasets::yml_file,
+        ))
+        .order((data_sources::name, datasets::schema, datasets::name))
+        .into_boxed();
+
+    let include_yml = query.include_yml;
+    if let Some(data_source_name) = query.data_source_name {
+        datasets_query = datasets_query.filter(data_sources::name.eq(data_source_name));
+    }
+    if let Some(schema) = query.schema {
+        datasets_query = datasets_query.filter(datasets::schema.eq(schema));
+    }
+    if let Some(env) = query.env {
+        datasets_query = datasets_query.filter(data_sources::env.eq(env));
+    }
+
+    type Row = (
+        Uuid,
+        String,
+        String,
+        String,
+        String,
+        String,
+        DatasetType,
+        bool,
+        DateTime<Utc>,
+        Option<String>,
+    );
+    let rows = datasets_query.load::<Row>(&mut conn).await?;
+
+    let column_counts: HashMap<Uuid, i64> = dataset_columns::table
+        .filter(dataset_columns::dataset_id.eq_any(rows.iter().map(|row| row.0)))
+        .filter(dataset_columns::deleted_at.is_null())
+        .group_by(dataset_columns::dataset_id)
+        .select((dataset_columns::dataset_id, count_star()))
+        .load::<(Uuid, i64)>(&mut conn)
+        .await?
+        .into_iter()
+        .collect();
+
+    Ok(rows
+        .into_iter()
+        .map(
+            |(
+                id,
+                name,
+                database_name,
+                schema,
+                data_source_name,
+                env,
+                type_,
+                enabled,
+                updated_at,
+                yml_file,
+            )| {
+                DeployedDataset {
+                    id,
+                    name,
+                    database_name,
+                    schema,
+                    data_source_name,
+                    env,
+                    type_,
+                    enabled,
+                    column_count: column_counts.get(&id).copied().unwrap_or_default(),
+                    updated_at,
+                    yml_file: yml_file.filter(|_| include_yml),
+                }
+            },
+        )
+        .collect())
+}
@@ -0,0 +1,254 @@
+use anyhow::Result;
+use axum::{extract::Query, Extension};
+use chrono::{DateTime, Utc};
+use diesel::{ExpressionMethods, JoinOnDsl, QueryDsl};
+use diesel_async::RunQueryDsl;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    database::{
+        enums::StoredValuesStatus,
+        lib::get_pg_pool,
+        models::User,
+        schema::{data_sources, dataset_columns, datasets},
+    },
+    routes::rest::ApiResponse,
+    utils::{
+        security::checks::is_user_workspace_admin_or_data_admin,
+        user::user_info::get_user_organization_id,
+    },
+};
+
+#[derive(Deserialize)]
+pub struct StoredValuesStatusQuery {
+    pub data_source_name: String,
+}
+
+/// Progress of a data source's stored values backfill, over the columns with
+/// `stored_values` enabled.
+#[derive(Serialize)]
+pub struct StoredValuesBackfill {
+    pub data_source_name: String,
+    pub total: usize,
+    /// Columns whose values haven't started syncing yet
+    pub pending: usize,
+    pub syncing: usize,
+    pub success: usize,
+    pub failed: usize,
+    /// Columns that stopped at the cap on values stored per column
+    pub truncated: usize,
+    /// Columns with too many distinct values to enumerate
+    pub skipped: usize,
+    /// Share of columns whose sync ended, however it ended
+    pub percent_complete: f64,
+    pub columns: Vec<StoredValuesColumnStatus>,
+}
+
+#[derive(Serialize)]
+pub struct StoredValuesColumnStatus {
+    pub dataset_id: Uuid,
+    pub dataset_name: String,
+    pub column_name: String,
+    /// `None` until the column's values start syncing
+    pub stored_values_status: Option<StoredValuesStatus>,
+    pub stored_values_count: Option<i64>,
+    pub stored_values_last_synced: Option<DateTime<Utc>>,
+    pub stored_values_error: Option<String>,
+}
+
+pub async fn list_stored_values_status(
+    Extension(user): Extension<User>,
+    Query(query): Query<StoredValuesStatusQuery>,
+) -> Result<ApiResponse<StoredValuesBackfill>, (StatusCode, String)> {
+    let organization_id = match get_user_organization_id(&user.id).await {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::error!("Error getting user organization id: {:?}", e);
+            return Err((e.status_code(), e.public_message().to_string()));
+        }
+    };
+
+    match is_user_workspace_admin_or_data_admin(&user, &organization_id).await {
+        Ok(true) => (),
+        Ok(false) => {
+            return Err((
+                StatusCode::FORBIDDEN,
+                "Insufficient permissions".to_string(),
+            ))
+        }
+        Err(e) => {
+            tracing::error!("Error checking user permissions: {:?}", e);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()));
+        }
+    }
+
+    match list_stored_values_status_handler(&organization_id, query.data_source_name).await {
+        Ok(backfill) => Ok(ApiResponse::JsonData(backfill)),
+        Err(e) => {
+            tracing::error!("Error listing stored values status: {:?}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Error listing stored values status".to_string(),
+            ))
+        }
+    }
+}
+
+async fn list_stored_values_status_handler(
+    organization_id: &Uuid,
+    data_source_name: String,
+) -> Result<StoredValuesBackfill> {
+    let mut conn = get_pg_pool().get().await?;
+
+    type Row = (
+        Uuid,
+        String,
+        String,
+        Option<StoredValuesStatus>,
+        Option<i64>,
+        Option<DateTime<Utc>>,
+        Option<String>,
+    );
+    let rows = dataset_columns::table
+        .inner_join(datasets::table.on(dataset_columns::dataset_id.eq(datasets::id)))
+        .inner_join(data_sources::table.on(datasets::data_source_id.eq(data_sources::id)))
+        .filter(datasets::organization_id.eq(organization_id))
+        .filter(data_sources::name.eq(&data_source_name))
+        .filter(datasets::deleted_at.is_null())
+        .filter(data_sources::deleted_at.is_null())
+        .filter(dataset_columns::deleted_at.is_null())
+        .filter(dataset_columns::stored_values.eq(Some(true)))
+        .select((
+            datasets::id,
+            datasets::name,
+            dataset_columns::name,
+            dataset_columns::stored_values_status,
+            dataset_columns::stored_values_count,
+            dataset_columns::stored_values_last_synced,
+            dataset_columns::stored_values_error,
+        ))
+        .order((datasets::name, dataset_columns::name))
+        .load::<Row>(&mut conn)
+        .await?;
+
+    let columns = rows
+        .into_iter()
+        .map(
+            |(dataset_id, dataset_name, column_name, status, count, last_synced, error)| {
+                StoredValuesColumnStatus {
+                    dataset_id,
+                    dataset_name,
+                    column_name,
+                    stored_values_status: status,
+                    stored_values_count: count,
+                    stored_values_last_synced: last_synced,
+                    stored_values_error: error,
+                }
+            },
+        )
+        .collect();
+
+    Ok(summarize_backfill(data_source_name, columns))
+}
+
+/// Counts the columns in each state. A data source with no stored values columns has
+/// nothing left to sync, so it's complete.
+fn summarize_backfill(
+    data_source_name: String,
+    columns: Vec<StoredValuesColumnStatus>,
+) -> StoredValuesBackfill {
+    let count = |status: Option<StoredValuesStatus>| {
+        columns
+            .iter()
+            .filter(|column| column.stored_values_status == status)
+            .count()
+    };
+    let pending = count(None);
+    let syncing = count(Some(StoredValuesStatus::Syncing));
+    let success = count(Some(StoredValuesStatus::Success));
+    let failed = count(Some(StoredValuesStatus::Failed));
+    let truncated = count(Some(StoredValuesStatus::Truncated));
+    let skipped = count(Some(StoredValuesStatus::Skipped));
+
+    let total = columns.len();
+    let percent_complete = if total == 0 {
+        100.0
+    } else {
+        ((total - pending - syncing) as f64 / total as f64 * 1000.0).round() / 10.0
+    };
+
+    StoredValuesBackfill {
+        data_source_name,
+        total,
+        pending,
+        syncing,
+        success,
+        failed,
+        truncated,
+        skipped,
+        percent_complete,
+        columns,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(name: &str, status: Option<StoredValuesStatus>) -> StoredValuesColumnStatus {
+        StoredValuesColumnStatus {
+            dataset_id: Uuid::nil(),
+            dataset_name: "orders".to_string(),
+            column_name: name.to_string(),
+            stored_values_status: status,
+            stored_values_count: None,
+            stored_values_last_synced: None,
+            stored_values_error: None,
+        }
+    }
+
+    #[test]
+    fn test_summarize_backfill() {
+        let backfill = summarize_backfill(
+            "warehouse".to_string(),
+            vec![
+                column("status", Some(StoredValuesStatus::Success)),
+                column("region", Some(StoredValuesStatus::Failed)),
+                column("channel", Some(StoredValuesStatus::Syncing)),
+                column("customer_name", None),
+                column("product_name", None),
+                column("sku", Some(StoredValuesStatus::Success)),
+                column("email", Some(StoredValuesStatus::Skipped)),
+                column("city", Some(StoredValuesStatus::Truncated)),
+            ],
+        );
+        assert_eq!(backfill.total, 8);
+        assert_eq!(
+            (
+                backfill.pending,
+                backfill.syncing,
+                backfill.success,
+                backfill.failed
+            ),
+            (2, 1, 2, 1)
+        );
+        assert_eq!((backfill.truncated, backfill.skipped), (1, 1));
+        assert_eq!(backfill.percent_complete, 62.5);
+
+        let one_of_three = vec![
+            column("status", Some(StoredValuesStatus::Success)),
+            column("region", None),
+            column("channel", None),
+        ];
+        assert_eq!(
+            summarize_backfill("warehouse".to_string(), one_of_three).percent_complete,
+            33.3
+        );
+        assert_eq!(
+            summarize_backfill("empty".to_string(), vec![]).percent_complete,
+            100.0
+        );
+    }
+}
@@ -5,7 +5,11 @@ mod generate_datasets;
 mod get_dataset;
 mod get_dataset_data_sample;
 mod list_datasets;
+mod list_deployed_datasets;
+mod list_deploy_events;
+mod list_stored_values_status;
 mod post_dataset;
+mod revoke_dataset_permissions;
 
 use axum::{
     routing::{get, post, delete},
@@ -17,7 +21,17 @@ pub fn router() -> Router {
         .route("/", get(list_datasets::list_datasets))
         .route("/", post(post_dataset::post_dataset))
         .route("/deploy", post(deploy_datasets::deploy_datasets))
+        .route("/deploy/events", get(list_deploy_events::list_deploy_events))
+        .route("/deployed", get(list_deployed_datasets::list_deployed_datasets))
+        .route(
+            "/stored_values/status",
+            get(list_stored_values_status::list_stored_values_status),
+        )
         .route("/generate", post(generate_datasets::generate_datasets))
+        .route(
+            "/permissions/revoke",
+            post(revoke_dataset_permissions::revoke_dataset_permissions),
+        )
         .route("/:dataset_id", get(get_dataset::get_dataset))
         .route("/:dataset_id", delete(delete_dataset::delete_dataset))
         .route(
@@ -128,6 +128,10 @@ async fn post_dataset_handler(
         model: None,
         yml_file: None,
         database_identifier: None,
+        refresh_schedule: None,
+        primary_time_dimension: None,
+        grain: None,
+        label: None,
     };
 
     diesel::insert_into(datasets::table)
@@ -0,0 +1,109 @@
+use anyhow::Result;
+use axum::http::StatusCode;
+use axum::{Extension, Json};
+use chrono::Utc;
+use diesel::{update, ExpressionMethods, QueryDsl};
+use diesel_async::RunQueryDsl;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::database::lib::get_pg_pool;
+use crate::database::models::User;
+use crate::database::schema::dataset_permissions;
+use crate::routes::rest::ApiResponse;
+use crate::utils::security::checks::is_user_workspace_admin_or_data_admin;
+use crate::utils::user::user_info::{get_user_organization_id, organization_error_response};
+
+#[derive(Debug, Deserialize)]
+pub struct RevokeDatasetPermissionsRequest {
+    /// The user, permission group or dataset group losing access
+    pub permission_id: Uuid,
+    /// `user`, `permission_group` or `dataset_group`
+    pub permission_type: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RevokeDatasetPermissionsResponse {
+    pub revoked: usize,
+}
+
+/// Revokes every dataset a user or group has been granted in the caller's organization,
+/// e.g. when offboarding someone, without listing the datasets.
+pub async fn revoke_dataset_permissions(
+    Extension(user): Extension<User>,
+    Json(request): Json<RevokeDatasetPermissionsRequest>,
+) -> Result<ApiResponse<RevokeDatasetPermissionsResponse>, (StatusCode, &'static str)> {
+    let permission_type = match dataset_permission_type(&request.permission_type) {
+        Some(permission_type) => permission_type,
+        None => return Err((StatusCode::BAD_REQUEST, "Invalid permission type")),
+    };
+
+    match revoke_dataset_permissions_handler(user, request.permission_id, permission_type).await {
+        Ok(revoked) => Ok(ApiResponse::JsonData(RevokeDatasetPermissionsResponse {
+            revoked,
+        })),
+        Err(e) => {
+            tracing::error!("Error revoking dataset permissions: {:?}", e);
+            Err(organization_error_response(
+                &e,
+                "Error revoking dataset permissions",
+            ))
+        }
+    }
+}
+
+async fn revoke_dataset_permissions_handler(
+    user: User,
+    permission_id: Uuid,
+    permission_type: &'static str,
+) -> Result<usize> {
+    let organization_id = get_user_organization_id(&user.id).await?;
+
+    if !is_user_workspace_admin_or_data_admin(&user, &organization_id).await? {
+        return Err(anyhow::anyhow!(
+            "User is not authorized to revoke dataset permissions"
+        ));
+    };
+
+    let mut conn = get_pg_pool().get().await?;
+    let revoked = update(dataset_permissions::table)
+        .filter(dataset_permissions::permission_id.eq(permission_id))
+        .filter(dataset_permissions::permission_type.eq(permission_type))
+        .filter(dataset_permissions::organization_id.eq(organization_id))
+        .filter(dataset_permissions::deleted_at.is_null())
+        .set(dataset_permissions::deleted_at.eq(Utc::now()))
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(revoked)
+}
+
+/// The `permission_type` dataset permissions are stored with, also accepting the plural
+/// names the dataset asset routes use.
+fn dataset_permission_type(permission_type: &str) -> Option<&'static str> {
+    match permission_type {
+        "user" | "users" => Some("user"),
+        "permission_group" | "permission_groups" => Some("permission_group"),
+        "dataset_group" | "dataset_groups" => Some("dataset_group"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dataset_permission_type() {
+        assert_eq!(dataset_permission_type("user"), Some("user"));
+        assert_eq!(
+            dataset_permission_type("permission_groups"),
+            Some("permission_group")
+        );
+        assert_eq!(
+            dataset_permission_type("dataset_group"),
+            Some("dataset_group")
+        );
+        assert_eq!(dataset_permission_type("team"), None);
+    }
+}
@@ -13,7 +13,7 @@ use crate::database::models::{DatasetPermission, User};
 use crate::database::schema::dataset_permissions;
 use crate::routes::rest::ApiResponse;
 use crate::utils::security::checks::is_user_workspace_admin_or_data_admin;
-use crate::utils::user::user_info::get_user_organization_id;
+use crate::utils::user::user_info::{get_user_organization_id, organization_error_response};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DatasetAssignment {
@@ -32,10 +32,7 @@ pub async fn put_datasets(
         Ok(_) => Ok(ApiResponse::NoContent),
         Err(e) => {
             tracing::error!("Error assigning datasets to permission group: {:?}", e);
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Error assigning datasets to permission group",
-            ));
+            return Err(organization_error_response(&e, "Error assigning datasets to permission group"));
         }
     }
 }
@@ -13,7 +13,7 @@ use crate::database::models::User;
 use crate::database::schema::{users, users_to_organizations};
 use crate::routes::rest::ApiResponse;
 use crate::utils::security::checks::is_user_workspace_admin_or_data_admin;
-use crate::utils::user::user_info::get_user_organization_id;
+use crate::utils::user::user_info::{get_user_organization_id, organization_error_response};
 
 #[derive(Debug, Serialize)]
 pub struct AttributeInfo {
@@ -30,10 +30,7 @@ pub async fn list_attributes(
         Ok(attrs) => attrs,
         Err(e) => {
             tracing::error!("Error listing attributes: {:?}", e);
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Error listing attributes",
-            ));
+            return Err(organization_error_response(&e, "Error listing attributes"));
         }
     };
 
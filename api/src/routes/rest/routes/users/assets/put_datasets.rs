@@ -5,6 +5,7 @@ use axum::{Extension, Json};
 use diesel::prelude::*;
 use diesel_async::RunQueryDsl;
 use serde::{Deserialize, Serialize};
+use std::fmt;
 use tokio::spawn;
 use uuid::Uuid;
 
@@ -13,7 +14,9 @@ use crate::database::models::{DatasetPermission, User};
 use crate::database::schema::dataset_permissions;
 use crate::routes::rest::ApiResponse;
 use crate::utils::security::checks::is_user_workspace_admin_or_data_admin;
-use crate::utils::user::user_info::get_user_organization_id;
+use crate::utils::user::user_info::{
+    get_user_organization_id, organization_error_response, OrganizationIdError,
+};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DatasetAssignment {
@@ -21,6 +24,38 @@ pub struct DatasetAssignment {
     pub assigned: bool,
 }
 
+/// Why datasets can't be assigned to the target user, as opposed to the acting user's own
+/// organization lookup failing.
+#[derive(Debug, PartialEq)]
+enum TargetUserError {
+    NoOrganization,
+    OtherOrganization,
+}
+
+impl TargetUserError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::NoOrganization => StatusCode::NOT_FOUND,
+            Self::OtherOrganization => StatusCode::FORBIDDEN,
+        }
+    }
+
+    fn public_message(&self) -> &'static str {
+        match self {
+            Self::NoOrganization => "Target user has no organization",
+            Self::OtherOrganization => "Target user belongs to a different organization",
+        }
+    }
+}
+
+impl fmt::Display for TargetUserError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.public_message())
+    }
+}
+
+impl std::error::Error for TargetUserError {}
+
 pub async fn put_datasets(
     Extension(user): Extension<User>,
     Path(user_id): Path<Uuid>,
@@ -30,10 +65,10 @@ pub async fn put_datasets(
         Ok(_) => Ok(ApiResponse::NoContent),
         Err(e) => {
             tracing::error!("Error assigning datasets: {:?}", e);
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Error assigning datasets",
-            ));
+            return Err(match e.downcast_ref::<TargetUserError>() {
+                Some(target_error) => (target_error.status_code(), target_error.public_message()),
+                None => organization_error_response(&e, "Error assigning datasets"),
+            });
         }
     }
 }
@@ -43,7 +78,9 @@ async fn put_datasets_handler(
     user_id: Uuid,
     assignments: Vec<DatasetAssignment>,
 ) -> Result<()> {
-    let organization_id = get_user_organization_id(&user_id).await?;
+    let user_organization_id = get_user_organization_id(&user.id).await?;
+    let organization_id =
+        target_organization_id(user_organization_id, get_user_organization_id(&user_id).await)?;
 
     if !is_user_workspace_admin_or_data_admin(&user, &organization_id).await? {
         return Err(anyhow::anyhow!("User is not authorized to assign datasets"));
@@ -114,3 +151,64 @@ async fn put_datasets_handler(
 
     Ok(())
 }
+
+/// The target user's organization, which has to be the acting user's: an admin can only
+/// assign datasets within their own organization.
+fn target_organization_id(
+    organization_id: Uuid,
+    target: Result<Uuid, OrganizationIdError>,
+) -> Result<Uuid> {
+    match target {
+        Ok(target_organization_id) if target_organization_id == organization_id => {
+            Ok(target_organization_id)
+        }
+        Ok(_) => Err(TargetUserError::OtherOrganization.into()),
+        Err(OrganizationIdError::NoOrganization) => Err(TargetUserError::NoOrganization.into()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target_error(result: Result<Uuid>) -> Option<TargetUserError> {
+        match result.unwrap_err().downcast::<TargetUserError>() {
+            Ok(e) => Some(e),
+            Err(_) => None,
+        }
+    }
+
+    #[test]
+    fn test_target_organization_id_same_organization() {
+        let organization_id = Uuid::new_v4();
+        assert_eq!(
+            target_organization_id(organization_id, Ok(organization_id)).unwrap(),
+            organization_id
+        );
+    }
+
+    #[test]
+    fn test_target_organization_id_cross_organization() {
+        let result = target_organization_id(Uuid::new_v4(), Ok(Uuid::new_v4()));
+        let error = target_error(result).unwrap();
+        assert_eq!(error, TargetUserError::OtherOrganization);
+        assert_eq!(error.status_code(), StatusCode::FORBIDDEN);
+    }
+
+    #[test]
+    fn test_target_organization_id_without_organization() {
+        let result =
+            target_organization_id(Uuid::new_v4(), Err(OrganizationIdError::NoOrganization));
+        let error = target_error(result).unwrap();
+        assert_eq!(error, TargetUserError::NoOrganization);
+        assert_eq!(error.status_code(), StatusCode::NOT_FOUND);
+
+        // Other lookup failures keep their own status
+        let result = target_organization_id(
+            Uuid::new_v4(),
+            Err(OrganizationIdError::MultipleOrganizations),
+        );
+        assert!(target_error(result).is_none());
+    }
+}
@@ -11,7 +11,7 @@ use crate::{
     database::{
         enums::{DataSourceType, UserOrganizationRole},
         lib::get_pg_pool,
-        models::Dataset,
+        models::{DataSource, Dataset},
         schema::{data_sources, datasets, organizations, users, users_to_organizations},
     },
     utils::query_engine::credentials::{get_data_source_credentials, Credential},
@@ -105,8 +105,19 @@ pub async fn get_data_source_state(user_id: &Uuid, id: Uuid) -> Result<DataSourc
         email: data_source.user_email,
     };
 
+    // Credentials are resolved from the full data source, since its organization decides
+    // which credential source they live in.
+    let full_data_source = match data_sources::table
+        .filter(data_sources::id.eq(data_source.id))
+        .first::<DataSource>(&mut conn)
+        .await
+    {
+        Ok(data_source) => data_source,
+        Err(e) => return Err(anyhow!("Error loading data source: {:?}", e)),
+    };
+
     let credentials =
-        match get_data_source_credentials(&data_source.secret_id, &data_source.type_, true).await {
+        match get_data_source_credentials(&full_data_source, &data_source.type_, true).await {
             Ok(credential) => credential,
             Err(e) => return Err(anyhow!("Error getting data source credentials: {:}", e)),
         };
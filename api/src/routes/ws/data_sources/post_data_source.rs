@@ -163,6 +163,7 @@ async fn post_data_source_handler(
         onboarding_status: DataSourceOnboardingStatus::NotStarted,
         onboarding_error: None,
         env: "dev".to_string(),
+        case_sensitive_identifiers: None,
     };
 
     match insert_into(data_sources::table)
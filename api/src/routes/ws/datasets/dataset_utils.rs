@@ -189,6 +189,9 @@ async fn get_dataset_and_columns(
                 dataset_columns::semantic_type.nullable(),
                 dataset_columns::dim_type.nullable(),
                 dataset_columns::expr.nullable(),
+                dataset_columns::ordinal.nullable(),
+                dataset_columns::hidden,
+                dataset_columns::label.nullable(),
             )
                 .nullable(),
             (
@@ -205,6 +208,7 @@ async fn get_dataset_and_columns(
                 data_sources::updated_at,
                 data_sources::deleted_at.nullable(),
                 data_sources::env,
+                data_sources::case_sensitive_identifiers,
             ),
             users::name.nullable(),
             users::email,
@@ -222,7 +226,7 @@ async fn get_dataset_and_columns(
         Err(e) => return Err(anyhow!("Error loading dataset: {:?}", e)),
     };
 
-    let (dataset, columns, data_source, created_by_name) = dataset_and_col_res
+    let (dataset, mut columns, data_source, created_by_name) = dataset_and_col_res
         .into_iter()
         .fold(
             None,
@@ -248,6 +252,9 @@ async fn get_dataset_and_columns(
             "Dataset, DataSource and created_by_name should always be present"
         ))?;
 
+    // Table order first; computed columns without a warehouse position go last
+    columns.sort_by_key(|col| (col.ordinal.is_none(), col.ordinal));
+
     Ok((dataset, columns, data_source, created_by_name))
 }
 
@@ -121,7 +121,7 @@ async fn update_dataset(user_id: &Uuid, dataset_id: &Uuid, name: &String) -> Res
     };
 
     let credentials = match get_data_source_credentials(
-        &dataset.data_source.secret_id,
+        &dataset.data_source,
         &dataset.data_source.type_,
         false,
     )
@@ -237,6 +237,10 @@ async fn create_dataset(user_id: &Uuid, name: &String, data_source_id: &Uuid) ->
         yml_file: None,
         model: None,
         database_identifier: None,
+        refresh_schedule: None,
+        primary_time_dimension: None,
+        grain: None,
+        label: None,
     };
 
     let mut conn = match get_pg_pool().get().await {
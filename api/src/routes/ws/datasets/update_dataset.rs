@@ -198,7 +198,7 @@ async fn update_dataset_handler(
             };
 
             let credentials = match get_data_source_credentials(
-                &dataset_state.data_source.secret_id,
+                &dataset_state.data_source,
                 &dataset_state.data_source.type_,
                 false,
             )
@@ -24,10 +24,19 @@ use crate::{
 pub struct UpdateOrganizationRequest {
     pub id: Uuid,
     pub name: String,
+    /// Opts the organization in or out of the agent result cache; left as is when unset
+    pub agent_result_cache_enabled: Option<bool>,
 }
 
 pub async fn update_organization(user: &User, req: UpdateOrganizationRequest) -> Result<()> {
-    let org_state = match update_organization_handler(user, req.id, req.name).await {
+    let org_state = match update_organization_handler(
+        user,
+        req.id,
+        req.name,
+        req.agent_result_cache_enabled,
+    )
+    .await
+    {
         Ok(state) => state,
         Err(e) => {
             tracing::error!("Error creating organization: {}", e);
@@ -67,14 +76,23 @@ pub async fn update_organization(user: &User, req: UpdateOrganizationRequest) ->
     Ok(())
 }
 
-async fn update_organization_handler(user: &User, id: Uuid, name: String) -> Result<()> {
+async fn update_organization_handler(
+    user: &User,
+    id: Uuid,
+    name: String,
+    agent_result_cache_enabled: Option<bool>,
+) -> Result<()> {
     let mut conn = match get_pg_pool().get().await {
         Ok(conn) => conn,
         Err(e) => return Err(anyhow!("Error getting pg connection: {}", e)),
     };
 
     match update(organizations::table)
-        .set(organizations::name.eq(name))
+        .set((
+            organizations::name.eq(name),
+            agent_result_cache_enabled
+                .map(|enabled| organizations::agent_result_cache_enabled.eq(enabled)),
+        ))
         .filter(organizations::id.eq(id))
         .execute(&mut conn)
         .await
@@ -117,7 +117,7 @@ async fn list_permission_groups_handler(
 
     let organization_id = match get_user_organization_id(&user.id).await {
         Ok(organization_id) => organization_id,
-        Err(e) => return Err(e),
+        Err(e) => return Err(e.into()),
     };
 
     if let Some(filters) = filters {
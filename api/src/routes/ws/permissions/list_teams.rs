@@ -104,7 +104,7 @@ async fn list_team_permissions_handler(
 ) -> Result<Vec<TeamPermissionInfo>> {
     let organization_id = match get_user_organization_id(&user.id).await {
         Ok(organization_id) => organization_id,
-        Err(e) => return Err(e),
+        Err(e) => return Err(e.into()),
     };
 
     let team_permissions = if let Some(filters) = filters {
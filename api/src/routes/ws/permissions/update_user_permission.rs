@@ -111,7 +111,7 @@ async fn update_user_permission_handler(
 ) -> Result<UserPermissionGroupState> {
     let user_organization_id = match get_user_organization_id(created_by).await {
         Ok(id) => id,
-        Err(e) => return Err(e),
+        Err(e) => return Err(e.into()),
     };
 
     let created_by = Arc::new(created_by.clone());
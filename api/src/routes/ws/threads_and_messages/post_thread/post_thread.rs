@@ -924,8 +924,12 @@ async fn process_dataset_records(
 fn create_dataset_ddl(dataset: &Dataset, dataset_columns: &Vec<DatasetColumn>) -> String {
     let mut ddl = String::new();
 
-    // Add header with table name and description
-    ddl.push_str(&format!("-- Dataset Readable Name: {}\n", dataset.name));
+    // Add header with table name and description. Labels only ever appear in comments, so
+    // generated SQL keeps using the column names
+    ddl.push_str(&format!(
+        "-- Dataset Readable Name: {}\n",
+        dataset.label.as_deref().unwrap_or(&dataset.name)
+    ));
     if let Some(when_to_use) = &dataset.when_to_use {
         ddl.push_str(&format!("    -- Description: {}\n", when_to_use));
     }
@@ -958,6 +962,10 @@ fn create_dataset_ddl(dataset: &Dataset, dataset_columns: &Vec<DatasetColumn>) -
             ddl.push('\n');
         }
 
+        if let Some(label) = &column.label {
+            ddl.push_str(&format!("        -- Label: {}\n", label));
+        }
+
         // Add column description as comment if present
         if let Some(description) = &column.description {
             ddl.push_str(&format!("        -- {}\n", description));
@@ -34,6 +34,7 @@ use crate::{
     },
     utils::{
         clients::typesense::{self, CollectionName, SearchRequestObject, StoredValueDocument},
+        query_engine::dialect_detection::cached_data_source_type,
         user::user_info::get_user_organization_id,
     },
 };
@@ -376,19 +377,20 @@ pub async fn get_data_source_type(pool: &PgPool, dataset_id: &Uuid) -> Result<Da
         Err(e) => return Err(anyhow!("Unable to get connection from pool: {}", e)),
     };
 
-    let data_source_type: DataSourceType = match data_sources::table
-        .select(data_sources::type_)
+    let (data_source_id, data_source_type) = match data_sources::table
+        .select((data_sources::id, data_sources::type_))
         .inner_join(datasets::table.on(datasets::data_source_id.eq(data_sources::id)))
         .filter(datasets::id.eq(&dataset_id))
         .filter(data_sources::deleted_at.is_null())
-        .first::<DataSourceType>(&mut conn)
+        .first::<(Uuid, DataSourceType)>(&mut conn)
         .await
     {
         Ok(sources) => sources,
         Err(e) => return Err(anyhow!("Unable to load data sources: {}", e)),
     };
 
-    Ok(data_source_type)
+    // Prefer a dialect detected from the warehouse during generate/deploy
+    Ok(cached_data_source_type(&data_source_id).unwrap_or(data_source_type))
 }
 
 pub async fn select_dataset_ai_handler(
@@ -0,0 +1,195 @@
+use anyhow::{anyhow, Result};
+use diesel::{ExpressionMethods, QueryDsl};
+use diesel_async::RunQueryDsl;
+use redis::AsyncCommands;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::{
+    database::{
+        lib::{get_pg_pool, get_redis_pool},
+        schema::organizations,
+    },
+    utils::user::user_info::get_user_organization_id,
+};
+
+use super::data_analyst_agent::DatasetWithMetadata;
+
+const KEY_PREFIX: &str = "agent_result_cache";
+/// How long an agent chain's result is served from the cache, unless
+/// `AGENT_RESULT_CACHE_TTL_SECS` is set
+const DEFAULT_TTL_SECS: u64 = 86_400;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheStatus {
+    Hit,
+    Miss,
+}
+
+impl CacheStatus {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            CacheStatus::Hit => "hit",
+            CacheStatus::Miss => "miss",
+        }
+    }
+}
+
+/// The cache key for a question asked against a set of datasets, or `None` when the user's
+/// organization hasn't opted into the cache. It's known before the agent chain starts, so a
+/// hit skips the whole chain. Lookup failures disable the cache for the request rather than
+/// failing it.
+pub async fn result_cache_key(
+    user_id: &Uuid,
+    question: &str,
+    datasets: &[DatasetWithMetadata],
+) -> Option<String> {
+    let organization_id = match get_user_organization_id(user_id).await {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::warn!("Skipping agent result cache, no organization: {}", e);
+            return None;
+        }
+    };
+
+    match cache_enabled(&organization_id).await {
+        Ok(true) => Some(cache_key(&organization_id, question, datasets)),
+        Ok(false) => None,
+        Err(e) => {
+            tracing::warn!("Skipping agent result cache: {}", e);
+            None
+        }
+    }
+}
+
+async fn cache_enabled(organization_id: &Uuid) -> Result<bool> {
+    let mut conn = get_pg_pool()
+        .get()
+        .await
+        .map_err(|e| anyhow!("Error getting pg connection: {}", e))?;
+
+    organizations::table
+        .select(organizations::agent_result_cache_enabled)
+        .filter(organizations::id.eq(organization_id))
+        .filter(organizations::deleted_at.is_null())
+        .first::<bool>(&mut conn)
+        .await
+        .map_err(|e| anyhow!("Error checking agent result cache setting: {}", e))
+}
+
+pub async fn get_cached_result(key: &str) -> Option<Value> {
+    let cached = async {
+        let mut redis_conn = get_redis_pool().get().await?;
+        let cached: Option<String> = redis_conn.get(key).await?;
+        anyhow::Ok(cached)
+    };
+
+    match cached.await {
+        Ok(cached) => cached
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .filter(Value::is_object),
+        Err(e) => {
+            tracing::warn!("Error reading agent result cache: {}", e);
+            None
+        }
+    }
+}
+
+pub async fn cache_result(key: &str, result: &Value) {
+    let ttl = ttl_secs(std::env::var("AGENT_RESULT_CACHE_TTL_SECS").ok().as_deref());
+    let stored = async {
+        let mut redis_conn = get_redis_pool().get().await?;
+        redis_conn
+            .set_ex::<_, _, ()>(key, result.to_string(), ttl)
+            .await?;
+        anyhow::Ok(())
+    };
+
+    if let Err(e) = stored.await {
+        tracing::warn!("Error writing agent result cache: {}", e);
+    }
+}
+
+fn ttl_secs(configured: Option<&str>) -> u64 {
+    configured
+        .and_then(|value| value.trim().parse().ok())
+        .filter(|ttl| *ttl > 0)
+        .unwrap_or(DEFAULT_TTL_SECS)
+}
+
+/// Keys on the normalized question and each dataset's id, dialect and deployed state.
+/// Redeploying a dataset with changes moves its questions to new keys and the old entries
+/// simply expire.
+fn cache_key(organization_id: &Uuid, question: &str, datasets: &[DatasetWithMetadata]) -> String {
+    let mut dataset_states: Vec<String> = datasets
+        .iter()
+        .map(|dataset| {
+            format!(
+                "{}:{}:{}",
+                dataset.dataset.id,
+                dataset.data_source.type_.to_string(),
+                dataset_state_hash(dataset)
+            )
+        })
+        .collect();
+    dataset_states.sort();
+
+    let payload = format!(
+        "{}\n{}",
+        normalize_question(question),
+        dataset_states.join("\n")
+    );
+    format!(
+        "{}:{}:{:x}",
+        KEY_PREFIX,
+        organization_id,
+        Sha256::digest(payload.as_bytes())
+    )
+}
+
+/// Hash of what a deploy writes for a dataset: its model file, definition and the DDL
+/// built from its columns.
+fn dataset_state_hash(dataset: &DatasetWithMetadata) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(dataset.dataset.yml_file.as_deref().unwrap_or_default());
+    hasher.update([0]);
+    hasher.update(&dataset.dataset.definition);
+    hasher.update([0]);
+    hasher.update(&dataset.dataset_ddl);
+    format!("{:x}", hasher.finalize())
+}
+
+/// Case, surrounding whitespace, repeated spaces and trailing punctuation don't change
+/// what's being asked.
+fn normalize_question(question: &str) -> String {
+    question
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_end_matches(['?', '.', '!'])
+        .trim_end()
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_question() {
+        assert_eq!(
+            normalize_question("  What were   Sales\nlast month?? "),
+            "what were sales last month"
+        );
+        assert_eq!(normalize_question("Revenue by region."), "revenue by region");
+    }
+
+    #[test]
+    fn test_ttl_secs() {
+        assert_eq!(ttl_secs(None), DEFAULT_TTL_SECS);
+        assert_eq!(ttl_secs(Some(" 600 ")), 600);
+        assert_eq!(ttl_secs(Some("0")), DEFAULT_TTL_SECS);
+        assert_eq!(ttl_secs(Some("soon")), DEFAULT_TTL_SECS);
+    }
+}
@@ -28,6 +28,7 @@ use crate::{
             prompt_node::{prompt_node, PromptNodeMessage, PromptNodeSettings},
         },
         agents::{
+            agent_result_cache::{cache_result, get_cached_result, result_cache_key, CacheStatus},
             failed_to_fix_sql_agent::{failed_to_fix_sql_agent, FailedToFixSqlAgentOptions},
             metadata_prompts_agent::{metadata_prompts_agent, MetadataPromptsAgentOptions},
             multiple_datasets_response_agent::handle_multiple_datasets_agent,
@@ -128,6 +129,19 @@ pub struct Thought {
 pub async fn data_analyst_agent(options: DataAnalystAgentOptions) -> Result<Value, ErrorNode> {
     let start_time = Instant::now();
 
+    // Only a thread's first question is cached, since follow-ups depend on what came
+    // before them
+    let cache_key = match get_previous_message(&options.message_history) {
+        Some(_) => None,
+        None => result_cache_key(&options.user_id, &options.input, &options.datasets).await,
+    };
+
+    if let Some(key) = &cache_key {
+        if let Some(outputs) = get_cached_result(key).await {
+            return replay_cached_result(outputs, &options.output_sender).await;
+        }
+    }
+
     let mut thoughts = Thoughts {
         title: "Understanding Your Request".to_string(),
         thoughts: vec![],
@@ -1523,6 +1537,13 @@ pub async fn data_analyst_agent(options: DataAnalystAgentOptions) -> Result<Valu
     outputs["master_response"] = master_response.clone();
     outputs["messages"] = Value::Array(vec![master_response]);
     outputs["created_at"] = Value::String(Utc::now().to_string());
+    outputs["agent_cache"] = match &cache_key {
+        Some(key) => {
+            cache_result(key, &outputs).await;
+            Value::String(CacheStatus::Miss.as_str().into())
+        }
+        None => Value::Null,
+    };
 
     match send_message(
         "data_analyst_agent_finished".to_string(),
@@ -1540,6 +1561,31 @@ pub async fn data_analyst_agent(options: DataAnalystAgentOptions) -> Result<Valu
     Ok(outputs)
 }
 
+/// Returns a cached chain result in place of running the chain. Its response is streamed in
+/// one chunk, the way it would have been generated.
+async fn replay_cached_result(
+    mut outputs: Value,
+    output_sender: &mpsc::Sender<Value>,
+) -> Result<Value, ErrorNode> {
+    let response = outputs
+        .get("master_response")
+        .cloned()
+        .unwrap_or(Value::Null);
+    send_message("master_response".to_string(), response, output_sender.clone()).await?;
+
+    outputs["agent_cache"] = Value::String(CacheStatus::Hit.as_str().into());
+    outputs["created_at"] = Value::String(Utc::now().to_string());
+
+    send_message(
+        "data_analyst_agent_finished".to_string(),
+        Value::String("[DONE]".to_string()),
+        output_sender.clone(),
+    )
+    .await?;
+
+    Ok(outputs)
+}
+
 fn merge_with_previous_message(current: &mut Value, previous: &Value) {
     if let (Value::Object(current_obj), Value::Object(previous_obj)) = (current, previous) {
         // Iterate through previous object's keys
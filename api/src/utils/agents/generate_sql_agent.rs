@@ -9,6 +9,7 @@ use uuid::Uuid;
 
 use crate::{
     database::{
+        enums::DataSourceType,
         lib::get_pg_pool,
         models::EntityRelationship,
         schema::{data_sources, datasets::data_source_id, entity_relationship},
@@ -629,6 +630,7 @@ pub async fn generate_sql_agent(options: GenerateSqlAgentOptions) -> Result<Valu
         output_sender: options.output_sender.clone(),
         thoughts: thoughts.clone(),
         start_time: options.start_time,
+        data_source_type: DataSourceType::from_str(&data_source_type),
     };
 
     let run_sql_result = match run_and_fix_sql_agent(run_and_fix_sql_agent_options).await {
@@ -1,3 +1,4 @@
+pub mod agent_result_cache;
 pub mod column_styling_agent;
 pub mod configure_charts_agent;
 pub mod custom_response_agent;
@@ -10,13 +10,19 @@ use std::collections::HashSet;
 const MAX_UNIQUE_VALUES: usize = 100;
 
 use crate::{
-    database::lib::{ColumnMetadata, DataMetadataJsonBody, MinMaxValue},
+    database::{
+        enums::DataSourceType,
+        lib::{ColumnMetadata, DataMetadataJsonBody, MinMaxValue},
+    },
     utils::{
         agent_builder::nodes::{
             error_node::ErrorNode,
             prompt_node::{prompt_node, PromptNodeMessage, PromptNodeSettings},
         },
-        query_engine::{data_types::DataType, query_engine::query_engine},
+        query_engine::{
+            data_types::DataType, dialect_functions::lint_dialect_functions,
+            query_engine::query_engine,
+        },
     },
 };
 
@@ -29,11 +35,14 @@ pub struct RunAndFixSqlAgentOptions {
     pub thoughts: Thoughts,
     pub start_time: Instant,
     pub output_sender: mpsc::Sender<Value>,
+    /// Target dialect, used to catch functions it doesn't support before running the SQL
+    pub data_source_type: Option<DataSourceType>,
 }
 
 pub enum RunAndFixSqlAgentError {
     NoSqlFound,
     SqlExecutionError,
+    UnsupportedDialectFunction,
     MaxRetriesExceeded,
     PromptNodeError,
 }
@@ -43,6 +52,7 @@ impl fmt::Display for RunAndFixSqlAgentError {
         match self {
             Self::NoSqlFound => write!(f, "no_sql_found"),
             Self::SqlExecutionError => write!(f, "sql_execution_error"),
+            Self::UnsupportedDialectFunction => write!(f, "unsupported_dialect_function"),
             Self::MaxRetriesExceeded => write!(f, "max_retries_exceeded"),
             Self::PromptNodeError => write!(f, "prompt_node_error"),
         }
@@ -83,7 +93,29 @@ pub async fn run_and_fix_sql_agent(options: RunAndFixSqlAgentOptions) -> Result<
         )
         .await?;
 
-        match fetch_data(&current_sql, &options.dataset_id).await {
+        // Functions the dialect is known not to have go straight back to the fixer instead
+        // of a round-trip to the warehouse. The last attempt always runs, in case the
+        // lint is wrong.
+        let dialect_hints = match &options.data_source_type {
+            Some(data_source_type) if attempt < max_retries - 1 => {
+                lint_dialect_functions(&current_sql, data_source_type)
+            }
+            _ => Vec::new(),
+        };
+
+        let execution = if dialect_hints.is_empty() {
+            fetch_data(&current_sql, &options.dataset_id).await
+        } else {
+            Err(ErrorNode::new(
+                RunAndFixSqlAgentError::UnsupportedDialectFunction.to_string(),
+                format!(
+                    "The SQL uses functions the warehouse does not support:\n{}",
+                    dialect_hints.join("\n")
+                ),
+            ))
+        };
+
+        match execution {
             Ok(result) => {
                 final_result = Some(result);
 
@@ -6,7 +6,10 @@ use tokio_stream::wrappers::ReceiverStream;
 
 use serde::{Deserialize, Serialize};
 
-use crate::utils::clients::sentry_utils::send_sentry_error;
+use crate::utils::clients::{
+    ai::llm_config::{llm_config, LlmProvider},
+    sentry_utils::send_sentry_error,
+};
 
 const ANTHROPIC_CHAT_URL: &str = "https://api.anthropic.com/v1/messages";
 
@@ -23,6 +26,23 @@ lazy_static::lazy_static! {
 pub enum AnthropicChatModel {
     #[serde(rename = "claude-3-opus-20240229")]
     Claude3Opus20240229,
+    /// A model named by `LLM_MODEL`
+    #[serde(untagged)]
+    Custom(String),
+}
+
+/// Where Anthropic requests go: the configured endpoint when Anthropic is the LLM
+/// provider, otherwise Anthropic's API with `ANTHROPIC_API_KEY`.
+fn anthropic_endpoint() -> (String, reqwest::header::HeaderMap) {
+    let config = llm_config();
+    if config.provider == LlmProvider::Anthropic {
+        return (config.base_url.clone(), config.auth_headers());
+    }
+
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert("x-api-key", ANTHROPIC_API_KEY.parse().unwrap());
+    headers.insert("anthropic-version", "2023-06-01".parse().unwrap());
+    (ANTHROPIC_CHAT_URL.to_string(), headers)
 }
 
 #[derive(Serialize, Clone)]
@@ -97,20 +117,10 @@ pub async fn anthropic_chat(
 
     let client = reqwest::Client::new();
 
-    let headers = {
-        let mut headers = reqwest::header::HeaderMap::new();
-        headers.insert(
-            "x-api-key",
-            format!("{}", ANTHROPIC_API_KEY.to_string())
-                .parse()
-                .unwrap(),
-        );
-        headers.insert("anthropic-version", "2023-06-01".parse().unwrap());
-        headers
-    };
+    let (url, headers) = anthropic_endpoint();
 
     let response = match client
-        .post(ANTHROPIC_CHAT_URL)
+        .post(url)
         .headers(headers)
         .json(&chat_request)
         .timeout(Duration::from_secs(timeout))
@@ -179,22 +189,13 @@ pub async fn anthropic_chat_stream(
 
     let client = reqwest::Client::new();
 
-    let headers = {
-        let mut headers = reqwest::header::HeaderMap::new();
-        headers.insert(
-            reqwest::header::AUTHORIZATION,
-            format!("Bearer {}", ANTHROPIC_API_KEY.to_string())
-                .parse()
-                .unwrap(),
-        );
-        headers
-    };
+    let (url, headers) = anthropic_endpoint();
 
     let (_tx, rx): (Sender<String>, Receiver<String>) = mpsc::channel(100);
 
     tokio::spawn(async move {
         let response = client
-            .post(ANTHROPIC_CHAT_URL)
+            .post(url)
             .headers(headers)
             .json(&chat_request)
             .timeout(Duration::from_secs(timeout))
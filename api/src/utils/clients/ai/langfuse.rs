@@ -63,6 +63,16 @@ impl LlmModel {
                 total_cost: (input_token.len() as f64 / 1_000_000.0) * 3.0
                     + (output_token.len() as f64 / 1_000_000.0) * 15.0,
             },
+            // Configured models have no known pricing
+            LlmModel::OpenAi(OpenAiChatModel::Custom(_))
+            | LlmModel::Anthropic(AnthropicChatModel::Custom(_)) => Usage {
+                input: input_token.len() as u32,
+                output: output_token.len() as u32,
+                unit: "TOKENS".to_string(),
+                input_cost: 0.0,
+                output_cost: 0.0,
+                total_cost: 0.0,
+            },
         }
     }
 }
@@ -0,0 +1,290 @@
+use std::{env, fmt, str::FromStr, time::Duration};
+
+use anyhow::{anyhow, Result};
+use once_cell::sync::OnceCell;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
+
+use super::{anthropic::AnthropicChatModel, llm_router::LlmModel, openai::OpenAiChatModel};
+
+static LLM_CONFIG: OnceCell<LlmConfig> = OnceCell::new();
+
+/// Resolves the LLM configuration from the environment, checks its endpoint answers and
+/// makes it the configuration every agent uses. Called once at startup.
+pub async fn init_llm_config() -> Result<()> {
+    let config = LlmConfig::from_env()?;
+    config.check_reachable().await?;
+
+    tracing::info!(
+        "Using LLM provider {} at {}{}",
+        config.provider,
+        config.base_url,
+        config
+            .model
+            .as_ref()
+            .map(|model| format!(" with model {}", model))
+            .unwrap_or_default()
+    );
+
+    LLM_CONFIG
+        .set(config)
+        .map_err(|_| anyhow!("LLM configuration is already initialized"))
+}
+
+/// The configuration agents' LLM requests are sent with. Falls back to reading the
+/// environment when `init_llm_config` hasn't run, e.g. in tests.
+pub fn llm_config() -> &'static LlmConfig {
+    LLM_CONFIG.get_or_init(|| LlmConfig::from_env().expect("Invalid LLM configuration"))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LlmProvider {
+    OpenAi,
+    AzureOpenAi,
+    Anthropic,
+    /// An OpenAI-compatible server such as Ollama or vLLM
+    Local,
+}
+
+impl LlmProvider {
+    fn default_base_url(&self) -> Option<&'static str> {
+        match self {
+            Self::OpenAi => Some("https://api.openai.com/v1/chat/completions"),
+            Self::AzureOpenAi => None,
+            Self::Anthropic => Some("https://api.anthropic.com/v1/messages"),
+            Self::Local => Some("http://localhost:11434/v1/chat/completions"),
+        }
+    }
+
+    fn default_api_key_env(&self) -> Option<&'static str> {
+        match self {
+            Self::OpenAi => Some("OPENAI_API_KEY"),
+            Self::AzureOpenAi => Some("AZURE_OPENAI_API_KEY"),
+            Self::Anthropic => Some("ANTHROPIC_API_KEY"),
+            Self::Local => None,
+        }
+    }
+}
+
+impl FromStr for LlmProvider {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "openai" => Ok(Self::OpenAi),
+            "azure_openai" | "azure" => Ok(Self::AzureOpenAi),
+            "anthropic" => Ok(Self::Anthropic),
+            "local" => Ok(Self::Local),
+            other => Err(anyhow!(
+                "Unknown LLM_PROVIDER '{}'; expected openai, azure_openai, anthropic or local",
+                other
+            )),
+        }
+    }
+}
+
+impl fmt::Display for LlmProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OpenAi => write!(f, "openai"),
+            Self::AzureOpenAi => write!(f, "azure_openai"),
+            Self::Anthropic => write!(f, "anthropic"),
+            Self::Local => write!(f, "local"),
+        }
+    }
+}
+
+/// Which LLM the agents talk to. Read from `LLM_PROVIDER` (default `openai`),
+/// `LLM_MODEL`, `LLM_BASE_URL` and `LLM_API_KEY_ENV`; everything but the provider has a
+/// per-provider default.
+#[derive(Debug, Clone)]
+pub struct LlmConfig {
+    pub provider: LlmProvider,
+    /// Model every request is sent to. Without one, OpenAI keeps each agent's choice and
+    /// Anthropic uses Claude 3 Opus.
+    pub model: Option<String>,
+    /// Chat endpoint requests are posted to. For Azure OpenAI this is the deployment's
+    /// full chat completions URL, including `api-version`.
+    pub base_url: String,
+    /// Environment variable holding the API key; `None` for servers without auth
+    pub api_key_env: Option<String>,
+}
+
+impl LlmConfig {
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|name| {
+            env::var(name)
+                .ok()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        })
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let provider = match var("LLM_PROVIDER") {
+            Some(provider) => provider.parse()?,
+            None => LlmProvider::OpenAi,
+        };
+
+        // OPENAI_CHAT_URL predates LLM_BASE_URL and still points OpenAI requests elsewhere
+        let base_url = var("LLM_BASE_URL")
+            .or_else(|| {
+                (provider == LlmProvider::OpenAi)
+                    .then(|| var("OPENAI_CHAT_URL"))
+                    .flatten()
+            })
+            .or_else(|| provider.default_base_url().map(str::to_string))
+            .ok_or_else(|| anyhow!("LLM_BASE_URL is required for LLM_PROVIDER={}", provider))?;
+
+        let config = Self {
+            provider,
+            model: var("LLM_MODEL"),
+            base_url,
+            api_key_env: var("LLM_API_KEY_ENV")
+                .or_else(|| provider.default_api_key_env().map(str::to_string)),
+        };
+        config.validate(&var)?;
+        Ok(config)
+    }
+
+    fn validate(&self, var: &impl Fn(&str) -> Option<String>) -> Result<()> {
+        let url = url::Url::parse(&self.base_url)
+            .map_err(|e| anyhow!("LLM_BASE_URL '{}' is not a valid URL: {}", self.base_url, e))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(anyhow!(
+                "LLM_BASE_URL '{}' must be an http or https URL",
+                self.base_url
+            ));
+        }
+
+        if let Some(name) = &self.api_key_env {
+            if var(name).is_none() {
+                return Err(anyhow!(
+                    "{} must be set to use LLM_PROVIDER={}",
+                    name,
+                    self.provider
+                ));
+            }
+        }
+
+        if self.provider == LlmProvider::Local && self.model.is_none() {
+            return Err(anyhow!("LLM_MODEL is required for LLM_PROVIDER=local"));
+        }
+
+        Ok(())
+    }
+
+    /// Fails when nothing answers at the base URL. Any HTTP response, even an error
+    /// status, shows the endpoint is up.
+    async fn check_reachable(&self) -> Result<()> {
+        reqwest::Client::new()
+            .get(&self.base_url)
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await
+            .map(|_| ())
+            .map_err(|e| anyhow!("LLM_BASE_URL {} is not reachable: {}", self.base_url, e))
+    }
+
+    pub fn api_key(&self) -> Option<String> {
+        self.api_key_env
+            .as_deref()
+            .and_then(|name| env::var(name).ok())
+    }
+
+    /// The headers authenticating a request to the configured provider.
+    pub fn auth_headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if self.provider == LlmProvider::Anthropic {
+            headers.insert("anthropic-version", HeaderValue::from_static("2023-06-01"));
+        }
+        let Some(key) = self.api_key() else {
+            return headers;
+        };
+
+        let (name, value) = match self.provider {
+            LlmProvider::OpenAi | LlmProvider::Local => (AUTHORIZATION, format!("Bearer {}", key)),
+            LlmProvider::AzureOpenAi => (HeaderName::from_static("api-key"), key),
+            LlmProvider::Anthropic => (HeaderName::from_static("x-api-key"), key),
+        };
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            headers.insert(name, value);
+        }
+        headers
+    }
+
+    /// The model an agent's request for `requested` is sent to.
+    pub fn resolve_model(&self, requested: LlmModel) -> LlmModel {
+        match (self.provider, &self.model) {
+            (LlmProvider::Anthropic, Some(model)) => {
+                LlmModel::Anthropic(AnthropicChatModel::Custom(model.clone()))
+            }
+            (LlmProvider::Anthropic, None) => match requested {
+                LlmModel::Anthropic(model) => LlmModel::Anthropic(model),
+                LlmModel::OpenAi(_) => LlmModel::Anthropic(AnthropicChatModel::Claude3Opus20240229),
+            },
+            (_, Some(model)) => LlmModel::OpenAi(OpenAiChatModel::Custom(model.clone())),
+            (_, None) => requested,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn config(vars: &[(&str, &str)]) -> Result<LlmConfig> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        LlmConfig::from_vars(|name| vars.get(name).cloned())
+    }
+
+    #[test]
+    fn test_llm_config_from_vars() {
+        let openai = config(&[("OPENAI_API_KEY", "sk-test")]).unwrap();
+        assert_eq!(openai.provider, LlmProvider::OpenAi);
+        assert_eq!(
+            openai.base_url,
+            "https://api.openai.com/v1/chat/completions"
+        );
+        assert_eq!(openai.api_key_env.as_deref(), Some("OPENAI_API_KEY"));
+        assert!(matches!(
+            openai.resolve_model(LlmModel::OpenAi(OpenAiChatModel::Gpt4o)),
+            LlmModel::OpenAi(OpenAiChatModel::Gpt4o)
+        ));
+
+        let anthropic = config(&[
+            ("LLM_PROVIDER", "anthropic"),
+            ("LLM_MODEL", "claude-3-5-sonnet-latest"),
+            ("ANTHROPIC_API_KEY", "key"),
+        ])
+        .unwrap();
+        assert!(matches!(
+            anthropic.resolve_model(LlmModel::OpenAi(OpenAiChatModel::O3Mini)),
+            LlmModel::Anthropic(AnthropicChatModel::Custom(model)) if model == "claude-3-5-sonnet-latest"
+        ));
+
+        let local = config(&[("LLM_PROVIDER", "local"), ("LLM_MODEL", "llama3.1")]).unwrap();
+        assert_eq!(local.api_key_env, None);
+        assert!(local.auth_headers().is_empty());
+
+        let error = |vars: &[(&str, &str)]| config(vars).unwrap_err().to_string();
+        assert!(error(&[]).contains("OPENAI_API_KEY must be set"));
+        assert!(error(&[("LLM_PROVIDER", "cohere")]).contains("Unknown LLM_PROVIDER"));
+        assert!(error(&[
+            ("LLM_PROVIDER", "azure_openai"),
+            ("AZURE_OPENAI_API_KEY", "key")
+        ])
+        .contains("LLM_BASE_URL is required"));
+        assert!(error(&[("LLM_PROVIDER", "local")]).contains("LLM_MODEL is required"));
+        assert!(error(&[
+            ("LLM_PROVIDER", "local"),
+            ("LLM_MODEL", "llama3.1"),
+            ("LLM_BASE_URL", "localhost:11434")
+        ])
+        .contains("must be an http or https URL"));
+    }
+}
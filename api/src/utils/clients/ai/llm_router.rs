@@ -17,6 +17,7 @@ use super::{
         AnthropicChatRole, AnthropicContent, AnthropicContentType,
     },
     langfuse::{send_langfuse_request, PromptName},
+    llm_config::llm_config,
     openai::{
         openai_chat, openai_chat_stream, OpenAiChatContent, OpenAiChatMessage, OpenAiChatModel,
         OpenAiChatRole,
@@ -76,6 +77,7 @@ pub async fn llm_chat(
     user_id: &Uuid,
     prompt_name: PromptName,
 ) -> Result<String> {
+    let model = llm_config().resolve_model(model);
     let start_time = Utc::now();
 
     let response_result = match &model {
@@ -131,6 +133,7 @@ pub async fn llm_chat_stream(
     user_id: &Uuid,
     prompt_name: PromptName,
 ) -> Result<(Receiver<String>, JoinHandle<Result<String>>)> {
+    let model = llm_config().resolve_model(model);
     let start_time = Utc::now();
 
     let stream_result = match &model {
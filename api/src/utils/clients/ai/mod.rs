@@ -2,6 +2,7 @@ mod anthropic;
 pub mod embedding_router;
 mod hugging_face;
 pub mod langfuse;
+pub mod llm_config;
 pub mod llm_router;
 pub mod ollama;
 pub mod openai;
@@ -7,14 +7,13 @@ use tokio_stream::wrappers::ReceiverStream;
 
 use serde::{Deserialize, Serialize};
 
-use crate::utils::clients::sentry_utils::send_sentry_error;
+use crate::utils::clients::{ai::llm_config::llm_config, sentry_utils::send_sentry_error};
 
 const OPENAI_EMBEDDING_URL: &str = "https://api.openai.com/v1/embeddings";
 
 lazy_static::lazy_static! {
     static ref OPENAI_API_KEY: String = env::var("OPENAI_API_KEY")
         .expect("OPENAI_API_KEY must be set");
-    static ref OPENAI_CHAT_URL: String = env::var("OPENAI_CHAT_URL").unwrap_or("https://api.openai.com/v1/chat/completions".to_string());
 }
 
 #[derive(Serialize, Clone)]
@@ -25,6 +24,9 @@ pub enum OpenAiChatModel {
     O3Mini,
     #[serde(rename = "gpt-3.5-turbo")]
     Gpt35Turbo,
+    /// A model named by `LLM_MODEL`
+    #[serde(untagged)]
+    Custom(String),
 }
 
 #[derive(Serialize, Clone)]
@@ -59,7 +61,11 @@ pub struct OpenAiChatMessage {
 
 // Helper functions for conditional serialization
 fn is_o3_model(model: &OpenAiChatModel) -> bool {
-    matches!(model, OpenAiChatModel::O3Mini)
+    match model {
+        OpenAiChatModel::O3Mini => true,
+        OpenAiChatModel::Custom(name) => name.starts_with("o1") || name.starts_with("o3"),
+        _ => false,
+    }
 }
 
 fn should_skip_temperature(val: &(&f32, &OpenAiChatModel)) -> bool {
@@ -178,19 +184,10 @@ pub async fn openai_chat(
 
     let client = reqwest::Client::new();
 
-    let headers = {
-        let mut headers = reqwest::header::HeaderMap::new();
-        headers.insert(
-            reqwest::header::AUTHORIZATION,
-            format!("Bearer {}", OPENAI_API_KEY.to_string())
-                .parse()
-                .unwrap(),
-        );
-        headers
-    };
+    let headers = llm_config().auth_headers();
 
     let response = match client
-        .post(OPENAI_CHAT_URL.to_string())
+        .post(&llm_config().base_url)
         .headers(headers)
         .json(&chat_request)
         .timeout(Duration::from_secs(timeout))
@@ -272,22 +269,13 @@ pub async fn openai_chat_stream(
 
     let client = reqwest::Client::new();
 
-    let headers = {
-        let mut headers = reqwest::header::HeaderMap::new();
-        headers.insert(
-            reqwest::header::AUTHORIZATION,
-            format!("Bearer {}", OPENAI_API_KEY.to_string())
-                .parse()
-                .unwrap(),
-        );
-        headers
-    };
+    let headers = llm_config().auth_headers();
 
     let (tx, rx): (Sender<String>, Receiver<String>) = mpsc::channel(100);
 
     tokio::spawn(async move {
         let response = client
-            .post(OPENAI_CHAT_URL.to_string())
+            .post(&llm_config().base_url)
             .headers(headers)
             .json(&chat_request)
             .timeout(Duration::from_secs(timeout))
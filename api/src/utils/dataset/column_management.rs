@@ -26,6 +26,7 @@ pub struct ColumnUpdate {
     pub dim_type: Option<String>,
     pub expr: Option<String>,
     pub searchable: bool,
+    pub ordinal: Option<i32>,
 }
 
 /// Retrieves column types from the data source
@@ -35,7 +36,7 @@ pub async fn get_column_types(
     data_source: &DataSource,
 ) -> Result<Vec<ColumnUpdate>> {
     let credentials =
-        get_data_source_credentials(&data_source.secret_id, &data_source.type_, false)
+        get_data_source_credentials(data_source, &data_source.type_, false)
             .await
             .map_err(|e| anyhow!("Error getting data source credentials: {}", e))?;
 
@@ -59,6 +60,7 @@ pub async fn get_column_types(
             dim_type: None,
             expr: None,
             searchable: false,
+            ordinal: Some(col.ordinal),
         })
         .collect())
 }
@@ -91,6 +93,9 @@ pub async fn update_dataset_columns(
             stored_values_last_synced: None,
             dim_type: col.dim_type,
             expr: col.expr,
+            ordinal: col.ordinal,
+            hidden: false,
+            label: None,
         })
         .collect();
 
@@ -107,6 +112,7 @@ pub async fn update_dataset_columns(
             dataset_columns::expr.eq(excluded(dataset_columns::expr)),
             dataset_columns::nullable.eq(excluded(dataset_columns::nullable)),
             dataset_columns::stored_values.eq(excluded(dataset_columns::stored_values)),
+            dataset_columns::ordinal.eq(excluded(dataset_columns::ordinal)),
             dataset_columns::updated_at.eq(Utc::now()),
             dataset_columns::deleted_at.eq(None::<chrono::DateTime<Utc>>),
         ))
@@ -0,0 +1,212 @@
+use crate::database::enums::DataSourceType;
+
+use super::{
+    import_dataset_columns::DatasetObjectType, sample_values::quote_identifier,
+    table_references::qualified_table_name,
+};
+
+/// The statements writing a model's descriptions to its table and columns as warehouse
+/// comments, or `None` when the dialect can't set comments without restating each column's
+/// definition (MySQL, MariaDB) or at all through SQL (SQL Server). Empty descriptions are
+/// skipped rather than clearing existing comments. Views and materialized views are
+/// commented on as such, since most warehouses reject `COMMENT ON TABLE` for them.
+pub fn comment_statements(
+    data_source_type: &DataSourceType,
+    database: Option<&str>,
+    schema: &str,
+    table: &str,
+    object_type: DatasetObjectType,
+    table_description: &str,
+    column_descriptions: &[(&str, &str)],
+) -> Option<Vec<String>> {
+    if matches!(
+        data_source_type,
+        DataSourceType::MySql | DataSourceType::Mariadb | DataSourceType::SqlServer
+    ) {
+        return None;
+    }
+
+    let table_name = qualified_table_name(data_source_type, database, schema, table);
+    let object = match object_type {
+        DatasetObjectType::View => "VIEW",
+        DatasetObjectType::MaterializedView => "MATERIALIZED VIEW",
+        DatasetObjectType::Table | DatasetObjectType::Other => "TABLE",
+    };
+    let mut statements = Vec::new();
+
+    if !table_description.trim().is_empty() {
+        let comment = string_literal(data_source_type, table_description.trim());
+        statements.push(match data_source_type {
+            DataSourceType::BigQuery => format!(
+                "ALTER {} {} SET OPTIONS (description = {})",
+                object, table_name, comment
+            ),
+            _ => format!("COMMENT ON {} {} IS {}", object, table_name, comment),
+        });
+    }
+
+    for (column, description) in column_descriptions {
+        if description.trim().is_empty() {
+            continue;
+        }
+        let column_name = quote_identifier(data_source_type, column);
+        let comment = string_literal(data_source_type, description.trim());
+        statements.push(match data_source_type {
+            DataSourceType::BigQuery => format!(
+                "ALTER {} {} ALTER COLUMN {} SET OPTIONS (description = {})",
+                object, table_name, column_name, comment
+            ),
+            DataSourceType::Databricks => format!(
+                "ALTER TABLE {} ALTER COLUMN {} COMMENT {}",
+                table_name, column_name, comment
+            ),
+            _ => format!(
+                "COMMENT ON COLUMN {}.{} IS {}",
+                table_name, column_name, comment
+            ),
+        });
+    }
+
+    Some(statements)
+}
+
+/// A single-quoted string literal. BigQuery, Databricks and Snowflake also treat a
+/// backslash as an escape character, so it's doubled for them.
+fn string_literal(data_source_type: &DataSourceType, value: &str) -> String {
+    let value = match data_source_type {
+        DataSourceType::BigQuery | DataSourceType::Databricks | DataSourceType::Snowflake => {
+            value.replace('\\', "\\\\").replace('\'', "\\'")
+        }
+        _ => value.replace('\'', "''"),
+    };
+    format!("'{}'", value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comment_statements() {
+        let columns = [
+            ("order_id", "Unique order ID"),
+            ("status", ""),
+            ("note", "Customer's note"),
+        ];
+
+        assert_eq!(
+            comment_statements(
+                &DataSourceType::Postgres,
+                None,
+                "analytics",
+                "orders",
+                DatasetObjectType::Table,
+                "One row per order",
+                &columns
+            )
+            .unwrap(),
+            vec![
+                "COMMENT ON TABLE \"analytics\".\"orders\" IS 'One row per order'",
+                "COMMENT ON COLUMN \"analytics\".\"orders\".\"order_id\" IS 'Unique order ID'",
+                "COMMENT ON COLUMN \"analytics\".\"orders\".\"note\" IS 'Customer''s note'",
+            ]
+        );
+
+        assert_eq!(
+            comment_statements(
+                &DataSourceType::BigQuery,
+                Some("acme-prod"),
+                "analytics",
+                "orders",
+                DatasetObjectType::Table,
+                "",
+                &[("note", "Customer's note")]
+            )
+            .unwrap(),
+            vec![
+                "ALTER TABLE `acme-prod`.`analytics`.`orders` ALTER COLUMN `note` SET OPTIONS (description = 'Customer\\'s note')",
+            ]
+        );
+
+        assert_eq!(
+            comment_statements(
+                &DataSourceType::Databricks,
+                None,
+                "analytics",
+                "orders",
+                DatasetObjectType::Table,
+                "Orders",
+                &[("order_id", "Path C:\\orders")]
+            )
+            .unwrap(),
+            vec![
+                "COMMENT ON TABLE `analytics`.`orders` IS 'Orders'",
+                "ALTER TABLE `analytics`.`orders` ALTER COLUMN `order_id` COMMENT 'Path C:\\\\orders'",
+            ]
+        );
+
+        assert!(comment_statements(
+            &DataSourceType::MySql,
+            None,
+            "shop",
+            "orders",
+            DatasetObjectType::Table,
+            "Orders",
+            &[]
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_comment_statements_for_views() {
+        assert_eq!(
+            comment_statements(
+                &DataSourceType::Postgres,
+                None,
+                "analytics",
+                "active_orders",
+                DatasetObjectType::View,
+                "Open orders",
+                &[("order_id", "Unique order ID")]
+            )
+            .unwrap(),
+            vec![
+                "COMMENT ON VIEW \"analytics\".\"active_orders\" IS 'Open orders'",
+                "COMMENT ON COLUMN \"analytics\".\"active_orders\".\"order_id\" IS 'Unique order ID'",
+            ]
+        );
+
+        assert_eq!(
+            comment_statements(
+                &DataSourceType::Snowflake,
+                None,
+                "ANALYTICS",
+                "DAILY_REVENUE",
+                DatasetObjectType::MaterializedView,
+                "Revenue per day",
+                &[]
+            )
+            .unwrap(),
+            vec![
+                "COMMENT ON MATERIALIZED VIEW \"ANALYTICS\".\"DAILY_REVENUE\" IS 'Revenue per day'",
+            ]
+        );
+
+        assert_eq!(
+            comment_statements(
+                &DataSourceType::BigQuery,
+                None,
+                "analytics",
+                "active_orders",
+                DatasetObjectType::View,
+                "Open orders",
+                &[("order_id", "Unique order ID")]
+            )
+            .unwrap(),
+            vec![
+                "ALTER VIEW `analytics`.`active_orders` SET OPTIONS (description = 'Open orders')",
+                "ALTER VIEW `analytics`.`active_orders` ALTER COLUMN `order_id` SET OPTIONS (description = 'Unique order ID')",
+            ]
+        );
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+use std::env;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{database::models::DataSource, utils::clients::supabase_vault};
+
+/// Where a data source's warehouse credentials are read from. The secret store is the
+/// default; self-hosted deployments can inject credentials through environment
+/// variables or HashiCorp Vault instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CredentialSource {
+    SecretStore,
+    Env,
+    Vault,
+}
+
+impl FromStr for CredentialSource {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "secret_store" => Ok(CredentialSource::SecretStore),
+            "env" => Ok(CredentialSource::Env),
+            "vault" => Ok(CredentialSource::Vault),
+            other => Err(anyhow!(
+                "Unknown credential source '{}' (expected secret_store, env or vault)",
+                other
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for CredentialSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CredentialSource::SecretStore => write!(f, "secret_store"),
+            CredentialSource::Env => write!(f, "env"),
+            CredentialSource::Vault => write!(f, "vault"),
+        }
+    }
+}
+
+/// Per-organization and per-data-source backends from `CREDENTIAL_SOURCE_OVERRIDES`,
+/// a comma-separated list of `<organization or data source id>=<source>`.
+static CREDENTIAL_SOURCE_OVERRIDES: Lazy<Result<HashMap<Uuid, CredentialSource>, String>> =
+    Lazy::new(|| {
+        parse_overrides(&env::var("CREDENTIAL_SOURCE_OVERRIDES").unwrap_or_default())
+            .map_err(|e| e.to_string())
+    });
+
+impl CredentialSource {
+    /// The backend for a data source: its own override, then its organization's, then
+    /// `CREDENTIAL_SOURCE` (the secret store when unset).
+    pub fn for_data_source(data_source: &DataSource) -> Result<Self> {
+        let overrides = CREDENTIAL_SOURCE_OVERRIDES
+            .as_ref()
+            .map_err(|e| anyhow!("Invalid CREDENTIAL_SOURCE_OVERRIDES: {}", e))?;
+
+        if let Some(source) = overrides
+            .get(&data_source.id)
+            .or_else(|| overrides.get(&data_source.organization_id))
+        {
+            return Ok(*source);
+        }
+
+        match env::var("CREDENTIAL_SOURCE") {
+            Ok(source) => source.parse(),
+            Err(_) => Ok(CredentialSource::SecretStore),
+        }
+    }
+}
+
+fn parse_overrides(overrides: &str) -> Result<HashMap<Uuid, CredentialSource>> {
+    overrides
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (id, source) = entry
+                .split_once('=')
+                .ok_or_else(|| anyhow!("expected '<id>=<source>', got '{}'", entry))?;
+            let id = Uuid::parse_str(id.trim())
+                .map_err(|e| anyhow!("invalid id '{}': {}", id.trim(), e))?;
+            Ok((id, source.parse()?))
+        })
+        .collect()
+}
+
+/// A backend that can look up the serialized credentials a data source's `secret_id`
+/// refers to. The returned string has the same JSON shape whichever backend holds it.
+pub(crate) trait CredentialResolver {
+    async fn read_secret(&self, secret_id: &Uuid) -> Result<String>;
+}
+
+struct SecretStoreResolver;
+
+impl CredentialResolver for SecretStoreResolver {
+    async fn read_secret(&self, secret_id: &Uuid) -> Result<String> {
+        supabase_vault::read_secret(secret_id).await
+    }
+}
+
+/// Reads credentials JSON from `BUSTER_SECRET_<SECRET_ID>`, with the id in its
+/// unhyphenated, uppercase form.
+struct EnvResolver;
+
+impl EnvResolver {
+    fn var_name(secret_id: &Uuid) -> String {
+        format!(
+            "BUSTER_SECRET_{}",
+            secret_id.simple().to_string().to_uppercase()
+        )
+    }
+}
+
+impl CredentialResolver for EnvResolver {
+    async fn read_secret(&self, secret_id: &Uuid) -> Result<String> {
+        let name = Self::var_name(secret_id);
+        env::var(&name).map_err(|_| anyhow!("environment variable {} is not set", name))
+    }
+}
+
+/// Reads credentials from a HashiCorp Vault KV v2 engine, at
+/// `<VAULT_MOUNT>/<VAULT_PATH_PREFIX>/<secret_id>`. The secret's fields are the
+/// credential fields.
+struct VaultResolver {
+    addr: String,
+    token: String,
+    mount: String,
+    path_prefix: String,
+    namespace: Option<String>,
+}
+
+impl VaultResolver {
+    fn from_env() -> Result<Self> {
+        Ok(Self {
+            addr: env::var("VAULT_ADDR")
+                .map_err(|_| anyhow!("VAULT_ADDR is not set"))?
+                .trim_end_matches('/')
+                .to_string(),
+            token: env::var("VAULT_TOKEN").map_err(|_| anyhow!("VAULT_TOKEN is not set"))?,
+            mount: env::var("VAULT_MOUNT").unwrap_or_else(|_| "secret".to_string()),
+            path_prefix: env::var("VAULT_PATH_PREFIX").unwrap_or_else(|_| "buster".to_string()),
+            namespace: env::var("VAULT_NAMESPACE").ok(),
+        })
+    }
+}
+
+impl CredentialResolver for VaultResolver {
+    async fn read_secret(&self, secret_id: &Uuid) -> Result<String> {
+        let path = format!("{}/{}", self.path_prefix, secret_id);
+        let mut request = reqwest::Client::new()
+            .get(format!("{}/v1/{}/data/{}", self.addr, self.mount, path))
+            .header("X-Vault-Token", &self.token);
+        if let Some(namespace) = &self.namespace {
+            request = request.header("X-Vault-Namespace", namespace);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| anyhow!("Vault request failed: {}", e))?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(anyhow!("no secret at {}/{} in Vault", self.mount, path));
+        }
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "Vault returned {} for {}/{}",
+                response.status(),
+                self.mount,
+                path
+            ));
+        }
+
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| anyhow!("Invalid response from Vault: {}", e))?;
+
+        match body.pointer("/data/data") {
+            Some(data @ Value::Object(_)) => Ok(data.to_string()),
+            _ => Err(anyhow!(
+                "secret at {}/{} in Vault has no data",
+                self.mount,
+                path
+            )),
+        }
+    }
+}
+
+/// Reads a data source's serialized credentials from the backend configured for it.
+pub async fn read_data_source_secret(data_source: &DataSource) -> Result<String> {
+    let source = CredentialSource::for_data_source(data_source)?;
+
+    let secret = match source {
+        CredentialSource::SecretStore => {
+            SecretStoreResolver
+                .read_secret(&data_source.secret_id)
+                .await
+        }
+        CredentialSource::Env => EnvResolver.read_secret(&data_source.secret_id).await,
+        CredentialSource::Vault => match VaultResolver::from_env() {
+            Ok(resolver) => resolver.read_secret(&data_source.secret_id).await,
+            Err(e) => Err(e),
+        },
+    };
+
+    secret.map_err(|e| {
+        anyhow!(
+            "Unable to resolve secret {} for data source '{}' from {}: {}",
+            data_source.secret_id,
+            data_source.name,
+            source,
+            e
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_overrides() {
+        let org = Uuid::new_v4();
+        let data_source = Uuid::new_v4();
+
+        let overrides = parse_overrides(&format!("{}=vault, {}=env,", org, data_source)).unwrap();
+        assert_eq!(overrides.get(&org), Some(&CredentialSource::Vault));
+        assert_eq!(overrides.get(&data_source), Some(&CredentialSource::Env));
+
+        assert!(parse_overrides("").unwrap().is_empty());
+        assert!(parse_overrides(&format!("{}=aws", org)).is_err());
+        assert!(parse_overrides("not-a-uuid=env").is_err());
+    }
+
+    #[test]
+    fn test_env_var_name() {
+        let secret_id = Uuid::parse_str("3f2a9c1e-7b4d-4e8f-9a0b-1c2d3e4f5a6b").unwrap();
+        assert_eq!(
+            EnvResolver::var_name(&secret_id),
+            "BUSTER_SECRET_3F2A9C1E7B4D4E8F9A0B1C2D3E4F5A6B"
+        );
+    }
+}
@@ -3,7 +3,10 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use uuid::Uuid;
 
-use crate::{database::enums::DataSourceType, utils::clients::supabase_vault::read_secret};
+use crate::{
+    database::{enums::DataSourceType, models::DataSource},
+    utils::query_engine::credential_sources::read_data_source_secret,
+};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(tag = "type")]
@@ -143,19 +146,26 @@ impl Credential {
     }
 }
 
+/// Reads and parses a data source's credentials from whichever credential source
+/// (secret store, environment or Vault) is configured for it.
 pub async fn get_data_source_credentials(
-    secret_id: &Uuid,
+    data_source: &DataSource,
     data_source_type: &DataSourceType,
     redact_secret: bool,
 ) -> Result<Credential> {
-    let secret_string = match read_secret(&secret_id).await {
-        Ok(secret) => secret,
-        Err(e) => return Err(anyhow!("Error reading secret: {:?}", e)),
-    };
+    let secret_string = read_data_source_secret(data_source).await?;
+
+    parse_credential(&secret_string, data_source_type, redact_secret)
+}
 
+fn parse_credential(
+    secret_string: &str,
+    data_source_type: &DataSourceType,
+    redact_secret: bool,
+) -> Result<Credential> {
     let credential: Credential = match data_source_type {
         DataSourceType::BigQuery => {
-            match serde_json::from_str::<BigqueryCredentials>(&secret_string) {
+            match serde_json::from_str::<BigqueryCredentials>(secret_string) {
                 Ok(mut credential) => {
                     println!("credential: {:?}", credential);
 
@@ -168,7 +178,7 @@ pub async fn get_data_source_credentials(
             }
         }
         DataSourceType::Databricks => {
-            match serde_json::from_str::<DatabricksCredentials>(&secret_string) {
+            match serde_json::from_str::<DatabricksCredentials>(secret_string) {
                 Ok(mut credential) => {
                     if redact_secret {
                         credential.api_key = "[REDACTED]".to_string();
@@ -178,7 +188,7 @@ pub async fn get_data_source_credentials(
                 Err(e) => return Err(anyhow!("Error deserializing Databricks secret: {:?}", e)),
             }
         }
-        DataSourceType::MySql => match serde_json::from_str::<MySqlCredentials>(&secret_string) {
+        DataSourceType::MySql => match serde_json::from_str::<MySqlCredentials>(secret_string) {
             Ok(mut credential) => {
                 if redact_secret {
                     credential.password = "[REDACTED]".to_string();
@@ -189,7 +199,7 @@ pub async fn get_data_source_credentials(
             }
             Err(e) => return Err(anyhow!("Error deserializing MySQL secret: {:?}", e)),
         },
-        DataSourceType::Mariadb => match serde_json::from_str::<MySqlCredentials>(&secret_string) {
+        DataSourceType::Mariadb => match serde_json::from_str::<MySqlCredentials>(secret_string) {
             Ok(mut credential) => {
                 if redact_secret {
                     credential.password = "[REDACTED]".to_string();
@@ -201,7 +211,7 @@ pub async fn get_data_source_credentials(
             Err(e) => return Err(anyhow!("Error deserializing MariaDB secret: {:?}", e)),
         },
         DataSourceType::Postgres => {
-            match serde_json::from_str::<PostgresCredentials>(&secret_string) {
+            match serde_json::from_str::<PostgresCredentials>(secret_string) {
                 Ok(mut credential) => {
                     if redact_secret {
                         credential.password = "[REDACTED]".to_string();
@@ -214,7 +224,7 @@ pub async fn get_data_source_credentials(
             }
         }
         DataSourceType::Redshift => {
-            match serde_json::from_str::<PostgresCredentials>(&secret_string) {
+            match serde_json::from_str::<PostgresCredentials>(secret_string) {
                 Ok(mut credential) => {
                     if redact_secret {
                         credential.password = "[REDACTED]".to_string();
@@ -225,7 +235,7 @@ pub async fn get_data_source_credentials(
             }
         }
         DataSourceType::Snowflake => {
-            match serde_json::from_str::<SnowflakeCredentials>(&secret_string) {
+            match serde_json::from_str::<SnowflakeCredentials>(secret_string) {
                 Ok(mut credential) => {
                     if redact_secret {
                         credential.password = "[REDACTED]".to_string();
@@ -236,7 +246,7 @@ pub async fn get_data_source_credentials(
             }
         }
         DataSourceType::SqlServer => {
-            match serde_json::from_str::<SqlServerCredentials>(&secret_string) {
+            match serde_json::from_str::<SqlServerCredentials>(secret_string) {
                 Ok(mut credential) => {
                     if redact_secret {
                         credential.password = "[REDACTED]".to_string();
@@ -249,7 +259,7 @@ pub async fn get_data_source_credentials(
             }
         }
         DataSourceType::Supabase => {
-            match serde_json::from_str::<PostgresCredentials>(&secret_string) {
+            match serde_json::from_str::<PostgresCredentials>(secret_string) {
                 Ok(mut credential) => {
                     if redact_secret {
                         credential.password = "[REDACTED]".to_string();
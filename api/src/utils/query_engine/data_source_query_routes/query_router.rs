@@ -5,8 +5,8 @@ use anyhow::{anyhow, Result};
 use crate::{
     database::{enums::DataSourceType, models::DataSource},
     utils::{
-        clients::supabase_vault::read_secret,
         query_engine::{
+            credential_sources::read_data_source_secret,
             credentials::{
                 BigqueryCredentials, DatabricksCredentials, MySqlCredentials, PostgresCredentials,
                 SnowflakeCredentials, SqlServerCredentials,
@@ -78,7 +78,7 @@ async fn route_to_query(
     sql: &String,
     limit: Option<i64>,
 ) -> Result<Vec<IndexMap<String, DataType>>> {
-    let credentials_string = match read_secret(&data_source.secret_id).await {
+    let credentials_string = match read_data_source_secret(data_source).await {
         Ok(credentials) => credentials,
         Err(e) => return Err(anyhow!(e)),
     };
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
+use uuid::Uuid;
+
+use crate::database::{enums::DataSourceType, models::DataSource};
+
+use super::data_source_query_routes::query_router::query_router;
+use super::data_types::DataType;
+
+/// Dialects detected from the warehouse itself, keyed by data source id. Entries live
+/// for the lifetime of the process so each data source is only probed once.
+static DETECTED_DIALECTS: Lazy<Mutex<HashMap<Uuid, DataSourceType>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Returns the dialect to use for a data source. When `autodetect` is set the warehouse
+/// is asked for its version banner (once per data source), otherwise the recorded type
+/// is used. Detection failures fall back to the recorded type.
+pub async fn resolve_data_source_type(data_source: &DataSource, autodetect: bool) -> DataSourceType {
+    if !autodetect {
+        return data_source.type_;
+    }
+
+    if let Some(detected) = cached_data_source_type(&data_source.id) {
+        return detected;
+    }
+
+    match detect_data_source_type(data_source).await {
+        Ok(detected) => {
+            if detected != data_source.type_ {
+                tracing::info!(
+                    "Detected dialect '{}' for data source '{}' (recorded as '{}')",
+                    detected.to_string(),
+                    data_source.name,
+                    data_source.type_.to_string()
+                );
+            }
+            DETECTED_DIALECTS
+                .lock()
+                .unwrap()
+                .insert(data_source.id, detected);
+            detected
+        }
+        Err(e) => {
+            tracing::warn!(
+                "Unable to detect dialect for data source '{}', using recorded type: {:?}",
+                data_source.name,
+                e
+            );
+            data_source.type_
+        }
+    }
+}
+
+/// The dialect previously detected for a data source in this process, if any.
+pub fn cached_data_source_type(data_source_id: &Uuid) -> Option<DataSourceType> {
+    DETECTED_DIALECTS.lock().unwrap().get(data_source_id).copied()
+}
+
+async fn detect_data_source_type(data_source: &DataSource) -> Result<DataSourceType> {
+    let sql = match version_query(&data_source.type_) {
+        Some(sql) => sql.to_string(),
+        None => return Ok(data_source.type_),
+    };
+
+    let rows = query_router(data_source, &sql, Some(1), false).await?;
+
+    let banner = rows
+        .first()
+        .and_then(|row| row.values().next())
+        .and_then(|value| match value {
+            DataType::Text(Some(s)) | DataType::Char(Some(s)) | DataType::Unknown(Some(s)) => {
+                Some(s.clone())
+            }
+            _ => None,
+        })
+        .ok_or_else(|| anyhow!("Version query returned no banner"))?;
+
+    Ok(dialect_from_version_banner(data_source.type_, &banner))
+}
+
+fn version_query(data_source_type: &DataSourceType) -> Option<&'static str> {
+    match data_source_type {
+        DataSourceType::Postgres
+        | DataSourceType::Supabase
+        | DataSourceType::Redshift
+        | DataSourceType::MySql
+        | DataSourceType::Mariadb => Some("SELECT version()"),
+        DataSourceType::Snowflake => Some("SELECT CURRENT_VERSION()"),
+        DataSourceType::SqlServer => Some("SELECT @@VERSION"),
+        // BigQuery and Databricks are only reachable through their own APIs, so the
+        // recorded type is already unambiguous.
+        DataSourceType::BigQuery | DataSourceType::Databricks => None,
+    }
+}
+
+/// Maps a version banner onto a dialect. Warehouses that speak another engine's wire
+/// protocol (Redshift and Postgres, MariaDB and MySQL) are told apart here.
+pub fn dialect_from_version_banner(recorded: DataSourceType, banner: &str) -> DataSourceType {
+    let banner = banner.to_lowercase();
+
+    match recorded {
+        DataSourceType::Postgres | DataSourceType::Supabase | DataSourceType::Redshift => {
+            if banner.contains("redshift") {
+                DataSourceType::Redshift
+            } else if banner.contains("postgresql") && recorded == DataSourceType::Redshift {
+                DataSourceType::Postgres
+            } else {
+                recorded
+            }
+        }
+        DataSourceType::MySql | DataSourceType::Mariadb => {
+            if banner.contains("mariadb") {
+                DataSourceType::Mariadb
+            } else {
+                DataSourceType::MySql
+            }
+        }
+        _ => recorded,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dialect_from_version_banner() {
+        assert_eq!(
+            dialect_from_version_banner(
+                DataSourceType::Postgres,
+                "PostgreSQL 8.0.2 on i686-pc-linux-gnu, compiled by GCC gcc (GCC) 3.4.2 20041017 (Red Hat 3.4.2-6.fc3), Redshift 1.0.77467"
+            ),
+            DataSourceType::Redshift
+        );
+        assert_eq!(
+            dialect_from_version_banner(
+                DataSourceType::Postgres,
+                "PostgreSQL 15.4 on aarch64-unknown-linux-gnu"
+            ),
+            DataSourceType::Postgres
+        );
+        assert_eq!(
+            dialect_from_version_banner(DataSourceType::MySql, "10.11.6-MariaDB-0+deb12u1"),
+            DataSourceType::Mariadb
+        );
+        assert_eq!(
+            dialect_from_version_banner(DataSourceType::Snowflake, "8.3.1"),
+            DataSourceType::Snowflake
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::BTreeSet;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::database::enums::DataSourceType;
+
+use DataSourceType::*;
+
+/// Functions that only exist in some dialects, with the dialects that support them and
+/// what to use elsewhere. This is deliberately coarse: it lists the functions that
+/// generated SQL most often carries over from the wrong warehouse, not every function.
+const DIALECT_FUNCTIONS: &[(&str, &[DataSourceType], &str)] = &[
+    ("iff", &[Snowflake, Databricks], "use CASE WHEN ... THEN ... ELSE ... END"),
+    ("safe_divide", &[BigQuery], "divide by NULLIF(denominator, 0)"),
+    ("format_date", &[BigQuery], "use the dialect's date formatting function"),
+    ("format_timestamp", &[BigQuery], "use the dialect's timestamp formatting function"),
+    ("parse_date", &[BigQuery], "cast the string to a date"),
+    (
+        "date_diff",
+        &[BigQuery, Databricks],
+        "use the dialect's date difference function",
+    ),
+    ("timestamp_diff", &[BigQuery], "use the dialect's timestamp difference function"),
+    ("timestamp_trunc", &[BigQuery], "use DATE_TRUNC"),
+    ("datetime_trunc", &[BigQuery], "use DATE_TRUNC"),
+    (
+        "dateadd",
+        &[Snowflake, Redshift, SqlServer, Databricks],
+        "add an INTERVAL (or DATE_ADD in BigQuery and MySQL)",
+    ),
+    (
+        "datediff",
+        &[Snowflake, Redshift, SqlServer, Databricks, MySql, Mariadb],
+        "subtract the dates (or DATE_DIFF in BigQuery)",
+    ),
+    (
+        "date_trunc",
+        &[Postgres, Supabase, Redshift, Snowflake, BigQuery, Databricks],
+        "truncate with DATE_FORMAT or DATEFROMPARTS",
+    ),
+    (
+        "to_char",
+        &[Postgres, Supabase, Redshift, Snowflake, Databricks],
+        "use FORMAT_DATE (BigQuery), DATE_FORMAT (MySQL) or FORMAT (SQL Server)",
+    ),
+    (
+        "date_format",
+        &[MySql, Mariadb, Databricks],
+        "use TO_CHAR or FORMAT_DATE",
+    ),
+    ("nvl", &[Snowflake, Redshift, Databricks], "use COALESCE"),
+    (
+        "ifnull",
+        &[BigQuery, MySql, Mariadb, Snowflake, Databricks],
+        "use COALESCE",
+    ),
+    ("getdate", &[Snowflake, Redshift, SqlServer], "use CURRENT_TIMESTAMP"),
+    (
+        "listagg",
+        &[Snowflake, Redshift, Databricks],
+        "use STRING_AGG (or GROUP_CONCAT in MySQL)",
+    ),
+    (
+        "string_agg",
+        &[Postgres, Supabase, BigQuery, SqlServer, Snowflake, Databricks],
+        "use LISTAGG (or GROUP_CONCAT in MySQL)",
+    ),
+    ("group_concat", &[MySql, Mariadb], "use STRING_AGG or LISTAGG"),
+    (
+        "split_part",
+        &[Postgres, Supabase, Redshift, Snowflake, Databricks],
+        "use SPLIT (BigQuery) or SUBSTRING_INDEX (MySQL)",
+    ),
+];
+
+static FUNCTION_CALL: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\b([a-z_][a-z0-9_]*)\s*\(").unwrap());
+static STRING_LITERAL: Lazy<Regex> = Lazy::new(|| Regex::new(r"'(?:[^']|'')*'").unwrap());
+
+/// Flags calls to functions the target dialect doesn't have, as hints that can be handed
+/// straight to the SQL fixer. An empty result doesn't mean the SQL is valid.
+pub fn lint_dialect_functions(sql: &str, data_source_type: &DataSourceType) -> Vec<String> {
+    let sql = STRING_LITERAL.replace_all(sql, "''");
+
+    let called: BTreeSet<String> = FUNCTION_CALL
+        .captures_iter(&sql)
+        .map(|caps| caps[1].to_lowercase())
+        .collect();
+
+    DIALECT_FUNCTIONS
+        .iter()
+        .filter(|(name, supported, _)| {
+            called.contains(*name) && !supported.contains(data_source_type)
+        })
+        .map(|(name, _, suggestion)| {
+            format!(
+                "{}() is not available in {}; {}",
+                name.to_uppercase(),
+                data_source_type.to_string(),
+                suggestion
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lint_dialect_functions() {
+        let sql = "SELECT IFF(status = 'active', 1, 0), SAFE_DIVIDE(a, b), COALESCE(c, 0) FROM t";
+
+        let hints = lint_dialect_functions(sql, &Postgres);
+        assert_eq!(hints.len(), 2);
+        assert!(hints[0].starts_with("IFF()"));
+        assert!(hints[1].starts_with("SAFE_DIVIDE()"));
+
+        assert_eq!(lint_dialect_functions(sql, &Snowflake).len(), 1);
+
+        // Each dialect's own spelling isn't flagged
+        let supported: &[(&str, &[DataSourceType])] = &[
+            ("SELECT IFF(paid, amount, 0) FROM t", &[Snowflake, Databricks]),
+            ("SELECT LISTAGG(name, ', ') FROM t", &[Snowflake, Redshift, Databricks]),
+            ("SELECT STRING_AGG(name, ', ') FROM t", &[Postgres, Snowflake, BigQuery]),
+            ("SELECT DATE_DIFF(shipped, ordered, DAY) FROM t", &[BigQuery, Databricks]),
+        ];
+        for (sql, dialects) in supported {
+            for dialect in *dialects {
+                assert!(
+                    lint_dialect_functions(sql, dialect).is_empty(),
+                    "{} flagged on {:?}",
+                    sql,
+                    dialect
+                );
+            }
+        }
+        assert!(lint_dialect_functions("SELECT LISTAGG(name, ', ') FROM t", &Postgres)[0]
+            .starts_with("LISTAGG() is not available"));
+        assert!(lint_dialect_functions("SELECT DATE_DIFF(a, b, DAY) FROM t", &Postgres)[0]
+            .starts_with("DATE_DIFF() is not available"));
+
+        // Function names inside string literals are not calls
+        let sql = "SELECT * FROM t WHERE note = 'iff(x)'";
+        assert!(lint_dialect_functions(sql, &Postgres).is_empty());
+    }
+}
@@ -0,0 +1,207 @@
+use anyhow::Result;
+use indexmap::IndexMap;
+use std::collections::BTreeMap;
+
+use crate::database::{enums::DataSourceType, models::DataSource};
+
+use super::data_source_query_routes::query_router::query_router;
+use super::data_types::DataType;
+
+/// The declared single-column primary and foreign keys of a schema's tables. Composite
+/// keys are left out, since an entity reads a single expression.
+#[derive(Debug, Clone, Default)]
+pub struct TableKeys {
+    /// Primary key column, keyed by lowercased table name
+    pub primary_keys: BTreeMap<String, String>,
+    pub foreign_keys: Vec<ForeignKey>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForeignKey {
+    pub table: String,
+    pub column: String,
+    pub referenced_table: String,
+    pub referenced_column: String,
+}
+
+/// Whether the dialect enforces declared foreign keys and exposes them through
+/// `information_schema`. Warehouses like Snowflake, BigQuery and Redshift accept
+/// constraints without enforcing them, so they can't be trusted to describe the data.
+pub fn reads_foreign_keys(data_source_type: &DataSourceType) -> bool {
+    matches!(
+        data_source_type,
+        DataSourceType::Postgres
+            | DataSourceType::Supabase
+            | DataSourceType::MySql
+            | DataSourceType::Mariadb
+    )
+}
+
+/// Reads the primary and foreign keys declared on the tables of a schema.
+pub async fn table_keys(data_source: &DataSource, schema: &str) -> Result<TableKeys> {
+    let primary_rows =
+        query_router(data_source, &primary_keys_sql(schema), None, false).await?;
+    let foreign_rows = query_router(
+        data_source,
+        &foreign_keys_sql(&data_source.type_, schema),
+        None,
+        false,
+    )
+    .await?;
+
+    Ok(TableKeys {
+        primary_keys: single_column_keys(&primary_rows)
+            .into_iter()
+            .filter_map(|row| {
+                Some((text(row, "table_name")?.to_lowercase(), text(row, "column_name")?))
+            })
+            .collect(),
+        foreign_keys: single_column_keys(&foreign_rows)
+            .into_iter()
+            .filter_map(|row| {
+                Some(ForeignKey {
+                    table: text(row, "table_name")?,
+                    column: text(row, "column_name")?,
+                    referenced_table: text(row, "referenced_table_name")?,
+                    referenced_column: text(row, "referenced_column_name")?,
+                })
+            })
+            .collect(),
+    })
+}
+
+fn primary_keys_sql(schema: &str) -> String {
+    format!(
+        "SELECT kcu.constraint_name AS constraint_name, kcu.table_name AS table_name, \
+         kcu.column_name AS column_name \
+         FROM information_schema.table_constraints tc \
+         JOIN information_schema.key_column_usage kcu \
+         ON kcu.constraint_schema = tc.constraint_schema \
+         AND kcu.constraint_name = tc.constraint_name \
+         AND kcu.table_name = tc.table_name \
+         WHERE tc.constraint_type = 'PRIMARY KEY' AND tc.table_schema = {}",
+        string_literal(schema)
+    )
+}
+
+/// MySQL records the referenced column on the key itself; Postgres only through the
+/// unique constraint the foreign key points at.
+fn foreign_keys_sql(data_source_type: &DataSourceType, schema: &str) -> String {
+    match data_source_type {
+        DataSourceType::MySql | DataSourceType::Mariadb => format!(
+            "SELECT kcu.constraint_name AS constraint_name, kcu.table_name AS table_name, \
+             kcu.column_name AS column_name, \
+             kcu.referenced_table_name AS referenced_table_name, \
+             kcu.referenced_column_name AS referenced_column_name \
+             FROM information_schema.key_column_usage kcu \
+             JOIN information_schema.referential_constraints rc \
+             ON rc.constraint_schema = kcu.constraint_schema \
+             AND rc.constraint_name = kcu.constraint_name \
+             AND rc.table_name = kcu.table_name \
+             WHERE kcu.table_schema = {}",
+            string_literal(schema)
+        ),
+        _ => format!(
+            "SELECT kcu.constraint_name AS constraint_name, kcu.table_name AS table_name, \
+             kcu.column_name AS column_name, \
+             ref.table_name AS referenced_table_name, \
+             ref.column_name AS referenced_column_name \
+             FROM information_schema.referential_constraints rc \
+             JOIN information_schema.key_column_usage kcu \
+             ON kcu.constraint_schema = rc.constraint_schema \
+             AND kcu.constraint_name = rc.constraint_name \
+             JOIN information_schema.key_column_usage ref \
+             ON ref.constraint_schema = rc.unique_constraint_schema \
+             AND ref.constraint_name = rc.unique_constraint_name \
+             AND ref.ordinal_position = kcu.position_in_unique_constraint \
+             WHERE kcu.table_schema = {}",
+            string_literal(schema)
+        ),
+    }
+}
+
+fn string_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// The rows of constraints that cover a single column.
+fn single_column_keys(rows: &[IndexMap<String, DataType>]) -> Vec<&IndexMap<String, DataType>> {
+    let mut constraints: BTreeMap<(String, String), Vec<&IndexMap<String, DataType>>> =
+        BTreeMap::new();
+    for row in rows {
+        let table = text(row, "table_name");
+        let constraint = text(row, "constraint_name");
+        if let (Some(table), Some(constraint)) = (table, constraint) {
+            constraints.entry((table, constraint)).or_default().push(row);
+        }
+    }
+
+    constraints
+        .into_values()
+        .filter(|rows| rows.len() == 1)
+        .flatten()
+        .collect()
+}
+
+/// A text value of a row, looked up case-insensitively since some drivers uppercase
+/// `information_schema` column names.
+pub(super) fn text(row: &IndexMap<String, DataType>, key: &str) -> Option<String> {
+    row.iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(key))
+        .and_then(|(_, value)| match value {
+            DataType::Text(Some(s)) | DataType::Char(Some(s)) | DataType::Unknown(Some(s)) => {
+                Some(s.clone())
+            }
+            _ => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(values: &[(&str, &str)]) -> IndexMap<String, DataType> {
+        values
+            .iter()
+            .map(|(key, value)| (key.to_string(), DataType::Text(Some(value.to_string()))))
+            .collect()
+    }
+
+    #[test]
+    fn test_single_column_keys() {
+        let key = |table, constraint, column| {
+            row(&[
+                ("table_name", table),
+                ("constraint_name", constraint),
+                ("column_name", column),
+            ])
+        };
+        let uppercased = key("order_lines", "order_lines_pk", "order_id")
+            .into_iter()
+            .map(|(name, value)| (name.to_uppercase(), value))
+            .collect();
+        let rows = vec![
+            key("orders", "orders_customer_fk", "customer_id"),
+            uppercased,
+            key("order_lines", "order_lines_pk", "line"),
+        ];
+
+        let keys = single_column_keys(&rows);
+        assert_eq!(keys.len(), 1);
+        assert_eq!(text(keys[0], "column_name").as_deref(), Some("customer_id"));
+    }
+
+    #[test]
+    fn test_foreign_keys_sql() {
+        assert!(reads_foreign_keys(&DataSourceType::Postgres));
+        assert!(!reads_foreign_keys(&DataSourceType::Snowflake));
+
+        let postgres = foreign_keys_sql(&DataSourceType::Postgres, "o'brien");
+        assert!(postgres.contains("position_in_unique_constraint"));
+        assert!(postgres.ends_with("WHERE kcu.table_schema = 'o''brien'"));
+
+        let mysql = foreign_keys_sql(&DataSourceType::MySql, "shop");
+        assert!(mysql.contains("kcu.referenced_table_name AS referenced_table_name"));
+        assert!(primary_keys_sql("shop").contains("tc.constraint_type = 'PRIMARY KEY'"));
+    }
+}
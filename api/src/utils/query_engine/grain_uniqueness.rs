@@ -0,0 +1,66 @@
+use anyhow::Result;
+
+use crate::database::{enums::DataSourceType, models::DataSource};
+
+use super::data_source_query_routes::query_router::query_router;
+use super::sample_values::quote_identifier;
+
+/// Checks that no two rows of a table share the same values for the grain columns.
+/// Groups over the whole table, so it's only run when a deploy asks for it.
+pub async fn grain_is_unique(
+    data_source: &DataSource,
+    schema: &str,
+    table: &str,
+    columns: &[String],
+) -> Result<bool> {
+    let sql = duplicate_grain_sql(&data_source.type_, schema, table, columns);
+    let rows = query_router(data_source, &sql, Some(1), false).await?;
+
+    Ok(rows.is_empty())
+}
+
+/// A query returning at most one grain combination that appears on more than one row.
+fn duplicate_grain_sql(
+    data_source_type: &DataSourceType,
+    schema: &str,
+    table: &str,
+    columns: &[String],
+) -> String {
+    let quote = |identifier: &str| quote_identifier(data_source_type, identifier);
+
+    let columns = columns
+        .iter()
+        .map(|column| quote(column))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let from = format!("{}.{}", quote(schema), quote(table));
+
+    match data_source_type {
+        DataSourceType::SqlServer => format!(
+            "SELECT TOP 1 {} FROM {} GROUP BY {} HAVING COUNT(*) > 1",
+            columns, from, columns
+        ),
+        _ => format!(
+            "SELECT {} FROM {} GROUP BY {} HAVING COUNT(*) > 1 LIMIT 1",
+            columns, from, columns
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duplicate_grain_sql() {
+        let columns = vec!["order_id".to_string(), "line_number".to_string()];
+        assert_eq!(
+            duplicate_grain_sql(&DataSourceType::Postgres, "public", "order_lines", &columns),
+            "SELECT \"order_id\", \"line_number\" FROM \"public\".\"order_lines\" GROUP BY \"order_id\", \"line_number\" HAVING COUNT(*) > 1 LIMIT 1"
+        );
+        assert_eq!(
+            duplicate_grain_sql(&DataSourceType::SqlServer, "dbo", "order_lines", &columns),
+            "SELECT TOP 1 [order_id], [line_number] FROM [dbo].[order_lines] GROUP BY [order_id], [line_number] HAVING COUNT(*) > 1"
+        );
+    }
+}
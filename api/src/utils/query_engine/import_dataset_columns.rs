@@ -10,6 +10,7 @@ use super::{
         get_postgres_connection::get_postgres_connection,
         get_snowflake_client::get_snowflake_client,
     },
+    warehouse_errors::{metadata_query_timeout, WarehouseTimeout},
 };
 use anyhow::{anyhow, Result};
 use arrow::array::Array;
@@ -29,6 +30,48 @@ pub struct DatasetColumnRecord {
     pub nullable: bool,
     pub comment: Option<String>,
     pub source_type: String,
+    /// 1-based position of the column in its table, as reported by the warehouse
+    pub ordinal: i32,
+}
+
+/// The kind of object a column belongs to, normalized across dialects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatasetObjectType {
+    Table,
+    View,
+    MaterializedView,
+    Other,
+}
+
+impl DatasetColumnRecord {
+    /// Normalizes `source_type`, which each warehouse spells differently
+    /// (`BASE TABLE`, `VIEW`, `MATERIALIZED VIEW`, `MATERIALIZED_VIEW`, ...).
+    pub fn object_type(&self) -> DatasetObjectType {
+        let source_type = self.source_type.to_uppercase().replace('_', " ");
+        match source_type.trim() {
+            "BASE TABLE" | "TABLE" | "EXTERNAL TABLE" | "FOREIGN" | "CLONE" => {
+                DatasetObjectType::Table
+            }
+            "VIEW" | "SYSTEM VIEW" | "SECURE VIEW" => DatasetObjectType::View,
+            "MATERIALIZED VIEW" => DatasetObjectType::MaterializedView,
+            _ => DatasetObjectType::Other,
+        }
+    }
+}
+
+/// A nullable column of the `public.orders` table, for tests.
+#[cfg(test)]
+pub(crate) fn column(name: &str, type_: &str) -> DatasetColumnRecord {
+    DatasetColumnRecord {
+        dataset_name: "orders".to_string(),
+        schema_name: "public".to_string(),
+        name: name.to_string(),
+        type_: type_.to_string(),
+        nullable: true,
+        comment: None,
+        source_type: "BASE TABLE".to_string(),
+        ordinal: 1,
+    }
 }
 
 impl<'r> FromRow<'r, sqlx::postgres::PgRow> for DatasetColumnRecord {
@@ -41,6 +84,7 @@ impl<'r> FromRow<'r, sqlx::postgres::PgRow> for DatasetColumnRecord {
             nullable: row.try_get("nullable")?,
             comment: row.try_get("comment")?,
             source_type: row.try_get("source_type")?,
+            ordinal: row.try_get("ordinal")?,
         })
     }
 }
@@ -55,6 +99,8 @@ impl<'r> FromRow<'r, sqlx::mysql::MySqlRow> for DatasetColumnRecord {
             nullable: row.try_get("nullable")?,
             comment: row.try_get("comment")?,
             source_type: row.try_get("source_type")?,
+            // MySQL reports ORDINAL_POSITION as an unsigned integer, cast to BIGINT in SQL
+            ordinal: row.try_get::<i64, _>("ordinal")? as i32,
         })
     }
 }
@@ -111,6 +157,9 @@ async fn create_dataset_columns(dataset_id: &Uuid, cols: &[DatasetColumnRecord])
             semantic_type: None,
             dim_type: None,
             expr: None,
+            ordinal: Some(col.ordinal),
+            hidden: false,
+            label: None,
         })
         .collect();
 
@@ -126,6 +175,7 @@ async fn create_dataset_columns(dataset_id: &Uuid, cols: &[DatasetColumnRecord])
         .set((
             dataset_columns::type_.eq(excluded(dataset_columns::type_)),
             dataset_columns::nullable.eq(excluded(dataset_columns::nullable)),
+            dataset_columns::ordinal.eq(excluded(dataset_columns::ordinal)),
             dataset_columns::updated_at.eq(Utc::now()),
             dataset_columns::deleted_at.eq::<Option<chrono::NaiveDateTime>>(None),
         ))
@@ -197,10 +247,26 @@ pub async fn retrieve_dataset_columns(
     Ok(cols_result)
 }
 
+/// Fetches columns for many tables in one query. Connecting and querying share one
+/// deadline (see `metadata_query_timeout`); running past it fails with `WarehouseTimeout`.
 pub async fn retrieve_dataset_columns_batch(
     datasets: &[(String, String)], // Vec of (dataset_name, schema_name)
     credentials: &Credential,
     database: Option<String>,
+) -> Result<Vec<DatasetColumnRecord>> {
+    let timeout = metadata_query_timeout();
+    tokio::time::timeout(
+        timeout,
+        retrieve_dataset_columns_batch_inner(datasets, credentials, database),
+    )
+    .await
+    .map_err(|_| anyhow::Error::new(WarehouseTimeout { after: timeout }))?
+}
+
+async fn retrieve_dataset_columns_batch_inner(
+    datasets: &[(String, String)],
+    credentials: &Credential,
+    database: Option<String>,
 ) -> Result<Vec<DatasetColumnRecord>> {
     match credentials {
         Credential::Postgres(credentials) => {
@@ -217,13 +283,24 @@ pub async fn retrieve_dataset_columns_batch(
     }
 }
 
-async fn get_snowflake_columns_batch(
+/// The metadata queries `retrieve_dataset_columns_batch` runs for these tables, in the
+/// order it runs them, with the table names inlined. Only query text is returned; the
+/// credentials are used to pick the dialect.
+pub fn dataset_columns_batch_sql(
     datasets: &[(String, String)],
-    credentials: &SnowflakeCredentials,
-    database: Option<String>,
-) -> Result<Vec<DatasetColumnRecord>> {
-    let snowflake_client = get_snowflake_client(credentials).await?;
+    credentials: &Credential,
+    database: Option<&str>,
+) -> Result<Vec<String>> {
+    match credentials {
+        Credential::Postgres(_) => Ok(postgres_columns_batch_sql(datasets).to_vec()),
+        Credential::MySQL(_) => Ok(vec![mysql_columns_batch_sql(datasets)]),
+        Credential::Bigquery(_) => Ok(vec![bigquery_columns_batch_sql(datasets)]),
+        Credential::Snowflake(_) => Ok(vec![snowflake_columns_batch_sql(datasets, database)]),
+        _ => Err(anyhow!("Unsupported data source type")),
+    }
+}
 
+fn snowflake_columns_batch_sql(datasets: &[(String, String)], database: Option<&str>) -> String {
     // Build the IN clause for (schema, table) pairs
     let table_pairs: Vec<String> = datasets
         .iter()
@@ -232,7 +309,14 @@ async fn get_snowflake_columns_batch(
 
     let table_pairs_str = table_pairs.join(", ");
 
-    let sql = format!(
+    // Views and tables outside the session's default database are only visible through
+    // that database's own INFORMATION_SCHEMA.
+    let information_schema = match database {
+        Some(database) => format!("{}.INFORMATION_SCHEMA", database.to_uppercase()),
+        None => "INFORMATION_SCHEMA".to_string(),
+    };
+
+    format!(
         "SELECT
             c.TABLE_NAME as dataset_name,
             c.TABLE_SCHEMA as schema_name,
@@ -240,22 +324,188 @@ async fn get_snowflake_columns_batch(
             c.DATA_TYPE AS type_,
             CASE WHEN c.IS_NULLABLE = 'YES' THEN true ELSE false END AS nullable,
             c.COMMENT AS comment,
-            t.TABLE_TYPE as source_type
+            t.TABLE_TYPE as source_type,
+            c.ORDINAL_POSITION AS ordinal
         FROM
-            INFORMATION_SCHEMA.COLUMNS c
+            {information_schema}.COLUMNS c
         JOIN 
-            INFORMATION_SCHEMA.TABLES t 
+            {information_schema}.TABLES t 
             ON c.TABLE_NAME = t.TABLE_NAME 
             AND c.TABLE_SCHEMA = t.TABLE_SCHEMA
         WHERE
-            (c.TABLE_SCHEMA, c.TABLE_NAME) IN ({})
+            (c.TABLE_SCHEMA, c.TABLE_NAME) IN ({table_pairs_str})
         ORDER BY 
             c.TABLE_SCHEMA,
             c.TABLE_NAME,
-            c.ORDINAL_POSITION;",
+            c.ORDINAL_POSITION;"
+    )
+}
+
+/// Columns of tables and views, then of materialized views, which Postgres leaves out
+/// of `information_schema`.
+fn postgres_columns_batch_sql(datasets: &[(String, String)]) -> [String; 2] {
+    // Build the IN clause for (schema, table) pairs
+    let table_pairs: Vec<String> = datasets
+        .iter()
+        .map(|(table, schema)| format!("('{schema}', '{table}')"))
+        .collect();
+    let table_pairs_str = table_pairs.join(", ");
+
+    // Query for tables and views
+    let regular_sql = format!(
+        "SELECT
+            c.table_name as dataset_name,
+            c.table_schema as schema_name,
+            c.column_name as name,
+            c.data_type as type_,
+            CASE WHEN c.is_nullable = 'YES' THEN true ELSE false END as nullable,
+            pgd.description AS comment,
+            t.table_type as source_type,
+            c.ordinal_position::int as ordinal
+        FROM
+            information_schema.columns c
+        JOIN
+            information_schema.tables t ON c.table_name = t.table_name AND c.table_schema = t.table_schema
+        JOIN
+            pg_catalog.pg_namespace pn on pn.nspname = c.table_schema
+        JOIN
+            pg_catalog.pg_class pc on pc.relnamespace = pn.oid and pc.relname = c.table_name
+        LEFT JOIN
+            pg_catalog.pg_description pgd on pgd.objoid = pc.oid and pgd.objsubid = c.ordinal_position
+        WHERE
+            (c.table_schema, c.table_name) IN ({})
+            AND t.table_type IN ('BASE TABLE', 'VIEW', 'FOREIGN')
+        ORDER BY
+            c.table_schema,
+            c.table_name,
+            c.ordinal_position;",
+        table_pairs_str
+    );
+
+    // Query for materialized views
+    let mv_sql = format!(
+        "SELECT 
+            c.relname as dataset_name,
+            n.nspname as schema_name,
+            a.attname as name,
+            format_type(a.atttypid, a.atttypmod) as type_,
+            NOT a.attnotnull as nullable,
+            d.description as comment,
+            'MATERIALIZED_VIEW' as source_type,
+            a.attnum::int as ordinal
+        FROM pg_class c
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        JOIN pg_attribute a ON a.attrelid = c.oid
+        LEFT JOIN pg_description d ON d.objoid = c.oid AND d.objsubid = a.attnum
+        WHERE c.relkind = 'm'
+        AND (n.nspname, c.relname) IN ({})
+        AND a.attnum > 0
+        AND NOT a.attisdropped
+        ORDER BY 
+            n.nspname,
+            c.relname,
+            a.attnum;",
         table_pairs_str
     );
 
+    [regular_sql, mv_sql]
+}
+
+fn mysql_columns_batch_sql(datasets: &[(String, String)]) -> String {
+    // Build the IN clause for table names
+    let table_pairs: Vec<String> = datasets
+        .iter()
+        .map(|(table, schema)| format!("('{schema}', '{table}')"))
+        .collect();
+    let table_pairs_str = table_pairs.join(", ");
+
+    format!(
+        "SELECT
+            c.TABLE_NAME as dataset_name,
+            c.TABLE_SCHEMA as schema_name,
+            CAST(c.COLUMN_NAME AS CHAR) as name,
+            CAST(c.DATA_TYPE AS CHAR) as type_,
+            CASE WHEN c.IS_NULLABLE = 'YES' THEN true ELSE false END as nullable,
+            CAST(c.COLUMN_COMMENT AS CHAR) as comment,
+            CAST(t.TABLE_TYPE AS CHAR) as source_type,
+            CAST(c.ORDINAL_POSITION AS SIGNED) as ordinal
+        FROM
+            INFORMATION_SCHEMA.COLUMNS c
+        JOIN
+            INFORMATION_SCHEMA.TABLES t ON c.TABLE_NAME = t.TABLE_NAME AND c.TABLE_SCHEMA = t.TABLE_SCHEMA
+        WHERE
+            (c.TABLE_SCHEMA, c.TABLE_NAME) IN ({})
+        ORDER BY
+            c.TABLE_SCHEMA,
+            c.TABLE_NAME,
+            c.ORDINAL_POSITION;",
+        table_pairs_str
+    )
+}
+
+fn bigquery_columns_batch_sql(datasets: &[(String, String)]) -> String {
+    // Build the IN clause for table names
+    let table_pairs: Vec<String> = datasets
+        .iter()
+        .map(|(table, schema)| format!("('{schema}', '{table}')"))
+        .collect();
+    let table_pairs_str = table_pairs.join(", ");
+
+    format!(
+        r#"
+        WITH all_columns AS (
+            -- Regular tables and views. Materialized views are also listed in TABLES, so
+            -- they are left to the query below to avoid returning their columns twice.
+            SELECT
+                t.table_name AS dataset_name,
+                t.table_schema AS schema_name,
+                column_name AS name,
+                data_type AS type_,
+                is_nullable = 'YES' AS nullable,
+                NULL as comment,
+                table_type as source_type,
+                ordinal_position AS ordinal
+            FROM `region-us`.INFORMATION_SCHEMA.COLUMNS c
+            JOIN `region-us`.INFORMATION_SCHEMA.TABLES t 
+                USING(table_name, table_schema)
+            WHERE (t.table_schema, t.table_name) IN ({})
+                AND t.table_type != 'MATERIALIZED VIEW'
+            
+            UNION ALL
+            
+            -- Materialized views specific metadata
+            SELECT
+                mv.table_name AS dataset_name,
+                mv.table_schema AS schema_name,
+                column_name AS name,
+                data_type AS type_,
+                is_nullable = 'YES' AS nullable,
+                NULL as comment,
+                'MATERIALIZED_VIEW' as source_type,
+                ordinal_position AS ordinal
+            FROM `region-us`.INFORMATION_SCHEMA.MATERIALIZED_VIEWS mv
+            JOIN `region-us`.INFORMATION_SCHEMA.COLUMNS c 
+                USING(table_name, table_schema)
+            WHERE (mv.table_schema, mv.table_name) IN ({})
+        )
+        SELECT * FROM all_columns
+        ORDER BY
+            schema_name,
+            dataset_name,
+            ordinal
+        "#,
+        table_pairs_str, table_pairs_str
+    )
+}
+
+async fn get_snowflake_columns_batch(
+    datasets: &[(String, String)],
+    credentials: &SnowflakeCredentials,
+    database: Option<String>,
+) -> Result<Vec<DatasetColumnRecord>> {
+    let snowflake_client = get_snowflake_client(credentials).await?;
+    let sql = snowflake_columns_batch_sql(datasets, database.as_deref());
+
     let results = snowflake_client
         .exec(&sql)
         .await
@@ -274,6 +524,7 @@ async fn get_snowflake_columns_batch(
             let nullable_index = schema.index_of("NULLABLE")?;
             let comment_index = schema.index_of("COMMENT")?;
             let source_type_index = schema.index_of("SOURCE_TYPE")?;
+            let ordinal_index = schema.index_of("ORDINAL")?;
 
             let dataset_name_array = batch
                 .column(dataset_name_index)
@@ -310,6 +561,7 @@ async fn get_snowflake_columns_batch(
                 .as_any()
                 .downcast_ref::<arrow::array::StringArray>()
                 .ok_or_else(|| anyhow!("Expected StringArray for SOURCE_TYPE"))?;
+            let ordinal_array = snowflake_ordinal_array(batch.column(ordinal_index))?;
 
             for i in 0..batch.num_rows() {
                 let dataset_name = dataset_name_array.value(i).to_string();
@@ -336,6 +588,7 @@ async fn get_snowflake_columns_batch(
                     nullable,
                     comment,
                     source_type,
+                    ordinal: ordinal_array.value(i),
                 });
             }
         }
@@ -348,6 +601,18 @@ async fn get_snowflake_columns_batch(
     Ok(columns)
 }
 
+/// Snowflake sizes the Arrow integer type of a NUMBER column to the values in each
+/// batch, so ORDINAL_POSITION may arrive as any integer width.
+fn snowflake_ordinal_array(column: &arrow::array::ArrayRef) -> Result<arrow::array::Int32Array> {
+    let cast = arrow::compute::cast(column, &arrow::datatypes::DataType::Int32)
+        .map_err(|e| anyhow!("Error casting ORDINAL to Int32: {:?}", e))?;
+
+    cast.as_any()
+        .downcast_ref::<arrow::array::Int32Array>()
+        .cloned()
+        .ok_or_else(|| anyhow!("Expected Int32Array for ORDINAL"))
+}
+
 async fn get_postgres_columns_batch(
     datasets: &[(String, String)],
     credentials: &PostgresCredentials,
@@ -358,65 +623,7 @@ async fn get_postgres_columns_batch(
         Err(e) => return Err(e),
     };
 
-    // Build the IN clause for (schema, table) pairs
-    let table_pairs: Vec<String> = datasets
-        .iter()
-        .map(|(table, schema)| format!("('{schema}', '{table}')"))
-        .collect();
-    let table_pairs_str = table_pairs.join(", ");
-
-    // Query for tables and views
-    let regular_sql = format!(
-        "SELECT
-            c.table_name as dataset_name,
-            c.table_schema as schema_name,
-            c.column_name as name,
-            c.data_type as type_,
-            CASE WHEN c.is_nullable = 'YES' THEN true ELSE false END as nullable,
-            pgd.description AS comment,
-            t.table_type as source_type
-        FROM
-            information_schema.columns c
-        JOIN
-            information_schema.tables t ON c.table_name = t.table_name AND c.table_schema = t.table_schema
-        LEFT JOIN
-            pg_catalog.pg_statio_all_tables as st on c.table_schema = st.schemaname and c.table_name = st.relname
-        LEFT JOIN
-            pg_catalog.pg_description pgd on pgd.objoid = st.relid and pgd.objsubid = c.ordinal_position
-        WHERE
-            (c.table_schema, c.table_name) IN ({})
-            AND t.table_type IN ('BASE TABLE', 'VIEW')
-        ORDER BY
-            c.table_schema,
-            c.table_name,
-            c.ordinal_position;",
-        table_pairs_str
-    );
-
-    // Query for materialized views
-    let mv_sql = format!(
-        "SELECT 
-            c.relname as dataset_name,
-            n.nspname as schema_name,
-            a.attname as name,
-            format_type(a.atttypid, a.atttypmod) as type_,
-            NOT a.attnotnull as nullable,
-            d.description as comment,
-            'MATERIALIZED_VIEW' as source_type
-        FROM pg_class c
-        JOIN pg_namespace n ON n.oid = c.relnamespace
-        JOIN pg_attribute a ON a.attrelid = c.oid
-        LEFT JOIN pg_description d ON d.objoid = c.oid AND d.objsubid = a.attnum
-        WHERE c.relkind = 'm'
-        AND (n.nspname, c.relname) IN ({})
-        AND a.attnum > 0
-        AND NOT a.attisdropped
-        ORDER BY 
-            n.nspname,
-            c.relname,
-            a.attnum;",
-        table_pairs_str
-    );
+    let [regular_sql, mv_sql] = postgres_columns_batch_sql(datasets);
 
     let mut columns = Vec::new();
 
@@ -461,34 +668,7 @@ async fn get_mysql_columns_batch(
         Err(e) => return Err(e),
     };
 
-    // Build the IN clause for table names
-    let table_pairs: Vec<String> = datasets
-        .iter()
-        .map(|(table, schema)| format!("('{schema}', '{table}')"))
-        .collect();
-    let table_pairs_str = table_pairs.join(", ");
-
-    let sql = format!(
-        "SELECT
-            c.TABLE_NAME as dataset_name,
-            c.TABLE_SCHEMA as schema_name,
-            CAST(c.COLUMN_NAME AS CHAR) as name,
-            CAST(c.DATA_TYPE AS CHAR) as type_,
-            CASE WHEN c.IS_NULLABLE = 'YES' THEN true ELSE false END as nullable,
-            CAST(c.COLUMN_COMMENT AS CHAR) as comment,
-            CAST(t.TABLE_TYPE AS CHAR) as source_type
-        FROM
-            INFORMATION_SCHEMA.COLUMNS c
-        JOIN
-            INFORMATION_SCHEMA.TABLES t ON c.TABLE_NAME = t.TABLE_NAME AND c.TABLE_SCHEMA = t.TABLE_SCHEMA
-        WHERE
-            (c.TABLE_SCHEMA, c.TABLE_NAME) IN ({})
-        ORDER BY
-            c.TABLE_SCHEMA,
-            c.TABLE_NAME,
-            c.ORDINAL_POSITION;",
-        table_pairs_str
-    );
+    let sql = mysql_columns_batch_sql(datasets);
 
     let columns = sqlx::query_as::<_, DatasetColumnRecord>(&sql)
         .fetch_all(&mysql_conn)
@@ -511,54 +691,7 @@ async fn get_bigquery_columns_batch(
 ) -> Result<Vec<DatasetColumnRecord>> {
     let (bigquery_client, project_id) = get_bigquery_client(credentials).await?;
 
-    // Build the IN clause for table names
-    let table_pairs: Vec<String> = datasets
-        .iter()
-        .map(|(table, schema)| format!("('{schema}', '{table}')"))
-        .collect();
-    let table_pairs_str = table_pairs.join(", ");
-
-    let sql = format!(
-        r#"
-        WITH all_columns AS (
-            -- Regular tables and views
-            SELECT
-                t.table_name AS dataset_name,
-                t.table_schema AS schema_name,
-                column_name AS name,
-                data_type AS type_,
-                is_nullable = 'YES' AS nullable,
-                NULL as comment,
-                table_type as source_type
-            FROM `region-us`.INFORMATION_SCHEMA.COLUMNS c
-            JOIN `region-us`.INFORMATION_SCHEMA.TABLES t 
-                USING(table_name, table_schema)
-            WHERE (t.table_schema, t.table_name) IN ({})
-            
-            UNION ALL
-            
-            -- Materialized views specific metadata
-            SELECT
-                mv.table_name AS dataset_name,
-                mv.table_schema AS schema_name,
-                column_name AS name,
-                data_type AS type_,
-                is_nullable = 'YES' AS nullable,
-                NULL as comment,
-                'MATERIALIZED_VIEW' as source_type
-            FROM `region-us`.INFORMATION_SCHEMA.MATERIALIZED_VIEWS mv
-            JOIN `region-us`.INFORMATION_SCHEMA.COLUMNS c 
-                USING(table_name, table_schema)
-            WHERE (mv.table_schema, mv.table_name) IN ({})
-        )
-        SELECT * FROM all_columns
-        ORDER BY
-            schema_name,
-            dataset_name,
-            name
-        "#,
-        table_pairs_str, table_pairs_str
-    );
+    let sql = bigquery_columns_batch_sql(datasets);
 
     let query_request = QueryRequest {
         query: sql,
@@ -627,6 +760,13 @@ async fn get_bigquery_columns_batch(
                     .ok_or_else(|| anyhow!("Missing source type"))?
                     .to_string();
 
+                let ordinal = cols[7]
+                    .value
+                    .as_ref()
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| anyhow!("Missing ordinal position"))?
+                    .parse::<i32>()?;
+
                 columns.push(DatasetColumnRecord {
                     dataset_name,
                     schema_name,
@@ -635,6 +775,7 @@ async fn get_bigquery_columns_batch(
                     nullable,
                     comment,
                     source_type,
+                    ordinal,
                 });
             }
         }
@@ -658,7 +799,8 @@ async fn get_snowflake_columns(
             c.DATA_TYPE AS type_,
             CASE WHEN c.IS_NULLABLE = 'YES' THEN true ELSE false END AS nullable,
             c.COMMENT AS comment,
-            t.TABLE_TYPE as source_type
+            t.TABLE_TYPE as source_type,
+            c.ORDINAL_POSITION AS ordinal
         FROM
             INFORMATION_SCHEMA.COLUMNS c
         JOIN 
@@ -698,6 +840,9 @@ async fn get_snowflake_columns(
             let source_type_index = schema
                 .index_of("SOURCE_TYPE")
                 .map_err(|e| anyhow!("Error getting index for SOURCE_TYPE: {:?}", e))?;
+            let ordinal_index = schema
+                .index_of("ORDINAL")
+                .map_err(|e| anyhow!("Error getting index for ORDINAL: {:?}", e))?;
 
             let name_column = batch.column(name_index);
             let type_column = batch.column(type_index);
@@ -730,6 +875,8 @@ async fn get_snowflake_columns(
                 .downcast_ref::<arrow::array::StringArray>()
                 .ok_or_else(|| anyhow!("Expected StringArray for SOURCE_TYPE"))?;
 
+            let ordinal_array = snowflake_ordinal_array(batch.column(ordinal_index))?;
+
             for i in 0..batch.num_rows() {
                 let name = name_array.value(i).to_string();
                 let type_ = type_array.value(i).to_string();
@@ -753,6 +900,7 @@ async fn get_snowflake_columns(
                     nullable,
                     comment,
                     source_type,
+                    ordinal: ordinal_array.value(i),
                 });
             }
         }
@@ -766,3 +914,41 @@ async fn get_snowflake_columns(
 
     Ok(columns)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_object_type() {
+        let record = |source_type: &str| DatasetColumnRecord {
+            source_type: source_type.to_string(),
+            ..column("id", "integer")
+        };
+
+        assert_eq!(record("BASE TABLE").object_type(), DatasetObjectType::Table);
+        assert_eq!(record("FOREIGN").object_type(), DatasetObjectType::Table);
+        assert_eq!(record("view").object_type(), DatasetObjectType::View);
+        assert_eq!(
+            record("MATERIALIZED_VIEW").object_type(),
+            DatasetObjectType::MaterializedView
+        );
+        assert_eq!(
+            record("MATERIALIZED VIEW").object_type(),
+            DatasetObjectType::MaterializedView
+        );
+        assert_eq!(record("SNAPSHOT").object_type(), DatasetObjectType::Other);
+    }
+
+    #[test]
+    fn test_snowflake_columns_batch_sql() {
+        let datasets = vec![("orders".to_string(), "public".to_string())];
+
+        let sql = snowflake_columns_batch_sql(&datasets, Some("analytics"));
+        assert!(sql.contains("FROM\n            ANALYTICS.INFORMATION_SCHEMA.COLUMNS c"));
+        assert!(sql.contains("IN (('PUBLIC', 'ORDERS'))"));
+
+        let sql = snowflake_columns_batch_sql(&datasets, None);
+        assert!(sql.contains("FROM\n            INFORMATION_SCHEMA.COLUMNS c"));
+    }
+}
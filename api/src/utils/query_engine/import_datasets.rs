@@ -83,6 +83,10 @@ async fn create_datasets(
             yml_file: None,
             model: None,
             database_identifier: None,
+            refresh_schedule: None,
+            primary_time_dimension: None,
+            grain: None,
+            label: None,
         })
         .collect::<Vec<Dataset>>();
 
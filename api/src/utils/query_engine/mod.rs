@@ -1,11 +1,21 @@
+pub mod column_comments;
+pub mod credential_sources;
 pub mod credentials;
 mod data_source_connections;
 mod data_source_query_routes;
 pub mod data_types;
+pub mod dialect_detection;
+pub mod dialect_functions;
+pub mod foreign_keys;
+pub mod grain_uniqueness;
 pub mod import_dataset_columns;
 pub mod import_datasets;
 pub mod query_engine;
+pub mod sample_values;
+pub mod similar_tables;
+pub mod table_references;
 pub mod test_data_source_connections;
 mod utils;
 pub mod values_index;
+pub mod warehouse_errors;
 pub mod write_query_engine;
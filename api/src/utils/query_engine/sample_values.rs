@@ -0,0 +1,236 @@
+use anyhow::Result;
+
+use crate::database::{enums::DataSourceType, models::DataSource};
+
+use super::data_source_query_routes::query_router::query_router;
+use super::data_types::DataType;
+
+/// Distinct values fetched per column.
+pub const MAX_SAMPLE_VALUES: usize = 5;
+
+/// Longer values are cut to this many characters so descriptions stay readable.
+const MAX_SAMPLE_VALUE_CHARS: usize = 40;
+
+/// Fetches up to `MAX_SAMPLE_VALUES` distinct, non-null values of a column through the
+/// read path. Only meant for string columns; values that don't come back as text are skipped.
+pub async fn sample_column_values(
+    data_source: &DataSource,
+    schema: &str,
+    table: &str,
+    column: &str,
+) -> Result<Vec<String>> {
+    let sql = sample_values_sql(&data_source.type_, schema, table, column);
+    let rows = query_router(data_source, &sql, Some(MAX_SAMPLE_VALUES as i64), false).await?;
+
+    Ok(rows
+        .iter()
+        .filter_map(|row| row.values().next())
+        .filter_map(|value| match value {
+            DataType::Text(Some(s)) | DataType::Char(Some(s)) | DataType::Unknown(Some(s)) => {
+                Some(truncate_sample_value(s.trim()))
+            }
+            _ => None,
+        })
+        .filter(|value| !value.is_empty())
+        .take(MAX_SAMPLE_VALUES)
+        .collect())
+}
+
+/// Whether a column has more than `limit` distinct non-null values. Reads at most
+/// `limit + 1` of them, though the warehouse may still scan the table to find them.
+pub async fn distinct_values_exceed(
+    data_source: &DataSource,
+    schema: &str,
+    table: &str,
+    column: &str,
+    limit: usize,
+) -> Result<bool> {
+    let sql = distinct_values_sql(&data_source.type_, schema, table, column, limit + 1);
+    let rows = query_router(data_source, &sql, Some(limit as i64 + 1), false).await?;
+
+    Ok(rows.len() > limit)
+}
+
+/// The number of distinct non-null values of a column, approximated where the warehouse
+/// can (Snowflake, BigQuery, Databricks, Redshift). Elsewhere they're counted exactly but
+/// only up to `cap + 1`, which is enough to tell the column has more than `cap` without
+/// counting every value of a large table.
+pub async fn approx_distinct_count(
+    data_source: &DataSource,
+    schema: &str,
+    table: &str,
+    column: &str,
+    cap: usize,
+) -> Result<Option<i64>> {
+    let sql = approx_distinct_count_sql(&data_source.type_, schema, table, column, cap);
+    let rows = query_router(data_source, &sql, Some(1), false).await?;
+
+    Ok(rows
+        .first()
+        .and_then(|row| row.values().next())
+        .and_then(|value| match value {
+            DataType::Int8(Some(n)) => Some(*n),
+            DataType::Int4(Some(n)) => Some(*n as i64),
+            DataType::Int2(Some(n)) => Some(*n as i64),
+            DataType::Float8(Some(n)) => Some(*n as i64),
+            DataType::Float4(Some(n)) => Some(*n as i64),
+            DataType::Decimal(Some(n)) => n.to_string().parse::<f64>().ok().map(|n| n as i64),
+            DataType::Text(Some(s)) | DataType::Unknown(Some(s)) => s.trim().parse().ok(),
+            _ => None,
+        }))
+}
+
+/// Formats sampled values as a description suffix, e.g. `e.g. active, churned, trial`.
+pub fn format_sample_values(values: &[String]) -> Option<String> {
+    if values.is_empty() {
+        None
+    } else {
+        Some(format!("e.g. {}", values.join(", ")))
+    }
+}
+
+fn sample_values_sql(
+    data_source_type: &DataSourceType,
+    schema: &str,
+    table: &str,
+    column: &str,
+) -> String {
+    distinct_values_sql(data_source_type, schema, table, column, MAX_SAMPLE_VALUES)
+}
+
+fn distinct_values_sql(
+    data_source_type: &DataSourceType,
+    schema: &str,
+    table: &str,
+    column: &str,
+    limit: usize,
+) -> String {
+    let quote = |identifier: &str| quote_identifier(data_source_type, identifier);
+
+    let column = quote(column);
+    let from = format!("{}.{}", quote(schema), quote(table));
+
+    match data_source_type {
+        DataSourceType::SqlServer => format!(
+            "SELECT DISTINCT TOP {} {} FROM {} WHERE {} IS NOT NULL",
+            limit, column, from, column
+        ),
+        _ => format!(
+            "SELECT DISTINCT {} FROM {} WHERE {} IS NOT NULL LIMIT {}",
+            column, from, column, limit
+        ),
+    }
+}
+
+/// Schema and table are left unquoted, as in the stored values enumeration, so the count
+/// reads the same table the values are enumerated from.
+fn approx_distinct_count_sql(
+    data_source_type: &DataSourceType,
+    schema: &str,
+    table: &str,
+    column: &str,
+    cap: usize,
+) -> String {
+    let quote = |identifier: &str| quote_identifier(data_source_type, identifier);
+
+    let column = quote(column);
+    let from = format!("{}.{}", schema, table);
+    match data_source_type {
+        DataSourceType::Snowflake | DataSourceType::BigQuery | DataSourceType::Databricks => {
+            format!(
+                "SELECT APPROX_COUNT_DISTINCT({}) AS distinct_count FROM {}",
+                column, from
+            )
+        }
+        DataSourceType::Redshift => format!(
+            "SELECT APPROXIMATE COUNT(DISTINCT {}) AS distinct_count FROM {}",
+            column, from
+        ),
+        DataSourceType::SqlServer => format!(
+            "SELECT COUNT(*) AS distinct_count FROM (SELECT DISTINCT TOP {} {} FROM {} WHERE {} IS NOT NULL) AS distinct_values",
+            cap + 1,
+            column,
+            from,
+            column
+        ),
+        _ => format!(
+            "SELECT COUNT(*) AS distinct_count FROM (SELECT DISTINCT {} FROM {} WHERE {} IS NOT NULL LIMIT {}) AS distinct_values",
+            column,
+            from,
+            column,
+            cap + 1
+        ),
+    }
+}
+
+/// Quotes an identifier for the warehouse's dialect.
+pub(crate) fn quote_identifier(data_source_type: &DataSourceType, identifier: &str) -> String {
+    match data_source_type {
+        DataSourceType::BigQuery
+        | DataSourceType::MySql
+        | DataSourceType::Mariadb
+        | DataSourceType::Databricks => format!("`{}`", identifier.replace('`', "``")),
+        DataSourceType::SqlServer => format!("[{}]", identifier.replace(']', "]]")),
+        _ => format!("\"{}\"", identifier.replace('"', "\"\"")),
+    }
+}
+
+fn truncate_sample_value(value: &str) -> String {
+    if value.chars().count() <= MAX_SAMPLE_VALUE_CHARS {
+        value.to_string()
+    } else {
+        let truncated: String = value.chars().take(MAX_SAMPLE_VALUE_CHARS - 1).collect();
+        format!("{}…", truncated.trim_end())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_values_sql() {
+        assert_eq!(
+            sample_values_sql(&DataSourceType::Postgres, "public", "users", "status"),
+            "SELECT DISTINCT \"status\" FROM \"public\".\"users\" WHERE \"status\" IS NOT NULL LIMIT 5"
+        );
+        assert_eq!(
+            sample_values_sql(&DataSourceType::SqlServer, "dbo", "users", "status"),
+            "SELECT DISTINCT TOP 5 [status] FROM [dbo].[users] WHERE [status] IS NOT NULL"
+        );
+        assert_eq!(
+            distinct_values_sql(&DataSourceType::BigQuery, "shop", "orders", "store_id", 1001),
+            "SELECT DISTINCT `store_id` FROM `shop`.`orders` WHERE `store_id` IS NOT NULL LIMIT 1001"
+        );
+    }
+
+    #[test]
+    fn test_approx_distinct_count_sql() {
+        assert_eq!(
+            approx_distinct_count_sql(&DataSourceType::Snowflake, "analytics", "events", "id", 100),
+            "SELECT APPROX_COUNT_DISTINCT(\"id\") AS distinct_count FROM analytics.events"
+        );
+        assert_eq!(
+            approx_distinct_count_sql(&DataSourceType::Redshift, "web", "events", "id", 100),
+            "SELECT APPROXIMATE COUNT(DISTINCT \"id\") AS distinct_count FROM web.events"
+        );
+        assert_eq!(
+            approx_distinct_count_sql(&DataSourceType::MySql, "shop", "orders", "sku", 100),
+            "SELECT COUNT(*) AS distinct_count FROM (SELECT DISTINCT `sku` FROM shop.orders WHERE `sku` IS NOT NULL LIMIT 101) AS distinct_values"
+        );
+        assert_eq!(
+            approx_distinct_count_sql(&DataSourceType::SqlServer, "dbo", "orders", "sku", 100),
+            "SELECT COUNT(*) AS distinct_count FROM (SELECT DISTINCT TOP 101 [sku] FROM dbo.orders WHERE [sku] IS NOT NULL) AS distinct_values"
+        );
+    }
+
+    #[test]
+    fn test_truncate_sample_value() {
+        assert_eq!(truncate_sample_value("active"), "active");
+
+        let long = "é".repeat(60);
+        let truncated = truncate_sample_value(&long);
+        assert_eq!(truncated.chars().count(), MAX_SAMPLE_VALUE_CHARS);
+        assert!(truncated.ends_with('…'));
+    }
+}
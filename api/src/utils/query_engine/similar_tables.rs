@@ -0,0 +1,121 @@
+use anyhow::Result;
+
+use crate::database::{enums::DataSourceType, models::DataSource};
+
+use super::data_source_query_routes::query_router::query_router;
+use super::foreign_keys::text;
+use super::sample_values::quote_identifier;
+
+/// How many similarly named tables a missing table is reported with.
+const MAX_SIMILAR_TABLES: usize = 5;
+
+/// Names of the tables and views in a schema, matched case-insensitively so a wrongly
+/// cased schema still lists its tables.
+pub async fn schema_tables(data_source: &DataSource, schema: &str) -> Result<Vec<String>> {
+    let rows = query_router(
+        data_source,
+        &schema_tables_sql(&data_source.type_, schema),
+        None,
+        false,
+    )
+    .await?;
+
+    Ok(rows
+        .iter()
+        .filter_map(|row| text(row, "table_name"))
+        .collect())
+}
+
+/// BigQuery keeps an `INFORMATION_SCHEMA` per dataset; elsewhere it covers the database.
+fn schema_tables_sql(data_source_type: &DataSourceType, schema: &str) -> String {
+    match data_source_type {
+        DataSourceType::BigQuery => format!(
+            "SELECT table_name AS table_name FROM {}.INFORMATION_SCHEMA.TABLES",
+            quote_identifier(data_source_type, schema)
+        ),
+        _ => format!(
+            "SELECT table_name AS table_name FROM information_schema.tables \
+             WHERE LOWER(table_schema) = LOWER('{}')",
+            schema.replace('\'', "''")
+        ),
+    }
+}
+
+/// The tables named most like `missing`, closest first: those within a few edits of it,
+/// ignoring case, and those whose name contains it or is contained in it.
+pub fn similar_tables(missing: &str, tables: &[String]) -> Vec<String> {
+    let missing = missing.to_lowercase();
+    let max_distance = (missing.chars().count() / 3).max(2);
+
+    let mut similar: Vec<(usize, &String)> = tables
+        .iter()
+        .filter_map(|table| {
+            let name = table.to_lowercase();
+            let distance = edit_distance(&missing, &name);
+            (distance <= max_distance || name.contains(&missing) || missing.contains(&name))
+                .then_some((distance, table))
+        })
+        .collect();
+    similar.sort();
+    similar.dedup_by(|a, b| a.1 == b.1);
+
+    similar
+        .into_iter()
+        .take(MAX_SIMILAR_TABLES)
+        .map(|(_, table)| table.clone())
+        .collect()
+}
+
+/// Levenshtein distance between two strings, in characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_similar_tables() {
+        let tables: Vec<String> = [
+            "ORDERS",
+            "order_lines",
+            "customers",
+            "orders_archive",
+            "refunds",
+        ]
+        .iter()
+        .map(|table| table.to_string())
+        .collect();
+
+        assert_eq!(
+            similar_tables("order", &tables),
+            vec!["ORDERS", "order_lines", "orders_archive"]
+        );
+        assert_eq!(similar_tables("custmers", &tables), vec!["customers"]);
+        assert!(similar_tables("payments", &tables).is_empty());
+    }
+
+    #[test]
+    fn test_schema_tables_sql() {
+        assert_eq!(
+            schema_tables_sql(&DataSourceType::Postgres, "o'brien"),
+            "SELECT table_name AS table_name FROM information_schema.tables \
+             WHERE LOWER(table_schema) = LOWER('o''brien')"
+        );
+        assert_eq!(
+            schema_tables_sql(&DataSourceType::BigQuery, "analytics"),
+            "SELECT table_name AS table_name FROM `analytics`.INFORMATION_SCHEMA.TABLES"
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use crate::database::enums::DataSourceType;
+
+use super::sample_values::quote_identifier;
+
+/// The definition stored for a model without its own SQL: every row of its table.
+pub fn default_sql_definition(
+    data_source_type: &DataSourceType,
+    database: Option<&str>,
+    schema: &str,
+    table: &str,
+) -> String {
+    format!(
+        "SELECT * FROM {}",
+        qualified_table_name(data_source_type, database, schema, table)
+    )
+}
+
+/// A schema-qualified table reference with the schema and table quoted for the dialect.
+/// Pass them as the warehouse stores them: quoting makes them case-sensitive. The database is
+/// only included where a query can reach across databases (a Snowflake database, BigQuery
+/// project, Databricks catalog or SQL Server database). It's named as configured rather than
+/// as stored, so it's left bare when it's a plain identifier and the warehouse folds its case
+/// as it would in any query (Snowflake uppercases it), and only quoted when it has to be,
+/// like a BigQuery project `acme-prod`.
+pub fn qualified_table_name(
+    data_source_type: &DataSourceType,
+    database: Option<&str>,
+    schema: &str,
+    table: &str,
+) -> String {
+    let database = database.filter(|_| {
+        matches!(
+            data_source_type,
+            DataSourceType::Snowflake
+                | DataSourceType::BigQuery
+                | DataSourceType::Databricks
+                | DataSourceType::SqlServer
+        )
+    });
+
+    database
+        .map(|database| {
+            if is_plain_identifier(database) {
+                database.to_string()
+            } else {
+                quote_identifier(data_source_type, database)
+            }
+        })
+        .into_iter()
+        .chain([schema, table].map(|part| quote_identifier(data_source_type, part)))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn is_plain_identifier(identifier: &str) -> bool {
+    identifier
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && identifier.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_sql_definition() {
+        assert_eq!(
+            default_sql_definition(
+                &DataSourceType::Snowflake,
+                Some("PROD"),
+                "ANALYTICS",
+                "ORDERS"
+            ),
+            "SELECT * FROM PROD.\"ANALYTICS\".\"ORDERS\""
+        );
+        // Configured in lowercase, which Snowflake resolves to PROD as long as it's bare
+        assert_eq!(
+            default_sql_definition(
+                &DataSourceType::Snowflake,
+                Some("prod"),
+                "ANALYTICS",
+                "ORDERS"
+            ),
+            "SELECT * FROM prod.\"ANALYTICS\".\"ORDERS\""
+        );
+        assert_eq!(
+            default_sql_definition(
+                &DataSourceType::Postgres,
+                Some("prod"),
+                "analytics",
+                "Orders"
+            ),
+            "SELECT * FROM \"analytics\".\"Orders\""
+        );
+        assert_eq!(
+            default_sql_definition(
+                &DataSourceType::BigQuery,
+                Some("acme-prod"),
+                "analytics",
+                "orders"
+            ),
+            "SELECT * FROM `acme-prod`.`analytics`.`orders`"
+        );
+        assert_eq!(
+            default_sql_definition(&DataSourceType::Databricks, Some("main"), "sales", "orders"),
+            "SELECT * FROM main.`sales`.`orders`"
+        );
+        assert_eq!(
+            default_sql_definition(&DataSourceType::SqlServer, None, "dbo", "orders"),
+            "SELECT * FROM [dbo].[orders]"
+        );
+    }
+}
@@ -0,0 +1,99 @@
+use std::fmt;
+use std::time::Duration;
+
+/// Warehouse drivers only report rejected credentials as text, so auth failures are
+/// recognized by the messages each driver uses.
+const AUTH_FAILURE_PATTERNS: &[&str] = &[
+    // Postgres, Redshift, Supabase
+    "password authentication failed",
+    // MySQL, MariaDB
+    "access denied for user",
+    // SQL Server
+    "login failed for user",
+    // Snowflake password and key pair auth
+    "incorrect username or password",
+    "jwt token is invalid",
+    // BigQuery service accounts
+    "invalid_grant",
+    "invalid jwt signature",
+    // Databricks personal access tokens
+    "invalid access token",
+];
+
+/// Whether a connection or query error means the warehouse rejected the stored
+/// credentials (expired password, rotated key, revoked token). Retrying these won't
+/// help and can lock the account.
+pub fn is_warehouse_auth_error(error: &anyhow::Error) -> bool {
+    let message = format!("{:?}", error).to_lowercase();
+    AUTH_FAILURE_PATTERNS
+        .iter()
+        .any(|pattern| message.contains(pattern))
+}
+
+const DEFAULT_METADATA_TIMEOUT_SECS: u64 = 30;
+
+/// How long metadata queries (connecting and listing columns) may take before the
+/// warehouse is treated as unresponsive. Set with `WAREHOUSE_METADATA_TIMEOUT_SECS`.
+pub fn metadata_query_timeout() -> Duration {
+    let secs = std::env::var("WAREHOUSE_METADATA_TIMEOUT_SECS")
+        .ok()
+        .and_then(|secs| secs.trim().parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(DEFAULT_METADATA_TIMEOUT_SECS);
+    Duration::from_secs(secs)
+}
+
+/// A warehouse query that was abandoned after running past its deadline.
+#[derive(Debug)]
+pub struct WarehouseTimeout {
+    pub after: Duration,
+}
+
+impl fmt::Display for WarehouseTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the warehouse did not respond within {}s", self.after.as_secs())
+    }
+}
+
+impl std::error::Error for WarehouseTimeout {}
+
+/// The deadline that was hit, if the error is a warehouse timeout.
+pub fn warehouse_timeout(error: &anyhow::Error) -> Option<&WarehouseTimeout> {
+    error.downcast_ref::<WarehouseTimeout>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn test_is_warehouse_auth_error() {
+        assert!(is_warehouse_auth_error(&anyhow!(
+            "Error fetching regular columns: Database(PgDatabaseError {{ code: \"28P01\", message: \"password authentication failed for user \\\"buster\\\"\" }})"
+        )));
+        assert!(is_warehouse_auth_error(&anyhow!(
+            "Error fetching columns: Access denied for user 'buster'@'10.0.0.1' (using password: YES)"
+        )));
+        assert!(is_warehouse_auth_error(
+            &anyhow!("Incorrect username or password was specified.").context("Error executing batch query")
+        ));
+        assert!(!is_warehouse_auth_error(&anyhow!(
+            "Error fetching columns: Connection refused (os error 111)"
+        )));
+    }
+
+    #[test]
+    fn test_warehouse_timeout() {
+        let timeout = anyhow::Error::new(WarehouseTimeout {
+            after: Duration::from_secs(30),
+        })
+        .context("Error retrieving columns");
+
+        assert_eq!(
+            warehouse_timeout(&timeout).map(|t| t.after),
+            Some(Duration::from_secs(30))
+        );
+        assert!(warehouse_timeout(&anyhow!("Connection refused (os error 111)")).is_none());
+    }
+}
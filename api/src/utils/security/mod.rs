@@ -1,2 +1,3 @@
 pub mod dataset_security;
-pub mod checks;
\ No newline at end of file
+pub mod checks;
+pub mod rate_limit;
\ No newline at end of file
@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use once_cell::sync::Lazy;
+use uuid::Uuid;
+
+/// Deploys per organization, refilled at `DEPLOY_RATE_LIMIT_PER_MINUTE` (default 30) up
+/// to a burst of `DEPLOY_RATE_LIMIT_BURST` (default 10).
+pub static DEPLOY_RATE_LIMITER: Lazy<RateLimiter> =
+    Lazy::new(|| RateLimiter::from_env("DEPLOY_RATE_LIMIT", 30, 10));
+
+/// Generate requests per organization, configured like deploys with the
+/// `GENERATE_RATE_LIMIT_*` variables.
+pub static GENERATE_RATE_LIMITER: Lazy<RateLimiter> =
+    Lazy::new(|| RateLimiter::from_env("GENERATE_RATE_LIMIT", 30, 10));
+
+/// A token bucket per organization. Buckets live in process memory, so each API
+/// instance enforces its own limit.
+pub struct RateLimiter {
+    per_minute: u32,
+    burst: u32,
+    buckets: Mutex<HashMap<Uuid, Bucket>>,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl RateLimiter {
+    /// A `per_minute` of 0 disables the limit.
+    pub fn new(per_minute: u32, burst: u32) -> Self {
+        Self {
+            per_minute,
+            burst: burst.max(1),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Reads `<prefix>_PER_MINUTE` and `<prefix>_BURST`, falling back to the defaults.
+    fn from_env(prefix: &str, default_per_minute: u32, default_burst: u32) -> Self {
+        let read = |name: &str, default: u32| {
+            std::env::var(format!("{}_{}", prefix, name))
+                .ok()
+                .and_then(|value| value.trim().parse().ok())
+                .unwrap_or(default)
+        };
+        Self::new(read("PER_MINUTE", default_per_minute), read("BURST", default_burst))
+    }
+
+    /// Takes a token from the organization's bucket. When it's empty, returns how long
+    /// until the next token is available.
+    pub fn check(&self, organization_id: &Uuid) -> Result<(), Duration> {
+        self.check_at(organization_id, Instant::now())
+    }
+
+    fn check_at(&self, organization_id: &Uuid, now: Instant) -> Result<(), Duration> {
+        if self.per_minute == 0 {
+            return Ok(());
+        }
+
+        let per_second = self.per_minute as f64 / 60.0;
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.entry(*organization_id).or_insert(Bucket {
+            tokens: self.burst as f64,
+            updated_at: now,
+        });
+
+        let elapsed = now.saturating_duration_since(bucket.updated_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_second).min(self.burst as f64);
+        bucket.updated_at = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / per_second))
+        }
+    }
+}
+
+/// A 429 telling the client how many whole seconds to wait before retrying.
+pub fn rate_limited_response(retry_after: Duration) -> Response {
+    let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(header::RETRY_AFTER, seconds.to_string())],
+        format!(
+            "Too many requests for this organization; retry in {}s",
+            seconds
+        ),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limiter_refills_per_organization() {
+        let limiter = RateLimiter::new(60, 2);
+        let (org, other_org) = (Uuid::new_v4(), Uuid::new_v4());
+        let start = Instant::now();
+
+        assert!(limiter.check_at(&org, start).is_ok());
+        assert!(limiter.check_at(&org, start).is_ok());
+        assert_eq!(limiter.check_at(&org, start), Err(Duration::from_secs(1)));
+
+        // Other organizations have their own bucket
+        assert!(limiter.check_at(&other_org, start).is_ok());
+
+        // One token a second comes back
+        assert!(limiter.check_at(&org, start + Duration::from_secs(1)).is_ok());
+        assert!(limiter.check_at(&org, start + Duration::from_secs(1)).is_err());
+
+        let unlimited = RateLimiter::new(0, 1);
+        assert!((0..100).all(|_| unlimited.check_at(&org, start).is_ok()));
+    }
+}
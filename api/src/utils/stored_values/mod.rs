@@ -2,17 +2,21 @@ pub mod search;
 
 pub use search::*;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use chrono::Utc;
+use std::collections::{HashMap, HashSet};
 use diesel::prelude::*;
-use diesel_async::RunQueryDsl;
+use futures::stream::{self, StreamExt};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
 use uuid::Uuid;
 use crate::database::enums::StoredValuesStatus;
-use crate::database::{lib::get_pg_pool, schema::dataset_columns};
+use crate::database::{lib::get_pg_pool, models::DataSource, schema::dataset_columns};
 use crate::utils::clients::ai::embedding_router::embedding_router;
 use diesel::sql_types::{Text, Uuid as SqlUuid, Array, Float4, Timestamptz, Integer};
 
-use super::query_engine::{data_types::DataType, query_engine::query_engine};
+use super::query_engine::{
+    data_types::DataType, query_engine::query_engine, sample_values::approx_distinct_count,
+};
 
 #[derive(Debug, QueryableByName)]
 pub struct StoredValueRow {
@@ -33,11 +37,75 @@ pub struct StoredValueWithDistance {
 const BATCH_SIZE: usize = 10_000;
 const MAX_VALUE_LENGTH: usize = 50;
 const TIMEOUT_SECONDS: u64 = 60;
+/// Columns whose values are synced at the same time during a backfill.
+const BACKFILL_CONCURRENCY: usize = 4;
+/// Distinct values stored per column, unless `STORED_VALUES_MAX_VALUES` says otherwise.
+const DEFAULT_MAX_STORED_VALUES: usize = 10_000;
+/// Columns with more distinct values than this aren't enumerated at all, unless
+/// `STORED_VALUES_MAX_CARDINALITY` says otherwise.
+const DEFAULT_MAX_CARDINALITY: usize = 100_000;
+
+/// How a column's values sync ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoredValuesSync {
+    Stored(i64),
+    /// Stopped once `cap` values were stored
+    Truncated { count: i64, cap: usize },
+    /// Not enumerated, since the warehouse counted more distinct values than `threshold`
+    Skipped { cardinality: i64, threshold: usize },
+}
 
-pub async fn ensure_stored_values_schema(organization_id: &Uuid) -> Result<()> {
-    let pool = get_pg_pool();
-    let mut conn = pool.get().await?;
+impl StoredValuesSync {
+    pub fn status(&self) -> StoredValuesStatus {
+        match self {
+            StoredValuesSync::Stored(_) => StoredValuesStatus::Success,
+            StoredValuesSync::Truncated { .. } => StoredValuesStatus::Truncated,
+            StoredValuesSync::Skipped { .. } => StoredValuesStatus::Skipped,
+        }
+    }
+
+    pub fn count(&self) -> Option<i64> {
+        match self {
+            StoredValuesSync::Stored(count) | StoredValuesSync::Truncated { count, .. } => {
+                Some(*count)
+            }
+            StoredValuesSync::Skipped { .. } => None,
+        }
+    }
 
+    /// Why not every value was stored, recorded as the column's `stored_values_error`.
+    pub fn reason(&self) -> Option<String> {
+        match self {
+            StoredValuesSync::Stored(_) => None,
+            StoredValuesSync::Truncated { cap, .. } => Some(format!(
+                "Stopped at the cap of {} distinct values per column",
+                cap
+            )),
+            StoredValuesSync::Skipped {
+                cardinality,
+                threshold,
+            } => Some(format!(
+                "About {} distinct values, over the limit of {} for enumerating a column",
+                cardinality, threshold
+            )),
+        }
+    }
+}
+
+/// A positive limit from the environment, or `default` when it's unset or invalid.
+fn configured_limit(configured: Option<&str>, default: usize) -> usize {
+    configured
+        .and_then(|value| value.trim().parse().ok())
+        .filter(|limit| *limit > 0)
+        .unwrap_or(default)
+}
+
+/// Creates the organization's values table if it doesn't exist yet, on `conn` so callers
+/// in a transaction create it in that transaction.
+pub async fn ensure_stored_values_schema(
+    conn: &mut AsyncPgConnection,
+    organization_id: &Uuid,
+) -> Result<()> {
     // Create schema and table using raw SQL
     let schema_name = organization_id.to_string().replace("-", "_");
     let create_schema_sql = format!(
@@ -65,13 +133,17 @@ pub async fn ensure_stored_values_schema(organization_id: &Uuid) -> Result<()> {
         schema_name
     );
 
-    diesel::sql_query(create_schema_sql).execute(&mut conn).await?;
-    diesel::sql_query(create_table_sql).execute(&mut conn).await?;
-    diesel::sql_query(create_index_sql).execute(&mut conn).await?;
+    diesel::sql_query(create_schema_sql).execute(conn).await?;
+    diesel::sql_query(create_table_sql).execute(conn).await?;
+    diesel::sql_query(create_index_sql).execute(conn).await?;
 
     Ok(())
 }
 
+/// Syncs a column's distinct values, up to the configured cap. Columns the warehouse
+/// counts more distinct values for than the cardinality threshold are skipped without
+/// enumerating them. The organization's values table must already exist, see
+/// `ensure_stored_values_schema`.
 pub async fn store_column_values(
     organization_id: &Uuid,
     dataset_id: &Uuid,
@@ -80,19 +152,50 @@ pub async fn store_column_values(
     data_source_id: &Uuid,
     schema: &str,
     table_name: &str,
-) -> Result<()> {
+) -> Result<StoredValuesSync> {
+    let max_values = configured_limit(
+        std::env::var("STORED_VALUES_MAX_VALUES").ok().as_deref(),
+        DEFAULT_MAX_STORED_VALUES,
+    );
+    let max_cardinality = configured_limit(
+        std::env::var("STORED_VALUES_MAX_CARDINALITY").ok().as_deref(),
+        DEFAULT_MAX_CARDINALITY,
+    );
+
+    let data_source = DataSource::find_by_dataset_id(dataset_id)
+        .await?
+        .ok_or_else(|| anyhow!("Data source not found"))?;
+    // A failed count only loses the shortcut; enumeration still stops at the cap
+    match approx_distinct_count(&data_source, schema, table_name, column_name, max_cardinality)
+        .await
+    {
+        Ok(Some(cardinality)) if cardinality > max_cardinality as i64 => {
+            return Ok(StoredValuesSync::Skipped {
+                cardinality,
+                threshold: max_cardinality,
+            });
+        }
+        Ok(_) => (),
+        Err(e) => tracing::warn!(
+            "Failed to count distinct values of {}.{}.{}, enumerating them anyway: {:?}",
+            schema,
+            table_name,
+            column_name,
+            e
+        ),
+    }
+
     let pool = get_pg_pool();
     let mut conn = pool.get().await?;
 
-    // Create schema and table if they don't exist
-    ensure_stored_values_schema(organization_id).await?;
-
-    // Query distinct values in batches
+    // Query distinct values in batches, one more than the cap to tell if it was reached
     let mut offset = 0;
     let mut first_batch = true;
+    let mut stored = 0;
     let schema_name = organization_id.to_string().replace("-", "_");
 
     loop {
+        let limit = BATCH_SIZE.min(max_values + 1 - offset);
         let query = format!(
             "SELECT DISTINCT \"{}\" as value 
              FROM {}.{} 
@@ -101,7 +204,7 @@ pub async fn store_column_values(
              ORDER BY \"{}\" 
              LIMIT {} OFFSET {}",
             column_name, schema, table_name, column_name, column_name, 
-            MAX_VALUE_LENGTH, column_name, BATCH_SIZE, offset
+            MAX_VALUE_LENGTH, column_name, limit, offset
         );
 
         let results = match query_engine(dataset_id, &query).await {
@@ -120,7 +223,7 @@ pub async fn store_column_values(
         }
 
         // Extract values from the query results
-        let values: Vec<String> = results
+        let mut values: Vec<String> = results
             .into_iter()
             .filter_map(|row| {
                 if let Some(DataType::Text(Some(value))) = row.get("value") {
@@ -135,8 +238,11 @@ pub async fn store_column_values(
             break;
         }
 
+        let truncated = offset + values.len() > max_values;
+        values.truncate(max_values - offset);
+
         // If this is the first batch and we have 15 or fewer values, handle as enum
-        if first_batch && values.len() <= 15 {
+        if first_batch && !truncated && values.len() <= 15 {
             // Get current description
             let current_description = diesel::sql_query("SELECT description FROM dataset_columns WHERE id = $1")
                 .bind::<SqlUuid, _>(column_id)
@@ -164,11 +270,15 @@ pub async fn store_column_values(
                 .execute(&mut conn)
                 .await?;
 
-            return Ok(());
+            return Ok(StoredValuesSync::Stored(values.len() as i64));
         }
 
         // Create embeddings for the batch
-        let embeddings = create_embeddings_batch(&values).await?;
+        let embeddings = if values.is_empty() {
+            vec![]
+        } else {
+            create_embeddings_batch(&values).await?
+        };
 
         // Insert values and embeddings
         for (value, embedding) in values.iter().zip(embeddings.iter()) {
@@ -192,11 +302,19 @@ pub async fn store_column_values(
                 .await?;
         }
 
+        stored += values.len() as i64;
+        if truncated {
+            return Ok(StoredValuesSync::Truncated {
+                count: stored,
+                cap: max_values,
+            });
+        }
+
         first_batch = false;
-        offset += BATCH_SIZE;
+        offset += limit;
     }
 
-    Ok(())
+    Ok(StoredValuesSync::Stored(stored))
 }
 
 async fn create_embeddings_batch(values: &[String]) -> Result<Vec<Vec<f32>>> {
@@ -235,6 +353,30 @@ pub async fn search_stored_values(
     Ok(results.into_iter().map(|r| (r.value, r.column_name, r.column_id)).collect())
 }
 
+/// Deletes the stored values of hard-deleted columns, on the caller's connection so it's
+/// part of the same transaction as the columns' removal.
+pub async fn delete_column_values(
+    conn: &mut AsyncPgConnection,
+    organization_id: &Uuid,
+    column_ids: &[Uuid],
+) -> Result<()> {
+    // A column can be marked searchable before its values were first synced
+    ensure_stored_values_schema(conn, organization_id).await?;
+
+    let schema_name = organization_id.to_string().replace("-", "_");
+    let query = format!(
+        "DELETE FROM values_{}.values_v1 WHERE column_id = ANY($1::uuid[])",
+        schema_name
+    );
+
+    diesel::sql_query(query)
+        .bind::<Array<SqlUuid>, _>(column_ids)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
 pub struct StoredValueColumn {
     pub organization_id: Uuid,
     pub dataset_id: Uuid,
@@ -245,32 +387,175 @@ pub struct StoredValueColumn {
     pub table_name: String,
 }
 
+/// Syncs the values of many columns, a few at a time. Each column is marked syncing
+/// when it starts and with how its sync ended when it's done, which is what the stored
+/// values status endpoint reports the backfill's progress from. Each organization's values
+/// table is created once up front; columns of an organization it couldn't be created for
+/// are marked failed.
 pub async fn process_stored_values_background(columns: Vec<StoredValueColumn>) {
-    for column in columns {
-        match store_column_values(
-            &column.organization_id,
-            &column.dataset_id,
-            &column.column_name,
-            &column.column_id,
-            &column.data_source_id,
-            &column.schema,
-            &column.table_name,
-        ).await {
-            Ok(_) => {
-                tracing::info!(
-                    "Successfully processed stored values for column '{}' in dataset '{}'",
-                    column.column_name,
-                    column.table_name
-                );
+    let organization_ids: HashSet<Uuid> =
+        columns.iter().map(|column| column.organization_id).collect();
+    let mut schema_errors = HashMap::new();
+    for organization_id in organization_ids {
+        let ensured = match get_pg_pool().get().await {
+            Ok(mut conn) => ensure_stored_values_schema(&mut conn, &organization_id).await,
+            Err(e) => Err(e.into()),
+        };
+        if let Err(e) = ensured {
+            tracing::error!(
+                "Failed to create the stored values table for organization '{}': {:?}",
+                organization_id,
+                e
+            );
+            schema_errors.insert(organization_id, e.to_string());
+        }
+    }
+    let schema_errors = &schema_errors;
+
+    stream::iter(columns)
+        .for_each_concurrent(BACKFILL_CONCURRENCY, |column| async move {
+            if let Some(error) = schema_errors.get(&column.organization_id) {
+                set_stored_values_status(
+                    &column.column_id,
+                    StoredValuesStatus::Failed,
+                    None,
+                    Some(error.clone()),
+                )
+                .await;
+                return;
             }
-            Err(e) => {
-                tracing::error!(
-                    "Failed to process stored values for column '{}' in dataset '{}': {:?}",
-                    column.column_name,
-                    column.table_name,
-                    e
-                );
+
+            set_stored_values_status(&column.column_id, StoredValuesStatus::Syncing, None, None)
+                .await;
+
+            match store_column_values(
+                &column.organization_id,
+                &column.dataset_id,
+                &column.column_name,
+                &column.column_id,
+                &column.data_source_id,
+                &column.schema,
+                &column.table_name,
+            )
+            .await
+            {
+                Ok(sync) => {
+                    tracing::info!(
+                        "Processed stored values for column '{}' in dataset '{}': {:?}",
+                        column.column_name,
+                        column.table_name,
+                        sync
+                    );
+                    set_stored_values_status(
+                        &column.column_id,
+                        sync.status(),
+                        sync.count(),
+                        sync.reason(),
+                    )
+                    .await;
+                }
+                Err(e) => {
+                    tracing::error!(
+                        "Failed to process stored values for column '{}' in dataset '{}': {:?}",
+                        column.column_name,
+                        column.table_name,
+                        e
+                    );
+                    set_stored_values_status(
+                        &column.column_id,
+                        StoredValuesStatus::Failed,
+                        None,
+                        Some(e.to_string()),
+                    )
+                    .await;
+                }
             }
-        }
+        })
+        .await;
+}
+
+/// Records a column's sync status with its reason, if any. Success and truncation also
+/// record the count and when it ran. Failing to record it is only logged, since it
+/// doesn't change whether the values were synced.
+async fn set_stored_values_status(
+    column_id: &Uuid,
+    status: StoredValuesStatus,
+    count: Option<i64>,
+    error: Option<String>,
+) {
+    let updated = async {
+        let mut conn = get_pg_pool().get().await?;
+        let column = dataset_columns::table.filter(dataset_columns::id.eq(column_id));
+        match status {
+            StoredValuesStatus::Success | StoredValuesStatus::Truncated => {
+                diesel::update(column)
+                    .set((
+                        dataset_columns::stored_values_status.eq(status),
+                        dataset_columns::stored_values_error.eq(error),
+                        dataset_columns::stored_values_count.eq(count),
+                        dataset_columns::stored_values_last_synced.eq(Utc::now()),
+                    ))
+                    .execute(&mut conn)
+                    .await?
+            }
+            StoredValuesStatus::Syncing
+            | StoredValuesStatus::Failed
+            | StoredValuesStatus::Skipped => {
+                diesel::update(column)
+                    .set((
+                        dataset_columns::stored_values_status.eq(status),
+                        dataset_columns::stored_values_error.eq(error),
+                    ))
+                    .execute(&mut conn)
+                    .await?
+            }
+        };
+        anyhow::Ok(())
+    };
+
+    if let Err(e) = updated.await {
+        tracing::error!("Error recording stored values status of column {}: {:?}", column_id, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_configured_limit() {
+        assert_eq!(configured_limit(None, DEFAULT_MAX_STORED_VALUES), 10_000);
+        assert_eq!(configured_limit(Some(" 500 "), DEFAULT_MAX_STORED_VALUES), 500);
+        assert_eq!(configured_limit(Some("0"), DEFAULT_MAX_CARDINALITY), 100_000);
+        assert_eq!(configured_limit(Some("lots"), DEFAULT_MAX_CARDINALITY), 100_000);
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_stored_values_sync() {
+        let stored = StoredValuesSync::Stored(42);
+        assert_eq!(stored.status(), StoredValuesStatus::Success);
+        assert_eq!((stored.count(), stored.reason()), (Some(42), None));
+
+        let truncated = StoredValuesSync::Truncated {
+            count: 10_000,
+            cap: 10_000,
+        };
+        assert_eq!(truncated.status(), StoredValuesStatus::Truncated);
+        assert_eq!(truncated.count(), Some(10_000));
+        assert_eq!(
+            truncated.reason().as_deref(),
+            Some("Stopped at the cap of 10000 distinct values per column")
+        );
+
+        let skipped = StoredValuesSync::Skipped {
+            cardinality: 2_500_000,
+            threshold: 100_000,
+        };
+        assert_eq!(skipped.status(), StoredValuesStatus::Skipped);
+        assert_eq!(skipped.count(), None);
+        assert_eq!(
+            skipped.reason().as_deref(),
+            Some("About 2500000 distinct values, over the limit of 100000 for enumerating a column")
+        );
+    }
+}
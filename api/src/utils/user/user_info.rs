@@ -1,6 +1,8 @@
 use anyhow::{anyhow, Result};
+use axum::http::StatusCode;
 use diesel::{BoolExpressionMethods, ExpressionMethods, JoinOnDsl, QueryDsl};
 use diesel_async::RunQueryDsl;
+use std::fmt;
 use uuid::Uuid;
 
 use crate::database::{
@@ -9,22 +11,82 @@ use crate::database::{
     schema::{organizations, users_to_organizations},
 };
 
-pub async fn get_user_organization_id(user_id: &Uuid) -> Result<Uuid> {
-    let mut conn = get_pg_pool().get().await?;
+#[derive(Debug)]
+pub enum OrganizationIdError {
+    NoOrganization,
+    MultipleOrganizations,
+    DatabaseError(String),
+}
+
+impl OrganizationIdError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Self::NoOrganization => StatusCode::BAD_REQUEST,
+            Self::MultipleOrganizations => StatusCode::CONFLICT,
+            Self::DatabaseError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Message that is safe to return to the client.
+    pub fn public_message(&self) -> &'static str {
+        match self {
+            Self::NoOrganization => {
+                "User does not belong to an organization. Ask a workspace admin to invite you."
+            }
+            Self::MultipleOrganizations => {
+                "User belongs to multiple organizations. Contact support to resolve the membership."
+            }
+            Self::DatabaseError(_) => "Error getting user organization id",
+        }
+    }
+}
+
+impl fmt::Display for OrganizationIdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoOrganization => write!(f, "User does not belong to an organization"),
+            Self::MultipleOrganizations => write!(f, "User belongs to multiple organizations"),
+            Self::DatabaseError(e) => write!(f, "Error getting user organization id: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for OrganizationIdError {}
 
-    let organization_id = match users_to_organizations::table
+/// Maps an error bubbled up through `anyhow` back to a status code and client message,
+/// falling back to a 500 with `default_message` for anything that isn't an organization
+/// lookup failure.
+pub fn organization_error_response(
+    error: &anyhow::Error,
+    default_message: &'static str,
+) -> (StatusCode, &'static str) {
+    match error.downcast_ref::<OrganizationIdError>() {
+        Some(e) => (e.status_code(), e.public_message()),
+        None => (StatusCode::INTERNAL_SERVER_ERROR, default_message),
+    }
+}
+
+pub async fn get_user_organization_id(user_id: &Uuid) -> Result<Uuid, OrganizationIdError> {
+    let mut conn = get_pg_pool()
+        .get()
+        .await
+        .map_err(|e| OrganizationIdError::DatabaseError(e.to_string()))?;
+
+    // Only need to know whether there are zero, one, or more memberships
+    let organization_ids = users_to_organizations::table
         .select(users_to_organizations::organization_id)
         .filter(users_to_organizations::user_id.eq(user_id))
         .filter(users_to_organizations::deleted_at.is_null())
-        .first::<Uuid>(&mut conn)
+        .limit(2)
+        .load::<Uuid>(&mut conn)
         .await
-    {
-        Ok(organization_id) => organization_id,
-        Err(diesel::NotFound) => return Err(anyhow!("User not found")),
-        Err(e) => return Err(anyhow!("Error getting user organization id: {}", e)),
-    };
+        .map_err(|e| OrganizationIdError::DatabaseError(e.to_string()))?;
 
-    Ok(organization_id)
+    match organization_ids.as_slice() {
+        [] => Err(OrganizationIdError::NoOrganization),
+        [organization_id] => Ok(*organization_id),
+        _ => Err(OrganizationIdError::MultipleOrganizations),
+    }
 }
 
 pub async fn get_user_organization(user_id: &Uuid) -> Result<Organization> {
@@ -7,6 +7,7 @@ use crate::{
         query_engine::{
             credentials::get_data_source_credentials,
             import_dataset_columns::retrieve_dataset_columns_batch,
+            warehouse_errors::warehouse_timeout,
         },
         validation::{
             types::{ValidationError, ValidationResult},
@@ -33,7 +34,7 @@ pub async fn validate_model(
 
     // Get credentials
     let credentials = match get_data_source_credentials(
-        &data_source.secret_id,
+        data_source,
         &data_source.type_,
         false,
     )
@@ -74,10 +75,13 @@ pub async fn validate_model(
         Ok(cols) => cols,
         Err(e) => {
             tracing::error!("Failed to get columns from data source: {}", e);
-            result.add_error(ValidationError::data_source_error(format!(
-                "Failed to get columns from data source: {}",
-                e
-            )));
+            result.add_error(match warehouse_timeout(&e) {
+                Some(timeout) => ValidationError::warehouse_timeout(&data_source.name, timeout.after),
+                None => ValidationError::data_source_error(format!(
+                    "Failed to get columns from data source: {}",
+                    e
+                )),
+            });
             return Ok(result);
         }
     };
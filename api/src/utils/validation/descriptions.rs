@@ -0,0 +1,215 @@
+/// Stands in for a code span or block while the rest of a description is sanitized.
+const CODE_PLACEHOLDER: char = '\u{e000}';
+
+/// `description` with HTML that could run in the UI removed, or `None` when it has none.
+/// The UI renders descriptions as markdown, which passes HTML through. Code spans and
+/// blocks render as written, so they're left alone, and so is text ammonia only escapes,
+/// like `amount < 100`.
+pub fn sanitize_description(description: &str) -> Option<String> {
+    let masked = mask_code(description);
+    let cleaned = ammonia::clean(&masked.text);
+
+    // The escaping is undone unless that would bring back markup ammonia removes, as for
+    // a description written as `&lt;script&gt;`
+    let unescaped = unescape_html(&cleaned);
+    let cleaned = if ammonia::clean(&unescaped) == cleaned {
+        unescaped
+    } else {
+        cleaned
+    };
+
+    let sanitized = masked.restore(&cleaned);
+    (sanitized != description).then_some(sanitized)
+}
+
+/// Whether `description` opens a code fence it never closes, which swallows the rest of
+/// the description into the code block.
+pub fn has_unclosed_code_fence(description: &str) -> bool {
+    mask_code(description).unclosed_fence
+}
+
+/// A description with its code swapped for numbered placeholders.
+struct MaskedCode<'a> {
+    text: String,
+    code: Vec<&'a str>,
+    unclosed_fence: bool,
+}
+
+impl MaskedCode<'_> {
+    fn push_code<'a>(text: &mut String, code: &mut Vec<&'a str>, snippet: &'a str) {
+        text.push(CODE_PLACEHOLDER);
+        text.push_str(&code.len().to_string());
+        text.push(CODE_PLACEHOLDER);
+        code.push(snippet);
+    }
+
+    /// `text` with each placeholder that's still in it replaced by its code.
+    fn restore(&self, text: &str) -> String {
+        self.code
+            .iter()
+            .enumerate()
+            .fold(text.to_string(), |text, (index, snippet)| {
+                text.replace(
+                    &format!("{}{}{}", CODE_PLACEHOLDER, index, CODE_PLACEHOLDER),
+                    snippet,
+                )
+            })
+    }
+}
+
+/// Masks fenced code blocks, including one left open, which runs to the end, and the
+/// code spans between them.
+fn mask_code(description: &str) -> MaskedCode<'_> {
+    let mut text = String::with_capacity(description.len());
+    let mut code = Vec::new();
+    let mut text_start = 0;
+    let mut fence: Option<(usize, &str)> = None;
+    let mut offset = 0;
+
+    for line in description.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        match fence {
+            None => {
+                if let Some(marker) = fence_marker(trimmed) {
+                    mask_code_spans(&description[text_start..offset], &mut text, &mut code);
+                    fence = Some((offset, marker));
+                }
+            }
+            Some((start, marker)) => {
+                let closing = trimmed.trim_end();
+                if closing.len() >= marker.len() && closing.chars().all(|c| marker.starts_with(c))
+                {
+                    let end = offset + line.len();
+                    MaskedCode::push_code(&mut text, &mut code, &description[start..end]);
+                    fence = None;
+                    text_start = end;
+                }
+            }
+        }
+        offset += line.len();
+    }
+
+    match fence {
+        Some((start, _)) => MaskedCode::push_code(&mut text, &mut code, &description[start..]),
+        None => mask_code_spans(&description[text_start..], &mut text, &mut code),
+    }
+
+    MaskedCode {
+        text,
+        code,
+        unclosed_fence: fence.is_some(),
+    }
+}
+
+/// The run of three or more backticks or tildes a fenced code block opens with. A backtick
+/// fence's info string can't contain backticks, so ```` ```code``` ```` is a code span.
+fn fence_marker(line: &str) -> Option<&str> {
+    let fence_char = line.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let length = line.len() - line.trim_start_matches(fence_char).len();
+    let opens = length >= 3 && (fence_char == '~' || !line[length..].contains('`'));
+    opens.then(|| &line[..length])
+}
+
+/// Appends `text` to `masked` with its code spans masked. A span closes at the next run of
+/// as many backticks as opened it; backticks that are never closed are plain text.
+fn mask_code_spans<'a>(text: &'a str, masked: &mut String, code: &mut Vec<&'a str>) {
+    let mut rest = text;
+    while let Some(open) = rest.find('`') {
+        let length = backtick_run(&rest[open..]);
+        let after = open + length;
+        match closing_backticks(&rest[after..], length) {
+            Some(close) => {
+                let end = after + close + length;
+                masked.push_str(&rest[..open]);
+                MaskedCode::push_code(masked, code, &rest[open..end]);
+                rest = &rest[end..];
+            }
+            None => {
+                masked.push_str(&rest[..after]);
+                rest = &rest[after..];
+            }
+        }
+    }
+    masked.push_str(rest);
+}
+
+fn backtick_run(text: &str) -> usize {
+    text.len() - text.trim_start_matches('`').len()
+}
+
+/// Where the first run of exactly `length` backticks in `text` starts.
+fn closing_backticks(text: &str, length: usize) -> Option<usize> {
+    let mut searched = 0;
+    while let Some(position) = text[searched..].find('`') {
+        let start = searched + position;
+        let run = backtick_run(&text[start..]);
+        if run == length {
+            return Some(start);
+        }
+        searched = start + run;
+    }
+    None
+}
+
+/// Undoes the escaping ammonia applies to text and attribute values.
+fn unescape_html(html: &str) -> String {
+    html.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&nbsp;", "\u{a0}")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_description() {
+        assert_eq!(
+            sanitize_description("Orders <script>alert('hi')</script>placed online").as_deref(),
+            Some("Orders placed online")
+        );
+        assert_eq!(
+            sanitize_description(
+                "<a href=\"jav&#x61;script:steal()\" onclick=\"steal()\">Sales</a>"
+            )
+            .as_deref(),
+            Some("<a rel=\"noopener noreferrer\">Sales</a>")
+        );
+        assert_eq!(sanitize_description("Refunds have amount < 0 & a note\n> see refunds"), None);
+        assert_eq!(sanitize_description("Sold by *region*, not <b>R&D</b>"), None);
+
+        // What's kept is stored unescaped
+        assert_eq!(
+            sanitize_description("Refunds have amount < 0 <script>x()</script>& a note")
+                .as_deref(),
+            Some("Refunds have amount < 0 & a note")
+        );
+        // Unless unescaping would bring back what was removed
+        assert_eq!(
+            sanitize_description("&lt;script&gt;x()&lt;/script&gt; <b onclick=\"x()\">hi</b>")
+                .as_deref(),
+            Some("&lt;script&gt;x()&lt;/script&gt; <b>hi</b>")
+        );
+
+        // Code renders as written
+        assert_eq!(sanitize_description("Set with `<input type=\"date\">`"), None);
+        assert_eq!(
+            sanitize_description("Example:\n```html\n<script>load()</script>\n```\n<script>"),
+            Some("Example:\n```html\n<script>load()</script>\n```\n".to_string())
+        );
+        assert_eq!(
+            sanitize_description("A stray ` before <script>x()</script>``<b>`` code").as_deref(),
+            Some("A stray ` before ``<b>`` code")
+        );
+    }
+
+    #[test]
+    fn test_has_unclosed_code_fence() {
+        assert!(has_unclosed_code_fence("Fulfillment state, e.g.\n```\nshipped\n"));
+        assert!(has_unclosed_code_fence("~~~~\nshipped\n~~~\n"));
+        assert!(!has_unclosed_code_fence("e.g.\n  ```sql\nSELECT 1\n```\nand `code`"));
+        assert!(!has_unclosed_code_fence("```code``` is a code span"));
+    }
+}
@@ -0,0 +1,75 @@
+use crate::utils::{
+    query_engine::import_dataset_columns::DatasetColumnRecord,
+    validation::{
+        measure_filters::{filter_identifiers, identifiers_match},
+        types::ValidationError,
+    },
+};
+
+/// Checks the columns an entity joins on exist in its dataset's warehouse table, or are
+/// among the model's `dimensions`. A typo in a join key otherwise deploys and only fails
+/// once a query joins on it. Expressions with subqueries are left to the warehouse.
+pub fn validate_entity_expr(
+    entity_name: &str,
+    expr: &str,
+    warehouse_columns: &[&DatasetColumnRecord],
+    dimensions: &[&str],
+    case_sensitive: bool,
+) -> Vec<ValidationError> {
+    let identifiers = filter_identifiers(expr);
+    if identifiers.iter().any(|identifier| identifier.eq_ignore_ascii_case("select")) {
+        return Vec::new();
+    }
+
+    identifiers
+        .iter()
+        .filter(|identifier| {
+            !warehouse_columns
+                .iter()
+                .any(|col| identifiers_match(&col.name, identifier, case_sensitive))
+                && !dimensions
+                    .iter()
+                    .any(|dimension| identifiers_match(dimension, identifier, case_sensitive))
+        })
+        .map(|identifier| ValidationError::unknown_entity_column(entity_name, expr, identifier))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::query_engine::import_dataset_columns::column;
+
+    #[test]
+    fn test_validate_entity_expr() {
+        let (customer_id, region) = (column("CUSTOMER_ID", "integer"), column("region", "text"));
+        let columns = vec![&customer_id, &region];
+        let validate = |expr: &str| validate_entity_expr("customer", expr, &columns, &[], false);
+
+        assert!(validate("customer_id").is_empty());
+        assert!(validate("orders.customer_id").is_empty());
+        assert!(validate("CAST(customer_id AS varchar)").is_empty());
+        assert!(validate("customer_id::text").is_empty());
+
+        let errors = validate("concat(customer_idd, region)");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].column_name.as_deref(), Some("customer_idd"));
+        assert_eq!(
+            errors[0].message,
+            "Entity 'customer' joins on 'concat(customer_idd, region)', which references \
+             unknown column 'customer_idd'"
+        );
+
+        // Dimensions the model declares can be joined on too
+        assert!(
+            validate_entity_expr("customer", "Customer_Key", &columns, &["customer_key"], false)
+                .is_empty()
+        );
+        assert_eq!(validate("customer_key").len(), 1);
+
+        // With case-sensitive identifiers, only the warehouse's casing matches
+        let errors = validate_entity_expr("customer", "customer_id", &columns, &[], true);
+        assert_eq!(errors.len(), 1);
+        assert!(validate_entity_expr("customer", "CUSTOMER_ID", &columns, &[], true).is_empty());
+    }
+}
@@ -0,0 +1,237 @@
+use std::collections::HashSet;
+use std::ops::Range;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::{
+    database::enums::DataSourceType,
+    utils::{
+        query_engine::{data_types::DataType, import_dataset_columns::DatasetColumnRecord},
+        validation::{type_mapping::normalize_type, types::ValidationError},
+    },
+};
+
+static STRING_LITERAL: Lazy<Regex> = Lazy::new(|| Regex::new(r"'(?:[^']|'')*'").unwrap());
+static IDENTIFIER: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)"([^"]+)"|\b(?:[a-z_][a-z0-9_]*\.)?([a-z_][a-z0-9_]*)\b(\s*\()?"#).unwrap()
+});
+/// The type of a `::` cast, including multi-word ones like `double precision`.
+static COLON_CAST_TYPE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(concat!(
+        r"(?i)::\s*[a-z_][a-z0-9_]*(?:\s*\([^)]*\))?",
+        r"(?:\s+(?:precision|varying|with|without|local|time|zone)\b)*",
+    ))
+    .unwrap()
+});
+static CAST_CALL: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\b(?:try_|safe_)?cast\s*\(").unwrap());
+static CAST_AS: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)\bas\b").unwrap());
+
+/// Words that can appear in a filter without referring to a column.
+const FILTER_KEYWORDS: &[&str] = &[
+    "and", "or", "not", "in", "is", "null", "true", "false", "like", "ilike", "between",
+    "case", "when", "then", "else", "end", "exists", "current_date", "current_timestamp",
+    "escape",
+];
+
+/// Checks a measure's filter against the warehouse columns of its dataset: every column
+/// it names must exist, and a filter that is just a column must be a boolean one. Filters
+/// with subqueries can't be checked this way and are left to the warehouse.
+pub fn validate_measure_filter(
+    column_name: &str,
+    filter: &str,
+    warehouse_columns: &[&DatasetColumnRecord],
+    data_source_type: &DataSourceType,
+    case_sensitive: bool,
+) -> Vec<ValidationError> {
+    let identifiers = filter_identifiers(filter);
+    if identifiers.iter().any(|identifier| identifier.eq_ignore_ascii_case("select")) {
+        return Vec::new();
+    }
+
+    let find_column = |identifier: &str| {
+        warehouse_columns
+            .iter()
+            .find(|col| identifiers_match(&col.name, identifier, case_sensitive))
+    };
+
+    let mut errors: Vec<ValidationError> = identifiers
+        .iter()
+        .filter(|identifier| find_column(identifier).is_none())
+        .map(|identifier| {
+            ValidationError::expression_error(
+                column_name,
+                filter,
+                &format!("filter references unknown column '{}'", identifier),
+            )
+        })
+        .collect();
+
+    // A bare column is only a valid condition if the warehouse says it's boolean. Types
+    // that can't be mapped are given the benefit of the doubt.
+    let bare = filter.trim().trim_matches('"');
+    if identifiers.len() == 1 && identifiers[0] == bare {
+        if let Some(col) = find_column(bare) {
+            let data_type = normalize_type(*data_source_type, &col.type_);
+            if !matches!(data_type, DataType::Bool(_) | DataType::Unknown(_)) {
+                errors.push(ValidationError::expression_error(
+                    column_name,
+                    filter,
+                    &format!(
+                        "filter must be a boolean expression, but column '{}' is {}",
+                        col.name, col.type_
+                    ),
+                ));
+            }
+        }
+    }
+
+    errors
+}
+
+/// Whether a warehouse identifier and one a model wrote name the same thing. Unless the data
+/// source is case-sensitive, casing is ignored, as warehouses fold unquoted identifiers.
+pub fn identifiers_match(warehouse: &str, model: &str, case_sensitive: bool) -> bool {
+    if case_sensitive {
+        warehouse == model
+    } else {
+        warehouse.to_lowercase() == model.to_lowercase()
+    }
+}
+
+/// Column names referenced by a filter or expression, as written, ignoring string literals,
+/// keywords and function names. Qualified names (`orders.status`) count as their column. Type names
+/// aren't columns, whether in a cast (`x::date`, `CAST(x AS varchar)`) or before a typed
+/// literal (`date '2024-01-01'`), but a column that happens to be called `date` still is.
+pub(crate) fn filter_identifiers(filter: &str) -> Vec<String> {
+    let filter = STRING_LITERAL.replace_all(filter, "''");
+    let types = cast_types(&filter);
+    let mut seen = HashSet::new();
+
+    IDENTIFIER
+        .captures_iter(&filter)
+        .filter(|caps| caps.get(3).is_none())
+        .filter_map(|caps| caps.get(1).or_else(|| caps.get(2)))
+        .filter(|m| !types.iter().any(|range| range.contains(&m.start())))
+        .filter(|m| !filter[m.end()..].trim_start().starts_with('\''))
+        .map(|m| m.as_str().to_string())
+        .filter(|identifier| !FILTER_KEYWORDS.contains(&identifier.to_lowercase().as_str()))
+        .filter(|identifier| seen.insert(identifier.clone()))
+        .collect()
+}
+
+/// Byte ranges of the type names in `::` casts and in `CAST(.. AS type)`, along with the
+/// `AS` itself.
+fn cast_types(filter: &str) -> Vec<Range<usize>> {
+    let mut types: Vec<Range<usize>> = COLON_CAST_TYPE
+        .find_iter(filter)
+        .map(|m| m.range())
+        .collect();
+
+    for call in CAST_CALL.find_iter(filter) {
+        // Find the cast's closing parenthesis, and its `AS` outside any nested call
+        let mut depth = 0;
+        let mut as_start = None;
+        for (offset, c) in filter[call.end()..].char_indices() {
+            let position = call.end() + offset;
+            match c {
+                '(' => depth += 1,
+                ')' if depth == 0 => {
+                    if let Some(start) = as_start {
+                        types.push(start..position);
+                    }
+                    break;
+                }
+                ')' => depth -= 1,
+                _ if depth == 0 && as_start.is_none() => {
+                    as_start = CAST_AS
+                        .find_at(filter, position)
+                        .filter(|m| m.start() == position)
+                        .map(|m| m.start());
+                }
+                _ => (),
+            }
+        }
+    }
+
+    types
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::query_engine::import_dataset_columns::column;
+
+    #[test]
+    fn test_filter_identifiers() {
+        assert_eq!(
+            filter_identifiers("status = 'completed' AND lower(o.region) IN ('us', 'eu')"),
+            vec!["status", "region"]
+        );
+        assert_eq!(filter_identifiers("\"Is Paid\" IS NOT NULL"), vec!["Is Paid"]);
+    }
+
+    #[test]
+    fn test_filter_identifiers_skip_cast_types() {
+        assert_eq!(
+            filter_identifiers(
+                "created_at::date >= date '2024-01-01' AND amount::numeric(10, 2) > 0"
+            ),
+            vec!["created_at", "amount"]
+        );
+        assert_eq!(
+            filter_identifiers(
+                "CAST(coalesce(code, 0) AS varchar) = '1' OR id::double precision > 1"
+            ),
+            vec!["code", "id"]
+        );
+        assert_eq!(
+            filter_identifiers(
+                "TRY_CAST(placed AS timestamp with time zone) > timestamp '2024-01-01'"
+            ),
+            vec!["placed"]
+        );
+
+        // Columns named after types are still columns
+        assert_eq!(
+            filter_identifiers("date >= '2024-01-01' AND timestamp IS NOT NULL"),
+            vec!["date", "timestamp"]
+        );
+    }
+
+    #[test]
+    fn test_validate_measure_filter() {
+        let status = column("status", "text");
+        let is_paid = column("is_paid", "boolean");
+        let columns = vec![&status, &is_paid];
+        let validate = |filter: &str| {
+            validate_measure_filter("revenue", filter, &columns, &DataSourceType::Postgres, false)
+        };
+
+        assert!(validate("status = 'completed'").is_empty());
+        assert!(validate("is_paid").is_empty());
+
+        let errors = validate("state = 'completed'");
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message.contains("unknown column 'state'"));
+
+        let errors = validate("status");
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message.contains("must be a boolean expression"));
+
+        assert!(validate("status IN (SELECT status FROM completed_statuses)").is_empty());
+        assert!(validate("STATUS = 'completed' AND \"IS_PAID\"").is_empty());
+
+        // With case-sensitive identifiers, only the warehouse's casing matches
+        let errors = validate_measure_filter(
+            "revenue",
+            "Status = 'completed' AND is_paid",
+            &columns,
+            &DataSourceType::Postgres,
+            true,
+        );
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message.contains("unknown column 'Status'"));
+    }
+}
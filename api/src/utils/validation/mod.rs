@@ -1,7 +1,17 @@
 pub mod dataset_validation;
+pub mod descriptions;
+pub mod entity_columns;
+pub mod measure_filters;
+pub mod relationship_cycles;
+pub mod schema_drift;
 pub mod types;
 pub mod type_mapping;
 
 pub use dataset_validation::*;
+pub use descriptions::*;
+pub use entity_columns::*;
+pub use measure_filters::*;
+pub use relationship_cycles::*;
+pub use schema_drift::*;
 pub use types::*; 
 pub use type_mapping::*;
\ No newline at end of file
@@ -0,0 +1,150 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Finds cycles in a graph of entity relationships, where each model points at the models
+/// its foreign entities reference. Every strongly connected component of two or more models
+/// is reported once, as its members sorted by name, along with each model that references
+/// itself.
+pub fn find_relationship_cycles(edges: &HashMap<String, Vec<String>>) -> Vec<Vec<String>> {
+    // Sorted so the reported cycles don't depend on hash order
+    let graph: BTreeMap<&str, BTreeSet<&str>> = edges
+        .iter()
+        .map(|(from, targets)| (from.as_str(), targets.iter().map(String::as_str).collect()))
+        .collect();
+
+    let mut tarjan = Tarjan {
+        graph: &graph,
+        index: BTreeMap::new(),
+        low_link: BTreeMap::new(),
+        stack: Vec::new(),
+        on_stack: BTreeSet::new(),
+        components: Vec::new(),
+    };
+    for node in graph.keys() {
+        if !tarjan.index.contains_key(node) {
+            tarjan.connect(node);
+        }
+    }
+
+    let mut cycles: Vec<Vec<String>> = tarjan
+        .components
+        .into_iter()
+        .filter(|component| match component.as_slice() {
+            [node] => graph.get(node).is_some_and(|targets| targets.contains(node)),
+            _ => true,
+        })
+        .map(|mut component| {
+            component.sort_unstable();
+            component.into_iter().map(str::to_string).collect()
+        })
+        .collect();
+    cycles.sort();
+    cycles
+}
+
+/// Tarjan's strongly connected components, over a graph whose edges may name models that
+/// aren't keys of it.
+struct Tarjan<'g, 'a> {
+    graph: &'g BTreeMap<&'a str, BTreeSet<&'a str>>,
+    index: BTreeMap<&'a str, usize>,
+    low_link: BTreeMap<&'a str, usize>,
+    stack: Vec<&'a str>,
+    on_stack: BTreeSet<&'a str>,
+    components: Vec<Vec<&'a str>>,
+}
+
+impl<'a> Tarjan<'_, 'a> {
+    fn connect(&mut self, node: &'a str) {
+        let index = self.index.len();
+        self.index.insert(node, index);
+        self.low_link.insert(node, index);
+        self.stack.push(node);
+        self.on_stack.insert(node);
+
+        for &target in self.graph.get(node).into_iter().flatten() {
+            if !self.index.contains_key(target) {
+                self.connect(target);
+                let low_link = self.low_link[node].min(self.low_link[target]);
+                self.low_link.insert(node, low_link);
+            } else if self.on_stack.contains(target) {
+                let low_link = self.low_link[node].min(self.index[target]);
+                self.low_link.insert(node, low_link);
+            }
+        }
+
+        if self.low_link[node] == index {
+            let mut component = Vec::new();
+            while let Some(member) = self.stack.pop() {
+                self.on_stack.remove(member);
+                component.push(member);
+                if member == node {
+                    break;
+                }
+            }
+            self.components.push(component);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(edges: &[(&str, &[&str])]) -> HashMap<String, Vec<String>> {
+        edges
+            .iter()
+            .map(|(from, to)| (from.to_string(), to.iter().map(|t| t.to_string()).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn test_find_relationship_cycles() {
+        assert!(find_relationship_cycles(&graph(&[
+            ("orders", &["customers", "products"]),
+            ("customers", &["regions"]),
+        ]))
+        .is_empty());
+
+        assert_eq!(
+            find_relationship_cycles(&graph(&[("employees", &["employees"])])),
+            vec![vec!["employees"]]
+        );
+
+        assert_eq!(
+            find_relationship_cycles(&graph(&[
+                ("customers", &["orders"]),
+                ("orders", &["customers"]),
+            ])),
+            vec![vec!["customers", "orders"]]
+        );
+
+        assert_eq!(
+            find_relationship_cycles(&graph(&[
+                ("a", &["b"]),
+                ("b", &["c"]),
+                ("c", &["a"]),
+                ("d", &["a"]),
+            ])),
+            vec![vec!["a", "b", "c"]]
+        );
+
+        // c's cycle closes through b, which a path-based search has finished by then
+        assert_eq!(
+            find_relationship_cycles(&graph(&[
+                ("a", &["b", "c"]),
+                ("b", &["a"]),
+                ("c", &["b"]),
+            ])),
+            vec![vec!["a", "b", "c"]]
+        );
+
+        assert_eq!(
+            find_relationship_cycles(&graph(&[
+                ("a", &["b"]),
+                ("b", &["a"]),
+                ("c", &["d"]),
+                ("d", &["c", "d"]),
+            ])),
+            vec![vec!["a", "b"], vec!["c", "d"]]
+        );
+    }
+}
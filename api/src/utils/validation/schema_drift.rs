@@ -0,0 +1,64 @@
+use std::collections::HashSet;
+
+use crate::utils::{
+    query_engine::import_dataset_columns::DatasetColumnRecord,
+    validation::measure_filters::filter_identifiers,
+};
+
+/// Warehouse columns that none of a model's expressions read, in table order. Each
+/// expression is a field's `expr` (or its name when it has none), a measure filter or an
+/// entity's key.
+pub fn undeclared_columns<'a, 'b>(
+    expressions: impl IntoIterator<Item = &'b str>,
+    warehouse_columns: &[&'a DatasetColumnRecord],
+) -> Vec<&'a DatasetColumnRecord> {
+    let declared: HashSet<String> = expressions
+        .into_iter()
+        .flat_map(filter_identifiers)
+        .map(|identifier| identifier.to_lowercase())
+        .collect();
+
+    let mut undeclared: Vec<_> = warehouse_columns
+        .iter()
+        .copied()
+        .filter(|col| !declared.contains(&col.name.to_lowercase()))
+        .collect();
+    undeclared.sort_by_key(|col| col.ordinal);
+    undeclared
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::query_engine::import_dataset_columns::column;
+
+    #[test]
+    fn test_undeclared_columns() {
+        let column_at = |name: &str, ordinal: i32| DatasetColumnRecord {
+            ordinal,
+            ..column(name, "text")
+        };
+        let id = column_at("id", 1);
+        let status = column_at("Status", 2);
+        let amount = column_at("amount", 3);
+        let discount = column_at("discount", 4);
+        let loaded_at = column_at("_loaded_at", 5);
+        let columns = vec![&loaded_at, &id, &status, &amount, &discount];
+
+        let undeclared = undeclared_columns(["id", "sum(amount)", "\"Status\" = 'paid'"], &columns);
+        let names: Vec<_> = undeclared.iter().map(|col| col.name.as_str()).collect();
+        assert_eq!(names, vec!["discount", "_loaded_at"]);
+
+        assert!(
+            undeclared_columns(["id, status, amount, discount, _loaded_at"], &columns).is_empty()
+        );
+
+        // Columns named after types count, type names in casts don't
+        let date = column_at("date", 6);
+        let varchar = column_at("varchar", 7);
+        let columns = vec![&date, &varchar];
+        let undeclared = undeclared_columns(["date", "CAST(id AS varchar)"], &columns);
+        let names: Vec<_> = undeclared.iter().map(|col| col.name.as_str()).collect();
+        assert_eq!(names, vec!["varchar"]);
+    }
+}
@@ -34,7 +34,7 @@ impl StandardType {
         match type_str.to_lowercase().as_str() {
             "text" | "string" | "varchar" | "char" => StandardType::Text,
             "int" | "integer" | "bigint" | "smallint" => StandardType::Integer,
-            "float" | "double" | "decimal" | "numeric" => StandardType::Float,
+            "float" | "double" | "decimal" | "numeric" | "number" => StandardType::Float,
             "bool" | "boolean" => StandardType::Boolean,
             "date" => StandardType::Date,
             "timestamp" | "datetime" | "timestamptz" => StandardType::Timestamp,
@@ -159,10 +159,40 @@ pub fn types_compatible(source_type: DataSourceType, ds_type: &str, model_type:
     }
 }
 
+/// Why a measure of `measure_type` can't be aggregated with `agg`, or `None` when they
+/// fit. Sums and averages need numbers; counts, min and max work on any type. Types not
+/// listed in `StandardType` are let through.
+pub fn measure_agg_mismatch(measure_type: &str, agg: &str) -> Option<String> {
+    let numeric_agg = matches!(agg.trim().to_lowercase().as_str(), "sum" | "avg");
+    match StandardType::from_str(measure_type.trim()) {
+        StandardType::Integer | StandardType::Float | StandardType::Unknown => None,
+        _ if !numeric_agg => None,
+        _ => Some(format!(
+            "`agg: {}` needs a numeric measure but the type is `{}`; use count or \
+             count_distinct, or change the type",
+            agg, measure_type
+        )),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_measure_agg_mismatch() {
+        assert_eq!(measure_agg_mismatch("number", "sum"), None);
+        assert_eq!(measure_agg_mismatch("integer", "avg"), None);
+        assert_eq!(measure_agg_mismatch("string", "count_distinct"), None);
+        assert_eq!(measure_agg_mismatch("timestamp", "max"), None);
+        assert_eq!(
+            measure_agg_mismatch("string", "sum").unwrap(),
+            "`agg: sum` needs a numeric measure but the type is `string`; use count or \
+             count_distinct, or change the type"
+        );
+        assert!(measure_agg_mismatch("Boolean", "AVG").is_some());
+    }
+
     #[test]
     fn test_postgres_type_normalization() {
         assert!(matches!(
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ValidationResult {
@@ -7,6 +8,9 @@ pub struct ValidationResult {
     pub data_source_name: String,
     pub schema: String,
     pub errors: Vec<ValidationError>,
+    /// Findings that don't stop the dataset from deploying
+    #[serde(default)]
+    pub warnings: Vec<ValidationError>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -15,6 +19,16 @@ pub struct ValidationError {
     pub column_name: Option<String>,
     pub message: String,
     pub suggestion: Option<String>,
+    /// Details for diagnosing the error, when the deploy asked for them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<ValidationErrorContext>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ValidationErrorContext {
+    /// Tables in the model's schema named most like a table that wasn't found, closest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub similar_tables: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -31,6 +45,22 @@ pub enum ValidationErrorType {
     DataSourceMismatch,
     RequiredFieldMissing,
     DataSourceNotFound,
+    InvalidRefreshSchedule,
+    AmbiguousDataSource,
+    EmptyModel,
+    WarehouseAuthFailed,
+    InvalidGrain,
+    WarehouseTimeout,
+    SchemaDrift,
+    CommentWriteFailed,
+    DisallowedDatasetType,
+    SearchableNotSupported,
+    SearchableEnrollsStoredValues,
+    StoredValuesColumnRemoved,
+    InvalidEnv,
+    DefaultedColumnType,
+    DatasetNotFound,
+    MalformedDescription,
 }
 
 impl ValidationResult {
@@ -41,6 +71,7 @@ impl ValidationResult {
             data_source_name,
             schema,
             errors: Vec::new(),
+            warnings: Vec::new(),
         }
     }
 
@@ -48,6 +79,10 @@ impl ValidationResult {
         self.success = false;
         self.errors.push(error);
     }
+
+    pub fn add_warning(&mut self, warning: ValidationError) {
+        self.warnings.push(warning);
+    }
 }
 
 impl ValidationError {
@@ -62,6 +97,7 @@ impl ValidationError {
             column_name,
             message,
             suggestion,
+            context: None,
         }
     }
 
@@ -83,6 +119,18 @@ impl ValidationError {
         )
     }
 
+    pub fn unknown_entity_column(entity_name: &str, expr: &str, column_name: &str) -> Self {
+        Self::new(
+            ValidationErrorType::ColumnNotFound,
+            Some(column_name.to_string()),
+            format!(
+                "Entity '{}' joins on '{}', which references unknown column '{}'",
+                entity_name, expr, column_name
+            ),
+            Some("Check the entity's expr names columns of the model's table".to_string()),
+        )
+    }
+
     pub fn type_mismatch(column_name: &str, expected: &str, found: &str) -> Self {
         Self::new(
             ValidationErrorType::TypeMismatch,
@@ -113,6 +161,15 @@ impl ValidationError {
         )
     }
 
+    pub fn dataset_not_found(dataset_id: &str) -> Self {
+        Self::new(
+            ValidationErrorType::DatasetNotFound,
+            None,
+            format!("Dataset '{}' not found in this organization", dataset_id),
+            Some("Remove the `id` to deploy it as a new dataset".to_string()),
+        )
+    }
+
     pub fn invalid_relationship(from: &str, to: &str, reason: &str) -> Self {
         Self::new(
             ValidationErrorType::InvalidRelationship,
@@ -130,4 +187,247 @@ impl ValidationError {
             None,
         )
     }
-} 
\ No newline at end of file
+
+    pub fn ambiguous_data_source(name: &str, env: &str, ids: &[uuid::Uuid]) -> Self {
+        Self::new(
+            ValidationErrorType::AmbiguousDataSource,
+            None,
+            format!(
+                "Data source '{}' is ambiguous: {} active data sources share this name in env '{}' ({})",
+                name,
+                ids.len(),
+                env,
+                ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(", ")
+            ),
+            Some("Delete or rename the duplicate data sources".to_string()),
+        )
+    }
+
+    pub fn invalid_refresh_schedule(cron: &str, reason: &str) -> Self {
+        Self::new(
+            ValidationErrorType::InvalidRefreshSchedule,
+            None,
+            format!("Invalid refresh.cron '{}': {}", cron, reason),
+            Some("Use a five-field cron expression, e.g. '0 6 * * *'".to_string()),
+        )
+    }
+
+    pub fn empty_model(model_name: &str) -> Self {
+        Self::new(
+            ValidationErrorType::EmptyModel,
+            None,
+            format!("Model '{}' has no dimensions or measures", model_name),
+            Some("Add at least one dimension or measure to the model".to_string()),
+        )
+    }
+
+    /// A table whose metadata query ran past its deadline, when the rest of its data
+    /// source answered.
+    pub fn table_timeout(table_name: &str, after: Duration) -> Self {
+        Self::new(
+            ValidationErrorType::WarehouseTimeout,
+            None,
+            format!(
+                "Table '{}' did not return its metadata within {}s",
+                table_name,
+                after.as_secs()
+            ),
+            Some(
+                "Check the table isn't locked or a slow view, or raise WAREHOUSE_METADATA_TIMEOUT_SECS"
+                    .to_string(),
+            ),
+        )
+    }
+
+    pub fn warehouse_auth_failed(data_source_name: &str, reason: &str) -> Self {
+        Self::new(
+            ValidationErrorType::WarehouseAuthFailed,
+            None,
+            format!(
+                "The warehouse rejected the credentials for data source '{}': {}",
+                data_source_name, reason
+            ),
+            Some(
+                "Re-enter the data source's credentials (the password may have expired or the key been rotated)"
+                    .to_string(),
+            ),
+        )
+    }
+
+    pub fn warehouse_timeout(data_source_name: &str, after: Duration) -> Self {
+        Self::new(
+            ValidationErrorType::WarehouseTimeout,
+            None,
+            format!(
+                "Data source '{}' did not return table metadata within {}s",
+                data_source_name,
+                after.as_secs()
+            ),
+            Some(
+                "Check the warehouse is running and not overloaded, or raise WAREHOUSE_METADATA_TIMEOUT_SECS"
+                    .to_string(),
+            ),
+        )
+    }
+
+    pub fn invalid_grain(component: Option<&str>, reason: &str) -> Self {
+        Self::new(
+            ValidationErrorType::InvalidGrain,
+            component.map(|c| c.to_string()),
+            format!("Invalid grain: {}", reason),
+            Some(
+                "List the dimensions or entities, each a plain column, that together identify one row"
+                    .to_string(),
+            ),
+        )
+    }
+
+    pub fn schema_drift(table_name: &str, columns: &[&str]) -> Self {
+        Self::new(
+            ValidationErrorType::SchemaDrift,
+            None,
+            format!(
+                "Table '{}' has columns the model doesn't declare: {}",
+                table_name,
+                columns.join(", ")
+            ),
+            Some("Add them to the model as dimensions or measures".to_string()),
+        )
+    }
+
+    pub fn disallowed_dataset_type(
+        model_name: &str,
+        dataset_type: &str,
+        allowed: &[String],
+    ) -> Self {
+        Self::new(
+            ValidationErrorType::DisallowedDatasetType,
+            None,
+            format!(
+                "Model '{}' has type '{}', which is not allowed here",
+                model_name, dataset_type
+            ),
+            Some(format!("Use one of: {}", allowed.join(", "))),
+        )
+    }
+
+    pub fn searchable_not_supported(column_name: &str, reason: &str) -> Self {
+        Self::new(
+            ValidationErrorType::SearchableNotSupported,
+            Some(column_name.to_string()),
+            format!("Column '{}' can't be searchable: {}", column_name, reason),
+            Some(
+                "Remove searchable: true; stored values lists every distinct value of a searchable dimension"
+                    .to_string(),
+            ),
+        )
+    }
+
+    pub fn searchable_enrolls_stored_values(column_name: &str) -> Self {
+        Self::new(
+            ValidationErrorType::SearchableEnrollsStoredValues,
+            Some(column_name.to_string()),
+            format!(
+                "Column '{}' is searchable, so every one of its distinct values will be synced \
+                 to stored values",
+                column_name
+            ),
+            Some("Remove searchable: true if the column doesn't need value search".to_string()),
+        )
+    }
+
+    pub fn stored_values_column_removed(column_name: &str) -> Self {
+        Self::new(
+            ValidationErrorType::StoredValuesColumnRemoved,
+            Some(column_name.to_string()),
+            format!(
+                "Column '{}' has stored values and would be hard-deleted with them",
+                column_name
+            ),
+            Some(
+                "Keep the column, deploy with column_delete_mode soft, or pass --force to delete it"
+                    .to_string(),
+            ),
+        )
+    }
+
+    pub fn defaulted_column_type(column_name: &str, warehouse_type: &str) -> Self {
+        Self::new(
+            ValidationErrorType::DefaultedColumnType,
+            Some(column_name.to_string()),
+            format!(
+                "Column '{}' has no type and was deployed as text (warehouse type '{}')",
+                column_name, warehouse_type
+            ),
+            Some("Set `type` on the column in the model file".to_string()),
+        )
+    }
+
+    /// A column's description, or a model's `field`, that won't render cleanly as markdown
+    /// in the UI.
+    pub fn malformed_description(
+        column_name: Option<&str>,
+        model_name: &str,
+        field: &str,
+        reason: &str,
+    ) -> Self {
+        let message = match column_name {
+            Some(column) => format!("Description of column '{}' {}", column, reason),
+            None => format!("`{}` of model '{}' {}", field, model_name, reason),
+        };
+        Self::new(
+            ValidationErrorType::MalformedDescription,
+            column_name.map(str::to_string),
+            message,
+            Some("Close code fences and describe the data in plain markdown".to_string()),
+        )
+    }
+
+    pub fn invalid_env(env: &str, allowed: &[String]) -> Self {
+        Self::new(
+            ValidationErrorType::InvalidEnv,
+            None,
+            format!("Unknown env '{}'", env),
+            Some(format!("Use one of: {}", allowed.join(", "))),
+        )
+    }
+
+    pub fn env_required(data_source_name: &str, envs: &[String]) -> Self {
+        Self::new(
+            ValidationErrorType::RequiredFieldMissing,
+            None,
+            format!(
+                "Data source '{}' exists in envs {}; the model must set env",
+                data_source_name,
+                envs.join(", ")
+            ),
+            Some("Set `env` on the model or in buster.yml, or deploy with --env".to_string()),
+        )
+    }
+
+    pub fn comment_write_failed(table_name: &str, reason: &str) -> Self {
+        Self::new(
+            ValidationErrorType::CommentWriteFailed,
+            None,
+            format!(
+                "Deployed, but descriptions weren't written to '{}' as comments: {}",
+                table_name, reason
+            ),
+            Some("Check the data source's user may comment on the table".to_string()),
+        )
+    }
+
+    pub fn relationship_cycle(members: &[String]) -> Self {
+        let cycle = match members {
+            [member] => format!("{} -> {}", member, member),
+            _ => members.join(", "),
+        };
+
+        Self::new(
+            ValidationErrorType::InvalidRelationship,
+            None,
+            format!("Entity relationships form a cycle: {}", cycle),
+            Some("Remove one of the foreign entities in the cycle".to_string()),
+        )
+    }
+}
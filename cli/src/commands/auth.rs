@@ -2,6 +2,7 @@ use anyhow::{Context, Result};
 use clap::Parser;
 use inquire::{Password, Text};
 use std::env;
+use std::io::{BufRead, IsTerminal};
 use thiserror::Error;
 
 use crate::utils::{
@@ -25,6 +26,19 @@ pub enum AuthError {
     StorageError(String),
 }
 
+impl AuthError {
+    /// Stable identifier for the variant, used by `--json-errors`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AuthError::MissingUrl => "missing_url",
+            AuthError::MissingApiKey => "missing_api_key",
+            AuthError::InvalidApiKey => "invalid_api_key",
+            AuthError::ValidationError(_) => "credential_validation_error",
+            AuthError::StorageError(_) => "credential_storage_error",
+        }
+    }
+}
+
 #[derive(Parser, Debug)]
 #[command(about = "Authenticate with Buster API")]
 pub struct AuthArgs {
@@ -32,13 +46,25 @@ pub struct AuthArgs {
     #[arg(long, env = "BUSTER_HOST")]
     pub host: Option<String>,
 
-    /// Your Buster API key
+    /// Your Buster API key, or `-` to read it from stdin
     #[arg(long, env = "BUSTER_API_KEY")]
     pub api_key: Option<String>,
 
+    /// Read the API key from stdin, e.g. piped from a secrets manager
+    #[arg(long)]
+    pub api_key_stdin: bool,
+
     /// Don't save credentials to disk
     #[arg(long)]
     pub no_save: bool,
+
+    /// Only check that the credentials are valid; nothing is saved
+    #[arg(long)]
+    pub test: bool,
+
+    /// Skip logging in when the credentials saved for the host are still valid
+    #[arg(long)]
+    pub if_needed: bool,
 }
 
 async fn validate_credentials(url: &str, api_key: &str) -> Result<(), AuthError> {
@@ -58,6 +84,42 @@ pub async fn auth() -> Result<()> {
     auth_with_args(args).await
 }
 
+/// Reads the API key piped to stdin. On a terminal it's prompted for instead, so the key
+/// is never echoed.
+fn api_key_from_stdin() -> Result<String> {
+    let stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        return Password::new("Enter your API key:")
+            .without_confirmation()
+            .prompt()
+            .context("Failed to get API key input");
+    }
+    read_api_key(stdin.lock())
+}
+
+/// The API key on the first line of `input`, without surrounding whitespace.
+fn read_api_key(mut input: impl BufRead) -> Result<String> {
+    let mut line = String::new();
+    input
+        .read_line(&mut line)
+        .context("Failed to read API key from stdin")?;
+
+    match line.trim() {
+        "" => Err(AuthError::MissingApiKey.into()),
+        api_key => Ok(api_key.to_string()),
+    }
+}
+
+/// Whether `saved` are credentials for `host`, the saved host when none is given, that
+/// `--if-needed` can check instead of logging in again.
+fn saved_for_host(saved: &BusterCredentials, host: Option<&str>) -> bool {
+    let same_host = match host {
+        Some(host) => host.trim_end_matches('/') == saved.url.trim_end_matches('/'),
+        None => true,
+    };
+    same_host && !saved.api_key.is_empty()
+}
+
 pub async fn auth_with_args(args: AuthArgs) -> Result<()> {
     // Get existing credentials or create default
     let mut buster_creds = match get_buster_credentials().await {
@@ -68,13 +130,26 @@ pub async fn auth_with_args(args: AuthArgs) -> Result<()> {
         },
     };
 
+    // Nothing is prompted for or written while the saved key still works
+    if args.if_needed && saved_for_host(&buster_creds, args.host.as_deref()) {
+        match validate_credentials(&buster_creds.url, &buster_creds.api_key).await {
+            Ok(()) => {
+                println!("Already authenticated with {}", buster_creds.url);
+                return Ok(());
+            }
+            Err(e) => println!("Saved credentials need refreshing: {}", e),
+        }
+    }
+
     // Apply host from args or use default
     if let Some(host) = args.host {
         buster_creds.url = host;
     }
 
-    // Apply API key from args or environment
-    if let Some(api_key) = args.api_key {
+    // Apply API key from stdin, args or environment
+    if args.api_key_stdin || args.api_key.as_deref() == Some("-") {
+        buster_creds.api_key = api_key_from_stdin()?;
+    } else if let Some(api_key) = args.api_key {
         buster_creds.api_key = api_key;
     }
 
@@ -112,6 +187,12 @@ pub async fn auth_with_args(args: AuthArgs) -> Result<()> {
     // Validate credentials
     validate_credentials(&buster_creds.url, &buster_creds.api_key).await?;
 
+    // --test never touches the credentials file, whatever --no-save says
+    if args.test {
+        println!("Credentials are valid for {}", buster_creds.url);
+        return Ok(());
+    }
+
     // Save credentials unless --no-save is specified
     if !args.no_save {
         set_buster_credentials(buster_creds).await
@@ -126,3 +207,37 @@ pub async fn auth_with_args(args: AuthArgs) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_saved_for_host() {
+        let saved = BusterCredentials {
+            url: "https://api2.buster.so/".to_string(),
+            api_key: "key-123".to_string(),
+        };
+        assert!(saved_for_host(&saved, None));
+        assert!(saved_for_host(&saved, Some("https://api2.buster.so")));
+        assert!(!saved_for_host(&saved, Some("https://staging.buster.so")));
+
+        let without_key = BusterCredentials {
+            api_key: String::new(),
+            ..saved
+        };
+        assert!(!saved_for_host(&without_key, None));
+    }
+
+    #[test]
+    fn test_read_api_key() {
+        assert_eq!(read_api_key("  key-123 \n".as_bytes()).unwrap(), "key-123");
+        assert_eq!(read_api_key("key-123".as_bytes()).unwrap(), "key-123");
+
+        let error = read_api_key("\n".as_bytes()).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<AuthError>(),
+            Some(AuthError::MissingApiKey)
+        ));
+    }
+}
@@ -1,35 +1,416 @@
 use anyhow::Result;
+use colored::*;
+use futures::stream::{self, StreamExt};
+use lazy_static::lazy_static;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use regex::Regex;
 use std::path::{Path, PathBuf};
 use tokio::task;
 
+use crate::error::BusterError;
 use crate::utils::{
-    buster_credentials::get_and_validate_buster_credentials, BusterClient,
-    DeployDatasetsColumnsRequest, DeployDatasetsEntityRelationshipsRequest, DeployDatasetsRequest,
-    ValidationError, ValidationErrorType, ValidationResult,
+    buster_credentials::{
+        credentials_override_host, get_and_validate_buster_credentials,
+        get_and_validate_buster_credentials_for_host,
+    },
+    BusterClient, ColumnDeleteMode, DataSourceSummary, DeploymentSummary, DeployTimings,
+    DeployDatasetsColumnsRequest, DeployDatasetsEntityRelationshipsRequest,
+    DeployDatasetsRatioRequest, DeployDatasetsRefreshRequest, DeployDatasetsRequest, DeployedDataset, ValidationError, ValidationErrorType, ValidationResult, ValidationSeverity,
+    ignore_files::{IgnoreRules, BUSTER_IGNORE_FILE},
+    lock::{content_hash, BusterLock},
+    manifest::GenerateManifest,
+    sources::DbtSources,
+    utf8_files::read_utf8_file,
 };
 
+/// Default convention for `--lint-names`: snake_case column names.
+const DEFAULT_COLUMN_NAME_PATTERN: &str = "^[a-z0-9_]+$";
+
+/// Dataset types a model can declare.
+const DATASET_TYPES: [&str; 3] = ["table", "view", "materialized_view"];
+
+/// Default minimum description length for `--lint-descriptions`.
+const DEFAULT_DESCRIPTION_MIN_LENGTH: usize = 15;
+
+#[derive(Debug, Clone)]
+pub struct DeployArgs {
+    /// Files or directories to deploy, defaults to the current directory
+    pub paths: Vec<String>,
+    /// Validate everything without calling the API
+    pub dry_run: bool,
+    /// Maximum number of model files parsed concurrently
+    pub parallel: usize,
+    /// Suppress per-model output and print only the final counts
+    pub summary_only: bool,
+    /// Suppress all output when every model deploys; report failures in full
+    pub quiet_success: bool,
+    /// Warn about column names that don't match the configured naming convention
+    pub lint_names: bool,
+    /// Warn about empty, very short, or name-only model and column descriptions
+    pub lint_descriptions: bool,
+    /// Treat lint warnings as deployment failures
+    pub fail_on_warning: bool,
+    /// Have the API detect the warehouse dialect instead of trusting the recorded type
+    pub dialect_autodetect: bool,
+    /// Have the API check the warehouse for duplicate rows per model grain
+    pub verify_grain: bool,
+    /// Have the API warn about warehouse columns a model doesn't declare
+    pub strict_schema: bool,
+    /// Have the API list similarly named tables when a model's table isn't found
+    pub explain_matching: bool,
+    /// Have the API write model and column descriptions to the warehouse as comments
+    pub write_comments: bool,
+    /// Data source every model deploys to, overriding model files and buster.yml
+    pub data_source: Option<String>,
+    /// Database every model is validated against and deployed with, overriding model files
+    /// and buster.yml
+    pub database: Option<String>,
+    /// Env of models that don't set one, taking precedence over buster.yml
+    pub env: Option<String>,
+    /// Data source groups the server validates in parallel; `None` uses the server default
+    pub concurrency: Option<usize>,
+    /// How results are printed
+    pub output: DeployOutput,
+    /// With `dry_run`, write the requests that would be deployed to this file
+    pub dump_plan: Option<PathBuf>,
+    /// Deploy exactly the requests in a plan written by `dump_plan` instead of model files
+    pub apply_plan: Option<PathBuf>,
+    /// Deploy exactly the model files listed in a manifest written by generate, in its order
+    pub models_from_manifest: Option<PathBuf>,
+    /// Print the slowest models and each data source group's timings after the summary
+    pub timings: bool,
+    /// Warn about models whose files changed since the import this lock file records
+    pub lock: Option<PathBuf>,
+    /// What happens to removed columns, overriding buster.yml's `column_delete_mode`
+    pub column_delete_mode: Option<ColumnDeleteMode>,
+    /// With hard deletes, also delete removed columns that have stored values
+    pub force: bool,
+    /// Only create models that aren't deployed yet; existing ones are left untouched
+    pub only_new: bool,
+    /// Columns left out of the deploy, as `model.column`, like columns with `skip: true`
+    pub exclude_columns: Vec<String>,
+    /// Delete deployed columns that are skipped instead of keeping them
+    pub prune_skipped: bool,
+    /// Hosts the deploy fans out to, overriding buster.yml's `hosts`
+    pub hosts: Vec<String>,
+    /// With several hosts, fail when any host fails instead of only when all do
+    pub require_all: bool,
+}
+
+impl DeployArgs {
+    pub fn new(path: Option<&str>, dry_run: bool) -> Self {
+        Self {
+            paths: path.map(str::to_string).into_iter().collect(),
+            dry_run,
+            parallel: default_parallelism(),
+            summary_only: false,
+            quiet_success: false,
+            lint_names: false,
+            lint_descriptions: false,
+            fail_on_warning: false,
+            dialect_autodetect: false,
+            verify_grain: false,
+            strict_schema: false,
+            explain_matching: false,
+            write_comments: false,
+            data_source: None,
+            database: None,
+            env: None,
+            concurrency: None,
+            output: DeployOutput::Text,
+            dump_plan: None,
+            apply_plan: None,
+            models_from_manifest: None,
+            timings: false,
+            lock: None,
+            column_delete_mode: None,
+            force: false,
+            only_new: false,
+            exclude_columns: Vec::new(),
+            prune_skipped: false,
+            hosts: Vec::new(),
+            require_all: false,
+        }
+    }
+}
+
+/// How `buster deploy` prints its results.
+#[derive(Debug, Clone, Copy, Default, PartialEq, clap::ValueEnum)]
+pub enum DeployOutput {
+    #[default]
+    Text,
+    /// One JSON object per model result as it arrives, then a summary object
+    Ndjson,
+}
+
+/// A line of `--output ndjson`, shaped like the server's streamed deploy events.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum NdjsonEvent<'a> {
+    Result(&'a ValidationResult),
+    Summary(&'a DeploymentSummary),
+    Error { message: String },
+    /// How the deploy went on one host of a fanned-out deploy
+    HostResult { success: bool, error: Option<String> },
+}
+
+/// An ndjson event tagged with the host it came from when the deploy fans out.
+#[derive(Serialize)]
+struct NdjsonLine<'a> {
+    #[serde(flatten)]
+    event: &'a NdjsonEvent<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    host: Option<&'a str>,
+}
+
+fn print_ndjson(event: &NdjsonEvent, host: Option<&str>) {
+    match serde_json::to_string(&NdjsonLine { event, host }) {
+        Ok(line) => println!("{}", line),
+        Err(e) => eprintln!("❌ Failed to serialize deploy result: {}", e),
+    }
+}
+
+/// Whether the server rejected the deploy because it can't serve this CLI version.
+fn is_incompatible_cli(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<BusterError>(),
+        Some(BusterError::IncompatibleCli { .. })
+    )
+}
+
+pub fn default_parallelism() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4)
+}
+
+/// The exact requests a deploy sends, and what each does to what's deployed. Written by
+/// `--dry-run --dump-plan` so it can be reviewed, then replayed unchanged by `--apply-plan`,
+/// which refuses to run once a model changed on the server since.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DeployPlan {
+    pub version: u32,
+    pub models: Vec<DeployDatasetsRequest>,
+    pub changes: Vec<PlannedChange>,
+}
+
+/// What deploying one model of a plan does.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct PlannedChange {
+    pub model_name: String,
+    pub data_source_name: String,
+    pub action: PlannedAction,
+    /// SHA-256 of the deployed model file when the plan was written; `None` when the model
+    /// wasn't deployed
+    pub deployed_hash: Option<String>,
+    /// Deployed columns the model no longer has, deleted as `column_delete_mode` says
+    pub pruned_columns: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlannedAction {
+    Create,
+    Update,
+    Unchanged,
+}
+
+impl DeployPlan {
+    const VERSION: u32 = 2;
+
+    /// Plans `models` against the datasets deployed now, which must include their model files.
+    fn new(models: Vec<DeployDatasetsRequest>, deployed: &[DeployedDataset]) -> Self {
+        let changes = models
+            .iter()
+            .map(|request| {
+                let deployed = find_deployed(deployed, &request.data_source_name, &request.name);
+                let deployed_hash = deployed.map(deployed_hash);
+                let action = match (deployed, &deployed_hash) {
+                    (None, _) => PlannedAction::Create,
+                    (Some(_), Some(hash))
+                        if request
+                            .yml_file
+                            .as_ref()
+                            .is_some_and(|yml| content_hash(yml.as_bytes()) == *hash) =>
+                    {
+                        PlannedAction::Unchanged
+                    }
+                    (Some(_), _) => PlannedAction::Update,
+                };
+                PlannedChange {
+                    model_name: request.name.clone(),
+                    data_source_name: request.data_source_name.clone(),
+                    action,
+                    deployed_hash,
+                    pruned_columns: deployed
+                        .map(|deployed| pruned_columns(request, deployed))
+                        .unwrap_or_default(),
+                }
+            })
+            .collect();
+
+        Self {
+            version: Self::VERSION,
+            models,
+            changes,
+        }
+    }
+
+    /// Models whose deployed state no longer matches what the plan was written against.
+    fn changed_since(&self, deployed: &[DeployedDataset]) -> Vec<String> {
+        self.changes
+            .iter()
+            .filter(|change| {
+                find_deployed(deployed, &change.data_source_name, &change.model_name)
+                    .map(deployed_hash)
+                    != change.deployed_hash
+            })
+            .map(|change| change.model_name.clone())
+            .collect()
+    }
+
+    fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json).map_err(|e| {
+            anyhow::anyhow!("Failed to write deploy plan to {}: {}", path.display(), e)
+        })
+    }
+
+    fn read(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path).map_err(|e| {
+            anyhow::anyhow!("Failed to read deploy plan from {}: {}", path.display(), e)
+        })?;
+        let plan: Self = serde_json::from_str(&json).map_err(|e| {
+            anyhow::anyhow!("Invalid deploy plan {}: {}", path.display(), e)
+        })?;
+
+        if plan.version != Self::VERSION {
+            return Err(anyhow::anyhow!(
+                "Unsupported deploy plan version {} in {} (expected {})",
+                plan.version,
+                path.display(),
+                Self::VERSION
+            ));
+        }
+
+        Ok(plan)
+    }
+}
+
+fn find_deployed<'a>(
+    deployed: &'a [DeployedDataset],
+    data_source_name: &str,
+    model_name: &str,
+) -> Option<&'a DeployedDataset> {
+    deployed
+        .iter()
+        .find(|dataset| dataset.data_source_name == data_source_name && dataset.name == model_name)
+}
+
+/// Hash of a deployed dataset's model file, or of nothing when the server has none for it.
+fn deployed_hash(dataset: &DeployedDataset) -> String {
+    content_hash(dataset.yml_file.as_deref().unwrap_or_default().as_bytes())
+}
+
+/// Dimensions and measures of the deployed model that `request` no longer has, leaving out
+/// skipped ones, which stay deployed. Empty when the deployed model file can't be read.
+fn pruned_columns(request: &DeployDatasetsRequest, deployed: &DeployedDataset) -> Vec<String> {
+    // Read loosely, since the file may have been deployed by an older CLI
+    let Some(file) = deployed
+        .yml_file
+        .as_deref()
+        .and_then(|yml| serde_yaml::from_str::<serde_yaml::Value>(yml).ok())
+    else {
+        return Vec::new();
+    };
+    let Some(model) = file["models"]
+        .as_sequence()
+        .into_iter()
+        .flatten()
+        .find(|model| model["name"].as_str() == Some(request.name.as_str()))
+    else {
+        return Vec::new();
+    };
+
+    ["dimensions", "measures"]
+        .iter()
+        .filter_map(|kind| model[*kind].as_sequence())
+        .flatten()
+        .filter_map(|column| column["name"].as_str())
+        .filter(|name| {
+            !request.columns.iter().any(|col| col.name == *name)
+                && !request.skipped_columns.iter().any(|skipped| skipped == name)
+        })
+        .map(str::to_string)
+        .collect()
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct BusterConfig {
     pub data_source_name: Option<String>,
     pub schema: Option<String>,
     pub database: Option<String>,
+    /// Regex column names must match when deploying with `--lint-names`
+    #[serde(default)]
+    pub column_name_pattern: Option<String>,
+    /// Minimum description length when deploying with `--lint-descriptions`
+    #[serde(default)]
+    pub description_min_length: Option<usize>,
+    /// Fail models whose descriptions `--lint-descriptions` finds markdown problems in,
+    /// instead of warning
+    #[serde(default)]
+    pub fail_on_markdown_issues: bool,
+    /// Type of models that don't declare one; `view` when unset
+    #[serde(default)]
+    pub dataset_type: Option<String>,
+    /// Dataset types models may be deployed as; the API rejects any other
+    #[serde(default)]
+    pub allowed_dataset_types: Option<Vec<String>>,
+    /// What deploy does with columns removed from a model; `soft` when unset
+    #[serde(default)]
+    pub column_delete_mode: Option<ColumnDeleteMode>,
+    /// Env of models that don't set one
+    #[serde(default)]
+    pub env: Option<String>,
+    /// Buster instances every deploy goes to, e.g. a primary and its DR mirror
+    #[serde(default)]
+    pub hosts: Option<Vec<DeployHost>>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+/// A Buster instance a deploy fans out to, from `--host` or buster.yml's `hosts`.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct DeployHost {
+    pub url: String,
+    /// Environment variable holding the host's API key; without it the key is found as
+    /// for the top-level `--host`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_env: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct BusterModel {
     #[serde(default)]
     version: i32, // Optional, only used for DBT models
     models: Vec<Model>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct Model {
     name: String,
+    /// Display name shown in place of `name`; never used in SQL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+    /// Warehouse table the model reads from, when it's named differently
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    database_name: Option<String>,
     data_source_name: Option<String>,
     schema: Option<String>,
     database: Option<String>,
+    /// Env of the data source; `--env` or buster.yml's `env` when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    env: Option<String>,
     description: String,
     model: Option<String>,
     #[serde(default)]
@@ -38,9 +419,62 @@ pub struct Model {
     dimensions: Vec<Dimension>,
     #[serde(default)]
     measures: Vec<Measure>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    refresh: Option<RefreshSchedule>,
+    /// Timestamp dimension used by default when filtering the model by time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    primary_time_dimension: Option<String>,
+    /// Dimensions or entities that together identify a single row
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    grain: Option<Vec<String>>,
+    /// `false` keeps the model deployed but hidden from users
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    enabled: Option<bool>,
+    /// `table`, `view` or `materialized_view`, defaulting to buster.yml's `dataset_type`
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    dataset_type: Option<String>,
+    /// Tells agents when to use the model; deploy falls back to `description`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    when_to_use: Option<String>,
+}
+
+impl Model {
+    fn dataset_type(&self, config: Option<&BusterConfig>) -> String {
+        self.dataset_type
+            .clone()
+            .or_else(|| config.and_then(|c| c.dataset_type.clone()))
+            .unwrap_or_else(|| "view".to_string())
+    }
+
+    fn time_dimensions(&self) -> impl Iterator<Item = &Dimension> {
+        self.dimensions
+            .iter()
+            .filter(|dim| is_time_type(&dim.dimension_type))
+    }
+
+    /// The designated primary time dimension, or the model's only timestamp dimension
+    /// when none is designated.
+    fn primary_time_dimension(&self) -> Option<String> {
+        if let Some(name) = &self.primary_time_dimension {
+            return Some(name.clone());
+        }
+
+        let mut time_dimensions = self.time_dimensions();
+        match (time_dimensions.next(), time_dimensions.next()) {
+            (Some(only), None) => Some(only.name.clone()),
+            _ => None,
+        }
+    }
+}
+
+fn is_time_type(dimension_type: &str) -> bool {
+    let dimension_type = dimension_type.trim().to_lowercase();
+    dimension_type.starts_with("timestamp")
+        || dimension_type == "datetime"
+        || dimension_type == "date"
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone, Eq, PartialEq, Hash)]
+#[derive(Debug, Deserialize, Serialize, Clone, Eq, PartialEq, Hash, JsonSchema)]
 pub struct Entity {
     name: String,
     #[serde(default)]
@@ -53,23 +487,75 @@ pub struct Entity {
     project_path: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct Dimension {
     name: String,
+    /// Display name shown in place of `name`; never used in SQL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
     expr: String,
     #[serde(rename = "type")]
     dimension_type: String,
     description: String,
     #[serde(default = "bool::default")]
     searchable: bool,
+    /// Modeled for joins and lineage but left out of the fields shown to end users
+    #[serde(default)]
+    hidden: bool,
+    /// Left out of deploys, e.g. while the column is backfilled; a deployed column is kept
+    #[serde(default)]
+    skip: bool,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct Measure {
     name: String,
+    /// Display name shown in place of `name`; never used in SQL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+    #[serde(default)]
     expr: String,
-    agg: String,
+    /// Result type of the measure, as written by generate (`number`)
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    measure_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    agg: Option<String>,
     description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ratio: Option<MeasureRatio>,
+    /// Quantile for `agg: percentile`, in (0, 1]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    percentile: Option<f64>,
+    /// Boolean expression; only rows matching it are aggregated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    filters: Option<String>,
+    /// Modeled but left out of the fields shown to end users
+    #[serde(default)]
+    hidden: bool,
+    /// Left out of deploys, e.g. while the column is backfilled; a deployed column is kept
+    #[serde(default)]
+    skip: bool,
+}
+
+/// A measure computed from two other measures in the same model. The ratio is
+/// taken after both measures have been aggregated, never row by row.
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+pub struct MeasureRatio {
+    numerator: String,
+    denominator: String,
+}
+
+/// Refresh schedule for models backed by a materialized view. `cron` is a standard
+/// five-field expression, evaluated by the scheduler in UTC.
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+pub struct RefreshSchedule {
+    cron: String,
+    #[serde(default = "default_refresh_enabled")]
+    enabled: bool,
+}
+
+fn default_refresh_enabled() -> bool {
+    true
 }
 
 #[derive(Debug)]
@@ -82,8 +568,14 @@ struct ModelFile {
 
 #[derive(Debug, Default)]
 pub struct DeployResult {
-    success: Vec<(String, String, String)>, // (filename, model_name, data_source)
-    failures: Vec<(String, String, Vec<String>)>, // (filename, model_name, errors)
+    success: Vec<(String, String, String, Vec<String>)>, // (filename, model_name, data_source, warnings)
+    failures: Vec<(String, String, Vec<String>)>,        // (filename, model_name, errors)
+    enabled: Vec<String>,         // previously disabled models that were enabled
+    disabled: Vec<String>,        // previously enabled models that were disabled
+    skipped: Vec<String>,         // existing models left untouched by --only-new
+    skipped_columns: Vec<String>, // model.column left out with skip or --exclude-column
+    ignored: usize,               // model files skipped by .busterignore
+    timings: Option<DeployTimings>,
 }
 
 // Track mapping between files and their models
@@ -99,19 +591,27 @@ struct DeployProgress {
     processed: usize,
     current_file: String,
     status: String,
+    quiet: bool,
+    /// Still report validation failures when `quiet`
+    report_failures: bool,
 }
 
 impl DeployProgress {
-    fn new(total_files: usize) -> Self {
+    fn new(total_files: usize, quiet: bool, report_failures: bool) -> Self {
         Self {
             total_files,
             processed: 0,
             current_file: String::new(),
             status: String::new(),
+            quiet,
+            report_failures,
         }
     }
 
     fn log_progress(&self) {
+        if self.quiet {
+            return;
+        }
         println!(
             "\n[{}/{}] Processing: {}",
             self.processed, self.total_files, self.current_file
@@ -120,26 +620,44 @@ impl DeployProgress {
     }
 
     fn log_error(&self, error: &str) {
+        if self.quiet {
+            return;
+        }
         eprintln!("❌ Error processing {}: {}", self.current_file, error);
     }
 
     fn log_success(&self) {
+        if self.quiet {
+            return;
+        }
         println!("✅ Successfully deployed: {}", self.current_file);
     }
 
     fn log_warning(&self, warning: &str) {
+        if self.quiet {
+            return;
+        }
         println!("⚠️  Warning for {}: {}", self.current_file, warning);
     }
 
     fn log_info(&self, info: &str) {
+        if self.quiet {
+            return;
+        }
         println!("ℹ️  {}: {}", self.current_file, info);
     }
 
     fn log_validation_start(&self, model_name: &str) {
+        if self.quiet {
+            return;
+        }
         println!("🔍 Validating model '{}'...", model_name);
     }
 
     fn log_sql_info(&self, model_name: &str, sql_path: Option<&PathBuf>) {
+        if self.quiet {
+            return;
+        }
         match sql_path {
             Some(path) => println!(
                 "📄 Found SQL file for '{}' at: {}",
@@ -154,7 +672,7 @@ impl DeployProgress {
     }
 
     pub fn log_validation_error(&self, validation: &ValidationResult) {
-        if !validation.success {
+        if !validation.success && (!self.quiet || self.report_failures) {
             println!("\n❌ Validation failed for {}", validation.model_name);
             println!("   Data Source: {}", validation.data_source_name);
             println!("   Schema: {}", validation.schema);
@@ -184,7 +702,27 @@ impl DeployProgress {
                     ValidationErrorType::ExpressionError => expression_errors.push(error),
                     ValidationErrorType::ProjectNotFound => project_errors.push(error),
                     ValidationErrorType::InvalidBusterYml => buster_yml_errors.push(error),
-                    ValidationErrorType::DataSourceMismatch => data_source_errors.push(error),
+                    ValidationErrorType::DataSourceMismatch
+                    | ValidationErrorType::AmbiguousDataSource
+                    | ValidationErrorType::WarehouseAuthFailed
+                    | ValidationErrorType::WarehouseTimeout => data_source_errors.push(error),
+                    ValidationErrorType::NameConvention
+                    | ValidationErrorType::WeakDescription
+                    | ValidationErrorType::LockDrift
+                    | ValidationErrorType::InvalidRefreshSchedule
+                    | ValidationErrorType::EmptyModel
+                    | ValidationErrorType::InvalidGrain
+                    | ValidationErrorType::SchemaDrift
+                    | ValidationErrorType::CommentWriteFailed
+                    | ValidationErrorType::DisallowedDatasetType
+                    | ValidationErrorType::SearchableNotSupported
+                    | ValidationErrorType::SearchableEnrollsStoredValues
+                    | ValidationErrorType::StoredValuesColumnRemoved
+                    | ValidationErrorType::InvalidEnv
+                    | ValidationErrorType::DefaultedColumnType
+                    | ValidationErrorType::DatasetNotFound
+                    | ValidationErrorType::DuplicateExpression
+                    | ValidationErrorType::MalformedDescription => other_errors.push(error),
                 }
             }
 
@@ -193,6 +731,9 @@ impl DeployProgress {
                 println!("\n   Table/View Errors:");
                 for error in table_errors {
                     println!("   - {}", error.message);
+                    if let Some(hint) = similar_tables_hint(error) {
+                        println!("     {}", hint);
+                    }
                 }
             }
 
@@ -259,15 +800,21 @@ impl DeployProgress {
     }
 
     pub fn log_validation_success(&self, validation: &ValidationResult) {
+        if self.quiet {
+            return;
+        }
         println!("\n✅ Validation passed for {}", validation.model_name);
         println!("   Data Source: {}", validation.data_source_name);
         println!("   Schema: {}", validation.schema);
+        for warning in &validation.warnings {
+            println!("{}", format!("   ⚠️  Warning: {}", warning.message).yellow());
+        }
     }
 }
 
 impl ModelFile {
     fn new(yml_path: PathBuf, config: Option<BusterConfig>) -> Result<Self> {
-        let yml_content = std::fs::read_to_string(&yml_path)?;
+        let yml_content = read_utf8_file(&yml_path)?;
         let model: BusterModel = serde_yaml::from_str(&yml_content)?;
 
         Ok(Self {
@@ -296,8 +843,7 @@ impl ModelFile {
     fn get_config(dir: &Path) -> Result<Option<BusterConfig>> {
         let config_path = dir.join("buster.yml");
         if config_path.exists() {
-            let content = std::fs::read_to_string(&config_path)
-                .map_err(|e| anyhow::anyhow!("Failed to read buster.yml: {}", e))?;
+            let content = read_utf8_file(&config_path)?;
 
             if content.trim().is_empty() {
                 return Ok(None);
@@ -327,11 +873,12 @@ impl ModelFile {
                 ),
                 column_name: None,
                 suggestion: Some(format!("Create {}.yml file with model definition", entity_name)),
+                context: None,
             });
         }
 
         // Quick verification that model exists in file
-        if let Ok(content) = std::fs::read_to_string(&target_file) {
+        if let Ok(content) = read_utf8_file(&target_file) {
             if let Ok(model_def) = serde_yaml::from_str::<BusterModel>(&content) {
                 if !model_def.models.iter().any(|m| m.name == entity_name) {
                     return Err(ValidationError {
@@ -345,6 +892,7 @@ impl ModelFile {
                             "Add model definition for '{}' in {}.yml",
                             entity_name, entity_name
                         )),
+                        context: None,
                     });
                 }
             }
@@ -353,7 +901,8 @@ impl ModelFile {
         Ok(())
     }
 
-    async fn validate(&self, config: Option<&BusterConfig>) -> Result<(), Vec<String>> {
+    /// Returns the file's warnings when it's valid, and its errors otherwise.
+    async fn validate(&self, config: Option<&BusterConfig>) -> Result<Vec<String>, Vec<String>> {
         let mut errors = Vec::new();
 
         // Basic validation first
@@ -395,39 +944,240 @@ impl ModelFile {
             }
         }
 
-        // Warnings
+        // Validate measure definitions, including ratio measures
+        for model in &self.model.models {
+            errors.extend(Self::validate_measures(model));
+        }
+
+        // Validate refresh schedules
+        for model in &self.model.models {
+            if let Some(error) = Self::validate_refresh(model) {
+                errors.push(error);
+            }
+        }
+
+        // Validate dataset types
+        for model in &self.model.models {
+            if let Some(error) = Self::validate_dataset_type(model, config) {
+                errors.push(error);
+            }
+        }
+
+        // Validate primary time dimensions
+        for model in &self.model.models {
+            if let Some(error) = Self::validate_primary_time_dimension(model) {
+                errors.push(error);
+            }
+        }
+
+        // Validate grains
+        for model in &self.model.models {
+            errors.extend(Self::validate_grain(model));
+        }
+
+        let mut warnings = Vec::new();
         for model in &self.model.models {
             if model.description.is_empty() {
-                println!("⚠️  Warning: Model '{}' has no description", model.name);
+                warnings.push(format!("Model '{}' has no description", model.name));
             }
             if model.dimensions.is_empty() && model.measures.is_empty() {
-                println!(
-                    "⚠️  Warning: Model '{}' has no dimensions or measures",
+                warnings.push(format!("Model '{}' has no dimensions or measures", model.name));
+            }
+            if model.primary_time_dimension.is_none() && model.time_dimensions().count() > 1 {
+                warnings.push(format!(
+                    "Model '{}' has several timestamp dimensions; set primary_time_dimension to choose the default for time filters",
                     model.name
-                );
+                ));
             }
         }
 
         if errors.is_empty() {
-            Ok(())
+            Ok(warnings)
         } else {
             Err(errors)
         }
     }
 
-    fn generate_default_sql(&self, model: &Model) -> String {
-        format!(
-            "select * from {}.{}",
-            model.schema.as_ref().map(String::as_str).unwrap_or(""),
-            model.name
-        )
+    fn validate_refresh(model: &Model) -> Option<String> {
+        let refresh = model.refresh.as_ref()?;
+        let cron = refresh.cron.trim();
+
+        if cron.is_empty() {
+            return Some(format!(
+                "Model '{}' has an empty refresh.cron; remove the refresh block or provide a schedule",
+                model.name
+            ));
+        }
+
+        match croner::Cron::new(cron).parse() {
+            Ok(_) => None,
+            Err(e) => Some(format!(
+                "Model '{}' has an invalid refresh.cron '{}': {}",
+                model.name, cron, e
+            )),
+        }
     }
 
-    fn get_sql_content(&self, model: &Model) -> Result<String> {
-        if let Some(ref sql_path) = self.sql_path {
-            Ok(std::fs::read_to_string(sql_path)?)
-        } else {
-            Ok(self.generate_default_sql(model))
+    fn validate_dataset_type(model: &Model, config: Option<&BusterConfig>) -> Option<String> {
+        let dataset_type = model.dataset_type(config);
+
+        (!DATASET_TYPES.contains(&dataset_type.as_str())).then(|| {
+            format!(
+                "Model '{}' has unknown type '{}'; expected one of {}",
+                model.name,
+                dataset_type,
+                DATASET_TYPES.join(", ")
+            )
+        })
+    }
+
+    fn validate_primary_time_dimension(model: &Model) -> Option<String> {
+        let name = model.primary_time_dimension.as_ref()?;
+
+        match model.dimensions.iter().find(|dim| &dim.name == name) {
+            None => Some(format!(
+                "Model '{}' has primary_time_dimension '{}', which is not one of its dimensions",
+                model.name, name
+            )),
+            Some(dim) if !is_time_type(&dim.dimension_type) => Some(format!(
+                "Model '{}' has primary_time_dimension '{}' of type '{}'; it must be a timestamp or date dimension",
+                model.name, name, dim.dimension_type
+            )),
+            Some(_) => None,
+        }
+    }
+
+    /// Each grain component must name one of the model's entities or dimensions, once.
+    /// The server checks the fields map to warehouse columns (and, with `--verify-grain`,
+    /// that the combination is unique).
+    fn validate_grain(model: &Model) -> Vec<String> {
+        let Some(grain) = &model.grain else {
+            return Vec::new();
+        };
+
+        if grain.is_empty() {
+            return vec![format!(
+                "Model '{}' has an empty grain; list the dimensions or entities that identify a row",
+                model.name
+            )];
+        }
+
+        let mut errors = Vec::new();
+        let mut seen = HashSet::new();
+        for component in grain {
+            let resolves = model.entities.iter().any(|entity| &entity.name == component)
+                || model.dimensions.iter().any(|dim| &dim.name == component);
+
+            if !resolves {
+                errors.push(format!(
+                    "Model '{}' grain component '{}' is not one of its entities or dimensions",
+                    model.name, component
+                ));
+            } else if !seen.insert(component.as_str()) {
+                errors.push(format!(
+                    "Model '{}' grain lists '{}' more than once",
+                    model.name, component
+                ));
+            }
+        }
+        errors
+    }
+
+    fn validate_measures(model: &Model) -> Vec<String> {
+        let mut errors = Vec::new();
+        let measures: HashMap<&str, &Measure> = model
+            .measures
+            .iter()
+            .map(|m| (m.name.as_str(), m))
+            .collect();
+
+        for measure in &model.measures {
+            match (measure.agg.as_deref(), measure.percentile) {
+                (Some("percentile"), None) => errors.push(format!(
+                    "Measure '{}' in model '{}' uses agg 'percentile' but has no percentile value",
+                    measure.name, model.name
+                )),
+                (Some("percentile"), Some(p)) if !(p > 0.0 && p <= 1.0) => errors.push(format!(
+                    "Measure '{}' in model '{}' has percentile {}; it must be in (0, 1]",
+                    measure.name, model.name, p
+                )),
+                (Some("percentile"), Some(_)) | (_, None) => {}
+                (_, Some(_)) => errors.push(format!(
+                    "Measure '{}' in model '{}' sets percentile but its agg is not 'percentile'",
+                    measure.name, model.name
+                )),
+            }
+
+            if measure.filters.as_deref().is_some_and(|f| f.trim().is_empty()) {
+                errors.push(format!(
+                    "Measure '{}' in model '{}' has an empty filters expression",
+                    measure.name, model.name
+                ));
+            }
+
+            let Some(ratio) = &measure.ratio else {
+                if measure.expr.is_empty() {
+                    errors.push(format!(
+                        "Measure '{}' in model '{}' is missing an expr",
+                        measure.name, model.name
+                    ));
+                }
+                if measure.agg.is_none() {
+                    errors.push(format!(
+                        "Measure '{}' in model '{}' is missing an agg",
+                        measure.name, model.name
+                    ));
+                }
+                continue;
+            };
+
+            if let Some(agg) = &measure.agg {
+                errors.push(format!(
+                    "Ratio measure '{}' in model '{}' cannot use row-level agg '{}'; the ratio is computed after its measures are aggregated",
+                    measure.name, model.name, agg
+                ));
+            }
+            if !measure.expr.is_empty() {
+                errors.push(format!(
+                    "Ratio measure '{}' in model '{}' cannot define an expr",
+                    measure.name, model.name
+                ));
+            }
+            if measure.filters.is_some() {
+                errors.push(format!(
+                    "Ratio measure '{}' in model '{}' cannot define filters; filter the measures it references instead",
+                    measure.name, model.name
+                ));
+            }
+
+            for referenced in [&ratio.numerator, &ratio.denominator] {
+                match measures.get(referenced.as_str()) {
+                    None => errors.push(format!(
+                        "Ratio measure '{}' in model '{}' references unknown measure '{}'",
+                        measure.name, model.name, referenced
+                    )),
+                    Some(m) if m.ratio.is_some() => errors.push(format!(
+                        "Ratio measure '{}' in model '{}' references ratio measure '{}'; ratios must reference aggregated measures",
+                        measure.name, model.name, referenced
+                    )),
+                    Some(m) if m.percentile.is_some() => errors.push(format!(
+                        "Ratio measure '{}' in model '{}' references percentile measure '{}', which is not supported",
+                        measure.name, model.name, referenced
+                    )),
+                    Some(_) => {}
+                }
+            }
+        }
+
+        errors
+    }
+
+    /// The model's SQL file, if it has one. Without one the server selects from the
+    /// warehouse table, quoted for its dialect.
+    fn get_sql_content(&self) -> Result<Option<String>> {
+        match self.sql_path {
+            Some(ref sql_path) => Ok(Some(std::fs::read_to_string(sql_path)?)),
+            None => Ok(None),
         }
     }
 
@@ -454,11 +1204,20 @@ impl ModelFile {
         (data_source_name, schema, database)
     }
 
-    fn to_deploy_request(&self, model: &Model, sql_content: String) -> DeployDatasetsRequest {
+    fn to_deploy_request(
+        &self,
+        model: &Model,
+        sql_content: Option<String>,
+    ) -> DeployDatasetsRequest {
         let mut columns = Vec::new();
+        let mut skipped_columns = Vec::new();
 
         // Convert dimensions to columns
         for dim in &model.dimensions {
+            if dim.skip {
+                skipped_columns.push(dim.name.clone());
+                continue;
+            }
             columns.push(DeployDatasetsColumnsRequest {
                 name: dim.name.clone(),
                 description: dim.description.clone(),
@@ -467,19 +1226,43 @@ impl ModelFile {
                 type_: Some(dim.dimension_type.clone()),
                 agg: None,
                 searchable: dim.searchable,
+                percentile: None,
+                filters: None,
+                hidden: dim.hidden,
+                label: dim.label.clone(),
+                ratio: None,
             });
         }
 
         // Convert measures to columns
         for measure in &model.measures {
+            if measure.skip {
+                skipped_columns.push(measure.name.clone());
+                continue;
+            }
+            // Ratio measures are sent as the measures they divide, with agg "ratio", and
+            // the server builds the expression from those measures' aggregates
+            let (expr, agg) = match &measure.ratio {
+                Some(_) => (None, Some("ratio".to_string())),
+                None => (Some(measure.expr.clone()), measure.agg.clone()),
+            };
+
             columns.push(DeployDatasetsColumnsRequest {
                 name: measure.name.clone(),
                 description: measure.description.clone(),
                 semantic_type: Some("measure".to_string()),
-                expr: Some(measure.expr.clone()),
-                type_: None,
-                agg: Some(measure.agg.clone()),
+                expr,
+                type_: measure.measure_type.clone(),
+                agg,
                 searchable: false, // Measures don't have stored values
+                percentile: measure.percentile,
+                filters: measure.filters.clone(),
+                hidden: measure.hidden,
+                label: measure.label.clone(),
+                ratio: measure.ratio.as_ref().map(|ratio| DeployDatasetsRatioRequest {
+                    numerator: ratio.numerator.clone(),
+                    denominator: ratio.denominator.clone(),
+                }),
             });
         }
 
@@ -510,17 +1293,48 @@ impl ModelFile {
         DeployDatasetsRequest {
             id: None,
             data_source_name,
-            env: "dev".to_string(),
-            type_: "view".to_string(),
+            env: model.env.clone(),
+            type_: model.dataset_type(self.config.as_ref()),
             name: model.name.clone(),
             model: model.model.clone(),
             schema,
             database, // This is already Option<String>
             description: model.description.clone(),
-            sql_definition: Some(sql_content),
+            sql_definition: sql_content,
             entity_relationships: Some(entity_relationships),
             columns,
             yml_file: Some(serde_yaml::to_string(&self.model).unwrap_or_default()),
+            dialect_autodetect: false,
+            refresh: model.refresh.as_ref().map(|refresh| DeployDatasetsRefreshRequest {
+                cron: refresh.cron.trim().to_string(),
+                enabled: refresh.enabled,
+            }),
+            primary_time_dimension: model.primary_time_dimension(),
+            grain: model.grain.clone(),
+            verify_grain: false,
+            database_name: model.database_name.clone(),
+            enabled: model.enabled.unwrap_or(true),
+            strict_schema: false,
+            explain_matching: false,
+            fail_on_warning: false,
+            write_comments: false,
+            lint_descriptions: false,
+            fail_on_markdown_issues: false,
+            allowed_dataset_types: self
+                .config
+                .as_ref()
+                .and_then(|c| c.allowed_dataset_types.clone()),
+            when_to_use: model.when_to_use.clone(),
+            version: self.model.version,
+            column_delete_mode: self
+                .config
+                .as_ref()
+                .and_then(|c| c.column_delete_mode)
+                .unwrap_or_default(),
+            force: false,
+            only_new: false,
+            label: model.label.clone(),
+            skipped_columns,
         }
     }
 
@@ -567,6 +1381,7 @@ impl ModelFile {
                             "Verify the project_path '{}' is correct",
                             project_path_display
                         )),
+                        context: None,
                     });
                     return (model_name, validation_errors);
                 }
@@ -584,12 +1399,13 @@ impl ModelFile {
                         suggestion: Some(
                             "Add a buster.yml file to the referenced project".to_string(),
                         ),
+                        context: None,
                     });
                     return (model_name, validation_errors);
                 }
 
                 // Parse and validate buster.yml
-                match std::fs::read_to_string(&buster_yml_path) {
+                match read_utf8_file(&buster_yml_path) {
                     Ok(content) => {
                         match serde_yaml::from_str::<BusterConfig>(&content) {
                             Ok(project_config) => {
@@ -604,6 +1420,7 @@ impl ModelFile {
                                             ),
                                             column_name: None,
                                             suggestion: Some("Ensure both projects use the same data source".to_string()),
+                                            context: None,
                                         });
                                     }
 
@@ -641,7 +1458,7 @@ impl ModelFile {
                                             model_file.path().display()
                                         );
                                         if let Ok(content) =
-                                            std::fs::read_to_string(model_file.path())
+                                            read_utf8_file(&model_file.path())
                                         {
                                             match serde_yaml::from_str::<BusterModel>(&content) {
                                                 Ok(model_def) => {
@@ -696,6 +1513,7 @@ impl ModelFile {
                                                 "Verify that the model '{}' exists in the target project",
                                                 entity.ref_.as_ref().unwrap_or(&entity.name)
                                             )),
+                                            context: None,
                                         });
                                     }
                                 } else {
@@ -707,6 +1525,7 @@ impl ModelFile {
                                         ),
                                         column_name: None,
                                         suggestion: Some("Add data_source_name to the referenced project's buster.yml".to_string()),
+                                        context: None,
                                     });
                                 }
                             }
@@ -719,6 +1538,7 @@ impl ModelFile {
                                     ),
                                     column_name: None,
                                     suggestion: Some("Fix the YAML syntax in the referenced project's buster.yml".to_string()),
+                                    context: None,
                                 });
                             }
                         }
@@ -732,6 +1552,7 @@ impl ModelFile {
                             ),
                             column_name: None,
                             suggestion: Some("Check file permissions and encoding".to_string()),
+                            context: None,
                         });
                     }
                 }
@@ -778,108 +1599,558 @@ impl ModelFile {
             message: "No data_source_name found in model or buster.yml".to_string(),
             column_name: None,
             suggestion: Some("Add data_source_name to your model or buster.yml".to_string()),
+            context: None,
         }])
     }
 }
 
-pub async fn deploy_v2(path: Option<&str>, dry_run: bool) -> Result<()> {
-    let target_path = PathBuf::from(path.unwrap_or("."));
-    let mut progress = DeployProgress::new(0);
-    let mut result = DeployResult::default();
-
-    // Only create client if not in dry-run mode
-    let client = if !dry_run {
-        // Create API client without explicit auth check
-        let creds = get_and_validate_buster_credentials().await?;
-        Some(BusterClient::new(creds.url, creds.api_key)?)
-    } else {
-        None
-    };
-
-    // Try to load buster.yml first
-    progress.status = "Looking for buster.yml configuration...".to_string();
-    progress.log_progress();
-
-    let config = match ModelFile::get_config(&target_path) {
-        Ok(Some(config)) => {
-            println!("✅ Found buster.yml configuration");
-            if let Some(ds) = &config.data_source_name {
-                println!("   - Default data source: {}", ds);
-            }
-            if let Some(schema) = &config.schema {
-                println!("   - Default schema: {}", schema);
+/// Parses model files on the blocking thread pool, at most `parallel` at a time. Results are
+/// returned in the same order as `yml_files` so the rest of the deploy stays deterministic.
+async fn load_model_files(
+    yml_files: Vec<PathBuf>,
+    config: Option<BusterConfig>,
+    parallel: usize,
+) -> Vec<(PathBuf, Result<ModelFile>)> {
+    stream::iter(yml_files)
+        .map(|yml_path| {
+            let config = config.clone();
+            async move {
+                let path = yml_path.clone();
+                let result = task::spawn_blocking(move || ModelFile::new(path, config))
+                    .await
+                    .unwrap_or_else(|e| Err(anyhow::anyhow!("Parser task failed: {}", e)));
+                (yml_path, result)
             }
-            if let Some(database) = &config.database {
-                println!("   - Default database: {}", database);
+        })
+        .buffered(parallel.max(1))
+        .collect()
+        .await
+}
+
+/// Collects the model files to deploy: each path that is a file, plus the `.yml` files
+/// directly inside each path that is a directory, except `buster.yml` and files matched
+/// by the directory's `.busterignore`. Returns the files and how many were ignored.
+fn find_model_files(paths: &[PathBuf]) -> Result<(Vec<PathBuf>, usize)> {
+    let mut yml_files = Vec::new();
+    let mut ignored = 0;
+
+    for path in paths {
+        if path.is_file() {
+            // Files named on the command line are deployed even if ignored
+            if !yml_files.contains(path) {
+                yml_files.push(path.clone());
             }
-            Some(config)
-        }
-        Ok(None) => {
-            println!("ℹ️  No buster.yml found, will require configuration in model files");
-            None
-        }
-        Err(e) => {
-            println!("⚠️  Error reading buster.yml: {}", e);
-            None
+            continue;
         }
-    };
-
-    // Find all .yml files
-    progress.status = "Discovering model files...".to_string();
-    progress.log_progress();
 
-    let yml_files: Vec<PathBuf> = if target_path.is_file() {
-        vec![target_path.clone()]
-    } else {
-        std::fs::read_dir(&target_path)?
+        let ignore_rules = IgnoreRules::load(path)?;
+        let mut entries: Vec<PathBuf> = std::fs::read_dir(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?
             .filter_map(|entry| entry.ok())
-            .filter(|entry| {
-                let path = entry.path();
-                path.extension()
-                    .and_then(|ext| ext.to_str())
-                    .map(|ext| ext == "yml")
-                    .unwrap_or(false)
-                    && path
-                        .file_name()
-                        .and_then(|name| name.to_str())
-                        .map(|name| name != "buster.yml")
-                        .unwrap_or(false)
-            })
             .map(|entry| entry.path())
-            .collect()
-    };
+            .filter(|file| {
+                file.is_file()
+                    && file.extension().and_then(|ext| ext.to_str()) == Some("yml")
+                    && file.file_name().and_then(|name| name.to_str()) != Some("buster.yml")
+            })
+            .collect();
+        entries.sort();
+
+        for file in entries {
+            let relative = file.strip_prefix(path).unwrap_or(&file);
+            if ignore_rules.is_ignored(relative) {
+                ignored += 1;
+            } else if !yml_files.contains(&file) {
+                yml_files.push(file);
+            }
+        }
+    }
 
-    println!(
-        "Found {} model files in {}",
-        yml_files.len(),
-        target_path.display()
-    );
-    progress.total_files = yml_files.len();
+    Ok((yml_files, ignored))
+}
 
-    let mut deploy_requests = Vec::new();
-    let mut model_mappings = Vec::new();
+/// Checks `name` exists in the env of every request, since models can target different envs.
+fn check_data_source_envs(
+    name: &str,
+    requests: &[DeployDatasetsRequest],
+    data_sources: &[DataSourceSummary],
+) -> Result<()> {
+    let envs: std::collections::BTreeSet<Option<&str>> =
+        requests.iter().map(|request| request.env.as_deref()).collect();
+    envs.into_iter()
+        .try_for_each(|env| check_data_source(name, env, data_sources))
+}
 
-    // Process each file
-    for yml_path in yml_files {
-        progress.processed += 1;
-        progress.current_file = yml_path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("unknown")
-            .to_string();
+/// Fails unless the organization has a data source named `name` in `env`, listing the ones
+/// it does have. Without an env it may be in any, and the server reports the model instead.
+fn check_data_source(
+    name: &str,
+    env: Option<&str>,
+    data_sources: &[DataSourceSummary],
+) -> Result<()> {
+    let in_env = |data_source: &&DataSourceSummary| env.is_none_or(|env| data_source.env == env);
+    if data_sources
+        .iter()
+        .filter(in_env)
+        .any(|data_source| data_source.name == name)
+    {
+        return Ok(());
+    }
 
-        progress.status = "Loading model file...".to_string();
-        progress.log_progress();
+    let available = data_sources
+        .iter()
+        .filter(in_env)
+        .map(|data_source| format!("{} ({})", data_source.name, data_source.type_))
+        .collect::<Vec<_>>();
+
+    Err(anyhow::anyhow!(
+        "Data source '{}' not found{}. Available: {}",
+        name,
+        env.map(|env| format!(" in env '{}'", env)).unwrap_or_default(),
+        if available.is_empty() {
+            "none".to_string()
+        } else {
+            available.join(", ")
+        }
+    ))
+}
+
+/// Points every request at `database`, returning how many had a different one.
+fn override_database(requests: &mut [DeployDatasetsRequest], database: &str) -> usize {
+    let mut overridden = 0;
+    for request in requests {
+        if request.database.as_deref() != Some(database) {
+            overridden += 1;
+        }
+        request.database = Some(database.to_string());
+    }
+    overridden
+}
+
+lazy_static! {
+    /// dbt's `{{ ref('model') }}` and `{{ source('source', 'table') }}`, also without braces
+    static ref REF_CALL_RE: Regex = Regex::new(
+        r#"\{\{\s*(ref|source)\s*\(([^)]*)\)\s*\}\}|\b(ref|source)\s*\((\s*['"][^)]*)\)"#
+    )
+    .unwrap();
+}
+
+fn has_ref_calls(request: &DeployDatasetsRequest) -> bool {
+    [&request.sql_definition, &request.model]
+        .into_iter()
+        .flatten()
+        .any(|sql| REF_CALL_RE.is_match(sql))
+}
+
+/// The models whose `ref()` or `source()` calls couldn't be resolved, and the refs a dry
+/// run leaves for the deploy to resolve, each with the model's name.
+#[derive(Debug, Default, PartialEq)]
+struct RefResolution {
+    failures: Vec<(String, String)>,
+    deferred: Vec<(String, String)>,
+}
+
+/// Rewrites the `ref()` and `source()` calls in each model's SQL to the schema-qualified
+/// tables they name. A ref resolves to a model of the same data source in this deploy, or
+/// else to one of the `existing` datasets. Without them, as in a dry run, other refs are
+/// left as they are until deploy time. Sources resolve through `source_of`.
+fn resolve_refs(
+    requests: &mut [DeployDatasetsRequest],
+    existing: Option<&[DeployedDataset]>,
+    source_of: impl Fn(&str, &str) -> Option<String>,
+) -> RefResolution {
+    let mut tables: HashMap<(String, String), String> = existing
+        .unwrap_or_default()
+        .iter()
+        .map(|dataset| {
+            (
+                (dataset.data_source_name.clone(), dataset.name.clone()),
+                format!("{}.{}", dataset.schema, dataset.database_name),
+            )
+        })
+        .collect();
+    tables.extend(requests.iter().map(|request| {
+        (
+            (request.data_source_name.clone(), request.name.clone()),
+            format!(
+                "{}.{}",
+                request.schema,
+                request.database_name.as_deref().unwrap_or(&request.name)
+            ),
+        )
+    }));
+
+    let mut resolution = RefResolution::default();
+    for request in requests.iter_mut() {
+        let data_source_name = request.data_source_name.clone();
+        let table_of = |model: &str| {
+            tables
+                .get(&(data_source_name.clone(), model.to_string()))
+                .cloned()
+        };
+        for sql in [&mut request.sql_definition, &mut request.model]
+            .into_iter()
+            .flatten()
+        {
+            match resolve_sql_refs(sql, table_of, &source_of, existing.is_none()) {
+                Ok((resolved, deferred)) => {
+                    *sql = resolved;
+                    resolution
+                        .deferred
+                        .extend(deferred.into_iter().map(|call| (request.name.clone(), call)));
+                }
+                Err(reason) => {
+                    resolution.failures.push((request.name.clone(), reason));
+                    break;
+                }
+            }
+        }
+    }
+    resolution
+}
+
+/// Replaces each `ref('model')` (or dbt's `ref('package', 'model')`) in `sql` with the table
+/// `table_of` finds for the model, and each `source('source', 'table')` with the one
+/// `source_of` finds. With `defer_refs`, refs `table_of` doesn't know are kept and returned
+/// instead of failing. The error names the first call that couldn't be resolved.
+fn resolve_sql_refs(
+    sql: &str,
+    table_of: impl Fn(&str) -> Option<String>,
+    source_of: impl Fn(&str, &str) -> Option<String>,
+    defer_refs: bool,
+) -> Result<(String, Vec<String>), String> {
+    let quoted = |arg: &str| {
+        let arg = arg.trim();
+        arg.strip_prefix('\'')
+            .and_then(|arg| arg.strip_suffix('\''))
+            .or_else(|| arg.strip_prefix('"').and_then(|arg| arg.strip_suffix('"')))
+            .map(str::to_string)
+    };
+
+    let mut error = None;
+    let mut deferred = Vec::new();
+    let resolved = REF_CALL_RE.replace_all(sql, |caps: &regex::Captures| {
+        let call = &caps[0];
+        let function = caps.get(1).or_else(|| caps.get(3)).map_or("", |m| m.as_str());
+        let args: Option<Vec<String>> = caps
+            .get(2)
+            .or_else(|| caps.get(4))
+            .map_or("", |m| m.as_str())
+            .split(',')
+            .map(quoted)
+            .collect();
+
+        let table = match (function, args.as_deref()) {
+            ("ref", Some([model])) | ("ref", Some([_, model])) => match table_of(model) {
+                Some(table) => Ok(table),
+                None if defer_refs => {
+                    deferred.push(call.trim().to_string());
+                    Ok(call.to_string())
+                }
+                None => Err(format!(
+                    "{} doesn't match a model in this deploy or a deployed dataset",
+                    call.trim()
+                )),
+            },
+            ("source", Some([source, table])) => source_of(source, table).ok_or_else(|| {
+                format!(
+                    "{} isn't defined in the dbt project's manifest or sources YAML",
+                    call.trim()
+                )
+            }),
+            _ => Err(format!(
+                "{} should be ref('model') or source('source', 'table')",
+                call.trim()
+            )),
+        };
+        table.unwrap_or_else(|reason| {
+            error.get_or_insert(reason);
+            call.to_string()
+        })
+    });
+
+    match error {
+        Some(reason) => Err(reason),
+        None => Ok((resolved.into_owned(), deferred)),
+    }
+}
+
+pub async fn deploy_v2(args: DeployArgs) -> Result<()> {
+    let config = ModelFile::get_config(&deploy_target_paths(&args)[0])
+        .ok()
+        .flatten();
+    let hosts = deploy_hosts(&args, config.as_ref(), credentials_override_host());
+    // A dry run never contacts a host
+    if hosts.is_empty() || args.dry_run {
+        return deploy_to_host(args, None).await;
+    }
+
+    let ndjson = args.output == DeployOutput::Ndjson;
+    let require_all = args.require_all;
+    let host_results = fan_out(&hosts, |host| {
+        if !ndjson {
+            println!("\n🌐 Deploying to {}", host.url);
+        }
+        deploy_to_host(args.clone(), Some(host))
+    })
+    .await;
+
+    if ndjson {
+        for (url, outcome) in &host_results {
+            print_ndjson(
+                &NdjsonEvent::HostResult {
+                    success: outcome.is_ok(),
+                    error: outcome.as_ref().err().map(|e| e.to_string()),
+                },
+                Some(url),
+            );
+        }
+    } else {
+        println!("\n🌐 Host Summary");
+        println!("==================");
+        for (url, outcome) in &host_results {
+            match outcome {
+                Ok(()) => println!("✅ {}", url),
+                Err(e) => println!("❌ {}: {}", url, e),
+            }
+        }
+    }
+
+    let failed = host_results.iter().filter(|(_, outcome)| outcome.is_err()).count();
+    fan_out_outcome(failed, hosts.len(), require_all)
+}
+
+/// Runs `deploy` against every host in turn. Every host gets the whole deploy, even after
+/// another one fails.
+async fn fan_out<'h, F, Fut>(hosts: &'h [DeployHost], mut deploy: F) -> Vec<(String, Result<()>)>
+where
+    F: FnMut(&'h DeployHost) -> Fut,
+    Fut: std::future::Future<Output = Result<()>>,
+{
+    let mut host_results = Vec::new();
+    for host in hosts {
+        let outcome = deploy(host).await;
+        host_results.push((host.url.clone(), outcome));
+    }
+    host_results
+}
+
+/// A fanned-out deploy fails when every host failed, or with `--require-all` when any did.
+fn fan_out_outcome(failed: usize, total: usize, require_all: bool) -> Result<()> {
+    if failed == total || (require_all && failed > 0) {
+        return Err(anyhow::anyhow!(
+            "Deploy failed on {} of {} hosts",
+            failed,
+            total
+        ));
+    }
+    Ok(())
+}
+
+/// Hosts the deploy fans out to: `deploy --host`, else buster.yml's `hosts`. Empty means the
+/// deploy goes to the saved credentials only, or to the top-level `--host`, which wins over
+/// buster.yml so `buster --host <URL> deploy` only ever reaches that host.
+fn deploy_hosts(
+    args: &DeployArgs,
+    config: Option<&BusterConfig>,
+    top_level_host: Option<&str>,
+) -> Vec<DeployHost> {
+    if !args.hosts.is_empty() {
+        return args
+            .hosts
+            .iter()
+            .map(|url| DeployHost {
+                url: url.clone(),
+                api_key_env: None,
+            })
+            .collect();
+    }
+    if top_level_host.is_some() {
+        return Vec::new();
+    }
+    config
+        .and_then(|config| config.hosts.clone())
+        .unwrap_or_default()
+}
+
+/// The API key a host's `api_key_env` names, looked up with `env_var`. `None` when the host
+/// doesn't name one, so the key is found as for the top-level `--host`.
+fn host_api_key(
+    host: &DeployHost,
+    env_var: impl Fn(&str) -> Option<String>,
+) -> Result<Option<String>> {
+    match &host.api_key_env {
+        Some(var) => env_var(var)
+            .filter(|api_key| !api_key.is_empty())
+            .map(Some)
+            .ok_or_else(|| anyhow::anyhow!("{} is not set, so {} has no API key", var, host.url)),
+        None => Ok(None),
+    }
+}
+
+fn deploy_target_paths(args: &DeployArgs) -> Vec<PathBuf> {
+    if !args.paths.is_empty() {
+        args.paths.iter().map(PathBuf::from).collect()
+    } else if let Some(manifest_path) = &args.models_from_manifest {
+        // Generate writes buster.yml next to the manifest
+        let manifest_dir = manifest_path.parent().filter(|dir| !dir.as_os_str().is_empty());
+        vec![manifest_dir.map_or_else(|| PathBuf::from("."), Path::to_path_buf)]
+    } else {
+        vec![PathBuf::from(".")]
+    }
+}
+
+async fn deploy_to_host(args: DeployArgs, host: Option<&DeployHost>) -> Result<()> {
+    let dry_run = args.dry_run;
+    let ndjson = args.output == DeployOutput::Ndjson;
+    let ndjson_host = host.map(|host| host.url.as_str());
+    // Human-readable output would interleave with the JSON lines; the summary modes only
+    // report once the deploy is done
+    let quiet = args.summary_only || args.quiet_success || ndjson;
+    let target_paths = deploy_target_paths(&args);
+    // buster.yml is read from the first path
+    let target_path = &target_paths[0];
+    let mut progress = DeployProgress::new(0, quiet, args.quiet_success && !ndjson);
+    let mut result = DeployResult::default();
+
+    // Only create client if not in dry-run mode
+    // A plan is written against what's deployed, so dumping one needs the server too
+    let client = if !dry_run || args.dump_plan.is_some() {
+        // Create API client without explicit auth check
+        let creds = match host {
+            Some(host) => {
+                let api_key = host_api_key(host, |var| std::env::var(var).ok())?;
+                get_and_validate_buster_credentials_for_host(&host.url, api_key).await?
+            }
+            None => get_and_validate_buster_credentials().await?,
+        };
+        Some(BusterClient::new(creds.url, creds.api_key)?)
+    } else {
+        None
+    };
+
+    // Try to load buster.yml first
+    progress.status = "Looking for buster.yml configuration...".to_string();
+    progress.log_progress();
+
+    let mut config = match ModelFile::get_config(target_path) {
+        Ok(Some(config)) => {
+            if !quiet {
+                println!("✅ Found buster.yml configuration");
+                if let Some(ds) = &config.data_source_name {
+                    println!("   - Default data source: {}", ds);
+                }
+                if let Some(schema) = &config.schema {
+                    println!("   - Default schema: {}", schema);
+                }
+                if let Some(database) = &config.database {
+                    println!("   - Default database: {}", database);
+                }
+            }
+            Some(config)
+        }
+        Ok(None) => {
+            if !quiet {
+                println!("ℹ️  No buster.yml found, will require configuration in model files");
+            }
+            None
+        }
+        Err(e) => {
+            eprintln!("⚠️  Error reading buster.yml: {}", e);
+            None
+        }
+    };
+
+    // Stand in for buster.yml's default so models without a data source still validate
+    if let Some(data_source) = &args.data_source {
+        let config = config.get_or_insert(BusterConfig {
+            data_source_name: None,
+            schema: None,
+            database: None,
+            column_name_pattern: None,
+            description_min_length: None,
+            fail_on_markdown_issues: false,
+            hosts: None,
+            dataset_type: None,
+            allowed_dataset_types: None,
+            column_delete_mode: None,
+            env: None,
+        });
+        config.data_source_name = Some(data_source.clone());
+        if !quiet {
+            println!("🎯 Deploying every model to data source '{}'", data_source);
+        }
+    }
+
+    // Find all .yml files
+    progress.status = "Discovering model files...".to_string();
+    progress.log_progress();
+
+    // A plan replaces the model files entirely
+    let yml_files: Vec<PathBuf> = if args.apply_plan.is_some() {
+        Vec::new()
+    } else if let Some(manifest_path) = &args.models_from_manifest {
+        GenerateManifest::read(manifest_path)?.model_files(manifest_path)?
+    } else {
+        let (yml_files, ignored) = find_model_files(&target_paths)?;
+        result.ignored = ignored;
+        yml_files
+    };
+
+    if !quiet {
+        println!(
+            "Found {} model files in {}",
+            yml_files.len(),
+            target_paths
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        if result.ignored > 0 {
+            println!(
+                "🙈 Ignored {} model files matching {}",
+                result.ignored, BUSTER_IGNORE_FILE
+            );
+        }
+    }
+    progress.total_files = yml_files.len();
+
+    let mut deploy_requests = Vec::new();
+    let mut model_mappings = Vec::new();
+    let mut local_hashes = HashMap::new();
+    let mut pruned_count = 0;
+
+    progress.status = format!("Parsing model files ({} at a time)...", args.parallel.max(1));
+    progress.log_progress();
+
+    let loaded_files = load_model_files(yml_files, config.clone(), args.parallel).await;
+
+    // Process each file
+    for (yml_path, loaded) in loaded_files {
+        progress.processed += 1;
+        progress.current_file = yml_path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("unknown")
+            .to_string();
+
+        progress.status = "Loading model file...".to_string();
+        progress.log_progress();
 
         // Load and validate model
-        let model_file = match ModelFile::new(yml_path.clone(), config.clone()) {
+        let model_file = match loaded {
             Ok(mf) => mf,
             Err(e) => {
-                progress.log_error(&format!("Failed to load model: {}", e));
+                progress.log_error(&format!(
+                    "Failed to load model from {}: {}",
+                    yml_path.display(),
+                    e
+                ));
                 result.failures.push((
                     progress.current_file.clone(),
                     "unknown".to_string(),
-                    vec![format!("Failed to load model: {}", e)],
+                    vec![format!("Failed to load model from {}: {}", yml_path.display(), e)],
                 ));
                 continue;
             }
@@ -888,18 +2159,34 @@ pub async fn deploy_v2(path: Option<&str>, dry_run: bool) -> Result<()> {
         progress.status = "Validating model...".to_string();
         progress.log_progress();
 
-        if let Err(errors) = model_file.validate(config.as_ref()).await {
-            for error in &errors {
-                progress.log_error(error);
+        match model_file.validate(config.as_ref()).await {
+            Ok(warnings) => {
+                for warning in &warnings {
+                    progress.log_warning(warning);
+                }
+            }
+            Err(errors) => {
+                for error in &errors {
+                    progress.log_error(error);
+                }
+                result
+                    .failures
+                    .push((progress.current_file.clone(), "unknown".to_string(), errors));
+                continue;
             }
-            result
-                .failures
-                .push((progress.current_file.clone(), "unknown".to_string(), errors));
-            continue;
         }
 
+        let file_hash = match &args.lock {
+            Some(_) => Some(content_hash(&std::fs::read(&yml_path)?)),
+            None => None,
+        };
+
         // Process each model in the file
         for model in &model_file.model.models {
+            if let Some(hash) = &file_hash {
+                local_hashes.insert(model.name.clone(), hash.clone());
+            }
+
             let (data_source_name, schema, database) =
                 model_file.resolve_model_config(model, config.as_ref());
 
@@ -930,7 +2217,7 @@ pub async fn deploy_v2(path: Option<&str>, dry_run: bool) -> Result<()> {
             }
 
             // Get SQL content
-            let sql_content = match model_file.get_sql_content(model) {
+            let sql_content = match model_file.get_sql_content() {
                 Ok(content) => content,
                 Err(e) => {
                     progress.log_error(&format!("Failed to read SQL content: {}", e));
@@ -950,14 +2237,240 @@ pub async fn deploy_v2(path: Option<&str>, dry_run: bool) -> Result<()> {
             });
 
             // Create deploy request
-            deploy_requests.push(model_file.to_deploy_request(model, sql_content));
+            let mut request = model_file.to_deploy_request(model, sql_content);
+            if request.env.is_none() {
+                request.env = args
+                    .env
+                    .clone()
+                    .or_else(|| model_file.config.as_ref().and_then(|c| c.env.clone()));
+            }
+            request.dialect_autodetect = args.dialect_autodetect;
+            request.verify_grain = args.verify_grain;
+            request.strict_schema = args.strict_schema;
+            request.explain_matching = args.explain_matching;
+            request.fail_on_warning = args.fail_on_warning;
+            request.write_comments = args.write_comments;
+            request.lint_descriptions = args.lint_descriptions;
+            request.fail_on_markdown_issues =
+                config.as_ref().is_some_and(|c| c.fail_on_markdown_issues);
+            if let Some(mode) = args.column_delete_mode {
+                request.column_delete_mode = mode;
+            }
+            request.force = args.force;
+            request.only_new = args.only_new;
+            deploy_requests.push(request);
         }
 
         progress.log_success();
     }
 
+    if let Some(plan_path) = &args.apply_plan {
+        let plan = DeployPlan::read(plan_path)?;
+        if let Some(client) = &client {
+            let deployed = client.list_deployed_datasets(None, None, true).await?;
+            let changed = plan.changed_since(&deployed);
+            if !changed.is_empty() {
+                return Err(anyhow::anyhow!(
+                    "Deploy plan {} is out of date: {} changed on the server since it was \
+                     written. Write a new plan with --dry-run --dump-plan",
+                    plan_path.display(),
+                    changed.join(", ")
+                ));
+            }
+        }
+        if !quiet {
+            println!(
+                "📋 Applying {} models from plan {}",
+                plan.models.len(),
+                plan_path.display()
+            );
+        }
+        for request in plan.models {
+            model_mappings.push(ModelMapping {
+                file: plan_path.display().to_string(),
+                model_name: request.name.clone(),
+            });
+            deploy_requests.push(request);
+        }
+    }
+
+    if let Some(data_source) = &args.data_source {
+        for request in &mut deploy_requests {
+            request.data_source_name = data_source.clone();
+        }
+    }
+
+    for entry in exclude_columns(&mut deploy_requests, &args.exclude_columns)? {
+        eprintln!("⚠️  --exclude-column {} matches no column in this deploy", entry);
+    }
+    for warning in skip_dependent_columns(&mut deploy_requests) {
+        eprintln!("⚠️  {}", warning);
+    }
+    result.skipped_columns = deploy_requests
+        .iter()
+        .flat_map(|request| {
+            request
+                .skipped_columns
+                .iter()
+                .map(|column| format!("{}.{}", request.name, column))
+        })
+        .collect();
+    if args.prune_skipped {
+        for request in &mut deploy_requests {
+            request.skipped_columns.clear();
+        }
+    }
+
+    // The warehouse columns are read from, and the dataset records, this database instead
+    if let Some(database) = &args.database {
+        let overridden = override_database(&mut deploy_requests, database);
+        eprintln!(
+            "⚠️  --database '{}' applies to all {} models in this deploy ({} set a different database)",
+            database,
+            deploy_requests.len(),
+            overridden
+        );
+    }
+
+    // dbt-style ref() and source() calls become the tables they name
+    let deployed_datasets = match &client {
+        Some(client) if deploy_requests.iter().any(has_ref_calls) => {
+            client.list_deployed_datasets(None, None, false).await?
+        }
+        _ => Vec::new(),
+    };
+    let sources = if deploy_requests.iter().any(has_ref_calls) {
+        DbtSources::load(&deploy_target_paths(&args)[0])?
+    } else {
+        DbtSources::default()
+    };
+    let RefResolution {
+        failures: unresolved,
+        deferred,
+    } = resolve_refs(
+        &mut deploy_requests,
+        client.as_ref().map(|_| deployed_datasets.as_slice()),
+        |source, table| sources.table(source, table).map(str::to_string),
+    );
+    if !quiet {
+        for (model_name, call) in &deferred {
+            eprintln!("ℹ️  Model '{}': {} is resolved at deploy time", model_name, call);
+        }
+    }
+    if !unresolved.is_empty() {
+        for (model_name, reason) in &unresolved {
+            let file = model_mappings
+                .iter()
+                .find(|mapping| mapping.model_name == *model_name)
+                .map(|mapping| mapping.file.clone())
+                .unwrap_or_default();
+            if !quiet {
+                eprintln!("❌ Model '{}': {}", model_name, reason);
+            }
+            result
+                .failures
+                .push((file, model_name.clone(), vec![reason.clone()]));
+        }
+        deploy_requests.retain(|request| {
+            !unresolved
+                .iter()
+                .any(|(model_name, _)| *model_name == request.name)
+        });
+    }
+
+    let mut warnings = Vec::new();
+    if args.lint_names {
+        let pattern = config
+            .as_ref()
+            .and_then(|c| c.column_name_pattern.as_deref())
+            .unwrap_or(DEFAULT_COLUMN_NAME_PATTERN);
+
+        warnings.extend(lint_column_names(&deploy_requests, pattern)?);
+    }
+    if args.lint_descriptions {
+        let min_length = config
+            .as_ref()
+            .and_then(|c| c.description_min_length)
+            .unwrap_or(DEFAULT_DESCRIPTION_MIN_LENGTH);
+
+        warnings.extend(lint_descriptions(&deploy_requests, min_length));
+    }
+    if let Some(lock_path) = &args.lock {
+        warnings.extend(BusterLock::read(lock_path)?.drift(&local_hashes));
+    }
+    warnings.extend(lint_duplicate_exprs(&deploy_requests));
+
+    if !warnings.is_empty() {
+        if !quiet {
+            for (model_name, warning) in &warnings {
+                let label = match warning.severity() {
+                    ValidationSeverity::Warning => "⚠️  Warning",
+                    ValidationSeverity::Error => "❌ Error",
+                };
+                println!("{} for model '{}': {}", label, model_name, warning.message);
+            }
+        }
+
+        if args.fail_on_warning && !warnings.is_empty() {
+            for mapping in &model_mappings {
+                let messages: Vec<String> = warnings
+                    .iter()
+                    .filter(|(model_name, _)| *model_name == mapping.model_name)
+                    .map(|(_, warning)| warning.message.clone())
+                    .collect();
+                if !messages.is_empty() {
+                    result
+                        .failures
+                        .push((mapping.file.clone(), mapping.model_name.clone(), messages));
+                }
+            }
+            // Nothing is deployed when warnings are fatal
+            deploy_requests.clear();
+        }
+    }
+
+    if dry_run {
+        if let (Some(plan_path), Some(client)) = (&args.dump_plan, &client) {
+            let deployed = client.list_deployed_datasets(None, None, true).await?;
+            let plan = DeployPlan::new(deploy_requests, &deployed);
+            plan.write(plan_path)?;
+            println!(
+                "📝 Wrote deploy plan for {} models to {}",
+                plan.models.len(),
+                plan_path.display()
+            );
+            for change in &plan.changes {
+                let action = match change.action {
+                    PlannedAction::Create => "create",
+                    PlannedAction::Update => "update",
+                    PlannedAction::Unchanged => "unchanged",
+                };
+                if change.pruned_columns.is_empty() {
+                    println!("   - {}: {}", change.model_name, action);
+                } else {
+                    println!(
+                        "   - {}: {}, prunes {}",
+                        change.model_name,
+                        action,
+                        change.pruned_columns.join(", ")
+                    );
+                }
+            }
+            deploy_requests = plan.models;
+        }
+    }
+
     // Deploy to API if we have valid models and not in dry-run mode
     if !deploy_requests.is_empty() {
+        if dry_run && quiet {
+            println!(
+                "🔍 Dry run: {} models would be deployed, {} failed validation",
+                deploy_requests.len(),
+                result.failures.len()
+            );
+            return Ok(());
+        }
+
         if dry_run {
             println!("\n🔍 Dry run mode - validation successful!");
             println!("\n📦 Would deploy {} models:", deploy_requests.len());
@@ -965,7 +2478,8 @@ pub async fn deploy_v2(path: Option<&str>, dry_run: bool) -> Result<()> {
                 println!("   - Model: {} ", request.name);
                 println!(
                     "     Data Source: {} (env: {})",
-                    request.data_source_name, request.env
+                    request.data_source_name,
+                    request.env.as_deref().unwrap_or("default")
                 );
                 println!("     Schema: {}", request.schema);
                 if let Some(database) = &request.database {
@@ -975,12 +2489,25 @@ pub async fn deploy_v2(path: Option<&str>, dry_run: bool) -> Result<()> {
                 if let Some(rels) = &request.entity_relationships {
                     println!("     Relationships: {}", rels.len());
                 }
+                if !request.enabled {
+                    println!("     Disabled: hidden from users");
+                }
             }
             return Ok(());
         }
 
         let client =
             client.expect("BusterClient should be initialized for non-dry-run deployments");
+
+        // Nothing is written until the override is known to exist
+        if let Some(data_source) = &args.data_source {
+            progress.status = format!("Checking data source '{}'...", data_source);
+            progress.log_progress();
+
+            let data_sources = client.list_data_sources().await?;
+            check_data_source_envs(data_source, &deploy_requests, &data_sources)?;
+        }
+
         progress.status = "Deploying models to Buster...".to_string();
         progress.log_progress();
 
@@ -988,26 +2515,54 @@ pub async fn deploy_v2(path: Option<&str>, dry_run: bool) -> Result<()> {
         let data_source_name = deploy_requests[0].data_source_name.clone();
 
         // Log what we're trying to deploy
-        println!("\n📦 Deploying {} models:", deploy_requests.len());
-        for request in &deploy_requests {
-            println!("   - Model: {} ", request.name);
-            println!(
-                "     Data Source: {} (env: {})",
-                request.data_source_name, request.env
-            );
-            println!("     Schema: {}", request.schema);
-            if let Some(database) = &request.database {
-                println!("     Database: {}", database);
-            }
-            println!("     Columns: {}", request.columns.len());
-            if let Some(rels) = &request.entity_relationships {
-                println!("     Relationships: {}", rels.len());
+        if !quiet {
+            println!("\n📦 Deploying {} models:", deploy_requests.len());
+            for request in &deploy_requests {
+                println!("   - Model: {} ", request.name);
+                println!(
+                    "     Data Source: {} (env: {})",
+                    request.data_source_name,
+                    request.env.as_deref().unwrap_or("default")
+                );
+                println!("     Schema: {}", request.schema);
+                if let Some(database) = &request.database {
+                    println!("     Database: {}", database);
+                }
+                println!("     Columns: {}", request.columns.len());
+                if let Some(rels) = &request.entity_relationships {
+                    println!("     Relationships: {}", rels.len());
+                }
             }
         }
 
-        match client.deploy_datasets(deploy_requests).await {
+        // Results stream back as each data source group finishes
+        let total_models = deploy_requests.len();
+        let mut completed = 0;
+        let deploy_response = client
+            .deploy_datasets_streaming(deploy_requests, args.concurrency, |validation| {
+                completed += 1;
+                if ndjson {
+                    print_ndjson(&NdjsonEvent::Result(validation), ndjson_host);
+                } else if !quiet {
+                    let status = if validation.success { "✅" } else { "❌" };
+                    println!(
+                        "   [{}/{}] {} {}",
+                        completed, total_models, status, validation.model_name
+                    );
+                }
+            })
+            .await;
+
+        match deploy_response {
             Ok(response) => {
                 let mut has_validation_errors = false;
+                pruned_count = response.summary.as_ref().map_or(0, |s| s.pruned_count);
+                if let Some(summary) = &response.summary {
+                    result.enabled = summary.enabled.clone();
+                    result.disabled = summary.disabled.clone();
+                    result.skipped = summary.skipped.clone();
+                    result.timings = summary.timings.clone();
+                }
 
                 // Process validation results
                 for validation in &response.results {
@@ -1018,12 +2573,21 @@ pub async fn deploy_v2(path: Option<&str>, dry_run: bool) -> Result<()> {
                         .map(|m| m.file.clone())
                         .unwrap_or_else(|| "unknown".to_string());
 
+                    // Existing models `--only-new` left untouched are reported as skipped
+                    if validation.success && result.skipped.contains(&validation.model_name) {
+                        continue;
+                    }
                     if validation.success {
                         progress.log_validation_success(validation);
                         result.success.push((
                             file,
                             validation.model_name.clone(),
                             validation.data_source_name.clone(),
+                            validation
+                                .warnings
+                                .iter()
+                                .map(|w| w.message.clone())
+                                .collect(),
                         ));
                     } else {
                         has_validation_errors = true;
@@ -1042,12 +2606,13 @@ pub async fn deploy_v2(path: Option<&str>, dry_run: bool) -> Result<()> {
                     }
                 }
 
-                if has_validation_errors {
+                // In summary-only mode the failures are reported by the summary below
+                if has_validation_errors && !quiet {
                     println!("\n❌ Deployment failed due to validation errors!");
                     println!("\n💡 Troubleshooting:");
                     println!("1. Check data source:");
                     println!("   - Verify '{}' exists in Buster", data_source_name);
-                    println!("   - Confirm it has env='dev'");
+                    println!("   - Confirm its env matches the models' env");
                     println!("   - Check your access permissions");
                     println!("2. Check model definitions:");
                     println!("   - Validate SQL syntax");
@@ -1060,7 +2625,26 @@ pub async fn deploy_v2(path: Option<&str>, dry_run: bool) -> Result<()> {
                     ));
                 }
 
-                println!("\n✅ All models deployed successfully!");
+                if !quiet && !has_validation_errors {
+                    println!("\n✅ All models deployed successfully!");
+                }
+            }
+            Err(e) if !ndjson && is_incompatible_cli(&e) => {
+                println!("\n❌ This CLI version can't deploy to this Buster server");
+                return Err(e);
+            }
+            Err(e) if ndjson => {
+                print_ndjson(
+                    &NdjsonEvent::Error {
+                        message: e.to_string(),
+                    },
+                    ndjson_host,
+                );
+                return Err(anyhow::anyhow!("Failed to deploy models to Buster: {}", e));
+            }
+            Err(e) if quiet => {
+                println!("❌ Deployment failed: {}", e);
+                return Err(anyhow::anyhow!("Failed to deploy models to Buster: {}", e));
             }
             Err(e) => {
                 println!("\n❌ Deployment failed!");
@@ -1068,7 +2652,7 @@ pub async fn deploy_v2(path: Option<&str>, dry_run: bool) -> Result<()> {
                 println!("\n💡 Troubleshooting:");
                 println!("1. Check data source:");
                 println!("   - Verify '{}' exists in Buster", data_source_name);
-                println!("   - Confirm it has env='dev'");
+                println!("   - Confirm its env matches the models' env");
                 println!("   - Check your access permissions");
                 println!("2. Check model definitions:");
                 println!("   - Validate SQL syntax");
@@ -1081,19 +2665,89 @@ pub async fn deploy_v2(path: Option<&str>, dry_run: bool) -> Result<()> {
         }
     }
 
-    // Print final summary with more details
-    println!("\n📊 Deployment Summary");
-    println!("==================");
-    println!("✅ Successfully deployed: {} models", result.success.len());
-    if !result.success.is_empty() {
-        println!("\nSuccessful deployments:");
-        for (file, model_name, data_source) in &result.success {
-            println!(
-                "   - {} (Model: {}, Data Source: {})",
+    if ndjson {
+        let summary = DeploymentSummary {
+            total_models: result.success.len() + result.failures.len(),
+            successful_models: result.success.len(),
+            failed_models: result.failures.len(),
+            pruned_count,
+            enabled: result.enabled.clone(),
+            disabled: result.disabled.clone(),
+            skipped: result.skipped.clone(),
+            skipped_columns: result.skipped_columns.clone(),
+            timings: result.timings.clone(),
+        };
+        print_ndjson(&NdjsonEvent::Summary(&summary), ndjson_host);
+        if !result.failures.is_empty() {
+            return Err(anyhow::anyhow!("Some models failed to deploy"));
+        }
+        return Ok(());
+    }
+
+    if args.quiet_success && result.failures.is_empty() {
+        println!("✅ All {} models deployed successfully", result.success.len());
+        if !result.skipped_columns.is_empty() {
+            println!("⏭️  Skipped columns: {}", result.skipped_columns.join(", "));
+        }
+        if args.timings {
+            print_timings(result.timings.as_ref());
+        }
+        return Ok(());
+    }
+
+    if args.summary_only {
+        print_summary_only(&result, pruned_count);
+        if args.timings {
+            print_timings(result.timings.as_ref());
+        }
+        if !result.failures.is_empty() {
+            return Err(anyhow::anyhow!("Some models failed to deploy"));
+        }
+        return Ok(());
+    }
+
+    // Print final summary with more details
+    println!("\n📊 Deployment Summary");
+    println!("==================");
+    println!("✅ Successfully deployed: {} models", result.success.len());
+    if !result.success.is_empty() {
+        println!("\nSuccessful deployments:");
+        for (file, model_name, data_source, warnings) in &result.success {
+            println!(
+                "   - {} (Model: {}, Data Source: {})",
                 file, model_name, data_source
             );
+            for warning in warnings {
+                println!("{}", format!("     ⚠️  {}", warning).yellow());
+            }
         }
     }
+    if !result.enabled.is_empty() {
+        println!("\n🔔 Enabled: {}", result.enabled.join(", "));
+    }
+    if !result.disabled.is_empty() {
+        println!("\n🔕 Disabled: {}", result.disabled.join(", "));
+    }
+    if !result.skipped.is_empty() {
+        println!("\n⏭️  Skipped (exists): {}", result.skipped.join(", "));
+    }
+    if !result.skipped_columns.is_empty() {
+        println!(
+            "\n⏭️  Skipped columns ({}): {}",
+            if args.prune_skipped { "pruned" } else { "kept as deployed" },
+            result.skipped_columns.join(", ")
+        );
+    }
+    if result.ignored > 0 {
+        println!(
+            "\n🙈 Ignored: {} model files matching {}",
+            result.ignored, BUSTER_IGNORE_FILE
+        );
+    }
+    if args.timings {
+        println!();
+        print_timings(result.timings.as_ref());
+    }
 
     if !result.failures.is_empty() {
         println!("\n❌ Failed deployments: {} models", result.failures.len());
@@ -1112,9 +2766,275 @@ pub async fn deploy_v2(path: Option<&str>, dry_run: bool) -> Result<()> {
     Ok(())
 }
 
+/// Moves the columns named by `--exclude-column` entries (`model.column`) out of their
+/// requests and into `skipped_columns`. Returns the entries that matched no column.
+fn exclude_columns(
+    requests: &mut [DeployDatasetsRequest],
+    excluded: &[String],
+) -> Result<Vec<String>> {
+    let mut unmatched = Vec::new();
+    for entry in excluded {
+        let (model_name, column_name) = entry.split_once('.').ok_or_else(|| {
+            anyhow::anyhow!("--exclude-column '{}' must be model.column", entry)
+        })?;
+        let request = requests
+            .iter_mut()
+            .find(|request| request.name == model_name);
+        let position = request.as_ref().and_then(|request| {
+            request
+                .columns
+                .iter()
+                .position(|col| col.name == column_name)
+        });
+        match (request, position) {
+            (Some(request), Some(position)) => {
+                request.columns.remove(position);
+                request.skipped_columns.push(column_name.to_string());
+            }
+            _ => unmatched.push(entry.clone()),
+        }
+    }
+    Ok(unmatched)
+}
+
+/// Skips the ratio measures that divide a skipped measure, since the server can't build
+/// them without it. Returns a warning for each, and for each entity whose `expr` names a
+/// skipped column.
+fn skip_dependent_columns(requests: &mut [DeployDatasetsRequest]) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for request in requests.iter_mut() {
+        let skipped = request.skipped_columns.clone();
+        let is_skipped = |name: &str| skipped.iter().any(|column| column == name);
+
+        for col in std::mem::take(&mut request.columns) {
+            let skipped_input = col.ratio.as_ref().and_then(|ratio| {
+                [&ratio.numerator, &ratio.denominator]
+                    .into_iter()
+                    .find(|name| is_skipped(name))
+                    .cloned()
+            });
+            match skipped_input {
+                Some(input) => {
+                    warnings.push(format!(
+                        "Ratio measure '{}' in model '{}' is skipped because it divides skipped measure '{}'",
+                        col.name, request.name, input
+                    ));
+                    request.skipped_columns.push(col.name);
+                }
+                None => request.columns.push(col),
+            }
+        }
+
+        for entity in request.entity_relationships.iter().flatten() {
+            if is_skipped(entity.expr.trim()) {
+                warnings.push(format!(
+                    "Entity '{}' in model '{}' reads skipped column '{}'",
+                    entity.name, request.name, entity.expr
+                ));
+            }
+        }
+    }
+    warnings
+}
+
+/// Checks every column name in the deploy requests against `pattern`, returning
+/// a warning per offending column along with the model it belongs to.
+fn lint_column_names(
+    requests: &[DeployDatasetsRequest],
+    pattern: &str,
+) -> Result<Vec<(String, ValidationError)>> {
+    let re = Regex::new(pattern)
+        .map_err(|e| anyhow::anyhow!("Invalid column_name_pattern '{}': {}", pattern, e))?;
+
+    Ok(requests
+        .iter()
+        .flat_map(|request| {
+            request
+                .columns
+                .iter()
+                .filter(|col| !re.is_match(&col.name))
+                .map(|col| {
+                    (
+                        request.name.clone(),
+                        ValidationError::name_convention(&col.name, pattern),
+                    )
+                })
+        })
+        .collect())
+}
+
+/// Flags model and column descriptions that are empty, shorter than `min_length`
+/// characters, or just repeat the name.
+fn lint_descriptions(
+    requests: &[DeployDatasetsRequest],
+    min_length: usize,
+) -> Vec<(String, ValidationError)> {
+    let weakness = |name: &str, description: &str| {
+        let description = description.trim();
+        if description.is_empty() {
+            Some("is empty".to_string())
+        } else if description.replace([' ', '_'], "").eq_ignore_ascii_case(&name.replace([' ', '_'], "")) {
+            Some("only repeats the name".to_string())
+        } else if description.chars().count() < min_length {
+            Some(format!("is shorter than {} characters", min_length))
+        } else {
+            None
+        }
+    };
+
+    let mut warnings = Vec::new();
+    for request in requests {
+        if let Some(reason) = weakness(&request.name, &request.description) {
+            warnings.push((
+                request.name.clone(),
+                ValidationError::weak_description(None, &request.name, &reason),
+            ));
+        }
+        for col in &request.columns {
+            if let Some(reason) = weakness(&col.name, &col.description) {
+                warnings.push((
+                    request.name.clone(),
+                    ValidationError::weak_description(Some(&col.name), &request.name, &reason),
+                ));
+            }
+        }
+    }
+
+    warnings
+}
+
+/// Flags measures aggregating the same expression the same way, and dimensions reading the
+/// same expression, within each model. Expressions are compared ignoring whitespace; a
+/// measure's filter and percentile are part of what it computes.
+fn lint_duplicate_exprs(requests: &[DeployDatasetsRequest]) -> Vec<(String, ValidationError)> {
+    let normalize = |expr: &str| expr.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    let mut warnings = Vec::new();
+    for request in requests {
+        // (kind, expression, aggregation, field names) in the order fields are declared
+        let mut groups: Vec<(&str, String, String, Vec<&str>)> = Vec::new();
+        for col in &request.columns {
+            let expr = normalize(col.expr.as_deref().unwrap_or(&col.name));
+            if expr.is_empty() {
+                continue;
+            }
+            let (kind, aggregation) = match col.semantic_type.as_deref() {
+                Some("measure") => (
+                    "Measures",
+                    format!(
+                        "{}|{}|{}",
+                        col.agg.as_deref().unwrap_or_default().to_lowercase(),
+                        col.filters.as_deref().map(normalize).unwrap_or_default(),
+                        col.percentile.map(|p| p.to_string()).unwrap_or_default()
+                    ),
+                ),
+                _ => ("Dimensions", String::new()),
+            };
+            match groups
+                .iter_mut()
+                .find(|(k, e, a, _)| *k == kind && *e == expr && *a == aggregation)
+            {
+                Some((.., names)) => names.push(&col.name),
+                None => groups.push((kind, expr, aggregation, vec![&col.name])),
+            }
+        }
+
+        for (kind, expr, _, names) in groups.iter().filter(|group| group.3.len() > 1) {
+            warnings.push((
+                request.name.clone(),
+                ValidationError::duplicate_expression(kind, expr, names),
+            ));
+        }
+    }
+
+    warnings
+}
+
+/// Suggests the closest table the server found for a missing one, and lists the rest.
+fn similar_tables_hint(error: &ValidationError) -> Option<String> {
+    let (closest, others) = error.context.as_ref()?.similar_tables.split_first()?;
+    let mut hint = format!("Did you mean '{}'?", closest);
+    if !others.is_empty() {
+        hint.push_str(&format!(" Also similar: {}", others.join(", ")));
+    }
+    Some(hint)
+}
+
+fn print_timings(timings: Option<&DeployTimings>) {
+    match timings {
+        Some(timings) => print!("{}", format_timings(timings)),
+        None => println!("⏱️  This Buster server doesn't report deploy timings"),
+    }
+}
+
+/// The `--timings` report: the slowest models, then each data source group's fetch and
+/// write time.
+fn format_timings(timings: &DeployTimings) -> String {
+    let seconds = |ms: u64| format!("{:.2}s", ms as f64 / 1000.0);
+    let mut report = String::from("⏱️  Slowest models:\n");
+    for model in &timings.slowest_models {
+        report.push_str(&format!(
+            "   - {} ({}): {}\n",
+            model.model_name,
+            model.data_source_name,
+            seconds(model.duration_ms)
+        ));
+    }
+    report.push_str("⏱️  Data source groups:\n");
+    for group in &timings.groups {
+        let data_source = match &group.database {
+            Some(database) => format!("{}/{}", group.data_source_name, database),
+            None => group.data_source_name.clone(),
+        };
+        report.push_str(&format!(
+            "   - {}: {} models, fetch {}, write {}\n",
+            data_source,
+            group.models,
+            seconds(group.fetch_ms),
+            seconds(group.write_ms)
+        ));
+    }
+    report
+}
+
+/// Terse summary for CI logs: the counts on one line, then one line per failed model.
+fn print_summary_only(result: &DeployResult, pruned_count: usize) {
+    println!(
+        "📊 Deployment Summary: total {}, successful {}, failed {}, pruned {}",
+        result.success.len() + result.failures.len(),
+        result.success.len(),
+        result.failures.len(),
+        pruned_count
+    );
+    if result.ignored > 0 {
+        println!("🙈 {} model files ignored by {}", result.ignored, BUSTER_IGNORE_FILE);
+    }
+    for model_name in &result.enabled {
+        println!("🔔 {} enabled", model_name);
+    }
+    for model_name in &result.disabled {
+        println!("🔕 {} disabled", model_name);
+    }
+    for model_name in &result.skipped {
+        println!("⏭️  {} skipped (exists)", model_name);
+    }
+    for column in &result.skipped_columns {
+        println!("⏭️  {} column skipped", column);
+    }
+    for (_, model_name, _, warnings) in &result.success {
+        for warning in warnings {
+            println!("{}", format!("⚠️  {}: {}", model_name, warning).yellow());
+        }
+    }
+    for (file, model_name, errors) in &result.failures {
+        println!("❌ {} ({}): {}", model_name, file, errors.join("; "));
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::{DeployStreamEvent, ValidationErrorContext};
     use anyhow::Result;
     use std::fs;
     use tempfile::TempDir;
@@ -1171,12 +3091,110 @@ mod tests {
         create_test_yaml(temp_dir.path(), "test_model.yml", model_yml).await?;
 
         // Test dry run
-        let result = deploy_v2(Some(temp_dir.path().to_str().unwrap()), true).await;
+        let result = deploy_v2(DeployArgs::new(Some(temp_dir.path().to_str().unwrap()), true)).await;
         assert!(result.is_ok());
 
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_deploy_plan() -> Result<()> {
+        let temp_dir = setup_test_dir().await?;
+
+        let model_yml = r#"
+            version: 1
+            models:
+              - name: orders
+                description: "Test model"
+                data_source_name: "test_source"
+                schema: "test_schema"
+                dimensions:
+                  - name: status
+                    expr: "status"
+                    type: "string"
+                    description: "Fulfillment state"
+              - name: customers
+                description: "Test model"
+                data_source_name: "test_source"
+                schema: "test_schema"
+                dimensions: []
+        "#;
+        let yml_path = create_test_yaml(temp_dir.path(), "models.yml", model_yml).await?;
+        let model_file = ModelFile::new(yml_path, None)?;
+        let requests = || -> Vec<DeployDatasetsRequest> {
+            model_file
+                .model
+                .models
+                .iter()
+                .map(|model| model_file.to_deploy_request(model, None))
+                .collect()
+        };
+
+        let deployed_orders = r#"
+            models:
+              - name: orders
+                description: "Test model"
+                dimensions:
+                  - name: status
+                    expr: "status"
+                  - name: legacy_code
+                    expr: "code"
+        "#;
+        let dataset = |name: &str, yml_file: &str| DeployedDataset {
+            id: uuid::Uuid::new_v4(),
+            name: name.to_string(),
+            database_name: name.to_string(),
+            schema: "test_schema".to_string(),
+            data_source_name: "test_source".to_string(),
+            type_: "view".to_string(),
+            enabled: true,
+            column_count: 2,
+            updated_at: "2024-01-01T00:00:00Z".to_string(),
+            yml_file: Some(yml_file.to_string()),
+        };
+        let mut deployed = vec![dataset("orders", deployed_orders)];
+
+        let plan = DeployPlan::new(requests(), &deployed);
+        assert_eq!(
+            plan.changes,
+            vec![
+                PlannedChange {
+                    model_name: "orders".to_string(),
+                    data_source_name: "test_source".to_string(),
+                    action: PlannedAction::Update,
+                    deployed_hash: Some(content_hash(deployed_orders.as_bytes())),
+                    pruned_columns: vec!["legacy_code".to_string()],
+                },
+                PlannedChange {
+                    model_name: "customers".to_string(),
+                    data_source_name: "test_source".to_string(),
+                    action: PlannedAction::Create,
+                    deployed_hash: None,
+                    pruned_columns: vec![],
+                },
+            ]
+        );
+
+        let plan_path = temp_dir.path().join("plan.json");
+        plan.write(&plan_path)?;
+        let plan = DeployPlan::read(&plan_path)?;
+        assert_eq!(plan.models.len(), 2);
+        assert_eq!(plan.models[0].columns.len(), 1);
+        assert!(plan.changed_since(&deployed).is_empty());
+
+        // Anything deployed after the plan was written makes it stale
+        deployed[0].yml_file = requests()[0].yml_file.clone();
+        deployed.push(dataset("customers", ""));
+        assert_eq!(plan.changed_since(&deployed), vec!["orders", "customers"]);
+
+        // Deploying the same model file again changes nothing
+        let replanned = DeployPlan::new(requests(), &deployed);
+        assert_eq!(replanned.changes[0].action, PlannedAction::Unchanged);
+        assert!(replanned.changes[0].pruned_columns.is_empty());
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_deploy_cross_project_references() -> Result<()> {
         let temp_dir = setup_test_dir().await?;
@@ -1215,7 +3233,7 @@ mod tests {
         create_test_yaml(temp_dir.path(), "test_model.yml", model_yml).await?;
 
         // Test dry run
-        let result = deploy_v2(Some(temp_dir.path().to_str().unwrap()), true).await;
+        let result = deploy_v2(DeployArgs::new(Some(temp_dir.path().to_str().unwrap()), true)).await;
         assert!(result.is_ok());
 
         Ok(())
@@ -1259,7 +3277,7 @@ mod tests {
         create_test_yaml(temp_dir.path(), "test_model.yml", model_yml).await?;
 
         // Test dry run - should fail due to data source mismatch
-        let result = deploy_v2(Some(temp_dir.path().to_str().unwrap()), true).await;
+        let result = deploy_v2(DeployArgs::new(Some(temp_dir.path().to_str().unwrap()), true)).await;
         assert!(result.is_err());
 
         Ok(())
@@ -1294,7 +3312,7 @@ mod tests {
         create_test_yaml(temp_dir.path(), "test_model.yml", model_yml).await?;
 
         // Test dry run - should fail due to missing project
-        let result = deploy_v2(Some(temp_dir.path().to_str().unwrap()), true).await;
+        let result = deploy_v2(DeployArgs::new(Some(temp_dir.path().to_str().unwrap()), true)).await;
         assert!(result.is_err());
 
         Ok(())
@@ -1342,7 +3360,7 @@ mod tests {
         }
 
         // Test dry run
-        let result = deploy_v2(Some(temp_dir.path().to_str().unwrap()), true).await;
+        let result = deploy_v2(DeployArgs::new(Some(temp_dir.path().to_str().unwrap()), true)).await;
         assert!(result.is_ok());
 
         Ok(())
@@ -1364,7 +3382,7 @@ mod tests {
         create_test_yaml(temp_dir.path(), "invalid_model.yml", invalid_yml).await?;
 
         // Test dry run - should fail due to invalid YAML
-        let result = deploy_v2(Some(temp_dir.path().to_str().unwrap()), true).await;
+        let result = deploy_v2(DeployArgs::new(Some(temp_dir.path().to_str().unwrap()), true)).await;
         assert!(result.is_err());
 
         Ok(())
@@ -1416,7 +3434,7 @@ mod tests {
         create_test_yaml(temp_dir.path(), "test_model.yml", model_yml).await?;
 
         // Test dry run - should succeed because actual_model exists
-        let result = deploy_v2(Some(temp_dir.path().to_str().unwrap()), true).await;
+        let result = deploy_v2(DeployArgs::new(Some(temp_dir.path().to_str().unwrap()), true)).await;
         assert!(result.is_ok());
 
         Ok(())
@@ -1451,9 +3469,1107 @@ mod tests {
         create_test_yaml(temp_dir.path(), "test_model.yml", model_yml).await?;
 
         // Test dry run - should fail because referenced model doesn't exist
-        let result = deploy_v2(Some(temp_dir.path().to_str().unwrap()), true).await;
+        let result = deploy_v2(DeployArgs::new(Some(temp_dir.path().to_str().unwrap()), true)).await;
         assert!(result.is_err());
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_load_model_files_reports_each_bad_file() -> Result<()> {
+        let temp_dir = setup_test_dir().await?;
+
+        let valid_yml = r#"
+            version: 1
+            models:
+              - name: good_model
+                description: "Good model"
+        "#;
+        let good = create_test_yaml(temp_dir.path(), "good.yml", valid_yml).await?;
+        let bad_one = create_test_yaml(temp_dir.path(), "bad_one.yml", "models: : :").await?;
+        let bad_two = create_test_yaml(temp_dir.path(), "bad_two.yml", "not: [valid").await?;
+
+        let loaded = load_model_files(
+            vec![bad_one.clone(), good.clone(), bad_two.clone()],
+            None,
+            2,
+        )
+        .await;
+
+        // Order is preserved and every file gets its own result
+        let paths: Vec<_> = loaded.iter().map(|(p, _)| p.clone()).collect();
+        assert_eq!(paths, vec![bad_one, good, bad_two]);
+        assert!(loaded[0].1.is_err());
+        assert!(loaded[1].1.is_ok());
+        assert!(loaded[2].1.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_ratio_measures() -> Result<()> {
+        let yml = r#"
+            version: 1
+            models:
+              - name: orders
+                description: "Orders"
+                data_source_name: "warehouse"
+                schema: "public"
+                measures:
+                  - name: revenue
+                    expr: "amount"
+                    agg: "sum"
+                    description: "Total revenue"
+                  - name: order_count
+                    expr: "id"
+                    agg: "count"
+                    description: "Number of orders"
+                  - name: avg_order_value
+                    description: "Revenue per order"
+                    ratio:
+                      numerator: revenue
+                      denominator: order_count
+                  - name: bad_agg
+                    agg: "sum"
+                    description: "Ratio with a row-level agg"
+                    ratio:
+                      numerator: revenue
+                      denominator: order_count
+                  - name: bad_ref
+                    description: "Ratio with a missing measure"
+                    ratio:
+                      numerator: revenue
+                      denominator: refunds
+        "#;
+        let model: BusterModel = serde_yaml::from_str(yml)?;
+        let model = &model.models[0];
+
+        let errors = ModelFile::validate_measures(model);
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].contains("bad_agg") && errors[0].contains("row-level agg 'sum'"));
+        assert!(errors[1].contains("bad_ref") && errors[1].contains("unknown measure 'refunds'"));
+
+        // The server builds the ratio's expression from the measures it names
+        let model_file = ModelFile {
+            yml_path: PathBuf::from("orders.yml"),
+            sql_path: None,
+            model: serde_yaml::from_str(yml)?,
+            config: None,
+        };
+        let request = model_file.to_deploy_request(model, None);
+        let column = &request.columns[2];
+        assert_eq!(column.agg.as_deref(), Some("ratio"));
+        assert!(column.expr.is_none());
+        let ratio = column.ratio.as_ref().unwrap();
+        assert_eq!((ratio.numerator.as_str(), ratio.denominator.as_str()), ("revenue", "order_count"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_percentile_measures() -> Result<()> {
+        let yml = r#"
+            version: 1
+            models:
+              - name: requests
+                description: "Requests"
+                measures:
+                  - name: p90_latency
+                    expr: "latency_ms"
+                    agg: "percentile"
+                    percentile: 0.9
+                    description: "p90 latency"
+                  - name: missing_value
+                    expr: "latency_ms"
+                    agg: "percentile"
+                    description: "No percentile given"
+                  - name: out_of_range
+                    expr: "latency_ms"
+                    agg: "percentile"
+                    percentile: 90
+                    description: "Percent instead of fraction"
+                  - name: wrong_agg
+                    expr: "latency_ms"
+                    agg: "avg"
+                    percentile: 0.5
+                    description: "Percentile on a non-percentile agg"
+        "#;
+        let model: BusterModel = serde_yaml::from_str(yml)?;
+
+        let errors = ModelFile::validate_measures(&model.models[0]);
+        assert_eq!(errors.len(), 3);
+        assert!(errors[0].contains("missing_value"));
+        assert!(errors[1].contains("out_of_range") && errors[1].contains("(0, 1]"));
+        assert!(errors[2].contains("wrong_agg"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_measure_filters() -> Result<()> {
+        let yml = r#"
+            version: 1
+            models:
+              - name: orders
+                description: "Orders"
+                measures:
+                  - name: completed_revenue
+                    expr: "amount"
+                    agg: "sum"
+                    filters: "status = 'completed'"
+                    description: "Revenue from completed orders"
+                  - name: order_count
+                    expr: "id"
+                    agg: "count"
+                    description: "Number of orders"
+                  - name: completed_share
+                    description: "Completed revenue per order"
+                    filters: "status = 'completed'"
+                    ratio:
+                      numerator: completed_revenue
+                      denominator: order_count
+                  - name: blank_filter
+                    expr: "amount"
+                    agg: "sum"
+                    filters: " "
+                    description: "Filter with no expression"
+        "#;
+        let model: BusterModel = serde_yaml::from_str(yml)?;
+        let model = &model.models[0];
+
+        let errors = ModelFile::validate_measures(model);
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].contains("completed_share") && errors[0].contains("cannot define filters"));
+        assert!(errors[1].contains("blank_filter") && errors[1].contains("empty filters"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_primary_time_dimension() -> Result<()> {
+        let yml = r#"
+            version: 1
+            models:
+              - name: orders
+                description: "Orders"
+                dimensions:
+                  - name: created_at
+                    expr: "created_at"
+                    type: "timestamp"
+                    description: "When the order was placed"
+                  - name: status
+                    expr: "status"
+                    type: "string"
+                    description: "Order status"
+              - name: shipments
+                description: "Shipments"
+                primary_time_dimension: status
+                dimensions:
+                  - name: shipped_at
+                    expr: "shipped_at"
+                    type: "timestamp"
+                    description: "When the shipment left"
+                  - name: delivered_on
+                    expr: "delivered_on"
+                    type: "date"
+                    description: "When the shipment arrived"
+                  - name: status
+                    expr: "status"
+                    type: "string"
+                    description: "Shipment status"
+        "#;
+        let model: BusterModel = serde_yaml::from_str(yml)?;
+        let (orders, shipments) = (&model.models[0], &model.models[1]);
+
+        // A single timestamp dimension is used without being designated
+        assert!(ModelFile::validate_primary_time_dimension(orders).is_none());
+        assert_eq!(orders.primary_time_dimension().as_deref(), Some("created_at"));
+
+        let error = ModelFile::validate_primary_time_dimension(shipments).unwrap();
+        assert!(error.contains("'status' of type 'string'"));
+        assert_eq!(shipments.time_dimensions().count(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generated_model_round_trips_through_deploy() -> Result<()> {
+        // Shaped like the API's generate output after a --strip-prefix rename
+        let generated = r#"
+models:
+- name: customer
+  label: Customer
+  database_name: dim_customer
+  description: Generated model for dim_customer
+  dimensions:
+  - name: region
+    label: Region
+    expr: region
+    type: string
+    description: Customer region
+    searchable: true
+  - name: customer_id
+    expr: customer_id
+    type: string
+    description: Surrogate key
+    hidden: true
+  measures:
+  - name: lifetime_value
+    expr: lifetime_value
+    type: number
+    agg: sum
+    description: Total spend
+"#;
+
+        // Generate merges into existing files through its own model structs
+        let merged: crate::utils::yaml_diff_merger::YamlFile = serde_yaml::from_str(generated)?;
+        let yml = serde_yaml::to_string(&merged)?;
+
+        let model: BusterModel = serde_yaml::from_str(&yml)?;
+        let model_file = ModelFile {
+            yml_path: PathBuf::from("customer.yml"),
+            sql_path: None,
+            model,
+            config: Some(BusterConfig {
+                data_source_name: Some("warehouse".to_string()),
+                schema: Some("analytics".to_string()),
+                database: None,
+                column_name_pattern: None,
+                description_min_length: None,
+                fail_on_markdown_issues: false,
+                hosts: None,
+                dataset_type: None,
+                allowed_dataset_types: None,
+                column_delete_mode: None,
+                env: None,
+            }),
+        };
+        let model = &model_file.model.models[0];
+        let request = model_file.to_deploy_request(model, None);
+
+        assert_eq!(request.name, "customer");
+        assert_eq!(request.label.as_deref(), Some("Customer"));
+        assert_eq!(request.database_name.as_deref(), Some("dim_customer"));
+        assert_eq!(request.description, "Generated model for dim_customer");
+        // Without a SQL file the server builds the definition for the warehouse's dialect
+        assert_eq!(request.sql_definition, None);
+
+        let region = &request.columns[0];
+        assert_eq!(region.name, "region");
+        assert_eq!(region.label.as_deref(), Some("Region"));
+        assert_eq!(region.expr.as_deref(), Some("region"));
+        assert_eq!(region.type_.as_deref(), Some("string"));
+        assert_eq!(region.description, "Customer region");
+        assert!(region.searchable);
+        assert!(!region.hidden);
+        assert!(request.columns[1].hidden);
+
+        let lifetime_value = &request.columns[2];
+        assert_eq!(lifetime_value.name, "lifetime_value");
+        assert_eq!(lifetime_value.expr.as_deref(), Some("lifetime_value"));
+        assert_eq!(lifetime_value.type_.as_deref(), Some("number"));
+        assert_eq!(lifetime_value.agg.as_deref(), Some("sum"));
+        assert_eq!(lifetime_value.description, "Total spend");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_model_enabled_flag() -> Result<()> {
+        let yml = r#"
+            version: 1
+            models:
+              - name: orders
+                description: "Orders"
+              - name: legacy_orders
+                description: "Orders from the old system"
+                enabled: false
+        "#;
+        let model_file = ModelFile {
+            yml_path: PathBuf::from("orders.yml"),
+            sql_path: None,
+            model: serde_yaml::from_str(yml)?,
+            config: Some(BusterConfig {
+                data_source_name: Some("warehouse".to_string()),
+                schema: Some("analytics".to_string()),
+                database: None,
+                column_name_pattern: None,
+                description_min_length: None,
+                fail_on_markdown_issues: false,
+                hosts: None,
+                dataset_type: None,
+                allowed_dataset_types: None,
+                column_delete_mode: None,
+                env: None,
+            }),
+        };
+
+        let requests: Vec<_> = model_file
+            .model
+            .models
+            .iter()
+            .map(|model| model_file.to_deploy_request(model, None))
+            .collect();
+        assert!(requests[0].enabled);
+        assert!(!requests[1].enabled);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_grain() -> Result<()> {
+        let yml = r#"
+            version: 1
+            models:
+              - name: order_lines
+                description: "Order lines"
+                grain: [order, line_number, line_number, sku]
+                entities:
+                  - name: order
+                    expr: "order_id"
+                    type: "foreign"
+                    description: "Order the line belongs to"
+                dimensions:
+                  - name: line_number
+                    expr: "line_number"
+                    type: "integer"
+                    description: "Position of the line in the order"
+        "#;
+        let model: BusterModel = serde_yaml::from_str(yml)?;
+        let order_lines = &model.models[0];
+
+        let errors = ModelFile::validate_grain(order_lines);
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].contains("lists 'line_number' more than once"));
+        assert!(errors[1].contains("grain component 'sku'"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_refresh_schedule() -> Result<()> {
+        let yml = r#"
+            version: 1
+            models:
+              - name: daily_orders
+                description: "Daily orders"
+                refresh:
+                  cron: "0 6 * * *"
+              - name: paused_orders
+                description: "Paused orders"
+                refresh:
+                  cron: "*/15 * * * *"
+                  enabled: false
+              - name: broken_orders
+                description: "Broken orders"
+                refresh:
+                  cron: "0 25 * * *"
+        "#;
+        let model: BusterModel = serde_yaml::from_str(yml)?;
+
+        assert!(ModelFile::validate_refresh(&model.models[0]).is_none());
+        assert!(model.models[0].refresh.as_ref().unwrap().enabled);
+        assert!(ModelFile::validate_refresh(&model.models[1]).is_none());
+        assert!(!model.models[1].refresh.as_ref().unwrap().enabled);
+
+        let error = ModelFile::validate_refresh(&model.models[2]).unwrap();
+        assert!(error.contains("broken_orders") && error.contains("refresh.cron '0 25 * * *'"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_timings() {
+        let timings = DeployTimings {
+            slowest_models: vec![crate::utils::ModelTiming {
+                model_name: "orders".to_string(),
+                data_source_name: "warehouse".to_string(),
+                duration_ms: 1240,
+            }],
+            groups: vec![crate::utils::GroupTiming {
+                data_source_name: "warehouse".to_string(),
+                database: Some("analytics".to_string()),
+                models: 3,
+                fetch_ms: 800,
+                write_ms: 1105,
+            }],
+        };
+
+        assert_eq!(
+            format_timings(&timings),
+            "⏱️  Slowest models:\n   - orders (warehouse): 1.24s\n\
+             ⏱️  Data source groups:\n   - warehouse/analytics: 3 models, fetch 0.80s, write 1.10s\n"
+        );
+    }
+
+    #[test]
+    fn test_dataset_type_defaults() -> Result<()> {
+        let yml = r#"
+            version: 1
+            models:
+              - name: daily_orders
+                description: "Daily orders"
+                type: materialized_view
+              - name: orders
+                description: "Orders"
+              - name: snapshots
+                description: "Snapshots"
+                type: snapshot
+        "#;
+        let model: BusterModel = serde_yaml::from_str(yml)?;
+        let config: BusterConfig =
+            serde_yaml::from_str("dataset_type: table\nallowed_dataset_types: [table, view]")?;
+
+        assert_eq!(model.models[0].dataset_type(Some(&config)), "materialized_view");
+        assert_eq!(model.models[1].dataset_type(Some(&config)), "table");
+        assert_eq!(model.models[1].dataset_type(None), "view");
+
+        assert!(ModelFile::validate_dataset_type(&model.models[0], Some(&config)).is_none());
+        let error = ModelFile::validate_dataset_type(&model.models[2], None).unwrap();
+        assert!(error.contains("snapshots") && error.contains("unknown type 'snapshot'"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_column_delete_mode_from_config() -> Result<()> {
+        let model: BusterModel = serde_yaml::from_str(
+            "models:\n  - name: orders\n    description: Orders\n    data_source_name: warehouse\n    schema: analytics",
+        )?;
+        let mut model_file = ModelFile {
+            yml_path: PathBuf::from("orders.yml"),
+            sql_path: None,
+            model,
+            config: None,
+        };
+        let request = model_file.to_deploy_request(&model_file.model.models[0], None);
+        assert_eq!(request.column_delete_mode, ColumnDeleteMode::Soft);
+
+        model_file.config = Some(serde_yaml::from_str("column_delete_mode: hard")?);
+        let request = model_file.to_deploy_request(&model_file.model.models[0], None);
+        assert_eq!(request.column_delete_mode, ColumnDeleteMode::Hard);
+        assert!(serde_json::to_string(&request)?.contains(r#""column_delete_mode":"hard""#));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_lint_column_names() -> Result<()> {
+        let temp_dir = setup_test_dir().await?;
+
+        let model_yml = r#"
+            version: 1
+            models:
+              - name: orders
+                description: "Orders"
+                data_source_name: "test_source"
+                schema: "test_schema"
+                dimensions:
+                  - name: order_id
+                    expr: "id"
+                    type: "string"
+                    description: "Order id"
+                  - name: CustomerName
+                    expr: "customer_name"
+                    type: "string"
+                    description: "Customer name"
+        "#;
+        let yml_path = create_test_yaml(temp_dir.path(), "orders.yml", model_yml).await?;
+        let model_file = ModelFile::new(yml_path, None)?;
+        let model = &model_file.model.models[0];
+        let requests = vec![model_file.to_deploy_request(model, None)];
+
+        let warnings = lint_column_names(&requests, DEFAULT_COLUMN_NAME_PATTERN)?;
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].0, "orders");
+        assert_eq!(warnings[0].1.column_name.as_deref(), Some("CustomerName"));
+        assert_eq!(warnings[0].1.severity(), ValidationSeverity::Warning);
+
+        // A custom convention from buster.yml replaces the default
+        assert!(lint_column_names(&requests, "^[A-Za-z_]+$")?.is_empty());
+        assert!(lint_column_names(&requests, "[").is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_find_model_files_honors_busterignore() -> Result<()> {
+        let models_dir = setup_test_dir().await?;
+        let vendor_dir = setup_test_dir().await?;
+        let models = models_dir.path();
+
+        create_test_yaml(models, "buster.yml", "data_source_name: warehouse").await?;
+        let orders = create_test_yaml(models, "orders.yml", "models: []").await?;
+        create_test_yaml(models, "orders_generated.yml", "models: []").await?;
+        let customers =
+            create_test_yaml(models, "customers_generated.yml", "models: []").await?;
+        create_test_yaml(models, BUSTER_IGNORE_FILE, "*_generated.yml\n").await?;
+        let payments = create_test_yaml(vendor_dir.path(), "payments.yml", "models: []").await?;
+
+        let (files, ignored) = find_model_files(&[
+            models.to_path_buf(),
+            vendor_dir.path().to_path_buf(),
+            customers.clone(),
+        ])?;
+
+        // Ignore rules only apply to the directory they're in, and named files are kept
+        assert_eq!(files, vec![orders, payments, customers]);
+        assert_eq!(ignored, 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_lint_descriptions() -> Result<()> {
+        let temp_dir = setup_test_dir().await?;
+
+        let model_yml = r#"
+            version: 1
+            models:
+              - name: orders
+                description: "One row per order placed in the storefront"
+                data_source_name: "test_source"
+                schema: "test_schema"
+                dimensions:
+                  - name: order_id
+                    expr: "id"
+                    type: "string"
+                    description: "Order ID"
+                  - name: status
+                    expr: "status"
+                    type: "string"
+                    description: "Fulfillment state of the order"
+                  - name: channel
+                    expr: "channel"
+                    type: "string"
+                    description: ""
+        "#;
+        let yml_path = create_test_yaml(temp_dir.path(), "orders.yml", model_yml).await?;
+        let model_file = ModelFile::new(yml_path, None)?;
+        let model = &model_file.model.models[0];
+        let requests = vec![model_file.to_deploy_request(model, None)];
+
+        let warnings = lint_descriptions(&requests, DEFAULT_DESCRIPTION_MIN_LENGTH);
+        let flagged: Vec<_> = warnings
+            .iter()
+            .map(|(_, w)| w.column_name.as_deref().unwrap_or_default())
+            .collect();
+        assert_eq!(flagged, vec!["order_id", "channel"]);
+        assert!(warnings[0].1.message.contains("repeats the name"));
+        assert!(warnings[1].1.message.contains("is empty"));
+
+        // A stricter threshold also catches the model description
+        assert_eq!(lint_descriptions(&requests, 60).len(), 4);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_lint_duplicate_exprs() -> Result<()> {
+        let temp_dir = setup_test_dir().await?;
+
+        let model_yml = r#"
+            version: 1
+            models:
+              - name: orders
+                description: "One row per order placed in the storefront"
+                data_source_name: "test_source"
+                schema: "test_schema"
+                dimensions:
+                  - name: status
+                    expr: "status"
+                    type: "string"
+                    description: "Fulfillment state of the order"
+                  - name: order_status
+                    expr: " status "
+                    type: "string"
+                    description: "Fulfillment state of the order"
+                measures:
+                  - name: revenue
+                    expr: "amount"
+                    agg: "sum"
+                    description: "Total order amount"
+                  - name: total_amount
+                    expr: "amount"
+                    agg: "SUM"
+                    description: "Total order amount"
+                  - name: average_amount
+                    expr: "amount"
+                    agg: "avg"
+                    description: "Average order amount"
+                  - name: refunded_amount
+                    expr: "amount"
+                    agg: "sum"
+                    filters: "status = 'refunded'"
+                    description: "Total refunded amount"
+        "#;
+        let yml_path = create_test_yaml(temp_dir.path(), "orders.yml", model_yml).await?;
+        let model_file = ModelFile::new(yml_path, None)?;
+        let model = &model_file.model.models[0];
+        let requests = vec![model_file.to_deploy_request(model, None)];
+
+        let warnings = lint_duplicate_exprs(&requests);
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(
+            warnings[0].1.message,
+            "Dimensions 'status', 'order_status' share the expression 'status'"
+        );
+        assert_eq!(
+            warnings[1].1.message,
+            "Measures 'revenue', 'total_amount' share the expression 'amount'"
+        );
+        assert_eq!(warnings[1].1.severity(), ValidationSeverity::Warning);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_override_database() -> Result<()> {
+        let temp_dir = setup_test_dir().await?;
+
+        let model_yml = r#"
+            version: 1
+            models:
+              - name: orders
+                description: "Orders of the storefront"
+                data_source_name: "test_source"
+                schema: "test_schema"
+                database: "analytics"
+              - name: customers
+                description: "Customers of the storefront"
+                data_source_name: "test_source"
+                schema: "test_schema"
+                database: "analytics_clone"
+              - name: products
+                description: "Products of the storefront"
+                data_source_name: "test_source"
+                schema: "test_schema"
+        "#;
+        let yml_path = create_test_yaml(temp_dir.path(), "models.yml", model_yml).await?;
+        let model_file = ModelFile::new(yml_path, None)?;
+        let mut requests: Vec<_> = model_file
+            .model
+            .models
+            .iter()
+            .map(|model| model_file.to_deploy_request(model, None))
+            .collect();
+
+        assert_eq!(override_database(&mut requests, "analytics_clone"), 2);
+        assert!(requests
+            .iter()
+            .all(|request| request.database.as_deref() == Some("analytics_clone")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_data_source() -> Result<()> {
+        let data_sources = vec![
+            DataSourceSummary {
+                name: "warehouse".to_string(),
+                env: "dev".to_string(),
+                type_: "snowflake".to_string(),
+            },
+            DataSourceSummary {
+                name: "staging".to_string(),
+                env: "prod".to_string(),
+                type_: "postgres".to_string(),
+            },
+        ];
+
+        assert!(check_data_source("warehouse", Some("dev"), &data_sources).is_ok());
+
+        let error = check_data_source("staging", Some("dev"), &data_sources)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            error,
+            "Data source 'staging' not found in env 'dev'. Available: warehouse (snowflake)"
+        );
+        // Models without an env are reported by the server
+        assert!(check_data_source("staging", None, &data_sources).is_ok());
+
+        assert!(check_data_source("warehouse", Some("dev"), &[])
+            .unwrap_err()
+            .to_string()
+            .ends_with("Available: none"));
+
+        // Every env the models deploy to is checked, not just the first model's
+        let model: BusterModel = serde_yaml::from_str(
+            "models:\n  - name: orders\n    description: Orders\n    data_source_name: warehouse\n    schema: analytics",
+        )?;
+        let model_file = ModelFile {
+            yml_path: PathBuf::from("orders.yml"),
+            sql_path: None,
+            model,
+            config: None,
+        };
+        let request = |env: &str| {
+            let mut request = model_file.to_deploy_request(&model_file.model.models[0], None);
+            request.env = Some(env.to_string());
+            request
+        };
+        assert!(check_data_source_envs("warehouse", &[request("dev")], &data_sources).is_ok());
+        let requests = [request("dev"), request("prod")];
+        let error = check_data_source_envs("warehouse", &requests, &data_sources)
+            .unwrap_err()
+            .to_string();
+        assert!(error.starts_with("Data source 'warehouse' not found in env 'prod'"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ndjson_events_match_server_format() -> Result<()> {
+        let result = ValidationResult {
+            success: false,
+            model_name: "orders".to_string(),
+            data_source_name: "warehouse".to_string(),
+            schema: "public".to_string(),
+            errors: vec![ValidationError {
+                error_type: ValidationErrorType::TableNotFound,
+                column_name: None,
+                message: "Table 'public.orders' not found".to_string(),
+                suggestion: None,
+                context: None,
+            }],
+            warnings: Vec::new(),
+        };
+
+        let line = serde_json::to_string(&NdjsonEvent::Result(&result))?;
+        let event: DeployStreamEvent = serde_json::from_str(&line)?;
+        assert!(matches!(
+            event,
+            DeployStreamEvent::Result(parsed)
+                if parsed.model_name == "orders"
+                    && parsed.errors[0].error_type == ValidationErrorType::TableNotFound
+        ));
+
+        let line = serde_json::to_string(&NdjsonEvent::Error {
+            message: "timed out".to_string(),
+        })?;
+        assert_eq!(line, r#"{"type":"error","message":"timed out"}"#);
+
+        // Fanned-out deploys tag each line with its host
+        let event = NdjsonEvent::HostResult {
+            success: true,
+            error: None,
+        };
+        let line = serde_json::to_string(&NdjsonLine {
+            event: &event,
+            host: Some("https://dr.buster.example.com"),
+        })?;
+        assert_eq!(
+            line,
+            r#"{"type":"host_result","success":true,"error":null,"host":"https://dr.buster.example.com"}"#
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_similar_tables_hint() {
+        let mut error = ValidationError {
+            error_type: ValidationErrorType::TableNotFound,
+            column_name: None,
+            message: "Table 'analytics.order' not found".to_string(),
+            suggestion: None,
+            context: None,
+        };
+        assert_eq!(similar_tables_hint(&error), None);
+
+        error.context = Some(ValidationErrorContext {
+            similar_tables: vec!["ORDERS".to_string(), "order_lines".to_string()],
+        });
+        assert_eq!(
+            similar_tables_hint(&error).as_deref(),
+            Some("Did you mean 'ORDERS'? Also similar: order_lines")
+        );
+
+        error.context = Some(ValidationErrorContext {
+            similar_tables: vec!["ORDERS".to_string()],
+        });
+        assert_eq!(
+            similar_tables_hint(&error).as_deref(),
+            Some("Did you mean 'ORDERS'?")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resolve_refs() -> Result<()> {
+        let temp_dir = setup_test_dir().await?;
+
+        let model_yml = r#"
+            version: 1
+            models:
+              - name: orders
+                description: "Orders of the storefront"
+                data_source_name: "test_source"
+                schema: "marts"
+                database_name: "fct_orders"
+              - name: order_totals
+                description: "Order totals per customer"
+                data_source_name: "test_source"
+                schema: "marts"
+              - name: refunds
+                description: "Refunded orders"
+                data_source_name: "test_source"
+                schema: "marts"
+        "#;
+        let yml_path = create_test_yaml(temp_dir.path(), "models.yml", model_yml).await?;
+        let model_file = ModelFile::new(yml_path, None)?;
+        let sql = [
+            None,
+            Some(
+                "SELECT o.customer_id, c.region FROM {{ ref('orders') }} o \
+                 JOIN {{ ref('customers') }} c ON c.id = o.customer_id \
+                 JOIN {{source('raw', 'regions')}} r ON r.name = c.region",
+            ),
+            Some("SELECT * FROM ref(\"orders\") WHERE status = 'refunded' AND ref('returns')"),
+        ];
+        let mut requests: Vec<_> = model_file
+            .model
+            .models
+            .iter()
+            .zip(sql)
+            .map(|(model, sql)| model_file.to_deploy_request(model, sql.map(str::to_string)))
+            .collect();
+        let existing = vec![DeployedDataset {
+            id: uuid::Uuid::nil(),
+            name: "customers".to_string(),
+            database_name: "dim_customers".to_string(),
+            schema: "core".to_string(),
+            data_source_name: "test_source".to_string(),
+            type_: "view".to_string(),
+            enabled: true,
+            column_count: 4,
+            updated_at: "2025-02-17T10:00:00Z".to_string(),
+            yml_file: None,
+        }];
+
+        let source_of = |source: &str, table: &str| {
+            (source == "raw" && table == "regions").then(|| "landing.regions".to_string())
+        };
+        let original = requests[1].sql_definition.clone();
+
+        // A dry run can't see deployed datasets, so refs to them wait for the deploy
+        let dry_run = resolve_refs(&mut requests, None, source_of);
+        assert_eq!(
+            dry_run.deferred,
+            vec![
+                ("order_totals".to_string(), "{{ ref('customers') }}".to_string()),
+                ("refunds".to_string(), "ref('returns')".to_string()),
+            ]
+        );
+        assert!(dry_run.failures.is_empty());
+
+        requests[1].sql_definition = original.clone();
+        let resolution = resolve_refs(&mut requests, Some(&existing), source_of);
+        assert_eq!(
+            requests[1].sql_definition.as_deref(),
+            Some(
+                "SELECT o.customer_id, c.region FROM marts.fct_orders o \
+                 JOIN core.dim_customers c ON c.id = o.customer_id \
+                 JOIN landing.regions r ON r.name = c.region"
+            )
+        );
+        assert_eq!(
+            resolution.failures,
+            vec![(
+                "refunds".to_string(),
+                "ref('returns') doesn't match a model in this deploy or a deployed dataset"
+                    .to_string()
+            )]
+        );
+
+        // A source the dbt project doesn't define isn't guessed at
+        requests[1].sql_definition = original;
+        let resolution = resolve_refs(&mut requests, Some(&existing), |_, _| None);
+        assert_eq!(
+            resolution.failures[0],
+            (
+                "order_totals".to_string(),
+                "{{source('raw', 'regions')}} isn't defined in the dbt project's manifest or \
+                 sources YAML"
+                    .to_string()
+            )
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_skipped_columns() -> Result<()> {
+        let temp_dir = setup_test_dir().await?;
+
+        let model_yml = r#"
+            version: 1
+            models:
+              - name: orders
+                description: "Orders placed in the storefront"
+                data_source_name: "test_source"
+                schema: "test_schema"
+                dimensions:
+                  - name: status
+                    expr: "status"
+                    type: "string"
+                    description: "Fulfillment state"
+                  - name: region
+                    expr: "region"
+                    type: "string"
+                    description: "Sales region, still being backfilled"
+                    skip: true
+                measures:
+                  - name: revenue
+                    expr: "amount"
+                    agg: "sum"
+                    description: "Total order value"
+                  - name: order_count
+                    expr: "id"
+                    agg: "count"
+                    description: "Number of orders"
+                  - name: avg_order_value
+                    description: "Revenue per order"
+                    ratio:
+                      numerator: revenue
+                      denominator: order_count
+                entities:
+                  - name: sales_region
+                    expr: "region"
+                    type: "foreign"
+                    description: "Region the order was sold in"
+        "#;
+        let yml_path = create_test_yaml(temp_dir.path(), "orders.yml", model_yml).await?;
+        let model_file = ModelFile::new(yml_path, None)?;
+        let model = &model_file.model.models[0];
+        let mut requests = vec![model_file.to_deploy_request(model, None)];
+        assert_eq!(requests[0].skipped_columns, vec!["region"]);
+
+        let unmatched = exclude_columns(
+            &mut requests,
+            &[
+                "orders.status".to_string(),
+                "orders.order_count".to_string(),
+                "orders.discount".to_string(),
+                "customers.id".to_string(),
+            ],
+        )?;
+        assert_eq!(unmatched, vec!["orders.discount", "customers.id"]);
+
+        // The ratio can't be built without the measure it divides by
+        let warnings = skip_dependent_columns(&mut requests);
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("'avg_order_value'") && warnings[0].contains("'order_count'"));
+        assert!(warnings[1].contains("Entity 'sales_region'") && warnings[1].contains("'region'"));
+
+        let columns: Vec<&str> = requests[0]
+            .columns
+            .iter()
+            .map(|col| col.name.as_str())
+            .collect();
+        assert_eq!(columns, vec!["revenue"]);
+        assert_eq!(
+            requests[0].skipped_columns,
+            vec!["region", "status", "order_count", "avg_order_value"]
+        );
+        assert!(serde_json::to_string(&requests[0])?.contains(
+            r#""skipped_columns":["region","status","order_count","avg_order_value"]"#
+        ));
+
+        assert!(exclude_columns(&mut requests, &["status".to_string()]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_deploy_hosts() -> Result<()> {
+        let config: BusterConfig = serde_yaml::from_str(
+            r#"
+            data_source_name: warehouse
+            schema: public
+            database: null
+            hosts:
+              - url: https://buster.example.com
+              - url: https://dr.buster.example.com
+                api_key_env: BUSTER_DR_API_KEY
+            "#,
+        )?;
+        let mut args = DeployArgs::new(None, false);
+        assert!(deploy_hosts(&args, None, None).is_empty());
+
+        let hosts = deploy_hosts(&args, Some(&config), None);
+        assert_eq!(hosts.len(), 2);
+        assert_eq!(hosts[1].url, "https://dr.buster.example.com");
+        assert_eq!(hosts[1].api_key_env.as_deref(), Some("BUSTER_DR_API_KEY"));
+
+        // The top-level --host wins over buster.yml
+        assert!(deploy_hosts(&args, Some(&config), Some("https://staging.buster.example.com"))
+            .is_empty());
+
+        // deploy --host replaces the configured hosts
+        args.hosts = vec!["https://staging.buster.example.com".to_string()];
+        assert_eq!(
+            deploy_hosts(&args, Some(&config), None),
+            vec![DeployHost {
+                url: "https://staging.buster.example.com".to_string(),
+                api_key_env: None,
+            }]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_host_api_key() -> Result<()> {
+        let env_var = |var: &str| (var == "BUSTER_DR_API_KEY").then(|| "dr-key".to_string());
+        let mut host = DeployHost {
+            url: "https://dr.buster.example.com".to_string(),
+            api_key_env: None,
+        };
+        assert_eq!(host_api_key(&host, env_var)?, None);
+
+        host.api_key_env = Some("BUSTER_DR_API_KEY".to_string());
+        assert_eq!(host_api_key(&host, env_var)?, Some("dr-key".to_string()));
+
+        host.api_key_env = Some("BUSTER_MISSING_API_KEY".to_string());
+        let error = host_api_key(&host, env_var).unwrap_err();
+        assert!(error.to_string().contains("BUSTER_MISSING_API_KEY is not set"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fan_out() {
+        let hosts: Vec<DeployHost> = ["https://a.example.com", "https://b.example.com"]
+            .iter()
+            .map(|url| DeployHost {
+                url: url.to_string(),
+                api_key_env: None,
+            })
+            .collect();
+
+        // A failing host doesn't stop the next one
+        let mut attempted = Vec::new();
+        let host_results = fan_out(&hosts, |host| {
+            attempted.push(host.url.clone());
+            let fails = host.url == "https://a.example.com";
+            async move {
+                if fails {
+                    Err(anyhow::anyhow!("unreachable"))
+                } else {
+                    Ok(())
+                }
+            }
+        })
+        .await;
+        assert_eq!(attempted, vec!["https://a.example.com", "https://b.example.com"]);
+        assert!(host_results[0].1.is_err());
+        assert!(host_results[1].1.is_ok());
+
+        let failed = host_results.iter().filter(|(_, outcome)| outcome.is_err()).count();
+        assert!(fan_out_outcome(failed, hosts.len(), false).is_ok());
+        assert!(fan_out_outcome(failed, hosts.len(), true).is_err());
+        assert!(fan_out_outcome(2, 2, false).is_err());
+        assert!(fan_out_outcome(0, 2, true).is_ok());
+    }
 }
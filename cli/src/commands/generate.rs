@@ -1,5 +1,5 @@
 use anyhow::Result;
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
 use std::collections::HashMap;
 use regex::Regex;
 use lazy_static::lazy_static;
@@ -7,12 +7,15 @@ use std::ffi::OsStr;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::fmt;
-use inquire::{Text, required};
+use std::io::IsTerminal;
+use inquire::{Select, Text, required};
 use crate::utils::{
     buster_credentials::get_and_validate_buster_credentials,
-    BusterClient, GenerateApiRequest, GenerateApiResponse,
-    yaml_diff_merger::YamlDiffMerger,
+    manifest::{GenerateManifest, ManifestModel, MANIFEST_FILE},
+    BusterClient, FlattenHint, GenerateApiRequest, GenerateApiResponse, IdentifierCase, MeasureAgg,
+    ModelCheck, UnsupportedColumn, artifacts::DbtArtifacts, yaml_diff_merger::YamlDiffMerger,
 };
+use super::schema::{generated_model_schema, OutputSchema, MODEL_SCHEMA_FILE};
 use glob;
 
 #[derive(Debug)]
@@ -23,6 +26,72 @@ pub struct GenerateCommand {
     schema: Option<String>,
     database: Option<String>,
     config: BusterConfig,
+    options: GenerateOptions,
+}
+
+/// Optional knobs for how models are generated.
+#[derive(Debug, Clone, Default)]
+pub struct GenerateOptions {
+    pub identifier_case: IdentifierCase,
+    /// Have the API detect the warehouse dialect instead of trusting the recorded type
+    pub dialect_autodetect: bool,
+    /// Append a few example values to string column descriptions (queries the warehouse)
+    pub sample_values: bool,
+    /// `searchable` set on generated dimensions
+    pub default_searchable: bool,
+    /// `agg` set on generated measures
+    pub default_agg: MeasureAgg,
+    /// Print the columns generate can't map instead of writing model files
+    pub report_unsupported: bool,
+    /// Keep writing the remaining model files when one fails, and report failures at the end
+    pub continue_on_error: bool,
+    /// Prefixes removed from table names to form model names (first match wins)
+    pub strip_prefixes: Vec<String>,
+    /// Suffixes removed from table names to form model names (first match wins)
+    pub strip_suffixes: Vec<String>,
+    /// dbt target directory whose `catalog.json`/`manifest.json` supply the columns and
+    /// descriptions, instead of the warehouse
+    pub dbt_target_dir: Option<PathBuf>,
+    /// Emit entities from the primary and foreign keys declared in the warehouse
+    pub emit_entities_from_fk: bool,
+    /// Print how each model's columns would map instead of writing model files
+    pub check: bool,
+    /// Schema of the generated model files to write next to them
+    pub output_schema: Option<OutputSchema>,
+    /// What happens to model files that already exist
+    pub overwrite_mode: OverwriteMode,
+    /// Print the metadata queries columns would be read with instead of generating
+    pub dry_run_sql: bool,
+}
+
+/// What generate does with a model file that already exists. Merging keeps hand-edited
+/// descriptions and fields; overwriting replaces the file with the generated model, so they're
+/// lost unless backed up.
+#[derive(Debug, Clone, Copy, Default, PartialEq, clap::ValueEnum)]
+pub enum OverwriteMode {
+    /// Ask for each existing file
+    Prompt,
+    /// Leave existing files untouched
+    #[default]
+    Skip,
+    /// Merge the generated model into existing files
+    Merge,
+    /// Replace existing files
+    Overwrite,
+    /// Copy each existing file to `<file>.bak`, then replace it
+    Backup,
+}
+
+impl OverwriteMode {
+    /// `prompt` on a terminal and `skip` otherwise, so unattended runs never touch
+    /// existing files.
+    pub fn for_stdin() -> Self {
+        if std::io::stdin().is_terminal() {
+            OverwriteMode::Prompt
+        } else {
+            OverwriteMode::Skip
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -52,6 +121,10 @@ enum GenerateError {
         path: PathBuf,
         error: std::io::Error,
     },
+    WriteFailed {
+        path: PathBuf,
+        error: anyhow::Error,
+    },
 }
 
 impl fmt::Display for GenerateError {
@@ -67,6 +140,9 @@ impl fmt::Display for GenerateError {
             GenerateError::FileAccessError { path, error } => {
                 write!(f, "Failed to access file {}: {}", path.display(), error)
             }
+            GenerateError::WriteFailed { path, error } => {
+                write!(f, "{}: {:#}", path.display(), error)
+            }
         }
     }
 }
@@ -77,6 +153,18 @@ pub struct BusterConfig {
     pub schema: Option<String>,
     pub database: Option<String>,
     pub exclude_files: Option<Vec<String>>,
+    /// Regex renames applied to table names, in order, after prefixes and suffixes are stripped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rename: Option<Vec<RenameRule>>,
+    /// Fields generate extracts from ARRAY, OBJECT and VARIANT columns
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flatten: Option<Vec<FlattenHint>>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RenameRule {
+    pub pattern: String,
+    pub replacement: String,
 }
 
 impl BusterConfig {
@@ -91,6 +179,53 @@ impl BusterConfig {
         }
         Ok(())
     }
+
+    fn rename_rules(&self) -> Result<Vec<(Regex, String)>> {
+        self.rename
+            .iter()
+            .flatten()
+            .map(|rule| {
+                Regex::new(&rule.pattern)
+                    .map(|pattern| (pattern, rule.replacement.clone()))
+                    .map_err(|e| anyhow::anyhow!("Invalid rename pattern '{}': {}", rule.pattern, e))
+            })
+            .collect()
+    }
+}
+
+/// Turns physical table names into logical model names: strips the first matching
+/// prefix and suffix, then applies the buster.yml rename rules in order.
+#[derive(Debug, Default)]
+struct NameTransform {
+    strip_prefixes: Vec<String>,
+    strip_suffixes: Vec<String>,
+    renames: Vec<(Regex, String)>,
+}
+
+impl NameTransform {
+    fn apply(&self, table_name: &str) -> String {
+        let mut name = table_name;
+        if let Some(stripped) = self
+            .strip_prefixes
+            .iter()
+            .find_map(|prefix| name.strip_prefix(prefix.as_str()))
+        {
+            name = stripped;
+        }
+        if let Some(stripped) = self
+            .strip_suffixes
+            .iter()
+            .find_map(|suffix| name.strip_suffix(suffix.as_str()))
+        {
+            name = stripped;
+        }
+
+        self.renames
+            .iter()
+            .fold(name.to_string(), |name, (pattern, replacement)| {
+                pattern.replace_all(&name, replacement.as_str()).into_owned()
+            })
+    }
 }
 
 struct GenerateProgress {
@@ -149,12 +284,15 @@ impl GenerateCommand {
         data_source_name: Option<String>,
         schema: Option<String>,
         database: Option<String>,
+        options: GenerateOptions,
     ) -> Self {
         let config = BusterConfig {
             data_source_name: data_source_name.clone(),
             schema: schema.clone(),
             database: database.clone(),
             exclude_files: None,
+            rename: None,
+            flatten: None,
         };
 
         Self {
@@ -164,17 +302,33 @@ impl GenerateCommand {
             schema,
             database,
             config,
+            options,
         }
     }
 
     pub async fn execute(&self) -> Result<()> {
         let mut progress = GenerateProgress::new(0);
-        
+
+        // Make sure the output directory exists before anything gets written to it
+        let destination_path = self.prepare_destination_path()?;
+        println!("📂 Output directory: {}", destination_path.display());
+
         // First handle buster.yml
         progress.status = "Checking buster.yml configuration...".to_string();
         progress.log_progress();
         
-        let config = self.handle_buster_yml().await?;
+        let mut config = self.handle_buster_yml(&destination_path).await?;
+
+        // Columns of every model are read from the overriding database
+        if let Some(database) = &self.database {
+            if let Some(configured) = config.database.as_deref().filter(|db| *db != database) {
+                println!(
+                    "⚠️  --database '{}' overrides buster.yml's database '{}' for all models in this run",
+                    database, configured
+                );
+            }
+            config.database = Some(database.clone());
+        }
 
         progress.status = "Scanning source directory...".to_string();
         progress.log_progress();
@@ -182,23 +336,55 @@ impl GenerateCommand {
         // Create a new command with the loaded config
         let cmd = GenerateCommand {
             source_path: self.source_path.clone(),
-            destination_path: self.destination_path.clone(),
+            destination_path: destination_path.clone(),
             data_source_name: self.data_source_name.clone(),
             schema: self.schema.clone(),
             database: self.database.clone(),
             config,  // Use the loaded config
+            options: self.options.clone(),
         };
 
         let model_names = cmd.process_sql_files(&mut progress).await?;
-        
+
+        // Model names for each table, after the naming transforms
+        let name_transform = NameTransform {
+            strip_prefixes: cmd.options.strip_prefixes.clone(),
+            strip_suffixes: cmd.options.strip_suffixes.clone(),
+            renames: cmd.config.rename_rules()?,
+        };
+        let table_names: Vec<String> = model_names.iter().map(|m| m.name.clone()).collect();
+        let logical_names = logical_model_names(&table_names, &name_transform)?;
+        let file_names = model_file_names(logical_names.values())?;
+
         // Print results
         println!("\n✅ Successfully processed all files");
         println!("\nFound {} model names:", model_names.len());
         for model in &model_names {
-            println!("  - {} ({})", model.name, 
-                if model.is_from_alias { "from alias" } else { "from filename" });
+            let source = if model.is_from_alias { "from alias" } else { "from filename" };
+            match logical_names.get(&model.name).filter(|name| **name != model.name) {
+                Some(logical_name) => println!("  - {} -> {} ({})", model.name, logical_name, source),
+                None => println!("  - {} ({})", model.name, source),
+            }
         }
 
+        // Read columns and descriptions from dbt before anything goes over the network
+        let catalog = match &cmd.options.dbt_target_dir {
+            Some(target_dir) => {
+                progress.status =
+                    format!("Reading dbt artifacts from {}...", target_dir.display());
+                progress.log_progress();
+                let artifacts = DbtArtifacts::load(target_dir)?;
+                let schema = cmd.config.schema.as_deref().expect("schema is required");
+                Some(
+                    table_names
+                        .iter()
+                        .map(|table_name| artifacts.catalog_model(schema, table_name))
+                        .collect::<Result<Vec<_>>>()?,
+                )
+            }
+            None => None,
+        };
+
         // Create API client
         progress.status = "Connecting to Buster API...".to_string();
         progress.log_progress();
@@ -211,7 +397,18 @@ impl GenerateCommand {
             data_source_name: cmd.config.data_source_name.expect("data_source_name is required"),
             schema: cmd.config.schema.expect("schema is required"),
             database: cmd.config.database,
-            model_names: model_names.iter().map(|m| m.name.clone()).collect(),
+            model_names: table_names,
+            identifier_case: cmd.options.identifier_case,
+            dialect_autodetect: cmd.options.dialect_autodetect,
+            sample_values: cmd.options.sample_values,
+            default_searchable: cmd.options.default_searchable,
+            default_agg: cmd.options.default_agg,
+            report_unsupported: cmd.options.report_unsupported,
+            catalog,
+            flatten_hints: cmd.config.flatten.unwrap_or_default(),
+            emit_entities_from_fk: cmd.options.emit_entities_from_fk,
+            check: cmd.options.check,
+            dry_run_sql: cmd.options.dry_run_sql,
         };
 
         // Make API call
@@ -219,50 +416,118 @@ impl GenerateCommand {
         progress.log_progress();
 
         match client.generate_datasets(request).await {
+            Ok(response) if cmd.options.report_unsupported => {
+                print!("{}", format_unsupported_report(&response.unsupported_columns));
+            }
+            Ok(response) if cmd.options.dry_run_sql => {
+                if response.metadata_queries.is_empty() {
+                    return Err(anyhow::anyhow!(
+                        "This Buster server doesn't support --dry-run-sql"
+                    ));
+                }
+                eprint!("{}", format_metadata_queries(&response.metadata_queries));
+            }
+            Ok(response) if cmd.options.check => {
+                print!("{}", format_check_report(&response.model_checks));
+                for (model_name, error) in &response.errors {
+                    println!("❌ {}: {}", model_name, error);
+                }
+
+                let unclean = response
+                    .model_checks
+                    .iter()
+                    .filter(|check| check.unsupported > 0 || !check.defaulted_columns.is_empty())
+                    .count()
+                    + response.errors.len();
+                if unclean > 0 {
+                    return Err(anyhow::anyhow!("{} model(s) don't map cleanly", unclean));
+                }
+            }
             Ok(response) => {
+                for warning in &response.warnings {
+                    println!("⚠️  {}", warning);
+                }
+
                 // Process each model's YAML
-                for (model_name, yml_content) in response.yml_contents {
-                    let file_path = self.destination_path.join(format!("{}.yml", model_name));
-                    
-                    if file_path.exists() {
-                        // Use YAML diff merger for existing files
-                        let merger = YamlDiffMerger::new(file_path.clone(), yml_content);
-                        
-                        match merger.compute_diff() {
-                            Ok(diff_result) => {
-                                // Preview changes
-                                println!("\nProcessing model: {}", model_name);
-                                merger.preview_changes(&diff_result);
-
-                                // Apply changes
-                                match merger.apply_changes(&diff_result) {
-                                    Ok(_) => {
-                                        progress.log_success();
-                                        println!("✅ Updated {}.yml", model_name);
-                                    }
-                                    Err(e) => {
-                                        progress.log_error(&format!("Failed to update {}.yml: {}", model_name, e));
-                                    }
-                                }
-                            }
-                            Err(e) => {
-                                progress.log_error(&format!("Failed to compute diff for {}.yml: {}", model_name, e));
-                            }
-                        }
+                let mut write_failures = Vec::new();
+                let mut manifest_models = Vec::new();
+                for (table_name, yml_content) in response.yml_contents {
+                    let model_name = logical_names
+                        .get(&table_name)
+                        .cloned()
+                        .unwrap_or_else(|| table_name.clone());
+                    let yml_content = if model_name != table_name {
+                        rename_generated_model(&yml_content, &table_name, &model_name)?
+                    } else {
+                        yml_content
+                    };
+                    let yml_content = if cmd.options.emit_entities_from_fk {
+                        rename_foreign_entities(&yml_content, &logical_names)?
                     } else {
-                        // Create new file for models that don't exist yet
-                        match fs::write(&file_path, yml_content) {
-                            Ok(_) => {
-                                progress.log_success();
-                                println!("✅ Created new file {}.yml", model_name);
-                            }
-                            Err(e) => {
-                                progress.log_error(&format!("Failed to write {}.yml: {}", model_name, e));
-                            }
+                        yml_content
+                    };
+                    let file_name = match file_names.get(&model_name) {
+                        Some(file_name) => file_name.clone(),
+                        None => model_file_name(&model_name)?,
+                    };
+                    let file_name = match legacy_model_file(
+                        &destination_path,
+                        &model_name,
+                        &file_name,
+                    ) {
+                        Some(legacy) => {
+                            println!(
+                                "⚠️  Updating {} in place; rename it to {} to use the current naming",
+                                legacy, file_name
+                            );
+                            legacy
+                        }
+                        None => file_name,
+                    };
+                    let file_path = destination_path.join(&file_name);
+
+                    let written = write_model_file(
+                        &model_name,
+                        &file_path,
+                        yml_content,
+                        cmd.options.overwrite_mode,
+                    );
+                    if let Err(error) = written {
+                        progress.log_error(&format!("Failed to write {}: {:#}", file_name, error));
+                        if !cmd.options.continue_on_error {
+                            return Err(anyhow::anyhow!(
+                                "Failed to write {}: {:#}",
+                                file_path.display(),
+                                error
+                            ));
                         }
+                        write_failures.push(GenerateError::WriteFailed { path: file_path, error });
+                    } else {
+                        progress.log_success();
+                        manifest_models.push(ManifestModel {
+                            name: model_name,
+                            file: file_name,
+                        });
                     }
                 }
 
+                // Lets deploy act on exactly the files generated, in a stable order
+                if !manifest_models.is_empty() {
+                    let manifest_path = destination_path.join(MANIFEST_FILE);
+                    GenerateManifest::new(manifest_models).write(&manifest_path)?;
+                    println!("🧾 Wrote manifest of generated models to {}", manifest_path.display());
+                }
+
+                if let Some(OutputSchema::JsonSchema) = cmd.options.output_schema {
+                    let schema_path = destination_path.join(MODEL_SCHEMA_FILE);
+                    let schema = generated_model_schema(cmd.options.emit_entities_from_fk);
+                    fs::write(&schema_path, serde_json::to_string_pretty(&schema)? + "\n")
+                        .map_err(|e| {
+                            anyhow::anyhow!("Failed to write {}: {}", schema_path.display(), e)
+                        })?;
+                    println!("📐 Wrote model file schema to {}", schema_path.display());
+                }
+
                 // Report any errors
                 if !response.errors.is_empty() {
                     println!("\n⚠️  Some models had errors:");
@@ -270,6 +535,17 @@ impl GenerateCommand {
                         println!("❌ {}: {}", model_name, error);
                     }
                 }
+
+                if !write_failures.is_empty() {
+                    eprintln!("\n❌ Failed to write {} model file(s):", write_failures.len());
+                    for failure in &write_failures {
+                        eprintln!("  - {}", failure);
+                    }
+                    return Err(anyhow::anyhow!(
+                        "{} model file(s) could not be written",
+                        write_failures.len()
+                    ));
+                }
             }
             Err(e) => {
                 progress.log_error(&format!("API call failed: {}", e));
@@ -280,8 +556,34 @@ impl GenerateCommand {
         Ok(())
     }
 
-    async fn handle_buster_yml(&self) -> Result<BusterConfig> {
-        let buster_yml_path = self.destination_path.join("buster.yml");
+    /// Resolves the destination path to an absolute directory, creating it (and any
+    /// parents) if it doesn't exist yet. Relative paths that use `..` to escape the
+    /// current project root are rejected.
+    fn prepare_destination_path(&self) -> Result<PathBuf> {
+        let project_root = std::env::current_dir()?;
+        let destination_path = resolve_destination_path(&project_root, &self.destination_path)?;
+
+        if !destination_path.exists() {
+            fs::create_dir_all(&destination_path).map_err(|e| {
+                anyhow::anyhow!(
+                    "Failed to create output directory {}: {}",
+                    destination_path.display(),
+                    e
+                )
+            })?;
+            println!("✅ Created output directory {}", destination_path.display());
+        } else if !destination_path.is_dir() {
+            return Err(anyhow::anyhow!(
+                "Destination path {} exists but is not a directory",
+                destination_path.display()
+            ));
+        }
+
+        Ok(destination_path)
+    }
+
+    async fn handle_buster_yml(&self, destination_path: &Path) -> Result<BusterConfig> {
+        let buster_yml_path = destination_path.join("buster.yml");
 
         if buster_yml_path.exists() {
             println!("✅ Found existing buster.yml");
@@ -309,6 +611,10 @@ impl GenerateCommand {
                 return Err(anyhow::anyhow!("Invalid exclude_files configuration: {}", e));
             }
 
+            if let Err(e) = config.rename_rules() {
+                return Err(anyhow::anyhow!("Invalid rename configuration: {}", e));
+            }
+
             // Log exclude patterns if present
             if let Some(patterns) = &config.exclude_files {
                 println!("ℹ️  Found {} exclude pattern(s):", patterns.len());
@@ -348,6 +654,8 @@ impl GenerateCommand {
                 schema: Some(schema),
                 database,
                 exclude_files: None,
+                rename: None,
+                flatten: None,
             };
 
             // Write the config to file
@@ -482,6 +790,9 @@ impl GenerateCommand {
                     GenerateError::MissingBusterYmlField { field } => {
                         println!("  - Missing required field in buster.yml: {}", field);
                     }
+                    GenerateError::WriteFailed { .. } => {
+                        println!("  - {}", error);
+                    }
                 }
             }
             return Err(anyhow::anyhow!("Failed to process all SQL files"));
@@ -539,6 +850,671 @@ impl GenerateCommand {
     }
 }
 
+/// Maps each table name to its model name, rejecting transforms that leave a name
+/// empty or give two tables the same name.
+fn logical_model_names(
+    table_names: &[String],
+    transform: &NameTransform,
+) -> Result<HashMap<String, String>> {
+    let mut names = HashMap::new();
+    let mut tables_by_name: HashMap<String, &str> = HashMap::new();
+    let mut errors = Vec::new();
+
+    for table_name in table_names {
+        let model_name = transform.apply(table_name);
+        if model_name.trim().is_empty() {
+            errors.push(format!("'{}' becomes an empty model name", table_name));
+            continue;
+        }
+        if let Some(other) = tables_by_name.insert(model_name.clone(), table_name) {
+            errors.push(format!(
+                "'{}' and '{}' would both be named '{}'",
+                other, table_name, model_name
+            ));
+        }
+        names.insert(table_name.clone(), model_name);
+    }
+
+    if !errors.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Invalid model names after applying naming transforms:\n  - {}",
+            errors.join("\n  - ")
+        ));
+    }
+    Ok(names)
+}
+
+/// The file a model is written to: its name lowercased, with every character outside
+/// `[a-z0-9_]` replaced by `_` and runs of `_` collapsed. Depends only on the name, so
+/// regenerating never renames files.
+fn model_file_name(model_name: &str) -> Result<String> {
+    let mut stem = String::with_capacity(model_name.len());
+    for c in model_name.to_lowercase().chars() {
+        let c = if c.is_ascii_lowercase() || c.is_ascii_digit() { c } else { '_' };
+        if !(c == '_' && stem.ends_with('_')) {
+            stem.push(c);
+        }
+    }
+
+    if stem.trim_matches('_').is_empty() {
+        return Err(anyhow::anyhow!(
+            "Model name '{}' has no characters usable in a file name",
+            model_name
+        ));
+    }
+    Ok(format!("{}.yml", stem))
+}
+
+/// The file an earlier version of generate wrote `model_name` to, named after the model
+/// as written, when it exists and `file_name` doesn't. Regenerating updates that file
+/// rather than leaving it next to a new one that defines the same model.
+fn legacy_model_file(destination: &Path, model_name: &str, file_name: &str) -> Option<String> {
+    let legacy = format!("{}.yml", model_name);
+    (legacy != file_name
+        && destination.join(&legacy).is_file()
+        && !destination.join(file_name).exists())
+    .then_some(legacy)
+}
+
+/// Maps each model name to its file name, rejecting names that end up sharing a file
+/// instead of letting one model overwrite another.
+fn model_file_names<'a>(
+    model_names: impl IntoIterator<Item = &'a String>,
+) -> Result<HashMap<String, String>> {
+    let mut model_names: Vec<&String> = model_names.into_iter().collect();
+    model_names.sort();
+
+    let mut file_names = HashMap::new();
+    let mut models_by_file: HashMap<String, &str> = HashMap::new();
+    let mut errors = Vec::new();
+
+    for model_name in model_names {
+        let file_name = match model_file_name(model_name) {
+            Ok(file_name) => file_name,
+            Err(e) => {
+                errors.push(e.to_string());
+                continue;
+            }
+        };
+        if let Some(other) = models_by_file.insert(file_name.clone(), model_name) {
+            errors.push(format!(
+                "'{}' and '{}' would both be written to {}",
+                other, model_name, file_name
+            ));
+        }
+        file_names.insert(model_name.clone(), file_name);
+    }
+
+    if !errors.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Model names don't map to distinct files:\n  - {}",
+            errors.join("\n  - ")
+        ));
+    }
+    Ok(file_names)
+}
+
+/// Renames a generated model to its logical name, keeping the table it reads from
+/// as `database_name` right after `name`.
+fn rename_generated_model(yml_content: &str, table_name: &str, model_name: &str) -> Result<String> {
+    let mut config: serde_yaml::Value = serde_yaml::from_str(yml_content)?;
+    let models = config
+        .get_mut("models")
+        .and_then(|models| models.as_sequence_mut())
+        .ok_or_else(|| anyhow::anyhow!("Generated YAML for {} has no models", table_name))?;
+
+    for model in models.iter_mut() {
+        let Some(mapping) = model.as_mapping_mut() else {
+            continue;
+        };
+        if mapping.get("name").and_then(|name| name.as_str()) != Some(table_name) {
+            continue;
+        }
+
+        let mut renamed = serde_yaml::Mapping::new();
+        for (key, value) in std::mem::take(mapping) {
+            if key.as_str() == Some("name") {
+                renamed.insert(key, model_name.into());
+                renamed.insert("database_name".into(), table_name.into());
+            } else {
+                renamed.insert(key, value);
+            }
+        }
+        *mapping = renamed;
+    }
+
+    Ok(serde_yaml::to_string(&config)?)
+}
+
+/// Foreign entities are named after the model they reference, or point at it with `ref_`
+/// when several keys reference it. The API only knows the model by its table name, so
+/// either is renamed along with the models.
+fn rename_foreign_entities(
+    yml_content: &str,
+    logical_names: &HashMap<String, String>,
+) -> Result<String> {
+    let mut config: serde_yaml::Value = serde_yaml::from_str(yml_content)?;
+    let entities = config
+        .get_mut("models")
+        .and_then(|models| models.as_sequence_mut())
+        .into_iter()
+        .flatten()
+        .filter_map(|model| model.get_mut("entities")?.as_sequence_mut())
+        .flatten();
+
+    for entity in entities {
+        if entity.get("type").and_then(|type_| type_.as_str()) != Some("foreign") {
+            continue;
+        }
+        let key = if entity.get("ref_").is_some() { "ref_" } else { "name" };
+        let logical_name = entity
+            .get(key)
+            .and_then(|name| name.as_str())
+            .and_then(|name| logical_names.get(name))
+            .cloned();
+        if let (Some(logical_name), Some(mapping)) = (logical_name, entity.as_mapping_mut()) {
+            mapping.insert(key.into(), logical_name.into());
+        }
+    }
+
+    Ok(serde_yaml::to_string(&config)?)
+}
+
+/// Formats the unsupported-column inventory as an aligned table.
+fn format_unsupported_report(columns: &[UnsupportedColumn]) -> String {
+    if columns.is_empty() {
+        return "\n✅ No unsupported columns found\n".to_string();
+    }
+
+    let headers = ("MODEL", "COLUMN", "TYPE");
+    let model_width = columns
+        .iter()
+        .map(|col| col.model_name.len())
+        .chain([headers.0.len()])
+        .max()
+        .unwrap_or_default();
+    let column_width = columns
+        .iter()
+        .map(|col| col.column_name.len())
+        .chain([headers.1.len()])
+        .max()
+        .unwrap_or_default();
+
+    let mut report = format!("\n⚠️  {} unsupported column(s):\n", columns.len());
+    let rows = std::iter::once(headers).chain(
+        columns
+            .iter()
+            .map(|col| (col.model_name.as_str(), col.column_name.as_str(), col.data_type.as_str())),
+    );
+    for (model, column, data_type) in rows {
+        report.push_str(&format!(
+            "  {:<model_width$}  {:<column_width$}  {}\n",
+            model, column, data_type
+        ));
+    }
+    report.push_str(
+        "\nAdd a `flatten` entry to buster.yml for each field to generate from these columns\n",
+    );
+    report
+}
+
+/// Formats the column mapping counts of each model as an aligned table, followed by the
+/// columns whose types default to string dimensions.
+fn format_check_report(checks: &[ModelCheck]) -> String {
+    let headers = ("MODEL", "ENTITIES", "DIMENSIONS", "MEASURES", "UNSUPPORTED");
+    let model_width = checks
+        .iter()
+        .map(|check| check.model_name.len())
+        .chain([headers.0.len()])
+        .max()
+        .unwrap_or_default();
+
+    let mut report = format!(
+        "\n  {:<model_width$}  {}  {}  {}  {}\n",
+        headers.0, headers.1, headers.2, headers.3, headers.4
+    );
+    for check in checks {
+        report.push_str(&format!(
+            "  {:<model_width$}  {:>8}  {:>10}  {:>8}  {:>11}\n",
+            check.model_name,
+            check.entities,
+            check.dimensions,
+            check.measures,
+            check.unsupported
+        ));
+    }
+
+    let defaulted: Vec<_> = checks
+        .iter()
+        .flat_map(|check| check.defaulted_columns.iter().map(move |col| (check, col)))
+        .collect();
+    if !defaulted.is_empty() {
+        report.push_str(&format!(
+            "\n⚠️  {} column(s) have unknown types and default to string dimensions:\n",
+            defaulted.len()
+        ));
+        for (check, col) in defaulted {
+            report.push_str(&format!(
+                "  {}.{}: {}\n",
+                check.model_name, col.column_name, col.data_type
+            ));
+        }
+    }
+    report
+}
+
+/// Formats the metadata queries of `--dry-run-sql` as a SQL script, each query dedented
+/// and preceded by a comment numbering it.
+fn format_metadata_queries(queries: &[String]) -> String {
+    let mut script = String::new();
+    for (i, query) in queries.iter().enumerate() {
+        let lines: Vec<&str> = query.trim().lines().map(str::trim_end).collect();
+        let indent = lines
+            .iter()
+            .skip(1)
+            .filter(|line| !line.is_empty())
+            .map(|line| line.len() - line.trim_start().len())
+            .min()
+            .unwrap_or_default();
+
+        script.push_str(&format!("-- Metadata query {} of {}\n", i + 1, queries.len()));
+        for (n, line) in lines.iter().enumerate() {
+            let line = if n == 0 { line } else { line.get(indent..).unwrap_or_default() };
+            script.push_str(line);
+            script.push('\n');
+        }
+        if !script.trim_end().ends_with(';') {
+            script.truncate(script.trim_end().len());
+            script.push_str(";\n");
+        }
+        script.push('\n');
+    }
+    script
+}
+
+/// Writes one generated model, merging into or replacing the existing file if there is one
+/// and `overwrite_mode` allows it. Files are always written as UTF-8 without a BOM, even when
+/// the existing file had one.
+fn write_model_file(
+    model_name: &str,
+    file_path: &Path,
+    yml_content: String,
+    overwrite_mode: OverwriteMode,
+) -> Result<()> {
+    if !file_path.exists() {
+        // Create new file for models that don't exist yet
+        fs::write(file_path, yml_content)?;
+        println!("✅ Created new file {}", file_path.display());
+        return Ok(());
+    }
+
+    let overwrite_mode = match overwrite_mode {
+        OverwriteMode::Prompt => prompt_overwrite_mode(file_path)?,
+        mode => mode,
+    };
+    match overwrite_mode {
+        OverwriteMode::Skip => {
+            println!("⏭️  Skipped existing {}", file_path.display());
+            return Ok(());
+        }
+        OverwriteMode::Merge => {
+            let merger = YamlDiffMerger::new(file_path.to_path_buf(), yml_content);
+            let diff_result = merger.compute_diff()?;
+
+            // Preview changes
+            println!("\nProcessing model: {}", model_name);
+            merger.preview_changes(&diff_result);
+
+            merger.apply_changes(&diff_result)?;
+            println!("✅ Updated {}", file_path.display());
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    if overwrite_mode == OverwriteMode::Backup {
+        let backup_path = backup_path(file_path);
+        fs::copy(file_path, &backup_path)?;
+        println!("💾 Backed up the previous version to {}", backup_path.display());
+    }
+    fs::write(file_path, yml_content)?;
+    println!("✅ Replaced {} with the generated {}", file_path.display(), model_name);
+    Ok(())
+}
+
+fn prompt_overwrite_mode(file_path: &Path) -> Result<OverwriteMode> {
+    let choice = Select::new(
+        &format!("{} already exists. What should generate do?", file_path.display()),
+        vec!["Merge", "Overwrite", "Back up and overwrite", "Skip"],
+    )
+    .with_vim_mode(true)
+    .prompt()?;
+
+    Ok(match choice {
+        "Merge" => OverwriteMode::Merge,
+        "Overwrite" => OverwriteMode::Overwrite,
+        "Back up and overwrite" => OverwriteMode::Backup,
+        _ => OverwriteMode::Skip,
+    })
+}
+
+/// `orders.yml` is backed up to `orders.yml.bak`.
+fn backup_path(file_path: &Path) -> PathBuf {
+    let mut backup = file_path.as_os_str().to_owned();
+    backup.push(".bak");
+    PathBuf::from(backup)
+}
+
+/// Lexically resolves `destination` against `project_root` without touching the filesystem,
+/// so it also works for directories that don't exist yet.
+fn resolve_destination_path(project_root: &Path, destination: &Path) -> Result<PathBuf> {
+    if destination.is_absolute() {
+        return Ok(normalize_path(destination));
+    }
+
+    let resolved = normalize_path(&project_root.join(destination));
+    if !resolved.starts_with(normalize_path(project_root)) {
+        return Err(anyhow::anyhow!(
+            "Destination path {} resolves to {}, which is outside of the project root {}",
+            destination.display(),
+            resolved.display(),
+            project_root.display()
+        ));
+    }
+
+    Ok(resolved)
+}
+
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+    normalized
+}
+
 pub async fn generate() -> Result<()> {
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::DefaultedColumn;
+
+    #[test]
+    fn test_resolve_destination_path_within_root() {
+        let root = Path::new("/projects/buster");
+        let resolved = resolve_destination_path(root, Path::new("./models/../models/core")).unwrap();
+        assert_eq!(resolved, PathBuf::from("/projects/buster/models/core"));
+    }
+
+    #[test]
+    fn test_resolve_destination_path_rejects_traversal() {
+        let root = Path::new("/projects/buster");
+        assert!(resolve_destination_path(root, Path::new("../../etc")).is_err());
+        assert!(resolve_destination_path(root, Path::new("models/../../other")).is_err());
+    }
+
+    #[test]
+    fn test_resolve_destination_path_allows_absolute() {
+        let root = Path::new("/projects/buster");
+        let resolved = resolve_destination_path(root, Path::new("/tmp/models")).unwrap();
+        assert_eq!(resolved, PathBuf::from("/tmp/models"));
+    }
+
+    #[test]
+    fn test_write_model_file_reports_failed_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let written = dir.path().join("orders.yml");
+        write_model_file("orders", &written, "version: 2\n".to_string(), OverwriteMode::Skip)
+            .unwrap();
+        assert_eq!(fs::read_to_string(&written).unwrap(), "version: 2\n");
+
+        let unwritable = dir.path().join("missing").join("customers.yml");
+        let error =
+            write_model_file("customers", &unwritable, String::new(), OverwriteMode::Skip)
+                .unwrap_err();
+        let failure = GenerateError::WriteFailed { path: unwritable.clone(), error };
+        let message = failure.to_string();
+        assert!(message.starts_with(&unwritable.display().to_string()));
+        assert!(message.contains("No such file or directory"));
+    }
+
+    #[test]
+    fn test_write_model_file_overwrite_modes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("orders.yml");
+        let original = "models:\n- name: orders\n  description: Hand-written\n  dimensions: []\n";
+        let generated = "models:\n- name: orders\n  description: Generated\n  dimensions:\n  \
+                         - name: status\n    expr: status\n    type: string\n";
+        fs::write(&path, original).unwrap();
+
+        write_model_file("orders", &path, generated.to_string(), OverwriteMode::Skip).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), original);
+        assert!(!backup_path(&path).exists());
+
+        write_model_file("orders", &path, generated.to_string(), OverwriteMode::Backup).unwrap();
+        assert_eq!(backup_path(&path), dir.path().join("orders.yml.bak"));
+        assert_eq!(fs::read_to_string(backup_path(&path)).unwrap(), original);
+        assert_eq!(fs::read_to_string(&path).unwrap(), generated);
+
+        fs::write(&path, original).unwrap();
+        fs::remove_file(backup_path(&path)).unwrap();
+        write_model_file("orders", &path, generated.to_string(), OverwriteMode::Overwrite)
+            .unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), generated);
+        assert!(!backup_path(&path).exists());
+
+        fs::write(&path, original).unwrap();
+        write_model_file("orders", &path, generated.to_string(), OverwriteMode::Merge).unwrap();
+        let merged = fs::read_to_string(&path).unwrap();
+        assert!(merged.contains("description: Hand-written"));
+        assert!(merged.contains("name: status"));
+        assert!(!backup_path(&path).exists());
+    }
+
+    #[test]
+    fn test_format_unsupported_report() {
+        let columns = vec![
+            UnsupportedColumn {
+                model_name: "events".to_string(),
+                column_name: "payload".to_string(),
+                data_type: "VARIANT".to_string(),
+            },
+            UnsupportedColumn {
+                model_name: "events".to_string(),
+                column_name: "tags".to_string(),
+                data_type: "ARRAY".to_string(),
+            },
+        ];
+
+        let report = format_unsupported_report(&columns);
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines[1], "⚠️  2 unsupported column(s):");
+        assert_eq!(lines[2], "  MODEL   COLUMN   TYPE");
+        assert_eq!(lines[3], "  events  payload  VARIANT");
+        assert_eq!(lines[4], "  events  tags     ARRAY");
+        assert!(lines[6].contains("`flatten` entry to buster.yml"));
+
+        assert!(format_unsupported_report(&[]).contains("No unsupported columns"));
+    }
+
+    #[test]
+    fn test_format_check_report() {
+        let checks = vec![
+            ModelCheck {
+                model_name: "orders".to_string(),
+                entities: 2,
+                dimensions: 4,
+                measures: 2,
+                unsupported: 0,
+                defaulted_columns: Vec::new(),
+            },
+            ModelCheck {
+                model_name: "events".to_string(),
+                entities: 0,
+                dimensions: 3,
+                measures: 0,
+                unsupported: 1,
+                defaulted_columns: vec![DefaultedColumn {
+                    column_name: "location".to_string(),
+                    data_type: "GEOGRAPHY".to_string(),
+                }],
+            },
+        ];
+
+        let report = format_check_report(&checks);
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines[1], "  MODEL   ENTITIES  DIMENSIONS  MEASURES  UNSUPPORTED");
+        assert_eq!(lines[2], "  orders         2           4         2            0");
+        assert_eq!(lines[3], "  events         0           3         0            1");
+        assert!(lines[5].starts_with("⚠️  1 column(s) have unknown types"));
+        assert_eq!(lines[6], "  events.location: GEOGRAPHY");
+    }
+
+    #[test]
+    fn test_format_metadata_queries() {
+        let queries = vec![
+            "SELECT\n            c.column_name\n        FROM\n            information_schema.columns c;"
+                .to_string(),
+            "\n        SELECT * FROM all_columns\n        ORDER BY\n            ordinal\n        "
+                .to_string(),
+        ];
+
+        assert_eq!(
+            format_metadata_queries(&queries),
+            "-- Metadata query 1 of 2\n\
+             SELECT\n    c.column_name\nFROM\n    information_schema.columns c;\n\n\
+             -- Metadata query 2 of 2\n\
+             SELECT * FROM all_columns\nORDER BY\n    ordinal;\n\n"
+        );
+    }
+
+    #[test]
+    fn test_flatten_hints_from_buster_yml() {
+        let config: BusterConfig = serde_yaml::from_str(
+            "data_source_name: warehouse\n\
+             schema: analytics\n\
+             flatten:\n\
+             \x20 - model_name: events\n\
+             \x20   path: payload:customer.id\n\
+             \x20 - model_name: events\n\
+             \x20   path: payload:amount\n\
+             \x20   name: order_amount\n\
+             \x20   type: number\n",
+        )
+        .unwrap();
+
+        let hints = config.flatten.unwrap();
+        assert_eq!(hints[0].path, "payload:customer.id");
+        assert_eq!((hints[0].name.as_deref(), hints[0].type_.as_deref()), (None, None));
+        assert_eq!(
+            serde_json::to_value(&hints[1]).unwrap(),
+            serde_json::json!({
+                "model_name": "events",
+                "path": "payload:amount",
+                "name": "order_amount",
+                "type": "number",
+            })
+        );
+    }
+
+    #[test]
+    fn test_logical_model_names() {
+        let transform = NameTransform {
+            strip_prefixes: vec!["dim_".to_string(), "fct_".to_string()],
+            strip_suffixes: vec!["_v2".to_string()],
+            renames: vec![(Regex::new("^stg_(.*)$").unwrap(), "staging_$1".to_string())],
+        };
+        let tables = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+
+        let names =
+            logical_model_names(&tables(&["dim_customer", "fct_orders_v2", "stg_events"]), &transform)
+                .unwrap();
+        assert_eq!(names["dim_customer"], "customer");
+        assert_eq!(names["fct_orders_v2"], "orders");
+        assert_eq!(names["stg_events"], "staging_events");
+
+        let error = logical_model_names(&tables(&["dim_orders", "fct_orders", "dim_"]), &transform)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("'dim_orders' and 'fct_orders' would both be named 'orders'"));
+        assert!(error.contains("'dim_' becomes an empty model name"));
+    }
+
+    #[test]
+    fn test_model_file_names_are_stable() {
+        let names = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        let models = names(&["Orders", "customer-events", "Revenue (EUR)", "line__items"]);
+
+        let first = model_file_names(&models).unwrap();
+        let reversed: Vec<String> = models.iter().rev().cloned().collect();
+        assert_eq!(model_file_names(&reversed).unwrap(), first);
+
+        assert_eq!(first["Orders"], "orders.yml");
+        assert_eq!(first["customer-events"], "customer_events.yml");
+        assert_eq!(first["Revenue (EUR)"], "revenue_eur_.yml");
+        assert_eq!(first["line__items"], "line_items.yml");
+
+        let error = model_file_names(&names(&["orders", "Orders", "???"]))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("'Orders' and 'orders' would both be written to orders.yml"));
+        assert!(error.contains("'???' has no characters usable in a file name"));
+    }
+
+    #[test]
+    fn test_legacy_model_file() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("Orders.yml"), "models: []\n").unwrap();
+
+        assert_eq!(
+            legacy_model_file(dir.path(), "Orders", "orders.yml").as_deref(),
+            Some("Orders.yml")
+        );
+        assert_eq!(legacy_model_file(dir.path(), "Customers", "customers.yml"), None);
+        assert_eq!(legacy_model_file(dir.path(), "orders", "orders.yml"), None);
+
+        // Once the current file exists, it's the one kept up to date
+        fs::write(dir.path().join("orders.yml"), "models: []\n").unwrap();
+        assert_eq!(legacy_model_file(dir.path(), "Orders", "orders.yml"), None);
+    }
+
+    #[test]
+    fn test_rename_generated_model() {
+        let yml = "models:\n- name: dim_customer\n  description: Generated model for dim_customer\n  dimensions: []\n  measures: []\n";
+
+        let renamed = rename_generated_model(yml, "dim_customer", "customer").unwrap();
+        assert_eq!(
+            renamed,
+            "models:\n- name: customer\n  database_name: dim_customer\n  description: Generated model for dim_customer\n  dimensions: []\n  measures: []\n"
+        );
+    }
+    #[test]
+    fn test_rename_foreign_entities() {
+        let yml = "models:\n- name: fct_order\n  entities:\n  - name: id\n    expr: id\n    type: primary\n  - name: dim_customer\n    expr: customer_id\n    type: foreign\n";
+        let logical_names = HashMap::from([
+            ("dim_customer".to_string(), "customer".to_string()),
+            ("fct_order".to_string(), "order".to_string()),
+        ]);
+
+        let renamed = rename_foreign_entities(yml, &logical_names).unwrap();
+        assert_eq!(
+            renamed,
+            "models:\n- name: fct_order\n  entities:\n  - name: id\n    expr: id\n    type: primary\n  - name: customer\n    expr: customer_id\n    type: foreign\n"
+        );
+
+        // Entities named after their column keep the name and rename the reference
+        let yml = "models:\n- name: fct_order\n  entities:\n  - name: billing_customer_id\n    ref_: dim_customer\n    expr: billing_customer_id\n    type: foreign\n";
+        let renamed = rename_foreign_entities(yml, &logical_names).unwrap();
+        assert!(renamed.contains("name: billing_customer_id\n    ref_: customer\n"));
+    }
+}
+
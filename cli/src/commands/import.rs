@@ -1,5 +1,311 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use serde_yaml::{Mapping, Value};
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use crate::utils::{
+    buster_credentials::get_and_validate_buster_credentials,
+    lock::{content_hash, BusterLock, LockedModel, LOCK_FILE},
+    BusterClient, DeployedDataset,
+};
+
+/// How import splits the deployed models into files. Files are always YAML, the only
+/// format deploy reads.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ImportLayout {
+    /// One file per file the models were deployed from, as it was deployed
+    #[default]
+    AsDeployed,
+    /// One file per model, named after it
+    PerModel,
+    /// One file per schema, named after it
+    PerSchema,
+    /// Every model in one `models.yml`
+    Single,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportArgs {
+    /// Directory the model files are written to
+    pub path: PathBuf,
+    /// How the models are split into files
+    pub layout: ImportLayout,
+    /// Only import models of this data source
+    pub data_source: Option<String>,
+    /// Only import models in this schema
+    pub schema: Option<String>,
+    /// Also write a `buster.lock` recording each imported model's id and file hash
+    pub lock: bool,
+    /// Overwrite local model files that differ from the deployed ones
+    pub force: bool,
+}
+
+/// Writes the model files of deployed datasets to a directory, laid out by `layout`, so
+/// they can be edited and re-deployed. By default models deployed from the same file are
+/// written back together.
+pub async fn import(args: ImportArgs) -> Result<()> {
+    let creds = get_and_validate_buster_credentials().await?;
+    let client = BusterClient::new(creds.url, creds.api_key)?;
+
+    let datasets = client
+        .list_deployed_datasets(args.data_source.as_deref(), args.schema.as_deref(), true)
+        .await?;
+    if datasets.is_empty() {
+        println!("No deployed models found");
+        return Ok(());
+    }
+
+    std::fs::create_dir_all(&args.path)
+        .map_err(|e| anyhow!("Failed to create {}: {}", args.path.display(), e))?;
+
+    let mut locked = Vec::new();
+    let mut skipped = 0;
+    for (file_name, yml, models) in model_files(&datasets, args.layout)? {
+        let file_path = args.path.join(&file_name);
+        match std::fs::read_to_string(&file_path) {
+            Ok(existing) if existing == yml => {
+                println!("✓ {} is up to date", file_name);
+            }
+            Ok(_) if !args.force => {
+                println!(
+                    "⚠️  Skipping {}: local file differs from the deployed one (use --force to overwrite)",
+                    file_name
+                );
+                skipped += 1;
+                continue;
+            }
+            _ => {
+                std::fs::write(&file_path, &yml)
+                    .map_err(|e| anyhow!("Failed to write {}: {}", file_path.display(), e))?;
+                println!("✅ Wrote {}", file_name);
+            }
+        }
+
+        let hash = content_hash(yml.as_bytes());
+        locked.extend(models.into_iter().map(|dataset| LockedModel {
+            name: dataset.name.clone(),
+            id: dataset.id,
+            data_source_name: dataset.data_source_name.clone(),
+            schema: dataset.schema.clone(),
+            file: file_name.clone(),
+            hash: hash.clone(),
+            updated_at: dataset.updated_at.clone(),
+        }));
+    }
+
+    for dataset in datasets.iter().filter(|d| d.yml_file.is_none()) {
+        println!(
+            "⚠️  Skipping {}: no model file was recorded when it was deployed",
+            dataset.name
+        );
+    }
+
+    println!("\nImported {} models", locked.len());
+    if skipped > 0 {
+        println!("{} files differ locally and were not imported", skipped);
+    }
+
+    if args.lock {
+        let lock_path = args.path.join(LOCK_FILE);
+        BusterLock::new(locked).write(&lock_path)?;
+        println!("🔒 Wrote {}", lock_path.display());
+    }
 
-pub async fn import() -> Result<()> {
     Ok(())
 }
+
+/// The files `layout` writes, each with its name, content and the models it holds.
+/// Datasets without a recorded model file are left out.
+fn model_files(
+    datasets: &[DeployedDataset],
+    layout: ImportLayout,
+) -> Result<Vec<(String, String, Vec<&DeployedDataset>)>> {
+    if layout == ImportLayout::AsDeployed {
+        return Ok(group_by_file(datasets)
+            .into_iter()
+            .map(|(file_name, yml, models)| (file_name, yml.to_string(), models))
+            .collect());
+    }
+
+    let mut groups: Vec<(&str, Vec<&DeployedDataset>)> = Vec::new();
+    for dataset in datasets.iter().filter(|dataset| dataset.yml_file.is_some()) {
+        let group = match layout {
+            ImportLayout::PerSchema => groups
+                .iter_mut()
+                .find(|(schema, _)| schema.eq_ignore_ascii_case(&dataset.schema)),
+            ImportLayout::Single => groups.first_mut(),
+            ImportLayout::AsDeployed | ImportLayout::PerModel => None,
+        };
+        match group {
+            Some((_, models)) => models.push(dataset),
+            None if layout == ImportLayout::PerSchema => {
+                groups.push((&dataset.schema, vec![dataset]))
+            }
+            None if layout == ImportLayout::Single => groups.push(("models", vec![dataset])),
+            None => groups.push((&dataset.name, vec![dataset])),
+        }
+    }
+
+    let mut used = HashSet::new();
+    groups
+        .into_iter()
+        .map(|(stem, models)| {
+            let yml = combined_model_file(&models)?;
+            Ok((unique_file_name(stem, &mut used), yml, models))
+        })
+        .collect()
+}
+
+/// A model file with each model's own entry from the file it was deployed from, and the
+/// first of those files' `version`.
+fn combined_model_file(models: &[&DeployedDataset]) -> Result<String> {
+    let mut version = None;
+    let mut entries = Vec::new();
+    for dataset in models {
+        let yml = dataset.yml_file.as_deref().unwrap_or_default();
+        let file: Value = serde_yaml::from_str(yml)
+            .map_err(|e| anyhow!("Failed to parse the deployed file of {}: {}", dataset.name, e))?;
+        version = version.or_else(|| file.get("version").cloned());
+        let entry = file
+            .get("models")
+            .and_then(Value::as_sequence)
+            .into_iter()
+            .flatten()
+            .find(|model| model.get("name").and_then(Value::as_str) == Some(&dataset.name))
+            .ok_or_else(|| anyhow!("The deployed file of {} doesn't define it", dataset.name))?;
+        entries.push(entry.clone());
+    }
+
+    let mut file = Mapping::new();
+    if let Some(version) = version {
+        file.insert("version".into(), version);
+    }
+    file.insert("models".into(), Value::Sequence(entries));
+    Ok(serde_yaml::to_string(&file)?)
+}
+
+/// `{stem}.yml`, suffixed when a file of that name, in any casing, was already used.
+fn unique_file_name(stem: &str, used: &mut HashSet<String>) -> String {
+    let mut file_name = format!("{}.yml", stem);
+    let mut n = 2;
+    while !used.insert(file_name.to_lowercase()) {
+        file_name = format!("{}_{}.yml", stem, n);
+        n += 1;
+    }
+    file_name
+}
+
+/// Deployed datasets grouped by their model file, in listing order, each with the file
+/// name it's written to: the first model's name, suffixed when two files would share it.
+/// Datasets without a recorded model file are left out.
+fn group_by_file(datasets: &[DeployedDataset]) -> Vec<(String, &str, Vec<&DeployedDataset>)> {
+    let mut groups: Vec<(&str, Vec<&DeployedDataset>)> = Vec::new();
+    for dataset in datasets {
+        let Some(yml) = dataset.yml_file.as_deref() else {
+            continue;
+        };
+        match groups.iter_mut().find(|(content, _)| *content == yml) {
+            Some((_, models)) => models.push(dataset),
+            None => groups.push((yml, vec![dataset])),
+        }
+    }
+
+    let mut used = HashSet::new();
+    groups
+        .into_iter()
+        .map(|(yml, models)| (unique_file_name(&models[0].name, &mut used), yml, models))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn dataset(name: &str, schema: &str, yml: Option<&str>) -> DeployedDataset {
+        DeployedDataset {
+            id: Uuid::nil(),
+            name: name.to_string(),
+            database_name: "prod".to_string(),
+            schema: schema.to_string(),
+            data_source_name: "warehouse".to_string(),
+            type_: "view".to_string(),
+            enabled: true,
+            column_count: 1,
+            updated_at: "2025-02-14T10:00:00Z".to_string(),
+            yml_file: yml.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_group_by_file() {
+        let dataset = |name: &str, yml: Option<&str>| dataset(name, "analytics", yml);
+        let datasets = vec![
+            dataset("orders", Some("models: [orders, order_items]")),
+            dataset("legacy", None),
+            dataset("order_items", Some("models: [orders, order_items]")),
+            dataset("Orders", Some("models: [Orders]")),
+        ];
+
+        let groups: Vec<(String, Vec<&str>)> = group_by_file(&datasets)
+            .into_iter()
+            .map(|(file, _, models)| (file, models.iter().map(|d| d.name.as_str()).collect()))
+            .collect();
+        assert_eq!(
+            groups,
+            vec![
+                ("orders.yml".to_string(), vec!["orders", "order_items"]),
+                ("Orders_2.yml".to_string(), vec!["Orders"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_model_files_per_layout() -> Result<()> {
+        let sales = "version: 1\nmodels:\n- name: orders\n  description: Orders\n\
+                     - name: order_items\n  description: Order lines\n";
+        let crm = "models:\n- name: customers\n  description: People\n";
+        let datasets = vec![
+            dataset("orders", "sales", Some(sales)),
+            dataset("order_items", "sales", Some(sales)),
+            dataset("customers", "crm", Some(crm)),
+            dataset("legacy", "crm", None),
+        ];
+        let layout = |layout: ImportLayout| -> Result<Vec<(String, String, Vec<String>)>> {
+            Ok(model_files(&datasets, layout)?
+                .into_iter()
+                .map(|(file, yml, models)| {
+                    (file, yml, models.iter().map(|d| d.name.clone()).collect())
+                })
+                .collect())
+        };
+
+        let per_model = layout(ImportLayout::PerModel)?;
+        let files: Vec<&str> = per_model.iter().map(|(file, _, _)| file.as_str()).collect();
+        assert_eq!(files, vec!["orders.yml", "order_items.yml", "customers.yml"]);
+        assert_eq!(
+            per_model[1].1,
+            "version: 1\nmodels:\n- name: order_items\n  description: Order lines\n"
+        );
+
+        let per_schema = layout(ImportLayout::PerSchema)?;
+        assert_eq!(
+            per_schema
+                .iter()
+                .map(|(file, _, models)| (file.as_str(), models.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("sales.yml", vec!["orders".to_string(), "order_items".to_string()]),
+                ("crm.yml", vec!["customers".to_string()]),
+            ]
+        );
+        assert_eq!(per_schema[0].1, sales);
+
+        let single = layout(ImportLayout::Single)?;
+        assert_eq!(single.len(), 1);
+        assert_eq!(single[0].0, "models.yml");
+        assert_eq!(single[0].2, vec!["orders", "order_items", "customers"]);
+
+        Ok(())
+    }
+}
@@ -4,13 +4,19 @@ mod deploy_v2;
 mod generate;
 mod import;
 mod init;
+mod models;
+mod schema;
+mod stored_values;
 pub mod version;
 pub mod update;
 
 pub use auth::{auth, auth_with_args, AuthArgs};
 pub use deploy::deploy;
-pub use deploy_v2::deploy_v2;
-pub use generate::GenerateCommand;
-pub use import::import;
+pub use deploy_v2::{default_parallelism, deploy_v2, DeployArgs, DeployOutput};
+pub use generate::{GenerateCommand, GenerateOptions, OverwriteMode};
+pub use import::{import, ImportArgs, ImportLayout};
 pub use init::init;
+pub use models::{models, ModelsOutput};
+pub use schema::{schema, OutputSchema, SchemaTarget};
+pub use stored_values::{stored_values, StoredValuesCommand};
 pub use update::UpdateCommand;
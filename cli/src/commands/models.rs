@@ -0,0 +1,121 @@
+use anyhow::Result;
+
+use crate::utils::{
+    buster_credentials::get_and_validate_buster_credentials, BusterClient, DeployedDataset,
+};
+
+/// How `buster models` prints the deployed datasets.
+#[derive(Debug, Clone, Copy, Default, PartialEq, clap::ValueEnum)]
+pub enum ModelsOutput {
+    #[default]
+    Table,
+    Json,
+}
+
+/// Lists the datasets deployed to Buster, optionally narrowed to one data source
+/// and/or schema, so they can be compared against local model files.
+pub async fn models(
+    data_source: Option<String>,
+    schema: Option<String>,
+    output: ModelsOutput,
+) -> Result<()> {
+    let creds = get_and_validate_buster_credentials().await?;
+    let client = BusterClient::new(creds.url, creds.api_key)?;
+
+    let datasets = client
+        .list_deployed_datasets(data_source.as_deref(), schema.as_deref(), false)
+        .await?;
+
+    match output {
+        ModelsOutput::Json => println!("{}", serde_json::to_string_pretty(&datasets)?),
+        ModelsOutput::Table => print!("{}", format_deployed_datasets(&datasets)),
+    }
+    Ok(())
+}
+
+/// Formats deployed datasets as an aligned table.
+fn format_deployed_datasets(datasets: &[DeployedDataset]) -> String {
+    if datasets.is_empty() {
+        return "No deployed models found\n".to_string();
+    }
+
+    let headers = ["NAME", "DATA SOURCE", "SCHEMA", "TYPE", "ENABLED", "COLUMNS"];
+    let rows: Vec<[String; 6]> = datasets
+        .iter()
+        .map(|dataset| {
+            [
+                dataset.name.clone(),
+                dataset.data_source_name.clone(),
+                dataset.schema.clone(),
+                dataset.type_.clone(),
+                if dataset.enabled { "yes" } else { "no" }.to_string(),
+                dataset.column_count.to_string(),
+            ]
+        })
+        .collect();
+
+    let mut widths = headers.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    let header_row = headers.map(str::to_string);
+    let mut table = String::new();
+    for row in std::iter::once(&header_row).chain(&rows) {
+        let line = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:<width$}", cell))
+            .collect::<Vec<_>>()
+            .join("  ");
+        table.push_str(line.trim_end());
+        table.push('\n');
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_format_deployed_datasets() {
+        let datasets = vec![
+            DeployedDataset {
+                id: Uuid::nil(),
+                name: "orders".to_string(),
+                database_name: "fct_orders".to_string(),
+                schema: "analytics".to_string(),
+                data_source_name: "warehouse".to_string(),
+                type_: "view".to_string(),
+                enabled: true,
+                column_count: 12,
+                updated_at: "2025-02-14T10:00:00Z".to_string(),
+                yml_file: None,
+            },
+            DeployedDataset {
+                id: Uuid::nil(),
+                name: "legacy_orders".to_string(),
+                database_name: "legacy_orders".to_string(),
+                schema: "archive".to_string(),
+                data_source_name: "warehouse".to_string(),
+                type_: "table".to_string(),
+                enabled: false,
+                column_count: 3,
+                updated_at: "2025-01-02T10:00:00Z".to_string(),
+                yml_file: None,
+            },
+        ];
+
+        let table = format_deployed_datasets(&datasets);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[0], "NAME           DATA SOURCE  SCHEMA     TYPE   ENABLED  COLUMNS");
+        assert_eq!(lines[1], "orders         warehouse    analytics  view   yes      12");
+        assert_eq!(lines[2], "legacy_orders  warehouse    archive    table  no       3");
+
+        assert_eq!(format_deployed_datasets(&[]), "No deployed models found\n");
+    }
+}
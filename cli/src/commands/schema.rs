@@ -0,0 +1,251 @@
+use anyhow::Result;
+use schemars::schema_for;
+use serde_json::{json, Value};
+
+use super::deploy_v2::BusterModel;
+use crate::utils::DeployDatasetsRequest;
+
+/// Name of the model file schema `buster generate --output-schema` writes next to the
+/// model files.
+pub const MODEL_SCHEMA_FILE: &str = "buster-models.schema.json";
+
+/// Schema formats `buster generate --output-schema` can write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputSchema {
+    JsonSchema,
+}
+
+/// Formats `buster schema` can describe.
+#[derive(Debug, Clone, Copy, clap::Subcommand)]
+pub enum SchemaTarget {
+    /// The deploy request body (`deploy_request`) and the model YAML file (`model_file`)
+    Deploy,
+}
+
+/// Prints the JSON Schema for `target`. The schemas are derived from the structs deploy
+/// reads and sends, so they can't drift from the actual format.
+pub fn schema(target: SchemaTarget) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(&schema_json(target))?);
+    Ok(())
+}
+
+fn schema_json(target: SchemaTarget) -> Value {
+    match target {
+        SchemaTarget::Deploy => json!({
+            "deploy_request": schema_for!(Vec<DeployDatasetsRequest>),
+            "model_file": schema_for!(BusterModel),
+        }),
+    }
+}
+
+/// JSON Schema of the model files generate writes: the model file format deploy reads,
+/// with the fields generate always writes marked required. Entities are only written
+/// for declared keys, so they're left out unless `emit_entities` is set.
+pub fn generated_model_schema(emit_entities: bool) -> Value {
+    let mut schema = serde_json::to_value(schema_for!(BusterModel))
+        .expect("JSON Schema always serializes");
+    require(&mut schema, "Model", &["dimensions", "measures"]);
+    require(&mut schema, "Dimension", &["searchable"]);
+    require(&mut schema, "Measure", &["agg", "expr", "type"]);
+    if !emit_entities {
+        let model = &mut schema["definitions"]["Model"]["properties"];
+        if let Some(properties) = model.as_object_mut() {
+            properties.remove("entities");
+        }
+    }
+    schema
+}
+
+fn require(schema: &mut Value, definition: &str, fields: &[&str]) {
+    let required = &mut schema["definitions"][definition]["required"];
+    let mut names: Vec<String> = required
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|name| name.as_str().map(str::to_string))
+        .chain(fields.iter().map(|field| field.to_string()))
+        .collect();
+    names.sort();
+    names.dedup();
+    *required = json!(names);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deploy_schema_describes_request_and_model_file() {
+        let schema = schema_json(SchemaTarget::Deploy);
+
+        let request = &schema["deploy_request"];
+        assert_eq!(request["type"], "array");
+        let request_fields = &request["definitions"]["DeployDatasetsRequest"]["properties"];
+        assert!(request_fields["data_source_name"].is_object());
+        assert!(request_fields["type"].is_object());
+        assert!(request_fields.get("type_").is_none());
+
+        let model_file = &schema["model_file"];
+        assert!(model_file["properties"]["models"].is_object());
+        let model_fields = &model_file["definitions"]["Model"]["properties"];
+        assert!(model_fields["dimensions"].is_object());
+        assert!(model_fields["grain"].is_object());
+        assert_eq!(
+            model_file["definitions"]["Dimension"]["required"],
+            json!(["description", "expr", "name", "type"])
+        );
+    }
+
+    #[test]
+    fn test_generated_model_schema_follows_flags() {
+        let schema = generated_model_schema(false);
+        let definitions = &schema["definitions"];
+        assert_eq!(
+            definitions["Model"]["required"],
+            json!(["description", "dimensions", "measures", "name"])
+        );
+        assert_eq!(
+            definitions["Dimension"]["required"],
+            json!(["description", "expr", "name", "searchable", "type"])
+        );
+        assert_eq!(
+            definitions["Measure"]["required"],
+            json!(["agg", "description", "expr", "name", "type"])
+        );
+        assert!(definitions["Model"]["properties"].get("entities").is_none());
+
+        let schema = generated_model_schema(true);
+        assert!(schema["definitions"]["Model"]["properties"]["entities"].is_object());
+    }
+
+    /// Where `value` breaks `schema`: missing required fields, fields it doesn't describe,
+    /// and values of the wrong JSON type.
+    fn violations(root: &Value, schema: &Value, value: &Value, path: &str) -> Vec<String> {
+        if let Some(reference) = schema["$ref"].as_str() {
+            let name = reference.trim_start_matches("#/definitions/");
+            return violations(root, &root["definitions"][name], value, path);
+        }
+        if let Some(variants) = schema["anyOf"].as_array() {
+            let matches = variants
+                .iter()
+                .any(|variant| violations(root, variant, value, path).is_empty());
+            return if matches {
+                Vec::new()
+            } else {
+                vec![format!("{} matches none of its variants", path)]
+            };
+        }
+
+        let json_type = match value {
+            Value::Null => "null",
+            Value::Bool(_) => "boolean",
+            Value::Number(number) if number.is_i64() || number.is_u64() => "integer",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::Array(_) => "array",
+            Value::Object(_) => "object",
+        };
+        let allowed: Vec<&str> = match &schema["type"] {
+            Value::String(type_) => vec![type_.as_str()],
+            Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        let type_matches = allowed.is_empty()
+            || allowed.contains(&json_type)
+            || (json_type == "integer" && allowed.contains(&"number"));
+        if !type_matches {
+            return vec![format!("{} is {}, not {:?}", path, json_type, allowed)];
+        }
+
+        let mut found = Vec::new();
+        match value {
+            Value::Object(fields) => {
+                for required in schema["required"].as_array().into_iter().flatten() {
+                    let required = required.as_str().unwrap_or_default();
+                    if !fields.contains_key(required) {
+                        found.push(format!("{} is missing {}", path, required));
+                    }
+                }
+                for (name, field) in fields {
+                    let field_path = format!("{}.{}", path, name);
+                    match schema["properties"].get(name) {
+                        Some(property) => {
+                            found.extend(violations(root, property, field, &field_path))
+                        }
+                        None => found.push(format!("{} isn't in the schema", field_path)),
+                    }
+                }
+            }
+            Value::Array(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    let item_path = format!("{}[{}]", path, index);
+                    found.extend(violations(root, &schema["items"], item, &item_path));
+                }
+            }
+            _ => (),
+        }
+        found
+    }
+
+    #[test]
+    fn test_generated_yaml_matches_generated_model_schema() {
+        // Every field the API's generate writes, as it serializes them
+        let yml = r#"
+            models:
+              - name: orders
+                label: Orders
+                description: One row per order
+                when_to_use: Revenue and order counts
+                entities:
+                  - name: customers
+                    ref_: customers
+                    expr: customer_id
+                    type: foreign
+                    description: The customer who placed the order
+                dimensions:
+                  - name: status
+                    label: Status
+                    expr: status
+                    type: string
+                    description: Fulfillment state
+                    searchable: false
+                  - name: customer_id
+                    label: Customer Id
+                    expr: customer_id
+                    type: string
+                    description: Customer reference
+                    searchable: false
+                    hidden: true
+                measures:
+                  - name: amount
+                    label: Amount
+                    expr: amount
+                    type: number
+                    agg: sum
+                    description: Order value
+                    hidden: false
+        "#;
+        let generated: Value = serde_yaml::from_str(yml).unwrap();
+
+        let schema = generated_model_schema(true);
+        assert_eq!(violations(&schema, &schema, &generated, "$"), Vec::<String>::new());
+
+        // Entities are only described when generate emits them
+        let schema = generated_model_schema(false);
+        assert_eq!(
+            violations(&schema, &schema, &generated, "$"),
+            vec!["$.models[0].entities isn't in the schema"]
+        );
+
+        let mut incomplete = generated.clone();
+        incomplete["models"][0]["dimensions"][0]
+            .as_object_mut()
+            .unwrap()
+            .remove("searchable");
+        let schema = generated_model_schema(true);
+        assert_eq!(
+            violations(&schema, &schema, &incomplete, "$"),
+            vec!["$.models[0].dimensions[0] is missing searchable"]
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use anyhow::Result;
+
+use crate::utils::{
+    buster_credentials::get_and_validate_buster_credentials, BusterClient, StoredValuesBackfill,
+};
+
+/// Subcommands of `buster stored-values`.
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum StoredValuesCommand {
+    /// Show how far a data source's stored values backfill has got, per column
+    Status {
+        /// Data source whose searchable columns are reported
+        #[arg(long)]
+        data_source: String,
+        #[arg(long, value_enum, default_value_t = StoredValuesOutput::Table)]
+        output: StoredValuesOutput,
+    },
+}
+
+/// How `buster stored-values status` prints the backfill.
+#[derive(Debug, Clone, Copy, Default, PartialEq, clap::ValueEnum)]
+pub enum StoredValuesOutput {
+    #[default]
+    Table,
+    Json,
+}
+
+pub async fn stored_values(command: StoredValuesCommand) -> Result<()> {
+    match command {
+        StoredValuesCommand::Status {
+            data_source,
+            output,
+        } => {
+            let creds = get_and_validate_buster_credentials().await?;
+            let client = BusterClient::new(creds.url, creds.api_key)?;
+
+            let backfill = client.stored_values_status(&data_source).await?;
+            match output {
+                StoredValuesOutput::Json => {
+                    println!("{}", serde_json::to_string_pretty(&backfill)?)
+                }
+                StoredValuesOutput::Table => print!("{}", format_backfill(&backfill)),
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Formats the backfill as a progress line, an aligned table of its columns and why each
+/// column that failed, was truncated or was skipped didn't store all its values.
+fn format_backfill(backfill: &StoredValuesBackfill) -> String {
+    if backfill.columns.is_empty() {
+        return format!(
+            "No searchable columns with stored values in {}\n",
+            backfill.data_source_name
+        );
+    }
+
+    let mut output = format!(
+        "{}: {:.1}% complete ({} synced, {} truncated, {} skipped, {} failed, {} syncing, \
+         {} pending of {} columns)\n\n",
+        backfill.data_source_name,
+        backfill.percent_complete,
+        backfill.success,
+        backfill.truncated,
+        backfill.skipped,
+        backfill.failed,
+        backfill.syncing,
+        backfill.pending,
+        backfill.total
+    );
+
+    let headers = ["DATASET", "COLUMN", "STATUS", "VALUES", "LAST SYNCED"];
+    let rows: Vec<[String; 5]> = backfill
+        .columns
+        .iter()
+        .map(|column| {
+            [
+                column.dataset_name.clone(),
+                column.column_name.clone(),
+                column
+                    .stored_values_status
+                    .clone()
+                    .unwrap_or_else(|| "pending".to_string()),
+                column
+                    .stored_values_count
+                    .map(|count| count.to_string())
+                    .unwrap_or_default(),
+                column.stored_values_last_synced.clone().unwrap_or_default(),
+            ]
+        })
+        .collect();
+
+    let mut widths = headers.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    let header_row = headers.map(str::to_string);
+    for row in std::iter::once(&header_row).chain(&rows) {
+        let line = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:<width$}", cell))
+            .collect::<Vec<_>>()
+            .join("  ");
+        output.push_str(line.trim_end());
+        output.push('\n');
+    }
+
+    let reasons: Vec<String> = backfill
+        .columns
+        .iter()
+        .filter_map(|column| {
+            let reason = column.stored_values_error.as_ref()?;
+            Some(format!(
+                "{}.{} {}: {}",
+                column.dataset_name,
+                column.column_name,
+                column.stored_values_status.as_deref().unwrap_or("pending"),
+                reason
+            ))
+        })
+        .collect();
+    if !reasons.is_empty() {
+        output.push('\n');
+        for reason in reasons {
+            output.push_str(&reason);
+            output.push('\n');
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::StoredValuesColumnStatus;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_format_backfill() {
+        let column = |name: &str, status: Option<&str>, count, error: Option<&str>| {
+            StoredValuesColumnStatus {
+                dataset_id: Uuid::nil(),
+                dataset_name: "orders".to_string(),
+                column_name: name.to_string(),
+                stored_values_status: status.map(str::to_string),
+                stored_values_count: count,
+                stored_values_last_synced: count.map(|_| "2025-02-16T10:00:00Z".to_string()),
+                stored_values_error: error.map(str::to_string),
+            }
+        };
+        let backfill = StoredValuesBackfill {
+            data_source_name: "warehouse".to_string(),
+            total: 4,
+            pending: 1,
+            syncing: 0,
+            success: 1,
+            failed: 1,
+            truncated: 1,
+            skipped: 0,
+            percent_complete: 75.0,
+            columns: vec![
+                column("status", Some("success"), Some(4), None),
+                column("region", Some("failed"), None, Some("query timed out")),
+                column("customer_name", None, None, None),
+                column(
+                    "city",
+                    Some("truncated"),
+                    Some(10000),
+                    Some("Stopped at the cap of 10000 distinct values per column"),
+                ),
+            ],
+        };
+
+        let output = format_backfill(&backfill);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines[0],
+            "warehouse: 75.0% complete (1 synced, 1 truncated, 0 skipped, 1 failed, 0 syncing, \
+             1 pending of 4 columns)"
+        );
+        assert_eq!(
+            lines[2],
+            "DATASET  COLUMN         STATUS     VALUES  LAST SYNCED"
+        );
+        assert_eq!(
+            lines[3],
+            "orders   status         success    4       2025-02-16T10:00:00Z"
+        );
+        assert_eq!(lines[4], "orders   region         failed");
+        assert_eq!(lines[5], "orders   customer_name  pending");
+        assert_eq!(lines[8], "orders.region failed: query timed out");
+        assert_eq!(
+            lines[9],
+            "orders.city truncated: Stopped at the cap of 10000 distinct values per column"
+        );
+    }
+}
@@ -3,7 +3,7 @@ use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::fs;
 use std::env;
-use reqwest::Client;
+use crate::utils::http::{http_client, send_with_retry};
 use indicatif::{ProgressBar, ProgressStyle};
 use colored::*;
 use futures_util::StreamExt;
@@ -31,9 +31,10 @@ impl UpdateCommand {
     pub async fn execute(&self) -> Result<()> {
         // Check current version and latest version
         let current_version = env!("CARGO_PKG_VERSION");
-        let latest_version = super::version::check_latest_version()
+        let latest = super::version::check_latest_version(current_version)
             .await?
             .context("Failed to get latest version")?;
+        let latest_version = latest.version;
 
         println!("Current version: {}", current_version);
         println!("Latest version: {}", latest_version);
@@ -57,12 +58,14 @@ impl UpdateCommand {
         if self.check_only {
             if update_available {
                 println!("\n{}", "Update available!".yellow().bold());
+                print_changelog(&latest.changelog);
                 println!("Run {} to update", "buster update".cyan());
             }
             return Ok(());
         }
 
         if !self.no_prompt {
+            print_changelog(&latest.changelog);
             let confirm = Confirm::new("Do you want to update to the latest version?")
                 .with_default(true)
                 .with_help_message("This will replace your current binary with the latest version")
@@ -166,12 +169,8 @@ impl UpdateCommand {
     }
 
     async fn download_file(&self, url: &str, path: &Path) -> Result<()> {
-        let client = Client::new();
-        let response = client
-            .get(url)
-            .header("User-Agent", "buster-cli")
-            .send()
-            .await?;
+        let client = http_client()?;
+        let response = send_with_retry(&client, client.get(url)).await?;
 
         if !response.status().is_success() {
             return Err(anyhow::anyhow!(
@@ -281,4 +280,15 @@ impl UpdateCommand {
 
         Ok(output.status.success())
     }
-} 
\ No newline at end of file
+}
+
+/// Prints the notes of the releases the update would install, so they can be read before
+/// deciding.
+fn print_changelog(changelog: &str) {
+    if changelog.is_empty() {
+        return;
+    }
+    println!("\n{}", "What's new:".bold());
+    println!("{}", changelog.trim_end());
+    println!();
+}
@@ -1,25 +1,61 @@
 use anyhow::Result;
-use reqwest::Client;
 use serde::Deserialize;
 
-const GITHUB_API_URL: &str = "https://api.github.com/repos/buster-so/buster/releases/latest";
+use crate::utils::http::{http_client, send_with_retry};
+
+const GITHUB_API_URL: &str = "https://api.github.com/repos/buster-so/buster/releases?per_page=100";
 
 #[derive(Deserialize)]
 struct GitHubRelease {
     tag_name: String,
     body: Option<String>,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    prerelease: bool,
+}
+
+/// The newest published release and the notes of every release after a given version.
+pub struct LatestRelease {
+    pub version: String,
+    /// Notes of the releases newer than the version asked about, newest first
+    pub changelog: String,
 }
 
-pub async fn check_latest_version() -> Result<Option<String>> {
-    let client = Client::new();
-    let response = client
-        .get(GITHUB_API_URL)
-        .header("User-Agent", "buster-cli")
-        .send()
-        .await?;
+/// Looks up the newest release, collecting the release notes published since `current`.
+pub async fn check_latest_version(current: &str) -> Result<Option<LatestRelease>> {
+    let client = http_client()?;
+    let response = send_with_retry(&client, client.get(GITHUB_API_URL)).await?;
+
+    // Releases come newest first; drafts and prereleases aren't offered as updates
+    let releases: Vec<GitHubRelease> = response.json().await?;
+    let releases: Vec<GitHubRelease> = releases
+        .into_iter()
+        .filter(|release| !release.draft && !release.prerelease)
+        .collect();
+
+    Ok(releases.first().map(|latest| LatestRelease {
+        version: latest.tag_name.clone(),
+        changelog: changelog_since(&releases, current),
+    }))
+}
 
-    let release: GitHubRelease = response.json().await?;
-    Ok(Some(release.tag_name))
+/// The notes of each release newer than `current`, under a heading with its version.
+fn changelog_since(releases: &[GitHubRelease], current: &str) -> String {
+    releases
+        .iter()
+        .filter(|release| is_update_available(current, &release.tag_name))
+        .map(|release| {
+            let notes = release
+                .body
+                .as_deref()
+                .map(str::trim)
+                .filter(|body| !body.is_empty())
+                .unwrap_or("No release notes");
+            format!("## {}\n{}\n", release.tag_name, notes)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 pub fn is_update_available(current: &str, latest: &str) -> bool {
@@ -45,4 +81,30 @@ pub fn is_update_available(current: &str, latest: &str) -> bool {
 
     // If we get here and latest has more components, it's newer
     latest_parts.len() > current_parts.len()
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changelog_since() {
+        let release = |tag: &str, body: Option<&str>| GitHubRelease {
+            tag_name: tag.to_string(),
+            body: body.map(str::to_string),
+            draft: false,
+            prerelease: false,
+        };
+        let releases = vec![
+            release("v0.0.6", Some("- Faster deploys\n")),
+            release("v0.0.5", None),
+            release("v0.0.4", Some("- Older fix")),
+        ];
+
+        assert_eq!(
+            changelog_since(&releases, "0.0.4"),
+            "## v0.0.6\n- Faster deploys\n\n## v0.0.5\nNo release notes\n"
+        );
+        assert_eq!(changelog_since(&releases, "0.0.6"), "");
+    }
+}
@@ -1,23 +1,150 @@
+use serde_json::{json, Map, Value};
 use std::path::PathBuf;
 use thiserror::Error;
 
+use crate::commands::auth::AuthError;
+
 #[derive(Debug, Error)]
 pub enum BusterError {
     #[error("Invalid credentials")]
     InvalidCredentials,
+    #[error(
+        "The API key saved for {host} was rejected as invalid or expired. Run `buster auth` \
+         to save a new one, or `buster auth --test` to check it"
+    )]
+    CredentialsRejected { host: String },
+    #[error(
+        "No API key for {host}. The saved key is only sent to the host it was saved for; \
+         pass `--api-key` or set BUSTER_API_KEY"
+    )]
+    MissingApiKey { host: String },
     #[error("File not found: {path}")]
     FileNotFound { path: PathBuf },
     #[error("Failed to parse file: {error}")]
     ParseError { error: String },
     #[error("Failed to write file: {path}")]
     FileWriteError { path: PathBuf, error: String },
+    #[error("{message}. Run `buster update` to install the latest CLI")]
+    IncompatibleCli { message: String },
     #[error("Other: {0}")]
     Other(String),
 }
 
+impl BusterError {
+    /// Stable identifier for the variant, used by `--json-errors`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            BusterError::InvalidCredentials => "invalid_credentials",
+            BusterError::CredentialsRejected { .. } => "credentials_rejected",
+            BusterError::MissingApiKey { .. } => "missing_api_key",
+            BusterError::FileNotFound { .. } => "file_not_found",
+            BusterError::ParseError { .. } => "parse_error",
+            BusterError::FileWriteError { .. } => "file_write_error",
+            BusterError::IncompatibleCli { .. } => "incompatible_cli",
+            BusterError::Other(_) => "other",
+        }
+    }
+
+    fn context(&self) -> Map<String, Value> {
+        let mut context = Map::new();
+        match self {
+            BusterError::FileNotFound { path } => {
+                context.insert("path".to_string(), json!(path.display().to_string()));
+            }
+            BusterError::ParseError { error } => {
+                context.insert("error".to_string(), json!(error));
+            }
+            BusterError::FileWriteError { path, error } => {
+                context.insert("path".to_string(), json!(path.display().to_string()));
+                context.insert("error".to_string(), json!(error));
+            }
+            BusterError::CredentialsRejected { host } | BusterError::MissingApiKey { host } => {
+                context.insert("host".to_string(), json!(host));
+            }
+            BusterError::IncompatibleCli { message } => {
+                context.insert("server_message".to_string(), json!(message));
+            }
+            BusterError::InvalidCredentials | BusterError::Other(_) => {}
+        }
+        context
+    }
+}
+
 // Add this near other error-related code
 impl From<anyhow::Error> for BusterError {
     fn from(error: anyhow::Error) -> Self {
         BusterError::Other(error.to_string())
     }
 }
+
+/// Serializes a command's final error as `{ "error": { "code", "message", "context" } }`.
+/// The code comes from the first structured error found in the chain; anything else is
+/// reported as `"error"`, with the underlying causes listed in the context.
+pub fn error_to_json(error: &anyhow::Error) -> Value {
+    let mut code = "error";
+    let mut context = Map::new();
+
+    for cause in error.chain() {
+        if let Some(e) = cause.downcast_ref::<BusterError>() {
+            code = e.code();
+            context = e.context();
+            break;
+        }
+        if let Some(e) = cause.downcast_ref::<AuthError>() {
+            code = e.code();
+            break;
+        }
+    }
+
+    let causes: Vec<String> = error.chain().skip(1).map(|c| c.to_string()).collect();
+    if !causes.is_empty() {
+        context.insert("causes".to_string(), json!(causes));
+    }
+
+    json!({
+        "error": {
+            "code": code,
+            "message": error.to_string(),
+            "context": context,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_error_to_json() {
+        let error = Err::<(), _>(BusterError::FileNotFound {
+            path: PathBuf::from("/tmp/credentials.yml"),
+        })
+        .context("Failed to load credentials")
+        .unwrap_err();
+
+        let value = error_to_json(&error);
+        assert_eq!(value["error"]["code"], "file_not_found");
+        assert_eq!(value["error"]["message"], "Failed to load credentials");
+        assert_eq!(value["error"]["context"]["path"], "/tmp/credentials.yml");
+        assert_eq!(
+            value["error"]["context"]["causes"][0],
+            "File not found: /tmp/credentials.yml"
+        );
+
+        let error: anyhow::Error = BusterError::IncompatibleCli {
+            message: "Buster CLI 0.0.1 is no longer supported".to_string(),
+        }
+        .into();
+        let value = error_to_json(&error);
+        assert_eq!(value["error"]["code"], "incompatible_cli");
+        assert!(value["error"]["message"]
+            .as_str()
+            .unwrap()
+            .ends_with("Run `buster update` to install the latest CLI"));
+
+        let value = error_to_json(&anyhow::anyhow!("Deploy failed"));
+        assert_eq!(value["error"]["code"], "error");
+        assert_eq!(value["error"]["context"], json!({}));
+    }
+}
@@ -5,8 +5,17 @@ mod utils;
 
 use clap::{Parser, Subcommand};
 use colored::*;
-use commands::{auth::AuthArgs, deploy, deploy_v2, import, init, GenerateCommand};
+use commands::{
+    auth::AuthArgs, default_parallelism, deploy, deploy_v2, import, init, models, schema,
+    stored_values,
+    DeployArgs, DeployOutput, GenerateCommand, GenerateOptions, ImportArgs, ImportLayout,
+    ModelsOutput, OutputSchema, OverwriteMode, SchemaTarget, StoredValuesCommand,
+};
 use std::path::PathBuf;
+use utils::{
+    buster_credentials::{set_credentials_override, CredentialsOverride},
+    ColumnDeleteMode, IdentifierCase, MeasureAgg,
+};
 
 pub const APP_NAME: &str = "buster";
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -23,13 +32,25 @@ pub enum Commands {
         #[arg(long, env = "BUSTER_HOST")]
         host: Option<String>,
 
-        /// Your Buster API key
+        /// Your Buster API key, or `-` to read it from stdin
         #[arg(long, env = "BUSTER_API_KEY")]
         api_key: Option<String>,
 
+        /// Read the API key from stdin, e.g. piped from a secrets manager
+        #[arg(long)]
+        api_key_stdin: bool,
+
         /// Don't save credentials to disk
         #[arg(long)]
         no_save: bool,
+
+        /// Only check that the credentials are valid; nothing is saved
+        #[arg(long)]
+        test: bool,
+
+        /// Skip logging in when the credentials saved for the host are still valid
+        #[arg(long)]
+        if_needed: bool,
     },
     /// Display version information
     Version,
@@ -54,15 +75,207 @@ pub enum Commands {
         data_source_name: Option<String>,
         #[arg(long)]
         schema: Option<String>,
+        /// Database columns are read from, overriding buster.yml for every model
         #[arg(long)]
         database: Option<String>,
+        /// Casing applied to generated names. Expressions keep the warehouse's case.
+        #[arg(long, value_enum, default_value_t = IdentifierCase::Preserve)]
+        identifier_case: IdentifierCase,
+        /// Detect the warehouse dialect (e.g. Postgres vs Redshift) from its version banner
+        #[arg(long, default_value_t = false)]
+        dialect_autodetect: bool,
+        /// Append up to 5 distinct example values to string column descriptions.
+        /// Runs a query per string column against the warehouse.
+        #[arg(long, default_value_t = false)]
+        sample_values: bool,
+        /// Mark generated dimensions as searchable
+        #[arg(long, default_value_t = false)]
+        default_searchable: bool,
+        /// Aggregation set on generated measures
+        #[arg(long, value_enum, default_value_t = MeasureAgg::Sum)]
+        default_agg: MeasureAgg,
+        /// List columns with unsupported warehouse types instead of writing YAML
+        #[arg(long, default_value_t = false)]
+        report_unsupported: bool,
+        /// Keep writing the other models when a file can't be written; failures are listed at the end
+        #[arg(long, default_value_t = false)]
+        continue_on_error: bool,
+        /// Prefix removed from table names to form model names (repeatable)
+        #[arg(long)]
+        strip_prefix: Vec<String>,
+        /// Suffix removed from table names to form model names (repeatable)
+        #[arg(long)]
+        strip_suffix: Vec<String>,
+        /// Read columns and descriptions from this dbt target directory's catalog.json
+        /// and manifest.json instead of the warehouse
+        #[arg(
+            long,
+            value_name = "TARGET_DIR",
+            conflicts_with_all = ["sample_values", "dialect_autodetect"]
+        )]
+        dbt: Option<PathBuf>,
+        /// Add entities for the primary and foreign keys declared in the warehouse
+        /// (Postgres and MySQL only)
+        #[arg(long)]
+        emit_entities_from_fk: bool,
+        /// Print how each model's columns would map instead of writing YAML; fails if any
+        /// column is unsupported or has an unknown type
+        #[arg(long, conflicts_with = "report_unsupported")]
+        check: bool,
+        /// Also write a schema of the generated model files to the output directory, for
+        /// editor validation and autocomplete
+        #[arg(long, value_enum, conflicts_with_all = ["report_unsupported", "check"])]
+        output_schema: Option<OutputSchema>,
+        /// What to do with model files that already exist; defaults to `prompt` on a
+        /// terminal and `skip` otherwise
+        #[arg(long, value_enum)]
+        overwrite_mode: Option<OverwriteMode>,
+        /// Print the metadata queries the warehouse's columns would be read with to stderr,
+        /// instead of generating
+        #[arg(long, conflicts_with_all = ["dbt", "report_unsupported", "check"])]
+        dry_run_sql: bool,
+    },
+    /// Write the model files of deployed models to a directory
+    Import {
+        /// Directory to write the model files to
+        #[arg(long, default_value = ".")]
+        path: PathBuf,
+        /// How to split the models into files
+        #[arg(long, value_enum, default_value_t = ImportLayout::AsDeployed)]
+        layout: ImportLayout,
+        /// Only import models of this data source
+        #[arg(long)]
+        data_source: Option<String>,
+        /// Only import models in this schema
+        #[arg(long)]
+        schema: Option<String>,
+        /// Also write buster.lock with each model's dataset id and file hash
+        #[arg(long, default_value_t = false)]
+        lock: bool,
+        /// Overwrite local model files that differ from the deployed ones
+        #[arg(long, default_value_t = false)]
+        force: bool,
+    },
+    /// List the models currently deployed to Buster
+    Models {
+        /// Only list models of this data source
+        #[arg(long)]
+        data_source: Option<String>,
+        /// Only list models in this schema
+        #[arg(long)]
+        schema: Option<String>,
+        #[arg(long, value_enum, default_value_t = ModelsOutput::Table)]
+        output: ModelsOutput,
     },
-    Import,
     Deploy {
+        /// File or directory to deploy; repeat to deploy several (defaults to the current directory)
         #[arg(long)]
-        path: Option<String>,
+        path: Vec<String>,
         #[arg(long, default_value_t = false)]
         dry_run: bool,
+        /// Number of model files to parse concurrently (defaults to the number of CPUs)
+        #[arg(long)]
+        parallel: Option<usize>,
+        /// Only print the final deployment counts and a line per failed model
+        #[arg(long, default_value_t = false)]
+        summary_only: bool,
+        /// Print a single line when every model deploys, and full detail when any fails
+        #[arg(long, default_value_t = false, conflicts_with = "summary_only")]
+        quiet_success: bool,
+        /// Warn about column names that don't match the naming convention
+        #[arg(long, default_value_t = false)]
+        lint_names: bool,
+        /// Warn about empty, very short, or name-only descriptions, and have the server check
+        /// their markdown
+        #[arg(long, default_value_t = false)]
+        lint_descriptions: bool,
+        /// Fail the deploy when any warnings are reported
+        #[arg(long, default_value_t = false)]
+        fail_on_warning: bool,
+        /// Detect the warehouse dialect (e.g. Postgres vs Redshift) from its version banner
+        #[arg(long, default_value_t = false)]
+        dialect_autodetect: bool,
+        /// Check the warehouse for duplicate rows per model grain (scans each table)
+        #[arg(long, default_value_t = false)]
+        verify_grain: bool,
+        /// Warn about warehouse columns a model doesn't declare (errors with --fail-on-warning)
+        #[arg(long, default_value_t = false)]
+        strict_schema: bool,
+        /// When a model's table isn't found, list similarly named tables in its schema
+        #[arg(long, default_value_t = false)]
+        explain_matching: bool,
+        /// After deploying, write model and column descriptions to the warehouse as comments
+        #[arg(long, default_value_t = false)]
+        write_comments: bool,
+        /// Deploy every model to this data source, overriding model files and buster.yml
+        #[arg(long)]
+        data_source: Option<String>,
+        /// Validate and deploy every model against this database (e.g. a clone), overriding
+        /// model files and buster.yml
+        #[arg(long)]
+        database: Option<String>,
+        /// Env of models that don't set one, taking precedence over buster.yml
+        #[arg(long)]
+        env: Option<String>,
+        /// Data source groups the server validates in parallel (server default when unset)
+        #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+        concurrency: Option<u16>,
+        /// Print each model's result as a JSON line as it arrives, then a summary line
+        #[arg(long, value_enum, default_value_t = DeployOutput::Text, conflicts_with = "dry_run")]
+        output: DeployOutput,
+        /// With --dry-run, write the models that would be deployed, and what each changes on
+        /// the server, to a JSON plan file
+        #[arg(long, requires = "dry_run")]
+        dump_plan: Option<PathBuf>,
+        /// Deploy exactly the models in a plan written by --dump-plan, ignoring model files.
+        /// Refuses a plan whose models changed on the server since
+        #[arg(long, conflicts_with = "dry_run")]
+        apply_plan: Option<PathBuf>,
+        /// Deploy exactly the model files listed in a manifest.json written by generate, in
+        /// its order, instead of the files found in the paths
+        #[arg(long, conflicts_with = "apply_plan")]
+        models_from_manifest: Option<PathBuf>,
+        /// After the summary, print the slowest models and each data source's fetch/write time
+        #[arg(long, default_value_t = false, conflicts_with = "dry_run")]
+        timings: bool,
+        /// Warn about models that changed since the import recorded in this buster.lock
+        #[arg(long, conflicts_with = "apply_plan")]
+        lock: Option<PathBuf>,
+        /// Mark columns removed from a model deleted (soft) or remove them (hard);
+        /// overrides buster.yml's column_delete_mode
+        #[arg(long, value_enum)]
+        column_delete_mode: Option<ColumnDeleteMode>,
+        /// With hard deletes, also delete removed columns that have stored values
+        #[arg(long, default_value_t = false)]
+        force: bool,
+        /// Only create models that aren't deployed yet; existing ones are left untouched
+        #[arg(long, default_value_t = false)]
+        only_new: bool,
+        /// Leave a column out of the deploy, as model.column (repeatable); a deployed
+        /// column is kept as it is
+        #[arg(long = "exclude-column", value_name = "MODEL.COLUMN")]
+        exclude_column: Vec<String>,
+        /// Delete deployed columns that are skipped, as if they'd been removed
+        #[arg(long, default_value_t = false)]
+        prune_skipped: bool,
+        /// Buster host to deploy to (repeatable); several hosts each get the whole deploy.
+        /// Overrides buster.yml's hosts
+        #[arg(long = "host", value_name = "URL")]
+        hosts: Vec<String>,
+        /// Fail when the deploy fails on any host, not only when it fails on all of them
+        #[arg(long, default_value_t = false)]
+        require_all: bool,
+    },
+    /// Inspect the values synced for searchable columns
+    StoredValues {
+        #[command(subcommand)]
+        command: StoredValuesCommand,
+    },
+    /// Print the JSON Schema of a request or file format, for editors and external tools
+    #[command(hide = true)]
+    Schema {
+        #[command(subcommand)]
+        target: SchemaTarget,
     },
 }
 
@@ -70,11 +283,37 @@ pub enum Commands {
 pub struct Args {
     #[command(subcommand)]
     pub cmd: Commands,
+
+    /// Talk to this Buster API host for this command only, instead of the saved one. Given
+    /// before the command, e.g. `buster --host https://staging.example.com deploy`
+    #[arg(long)]
+    pub host: Option<String>,
+
+    /// API key for this command only, instead of the saved one. With `--host` it otherwise
+    /// comes from `BUSTER_API_KEY`, then the saved key
+    #[arg(long)]
+    pub api_key: Option<String>,
+
+    /// Print the final error to stderr as JSON: {"error": {"code", "message", "context"}}
+    #[arg(
+        long,
+        global = true,
+        env = "BUSTER_JSON_ERRORS",
+        value_parser = clap::builder::FalseyValueParser::new()
+    )]
+    pub json_errors: bool,
 }
 
 #[tokio::main]
 async fn main() {
     let args = Args::parse();
+    let json_errors = args.json_errors;
+    if args.host.is_some() || args.api_key.is_some() {
+        set_credentials_override(CredentialsOverride {
+            host: args.host,
+            api_key: args.api_key,
+        });
+    }
 
     // TODO: All commands should check for an update.
     let result = match args.cmd {
@@ -82,12 +321,18 @@ async fn main() {
         Commands::Auth {
             host,
             api_key,
+            api_key_stdin,
             no_save,
+            test,
+            if_needed,
         } => {
             commands::auth::auth_with_args(AuthArgs {
                 host,
                 api_key,
+                api_key_stdin,
                 no_save,
+                test,
+                if_needed,
             })
             .await
         }
@@ -97,8 +342,9 @@ async fn main() {
             println!("Git Commit: {}", GIT_HASH);
 
             // Check for updates
-            match commands::version::check_latest_version().await {
-                Ok(Some(latest_version)) => {
+            match commands::version::check_latest_version(VERSION).await {
+                Ok(Some(latest)) => {
+                    let latest_version = latest.version;
                     if commands::version::is_update_available(VERSION, &latest_version) {
                         println!("\n{}", "Update available!".yellow().bold());
                         println!("Latest version: {}", latest_version.green());
@@ -126,6 +372,21 @@ async fn main() {
             data_source_name,
             schema,
             database,
+            identifier_case,
+            dialect_autodetect,
+            sample_values,
+            default_searchable,
+            default_agg,
+            report_unsupported,
+            continue_on_error,
+            strip_prefix,
+            strip_suffix,
+            dbt,
+            emit_entities_from_fk,
+            check,
+            output_schema,
+            overwrite_mode,
+            dry_run_sql,
         } => {
             let source = source_path
                 .map(PathBuf::from)
@@ -133,15 +394,125 @@ async fn main() {
             let dest = destination_path
                 .map(PathBuf::from)
                 .unwrap_or_else(|| PathBuf::from("."));
-            let cmd = GenerateCommand::new(source, dest, data_source_name, schema, database);
+            let options = GenerateOptions {
+                identifier_case,
+                dialect_autodetect,
+                sample_values,
+                default_searchable,
+                default_agg,
+                report_unsupported,
+                continue_on_error,
+                strip_prefixes: strip_prefix,
+                strip_suffixes: strip_suffix,
+                dbt_target_dir: dbt,
+                emit_entities_from_fk,
+                check,
+                output_schema,
+                overwrite_mode: overwrite_mode.unwrap_or_else(OverwriteMode::for_stdin),
+                dry_run_sql,
+            };
+            let cmd = GenerateCommand::new(source, dest, data_source_name, schema, database, options);
             cmd.execute().await
         }
-        Commands::Import => import().await,
-        Commands::Deploy { path, dry_run } => deploy_v2(path.as_deref(), dry_run).await,
+        Commands::Import {
+            path,
+            layout,
+            data_source,
+            schema,
+            lock,
+            force,
+        } => {
+            import(ImportArgs {
+                path,
+                layout,
+                data_source,
+                schema,
+                lock,
+                force,
+            })
+            .await
+        }
+        Commands::Models {
+            data_source,
+            schema,
+            output,
+        } => models(data_source, schema, output).await,
+        Commands::Deploy {
+            path,
+            dry_run,
+            parallel,
+            summary_only,
+            quiet_success,
+            lint_names,
+            lint_descriptions,
+            fail_on_warning,
+            dialect_autodetect,
+            verify_grain,
+            strict_schema,
+            explain_matching,
+            write_comments,
+            data_source,
+            database,
+            env,
+            concurrency,
+            output,
+            dump_plan,
+            apply_plan,
+            models_from_manifest,
+            timings,
+            lock,
+            column_delete_mode,
+            force,
+            only_new,
+            exclude_column,
+            prune_skipped,
+            hosts,
+            require_all,
+        } => {
+            deploy_v2(DeployArgs {
+                paths: path,
+                dry_run,
+                parallel: parallel.unwrap_or_else(default_parallelism),
+                summary_only,
+                quiet_success,
+                lint_names,
+                lint_descriptions,
+                fail_on_warning,
+                dialect_autodetect,
+                verify_grain,
+                strict_schema,
+                explain_matching,
+                write_comments,
+                data_source,
+                database,
+                env,
+                concurrency: concurrency.map(usize::from),
+                output,
+                dump_plan,
+                apply_plan,
+                models_from_manifest,
+                timings,
+                lock,
+                column_delete_mode,
+                force,
+                only_new,
+                exclude_columns: exclude_column,
+                prune_skipped,
+                hosts,
+                require_all,
+            })
+            .await
+        }
+        Commands::StoredValues { command } => stored_values(command).await,
+        Commands::Schema { target } => schema(target),
     };
 
     if let Err(e) = result {
-        eprintln!("{}", e);
+        if json_errors {
+            eprintln!("{}", error::error_to_json(&e));
+        } else {
+            eprintln!("{}", e);
+        }
         std::process::exit(1);
     }
 }
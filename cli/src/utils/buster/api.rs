@@ -1,15 +1,30 @@
 use anyhow::Result;
+use futures::StreamExt;
 use reqwest::{
     header::{HeaderMap, HeaderValue},
-    Client,
+    Client, RequestBuilder, Response, StatusCode,
 };
 use std::error::Error as StdError;
+use std::time::Duration;
+
+use crate::error::BusterError;
+use crate::utils::http::{http_client, reject_unauthorized, send_with_retry};
 
 use super::{
-    PostDataSourcesRequest, DeployDatasetsRequest, ValidateApiKeyRequest, ValidateApiKeyResponse,
-    DeployDatasetsResponse, GenerateApiRequest, GenerateApiResponse,
+    DataSourceSummary, PostDataSourcesRequest, DeployDatasetsRequest, ValidateApiKeyRequest, ValidateApiKeyResponse,
+    DeployDatasetsResponse, DeployStreamEvent, DeployedDataset, GenerateApiRequest,
+    GenerateApiResponse, StoredValuesBackfill, ValidationResult,
 };
 
+const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+/// Header the server reads the CLI version from, to reject versions it can't serve.
+const CLI_VERSION_HEADER: &str = "X-Buster-Cli-Version";
+
+/// Times a request rejected with `429 Too Many Requests` is retried.
+const RATE_LIMIT_RETRIES: u32 = 3;
+/// Longest wait honored from a `Retry-After` header.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(120);
+
 pub struct BusterClient {
     client: Client,
     base_url: String,
@@ -18,10 +33,7 @@ pub struct BusterClient {
 
 impl BusterClient {
     pub fn new(base_url: String, api_key: String) -> Result<Self> {
-        let client = Client::builder()
-            .use_rustls_tls()
-            .timeout(std::time::Duration::from_secs(30))
-            .build()?;
+        let client = http_client()?;
 
         Ok(Self {
             client,
@@ -30,21 +42,69 @@ impl BusterClient {
         })
     }
 
+    /// Sends a request to Buster. Every call goes through here, so a `401` always fails
+    /// with `BusterError::CredentialsRejected`, and other failures name `endpoint`.
+    async fn send(&self, request: RequestBuilder, endpoint: &str) -> Result<Response> {
+        let res = self
+            .send_with_backoff(request)
+            .await
+            .map_err(|e| anyhow::anyhow!("{} failed: {}", endpoint, e))?;
+        reject_unauthorized(res)
+    }
+
+    /// Sends a request, waiting out `429 Too Many Requests` answers. The server's
+    /// `Retry-After` is honored when present; otherwise waits double from 2s. Waits are
+    /// reported on stderr, so they never mix into output meant for other programs. Safe
+    /// methods are also retried after connection errors, see `send_with_retry`.
+    async fn send_with_backoff(&self, request: RequestBuilder) -> reqwest::Result<Response> {
+        let mut request = request;
+        let mut attempt = 0;
+        loop {
+            let retry = request.try_clone();
+            let res = send_with_retry(&self.client, request).await?;
+            if res.status() != StatusCode::TOO_MANY_REQUESTS || attempt >= RATE_LIMIT_RETRIES {
+                return Ok(res);
+            }
+            // Streaming bodies can't be replayed
+            let Some(retry) = retry else {
+                return Ok(res);
+            };
+
+            let retry_after = res
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok());
+            let wait = rate_limit_wait(retry_after, attempt);
+            eprintln!(
+                "⏳ Buster is rate limiting this organization, retrying in {}s...",
+                wait.as_secs()
+            );
+            tokio::time::sleep(wait).await;
+
+            request = retry;
+            attempt += 1;
+        }
+    }
+
     fn build_headers(&self) -> Result<HeaderMap> {
         let mut headers = HeaderMap::new();
         headers.insert(
             "Authorization",
             HeaderValue::from_str(&format!("Bearer {}", self.api_key))?,
         );
+        headers.insert(
+            CLI_VERSION_HEADER,
+            HeaderValue::from_static(env!("CARGO_PKG_VERSION")),
+        );
         Ok(headers)
     }
 
     pub async fn validate_api_key(&self) -> Result<bool> {
-        println!("Debug: Starting API key validation");
+        eprintln!("Debug: Starting API key validation");
         let request = ValidateApiKeyRequest {
             api_key: self.api_key.clone(),
         };
-        println!("Debug: Created request object");
+        eprintln!("Debug: Created request object");
 
         let mut headers = HeaderMap::new();
         headers.insert(
@@ -55,47 +115,43 @@ impl BusterClient {
             reqwest::header::ACCEPT,
             HeaderValue::from_static("*/*"),
         );
-        headers.insert(
-            reqwest::header::USER_AGENT,
-            HeaderValue::from_static("buster-cli"),
-        );
-        println!("Debug: Set up headers: {:?}", headers);
+        eprintln!("Debug: Set up headers: {:?}", headers);
 
         let url = format!("{}/api/v1/api_keys/validate", self.base_url);
-        println!("Debug: Making request to URL: {}", url);
+        eprintln!("Debug: Making request to URL: {}", url);
 
         let request = self
             .client
             .post(&url)
             .headers(headers)
             .json(&request);
-        println!("Debug: Built request: {:?}", request);
+        eprintln!("Debug: Built request: {:?}", request);
 
         let response = match request.send().await {
             Ok(resp) => resp,
             Err(e) => {
-                println!("Debug: Request failed with error: {:?}", e);
+                eprintln!("Debug: Request failed with error: {:?}", e);
                 if let Some(source) = e.source() {
-                    println!("Debug: Error source: {:?}", source);
+                    eprintln!("Debug: Error source: {:?}", source);
                 }
                 if e.is_timeout() {
-                    println!("Debug: Error was a timeout");
+                    eprintln!("Debug: Error was a timeout");
                 }
                 if e.is_connect() {
-                    println!("Debug: Error was a connection error");
+                    eprintln!("Debug: Error was a connection error");
                 }
                 if e.is_request() {
-                    println!("Debug: Error was a request error");
+                    eprintln!("Debug: Error was a request error");
                 }
                 return Err(anyhow::anyhow!("Request failed: {}", e));
             }
         };
-        println!("Debug: Got response: {:?}", response);
+        eprintln!("Debug: Got response: {:?}", response);
 
         if !response.status().is_success() {
             let status = response.status();
             let text = response.text().await?;
-            println!("Debug: Error response - Status: {}, Body: {}", status, text);
+            eprintln!("Debug: Error response - Status: {}, Body: {}", status, text);
             return Err(anyhow::anyhow!(
                 "Failed to validate API key. Status: {}, Response: {}",
                 status,
@@ -105,11 +161,11 @@ impl BusterClient {
 
         match response.json::<ValidateApiKeyResponse>().await {
             Ok(validate_response) => {
-                println!("Debug: Successfully parsed response: {:?}", validate_response);
+                eprintln!("Debug: Successfully parsed response: {:?}", validate_response);
                 Ok(validate_response.valid)
             }
             Err(e) => {
-                println!("Debug: Failed to parse response: {:?}", e);
+                eprintln!("Debug: Failed to parse response: {:?}", e);
                 Err(anyhow::anyhow!(
                     "Failed to parse validate API key response: {}",
                     e
@@ -121,72 +177,245 @@ impl BusterClient {
     pub async fn post_data_sources(&self, req_body: Vec<PostDataSourcesRequest>) -> Result<()> {
         let headers = self.build_headers()?;
 
-        match self
+        let request = self
             .client
             .post(format!("{}/api/v1/data_sources", self.base_url))
             .headers(headers)
-            .json(&req_body)
-            .send()
-            .await
-        {
-            Ok(res) => {
-                if !res.status().is_success() {
-                    return Err(anyhow::anyhow!(
-                        "POST /api/v1/data_sources failed: {}",
-                        res.text().await?
-                    ));
-                }
-                Ok(())
-            }
-            Err(e) => Err(anyhow::anyhow!("POST /api/v1/data_sources failed: {}", e)),
+            .json(&req_body);
+        let res = self.send(request, "POST /api/v1/data_sources").await?;
+        if !res.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "POST /api/v1/data_sources failed: {}",
+                res.text().await?
+            ));
         }
+        Ok(())
+    }
+
+    /// Lists the organization's data sources.
+    pub async fn list_data_sources(&self) -> Result<Vec<DataSourceSummary>> {
+        let headers = self.build_headers()?;
+
+        let request = self
+            .client
+            .get(format!("{}/api/v1/data_sources", self.base_url))
+            .headers(headers);
+        let res = self.send(request, "GET /api/v1/data_sources").await?;
+        if !res.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "GET /api/v1/data_sources failed: {}",
+                res.text().await?
+            ));
+        }
+        Ok(res.json().await?)
     }
 
     pub async fn deploy_datasets(&self, req_body: Vec<DeployDatasetsRequest>) -> Result<DeployDatasetsResponse> {
         let headers = self.build_headers()?;
 
-        match self
+        let request = self
             .client
             .post(format!("{}/api/v1/datasets/deploy", self.base_url))
             .headers(headers)
-            .json(&req_body)
-            .send()
-            .await
-        {
-            Ok(res) => {
-                if !res.status().is_success() {
-                    return Err(anyhow::anyhow!(
-                        "POST /api/v1/datasets/deploy failed: {}",
-                        res.text().await?
-                    ));
+            .json(&req_body);
+        let res = self.send(request, "POST /api/v1/datasets/deploy").await?;
+        if !res.status().is_success() {
+            return Err(deploy_error(res).await);
+        }
+        Ok(res.json().await?)
+    }
+
+    /// Deploys with a streamed NDJSON response, calling `on_result` for each model as the
+    /// server finishes it. Servers without streaming support answer with the buffered
+    /// response, in which case every result is reported once it arrives.
+    ///
+    /// `concurrency` caps how many data source groups the server validates at once; the
+    /// server clamps it and uses its own default when it is `None`.
+    pub async fn deploy_datasets_streaming<F>(
+        &self,
+        req_body: Vec<DeployDatasetsRequest>,
+        concurrency: Option<usize>,
+        mut on_result: F,
+    ) -> Result<DeployDatasetsResponse>
+    where
+        F: FnMut(&ValidationResult),
+    {
+        let mut headers = self.build_headers()?;
+        headers.insert(
+            reqwest::header::ACCEPT,
+            HeaderValue::from_static(NDJSON_CONTENT_TYPE),
+        );
+
+        let mut request = self
+            .client
+            .post(format!("{}/api/v1/datasets/deploy", self.base_url))
+            .headers(headers)
+            .json(&req_body);
+        if let Some(concurrency) = concurrency {
+            request = request.query(&[("concurrency", concurrency)]);
+        }
+
+        let res = self.send(request, "POST /api/v1/datasets/deploy").await?;
+        if !res.status().is_success() {
+            return Err(deploy_error(res).await);
+        }
+
+        let is_ndjson = res
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|content_type| content_type.starts_with(NDJSON_CONTENT_TYPE));
+
+        if !is_ndjson {
+            let response: DeployDatasetsResponse = res.json().await?;
+            response.results.iter().for_each(&mut on_result);
+            return Ok(response);
+        }
+
+        let mut response = DeployDatasetsResponse {
+            results: Vec::new(),
+            summary: None,
+        };
+        let mut handle_line = |line: &[u8]| -> Result<()> {
+            if line.iter().all(u8::is_ascii_whitespace) {
+                return Ok(());
+            }
+            match serde_json::from_slice::<DeployStreamEvent>(line)? {
+                DeployStreamEvent::Result(result) => {
+                    on_result(&result);
+                    response.results.push(result);
                 }
-                Ok(res.json().await?)
+                DeployStreamEvent::Summary(summary) => response.summary = Some(summary),
+                DeployStreamEvent::Error { message } => {
+                    return Err(anyhow::anyhow!("POST /api/v1/datasets/deploy failed: {}", message))
+                }
+            }
+            Ok(())
+        };
+
+        let mut buffer = Vec::new();
+        let mut stream = res.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            buffer.extend_from_slice(&chunk?);
+            while let Some(pos) = buffer.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=pos).collect();
+                handle_line(&line)?;
             }
-            Err(e) => Err(anyhow::anyhow!("POST /api/v1/datasets/deploy failed: {}", e)),
         }
+        handle_line(&buffer)?;
+
+        Ok(response)
     }
 
     pub async fn generate_datasets(&self, req_body: GenerateApiRequest) -> Result<GenerateApiResponse> {
         let headers = self.build_headers()?;
 
-        match self
+        let request = self
             .client
             .post(format!("{}/api/v1/datasets/generate", self.base_url))
             .headers(headers)
-            .json(&req_body)
-            .send()
-            .await
-        {
-            Ok(res) => {
-                if !res.status().is_success() {
-                    return Err(anyhow::anyhow!(
-                        "POST /api/v1/datasets/generate failed: {}",
-                        res.text().await?
-                    ));
-                }
-                Ok(res.json().await?)
-            }
-            Err(e) => Err(anyhow::anyhow!("POST /api/v1/datasets/generate failed: {}", e)),
+            .json(&req_body);
+        let res = self.send(request, "POST /api/v1/datasets/generate").await?;
+        if !res.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "POST /api/v1/datasets/generate failed: {}",
+                res.text().await?
+            ));
+        }
+        Ok(res.json().await?)
+    }
+
+    /// Lists deployed datasets, optionally only those of one data source and/or schema.
+    /// With `include_yml`, each dataset comes with its deployed model file.
+    pub async fn list_deployed_datasets(
+        &self,
+        data_source_name: Option<&str>,
+        schema: Option<&str>,
+        include_yml: bool,
+    ) -> Result<Vec<DeployedDataset>> {
+        let headers = self.build_headers()?;
+
+        let mut query = Vec::new();
+        if let Some(data_source_name) = data_source_name {
+            query.push(("data_source_name", data_source_name));
+        }
+        if let Some(schema) = schema {
+            query.push(("schema", schema));
+        }
+        if include_yml {
+            query.push(("include_yml", "true"));
+        }
+
+        let request = self
+            .client
+            .get(format!("{}/api/v1/datasets/deployed", self.base_url))
+            .headers(headers)
+            .query(&query);
+        let res = self.send(request, "GET /api/v1/datasets/deployed").await?;
+        if !res.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "GET /api/v1/datasets/deployed failed: {}",
+                res.text().await?
+            ));
+        }
+        Ok(res.json().await?)
+    }
+
+    /// Reports how far a data source's stored values backfill has got, per column.
+    pub async fn stored_values_status(&self, data_source_name: &str) -> Result<StoredValuesBackfill> {
+        let headers = self.build_headers()?;
+
+        let request = self
+            .client
+            .get(format!("{}/api/v1/datasets/stored_values/status", self.base_url))
+            .headers(headers)
+            .query(&[("data_source_name", data_source_name)]);
+        let res = self.send(request, "GET /api/v1/datasets/stored_values/status").await?;
+        if !res.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "GET /api/v1/datasets/stored_values/status failed: {}",
+                res.text().await?
+            ));
+        }
+        Ok(res.json().await?)
+    }
+}
+
+/// How long to wait before retrying a rate-limited request: the server's `Retry-After`
+/// seconds (capped), or an exponential backoff when it didn't send one.
+fn rate_limit_wait(retry_after: Option<&str>, attempt: u32) -> Duration {
+    retry_after
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(|seconds| Duration::from_secs(seconds).min(MAX_RETRY_AFTER))
+        .unwrap_or_else(|| Duration::from_secs(2 << attempt))
+}
+
+/// The error for a failed deploy response. `426 Upgrade Required` means the server can't
+/// serve this CLI version, which is reported as such rather than as a generic failure.
+async fn deploy_error(res: Response) -> anyhow::Error {
+    let status = res.status();
+    let body = match res.text().await {
+        Ok(body) => body,
+        Err(e) => return anyhow::anyhow!("POST /api/v1/datasets/deploy failed: {}", e),
+    };
+    if status == StatusCode::UPGRADE_REQUIRED {
+        return BusterError::IncompatibleCli {
+            message: body.trim().trim_end_matches('.').to_string(),
         }
+        .into();
+    }
+    anyhow::anyhow!("POST /api/v1/datasets/deploy failed: {}", body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limit_wait() {
+        assert_eq!(rate_limit_wait(Some("7"), 0), Duration::from_secs(7));
+        assert_eq!(rate_limit_wait(Some("3600"), 0), MAX_RETRY_AFTER);
+        assert_eq!(rate_limit_wait(None, 0), Duration::from_secs(2));
+        assert_eq!(rate_limit_wait(Some("soon"), 2), Duration::from_secs(8));
     }
 }
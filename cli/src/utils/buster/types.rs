@@ -1,3 +1,4 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use std::collections::HashMap;
@@ -22,11 +23,13 @@ pub struct PostDataSourcesRequest {
     pub credential: Credential,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct DeployDatasetsRequest {
     pub id: Option<Uuid>,
     pub data_source_name: String,
-    pub env: String,
+    /// The server rejects the model when this is unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env: Option<String>,
     #[serde(rename = "type")]
     pub type_: String,
     pub name: String,
@@ -38,9 +41,72 @@ pub struct DeployDatasetsRequest {
     pub entity_relationships: Option<Vec<DeployDatasetsEntityRelationshipsRequest>>,
     pub columns: Vec<DeployDatasetsColumnsRequest>,
     pub yml_file: Option<String>,
+    pub dialect_autodetect: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh: Option<DeployDatasetsRefreshRequest>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub primary_time_dimension: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grain: Option<Vec<String>>,
+    #[serde(default)]
+    pub verify_grain: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub database_name: Option<String>,
+    pub enabled: bool,
+    #[serde(default)]
+    pub strict_schema: bool,
+    #[serde(default)]
+    pub explain_matching: bool,
+    #[serde(default)]
+    pub fail_on_warning: bool,
+    #[serde(default)]
+    pub write_comments: bool,
+    #[serde(default)]
+    pub lint_descriptions: bool,
+    #[serde(default)]
+    pub fail_on_markdown_issues: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_dataset_types: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when_to_use: Option<String>,
+    /// `version` of the model file, checked by the server for compatibility
+    #[serde(default)]
+    pub version: i32,
+    #[serde(default)]
+    pub column_delete_mode: ColumnDeleteMode,
+    /// With hard deletes, also delete removed columns that have stored values
+    #[serde(default)]
+    pub force: bool,
+    /// Only create the dataset if it doesn't exist yet; an existing one is left untouched
+    #[serde(default)]
+    pub only_new: bool,
+    /// Display name shown in place of `name`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Columns left out of this deploy; the server keeps deployed ones as they are
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_columns: Vec<String>,
 }
 
-#[derive(Debug, Serialize)]
+/// What deploy does with columns removed from a model: `soft` marks them deleted so they
+/// can come back as they were, `hard` removes them and their stored values.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize, JsonSchema, clap::ValueEnum,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnDeleteMode {
+    #[default]
+    Soft,
+    Hard,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct DeployDatasetsRefreshRequest {
+    pub cron: String,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct DeployDatasetsColumnsRequest {
     pub name: String,
     pub description: String,
@@ -51,9 +117,27 @@ pub struct DeployDatasetsColumnsRequest {
     pub agg: Option<String>,
     #[serde(default)]
     pub searchable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub percentile: Option<f64>,
+    /// Boolean expression restricting the rows a measure aggregates
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filters: Option<String>,
+    #[serde(default)]
+    pub hidden: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// The measures an `agg: ratio` measure divides; the server builds its expression
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ratio: Option<DeployDatasetsRatioRequest>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct DeployDatasetsRatioRequest {
+    pub numerator: String,
+    pub denominator: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct DeployDatasetsEntityRelationshipsRequest {
     pub name: String,
     pub expr: String,
@@ -61,24 +145,36 @@ pub struct DeployDatasetsEntityRelationshipsRequest {
     pub type_: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct ValidationResult {
     pub success: bool,
     pub model_name: String,
     pub data_source_name: String,
     pub schema: String,
     pub errors: Vec<ValidationError>,
+    #[serde(default)]
+    pub warnings: Vec<ValidationError>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct ValidationError {
     pub error_type: ValidationErrorType,
     pub column_name: Option<String>,
     pub message: String,
     pub suggestion: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<ValidationErrorContext>,
 }
 
-#[derive(Debug, Deserialize, PartialEq)]
+/// Details the server adds to some errors when asked, e.g. with `--explain-matching`.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct ValidationErrorContext {
+    /// Tables in the model's schema named most like a table that wasn't found, closest first
+    #[serde(default)]
+    pub similar_tables: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
 pub enum ValidationErrorType {
     TableNotFound,
     ColumnNotFound,
@@ -90,11 +186,180 @@ pub enum ValidationErrorType {
     ProjectNotFound,
     InvalidBusterYml,
     DataSourceMismatch,
+    NameConvention,
+    WeakDescription,
+    LockDrift,
+    InvalidRefreshSchedule,
+    AmbiguousDataSource,
+    EmptyModel,
+    WarehouseAuthFailed,
+    InvalidGrain,
+    WarehouseTimeout,
+    SchemaDrift,
+    CommentWriteFailed,
+    DisallowedDatasetType,
+    SearchableNotSupported,
+    SearchableEnrollsStoredValues,
+    StoredValuesColumnRemoved,
+    InvalidEnv,
+    DefaultedColumnType,
+    DatasetNotFound,
+    DuplicateExpression,
+    MalformedDescription,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationSeverity {
+    Error,
+    Warning,
+}
+
+impl ValidationError {
+    pub fn name_convention(column_name: &str, pattern: &str) -> Self {
+        Self {
+            error_type: ValidationErrorType::NameConvention,
+            column_name: Some(column_name.to_string()),
+            message: format!(
+                "Column '{}' does not match the naming convention '{}'",
+                column_name, pattern
+            ),
+            suggestion: Some("Rename the column or adjust column_name_pattern in buster.yml".to_string()),
+            context: None,
+        }
+    }
+
+    pub fn weak_description(column_name: Option<&str>, model_name: &str, reason: &str) -> Self {
+        let message = match column_name {
+            Some(column) => format!("Description of column '{}' {}", column, reason),
+            None => format!("Description of model '{}' {}", model_name, reason),
+        };
+
+        Self {
+            error_type: ValidationErrorType::WeakDescription,
+            column_name: column_name.map(str::to_string),
+            message,
+            suggestion: Some(
+                "Describe what the data means and when to use it; descriptions feed the agents' prompts"
+                    .to_string(),
+            ),
+            context: None,
+        }
+    }
+
+    /// Fields of one kind that compute the same thing, e.g. copy-pasted measures.
+    pub fn duplicate_expression(kind: &str, expr: &str, names: &[&str]) -> Self {
+        Self {
+            error_type: ValidationErrorType::DuplicateExpression,
+            column_name: names.first().map(|name| name.to_string()),
+            message: format!(
+                "{} {} share the expression '{}'",
+                kind,
+                names
+                    .iter()
+                    .map(|name| format!("'{}'", name))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                expr
+            ),
+            suggestion: Some("Remove the copies, or keep them if they're meant as aliases".to_string()),
+            context: None,
+        }
+    }
+
+    /// A model file that no longer matches the `buster.lock` written by `buster import --lock`.
+    pub fn lock_drift(reason: &str) -> Self {
+        Self {
+            error_type: ValidationErrorType::LockDrift,
+            column_name: None,
+            message: reason.to_string(),
+            suggestion: Some(
+                "Re-run `buster import --lock` to accept the local files, or restore them"
+                    .to_string(),
+            ),
+            context: None,
+        }
+    }
+
+    /// Lint findings, lock and schema drift and failed comment write-back are warnings;
+    /// everything else the API reports is an error.
+    pub fn severity(&self) -> ValidationSeverity {
+        match self.error_type {
+            ValidationErrorType::NameConvention
+            | ValidationErrorType::WeakDescription
+            | ValidationErrorType::LockDrift
+            | ValidationErrorType::SchemaDrift
+            | ValidationErrorType::CommentWriteFailed
+            | ValidationErrorType::DefaultedColumnType
+            | ValidationErrorType::SearchableEnrollsStoredValues
+            | ValidationErrorType::DuplicateExpression
+            | ValidationErrorType::MalformedDescription => ValidationSeverity::Warning,
+            _ => ValidationSeverity::Error,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
 pub struct DeployDatasetsResponse {
     pub results: Vec<ValidationResult>,
+    #[serde(default)]
+    pub summary: Option<DeploymentSummary>,
+}
+
+/// A line of the streamed (`application/x-ndjson`) deploy response.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DeployStreamEvent {
+    Result(ValidationResult),
+    Summary(DeploymentSummary),
+    Error { message: String },
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DeploymentSummary {
+    pub total_models: usize,
+    pub successful_models: usize,
+    pub failed_models: usize,
+    #[serde(default)]
+    pub pruned_count: usize,
+    #[serde(default)]
+    pub enabled: Vec<String>,
+    #[serde(default)]
+    pub disabled: Vec<String>,
+    /// Existing models left untouched by `--only-new`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<String>,
+    /// Columns left out with `skip` or `--exclude-column`, as `model.column`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_columns: Vec<String>,
+    /// Where the server spent its time; servers predating timings don't send it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timings: Option<DeployTimings>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct DeployTimings {
+    /// Models that took longest to validate, slowest first
+    #[serde(default)]
+    pub slowest_models: Vec<ModelTiming>,
+    /// Column fetch and write time of each data source group
+    #[serde(default)]
+    pub groups: Vec<GroupTiming>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ModelTiming {
+    pub model_name: String,
+    pub data_source_name: String,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GroupTiming {
+    pub data_source_name: String,
+    pub database: Option<String>,
+    pub models: usize,
+    pub fetch_ms: u64,
+    pub write_ms: u64,
 }
 
 #[derive(Debug, Serialize)]
@@ -103,10 +368,178 @@ pub struct GenerateApiRequest {
     pub schema: String,
     pub database: Option<String>,
     pub model_names: Vec<String>,
+    pub identifier_case: IdentifierCase,
+    pub dialect_autodetect: bool,
+    pub sample_values: bool,
+    pub default_searchable: bool,
+    pub default_agg: MeasureAgg,
+    pub report_unsupported: bool,
+    /// Columns read from dbt artifacts; the API skips querying the warehouse when set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub catalog: Option<Vec<CatalogModel>>,
+    /// Fields to extract from ARRAY, OBJECT and VARIANT columns, which are otherwise skipped
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub flatten_hints: Vec<FlattenHint>,
+    pub emit_entities_from_fk: bool,
+    pub check: bool,
+    /// Only return the metadata queries columns would be read with, without running them
+    pub dry_run_sql: bool,
+}
+
+/// A field generate extracts from a semi-structured column, from buster.yml's `flatten`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct FlattenHint {
+    /// Table the column belongs to
+    pub model_name: String,
+    /// Column and path to the field, e.g. `payload:customer.id`
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// `string`, `number`, `boolean` or `timestamp`
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    pub type_: Option<String>,
+}
+
+/// A model's columns and descriptions as recorded in an external catalog.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CatalogModel {
+    pub name: String,
+    pub description: Option<String>,
+    pub columns: Vec<CatalogColumn>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CatalogColumn {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub description: Option<String>,
+}
+
+/// Casing applied to the `name` of generated dimensions and measures. Their `expr` always
+/// keeps the warehouse's casing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum IdentifierCase {
+    Lower,
+    Upper,
+    #[default]
+    Preserve,
+}
+
+/// Aggregations a generated measure can default to. Percentile and ratio measures need
+/// more than an `agg`, so they can't be used as a default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum MeasureAgg {
+    #[default]
+    Sum,
+    Avg,
+    Count,
+    #[value(name = "count_distinct")]
+    CountDistinct,
+    Min,
+    Max,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct GenerateApiResponse {
     pub yml_contents: HashMap<String, String>,
+    /// Options the API couldn't apply, e.g. foreign keys on a warehouse without them
+    #[serde(default)]
+    pub warnings: Vec<String>,
     pub errors: HashMap<String, String>,
+    #[serde(default)]
+    pub unsupported_columns: Vec<UnsupportedColumn>,
+    #[serde(default)]
+    pub model_checks: Vec<ModelCheck>,
+    /// Metadata queries returned instead of YAML by `generate --dry-run-sql`
+    #[serde(default)]
+    pub metadata_queries: Vec<String>,
+}
+
+/// How a model's columns would map, returned instead of YAML by `generate --check`.
+#[derive(Debug, Deserialize)]
+pub struct ModelCheck {
+    pub model_name: String,
+    /// Columns modeled as entities, from declared keys
+    #[serde(default)]
+    pub entities: usize,
+    pub dimensions: usize,
+    pub measures: usize,
+    pub unsupported: usize,
+    /// Columns with a type generate doesn't know, which default to string dimensions
+    #[serde(default)]
+    pub defaulted_columns: Vec<DefaultedColumn>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DefaultedColumn {
+    pub column_name: String,
+    pub data_type: String,
+}
+
+/// A column generate can't map to a dimension or measure (e.g. ARRAY, OBJECT, VARIANT).
+#[derive(Debug, Deserialize)]
+pub struct UnsupportedColumn {
+    pub model_name: String,
+    pub column_name: String,
+    pub data_type: String,
+}
+
+/// A dataset currently deployed to Buster.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DeployedDataset {
+    pub id: Uuid,
+    pub name: String,
+    pub database_name: String,
+    pub schema: String,
+    pub data_source_name: String,
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub enabled: bool,
+    pub column_count: i64,
+    pub updated_at: String,
+    /// The deployed model file, only sent when asked for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub yml_file: Option<String>,
+}
+
+/// Progress of a data source's stored values backfill.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct StoredValuesBackfill {
+    pub data_source_name: String,
+    pub total: usize,
+    pub pending: usize,
+    pub syncing: usize,
+    pub success: usize,
+    pub failed: usize,
+    #[serde(default)]
+    pub truncated: usize,
+    #[serde(default)]
+    pub skipped: usize,
+    pub percent_complete: f64,
+    pub columns: Vec<StoredValuesColumnStatus>,
+}
+
+/// The sync state of one column with stored values; `stored_values_status` is unset
+/// until the column starts syncing.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct StoredValuesColumnStatus {
+    pub dataset_id: Uuid,
+    pub dataset_name: String,
+    pub column_name: String,
+    pub stored_values_status: Option<String>,
+    pub stored_values_count: Option<i64>,
+    pub stored_values_last_synced: Option<String>,
+    pub stored_values_error: Option<String>,
+}
+
+/// A data source connected to the organization, as listed for deploy's preflight check.
+#[derive(Debug, Deserialize)]
+pub struct DataSourceSummary {
+    pub name: String,
+    pub env: String,
+    #[serde(rename = "type")]
+    pub type_: String,
 }
@@ -0,0 +1,288 @@
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::utils::{CatalogColumn, CatalogModel};
+
+/// The parts of dbt's `catalog.json` and `manifest.json` generate reads: warehouse column
+/// types from the catalog, descriptions from the manifest.
+#[derive(Debug)]
+pub struct DbtArtifacts {
+    catalog: DbtCatalog,
+    manifest: DbtManifest,
+}
+
+#[derive(Debug, Deserialize)]
+struct DbtCatalog {
+    #[serde(default)]
+    nodes: HashMap<String, CatalogNode>,
+    #[serde(default)]
+    sources: HashMap<String, CatalogNode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CatalogNode {
+    metadata: CatalogMetadata,
+    #[serde(default)]
+    columns: HashMap<String, CatalogNodeColumn>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CatalogMetadata {
+    schema: String,
+    name: String,
+    #[serde(default)]
+    comment: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CatalogNodeColumn {
+    name: String,
+    #[serde(rename = "type")]
+    type_: String,
+    index: i64,
+    #[serde(default)]
+    comment: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DbtManifest {
+    #[serde(default)]
+    nodes: HashMap<String, ManifestNode>,
+    #[serde(default)]
+    sources: HashMap<String, ManifestNode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ManifestNode {
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    columns: HashMap<String, ManifestColumn>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ManifestColumn {
+    name: String,
+    #[serde(default)]
+    description: String,
+}
+
+impl DbtArtifacts {
+    /// Reads `catalog.json` and `manifest.json` from a dbt target directory.
+    pub fn load(target_dir: &Path) -> Result<Self> {
+        Ok(Self {
+            catalog: read_artifact(target_dir, "catalog.json", "dbt docs generate")?,
+            manifest: read_artifact(target_dir, "manifest.json", "dbt compile")?,
+        })
+    }
+
+    /// The catalog entry for a table in `schema`, matched case-insensitively on its schema
+    /// and relation name, with the model's and columns' manifest descriptions. Columns dbt
+    /// hasn't documented fall back to their warehouse comment.
+    pub fn catalog_model(&self, schema: &str, table_name: &str) -> Result<CatalogModel> {
+        let mut matches: Vec<(&String, &CatalogNode)> = self
+            .catalog
+            .nodes
+            .iter()
+            .chain(&self.catalog.sources)
+            .filter(|(_, node)| {
+                node.metadata.schema.eq_ignore_ascii_case(schema)
+                    && node.metadata.name.eq_ignore_ascii_case(table_name)
+            })
+            .collect();
+        matches.sort_by_key(|(unique_id, _)| unique_id.as_str());
+
+        let (unique_id, node) = match matches.as_slice() {
+            [] => {
+                return Err(anyhow!(
+                    "Model '{}.{}' not found in the dbt catalog. Run `dbt docs generate` after \
+                     building it, or remove it from the source path",
+                    schema,
+                    table_name
+                ))
+            }
+            [single] => *single,
+            _ => {
+                return Err(anyhow!(
+                    "Model '{}.{}' matches several dbt catalog entries: {}",
+                    schema,
+                    table_name,
+                    matches
+                        .iter()
+                        .map(|(unique_id, _)| unique_id.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ))
+            }
+        };
+
+        let manifest_node = self
+            .manifest
+            .nodes
+            .get(unique_id)
+            .or_else(|| self.manifest.sources.get(unique_id));
+        let column_description = |column: &CatalogNodeColumn| {
+            manifest_node
+                .and_then(|node| {
+                    node.columns
+                        .values()
+                        .find(|c| c.name.eq_ignore_ascii_case(&column.name))
+                })
+                .map(|c| c.description.clone())
+                .filter(|description| !description.trim().is_empty())
+                .or_else(|| column.comment.clone())
+        };
+
+        let mut columns: Vec<&CatalogNodeColumn> = node.columns.values().collect();
+        columns.sort_by_key(|column| column.index);
+
+        Ok(CatalogModel {
+            name: table_name.to_string(),
+            description: manifest_node
+                .map(|node| node.description.clone())
+                .filter(|description| !description.trim().is_empty())
+                .or_else(|| node.metadata.comment.clone()),
+            columns: columns
+                .into_iter()
+                .map(|column| CatalogColumn {
+                    name: column.name.clone(),
+                    type_: column.type_.clone(),
+                    description: column_description(column),
+                })
+                .collect(),
+        })
+    }
+}
+
+fn read_artifact<T: serde::de::DeserializeOwned>(
+    target_dir: &Path,
+    file_name: &str,
+    dbt_command: &str,
+) -> Result<T> {
+    let path = target_dir.join(file_name);
+    if !path.is_file() {
+        return Err(anyhow!(
+            "No {} in {}. Run `{}` to create it",
+            file_name,
+            target_dir.display(),
+            dbt_command
+        ));
+    }
+
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog_model_from_dbt_artifacts() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = DbtArtifacts::load(dir.path()).unwrap_err().to_string();
+        assert!(missing.contains("No catalog.json"));
+        assert!(missing.contains("dbt docs generate"));
+
+        fs::write(
+            dir.path().join("catalog.json"),
+            r#"{
+                "nodes": {
+                    "model.shop.orders": {
+                        "metadata": {"type": "BASE TABLE", "schema": "analytics", "name": "orders", "comment": null},
+                        "columns": {
+                            "STATUS": {"type": "character varying", "index": 2, "name": "status", "comment": "Order status"},
+                            "ID": {"type": "integer", "index": 1, "name": "id", "comment": null},
+                            "AMOUNT": {"type": "numeric", "index": 3, "name": "amount", "comment": null}
+                        }
+                    }
+                },
+                "sources": {}
+            }"#,
+        )
+        .unwrap();
+        let missing = DbtArtifacts::load(dir.path()).unwrap_err().to_string();
+        assert!(missing.contains("No manifest.json"));
+
+        fs::write(
+            dir.path().join("manifest.json"),
+            r#"{
+                "nodes": {
+                    "model.shop.orders": {
+                        "resource_type": "model",
+                        "name": "orders",
+                        "description": "One row per order",
+                        "columns": {
+                            "id": {"name": "id", "description": "Order ID"},
+                            "amount": {"name": "amount", "description": ""}
+                        }
+                    }
+                },
+                "sources": {}
+            }"#,
+        )
+        .unwrap();
+        let artifacts = DbtArtifacts::load(dir.path()).unwrap();
+
+        let model = artifacts.catalog_model("Analytics", "ORDERS").unwrap();
+        assert_eq!(model.name, "ORDERS");
+        assert_eq!(model.description.as_deref(), Some("One row per order"));
+        let columns: Vec<(&str, &str, Option<&str>)> = model
+            .columns
+            .iter()
+            .map(|c| (c.name.as_str(), c.type_.as_str(), c.description.as_deref()))
+            .collect();
+        assert_eq!(
+            columns,
+            vec![
+                ("id", "integer", Some("Order ID")),
+                ("status", "character varying", Some("Order status")),
+                ("amount", "numeric", None),
+            ]
+        );
+
+        let not_found = artifacts.catalog_model("analytics", "customers").unwrap_err().to_string();
+        assert!(not_found.contains("Model 'analytics.customers' not found in the dbt catalog"));
+        let other_schema = artifacts.catalog_model("staging", "orders").unwrap_err().to_string();
+        assert!(other_schema.contains("Model 'staging.orders' not found"));
+    }
+
+    #[test]
+    fn test_catalog_model_matches_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let node = |schema: &str, comment: &str| {
+            serde_json::json!({
+                "metadata": {"type": "BASE TABLE", "schema": schema, "name": "orders", "comment": comment},
+                "columns": {}
+            })
+        };
+        let catalog = serde_json::json!({
+            "nodes": {
+                "model.shop.orders": node("analytics", "Modeled orders"),
+                "model.shop.stg_orders": node("staging", "Staged orders"),
+                "snapshot.shop.orders": node("staging", "Order snapshots")
+            },
+            "sources": {
+                "source.shop.raw.orders": node("raw", "Raw orders")
+            }
+        });
+        fs::write(dir.path().join("catalog.json"), catalog.to_string()).unwrap();
+        fs::write(dir.path().join("manifest.json"), "{}").unwrap();
+        let artifacts = DbtArtifacts::load(dir.path()).unwrap();
+
+        let model = |schema: &str| artifacts.catalog_model(schema, "orders");
+        assert_eq!(model("analytics").unwrap().description.as_deref(), Some("Modeled orders"));
+        assert_eq!(model("RAW").unwrap().description.as_deref(), Some("Raw orders"));
+
+        let ambiguous = model("staging").unwrap_err().to_string();
+        assert_eq!(
+            ambiguous,
+            "Model 'staging.orders' matches several dbt catalog entries: \
+             model.shop.stg_orders, snapshot.shop.orders"
+        );
+    }
+}
@@ -1 +1,3 @@
+pub mod artifacts;
 pub mod command;
+pub mod sources;
@@ -0,0 +1,176 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The tables a dbt project's sources point at, so `source('source', 'table')` resolves to
+/// the schema and identifier dbt would use rather than the source's name.
+#[derive(Debug, Default)]
+pub struct DbtSources {
+    tables: HashMap<(String, String), String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ManifestSources {
+    #[serde(default)]
+    sources: HashMap<String, ManifestSource>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ManifestSource {
+    source_name: String,
+    name: String,
+    schema: String,
+    identifier: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SourcesFile {
+    #[serde(default)]
+    sources: Vec<SourceDefinition>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SourceDefinition {
+    name: String,
+    /// dbt uses the source's name when it doesn't set a schema
+    #[serde(default)]
+    schema: Option<String>,
+    #[serde(default)]
+    tables: Vec<SourceTable>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SourceTable {
+    name: String,
+    #[serde(default)]
+    identifier: Option<String>,
+}
+
+impl DbtSources {
+    /// Reads the sources of the dbt project `path` is in, from its compiled
+    /// `target/manifest.json` or, before it's been compiled, from the `sources:` of its
+    /// YAML files. Outside a dbt project there are none.
+    pub fn load(path: &Path) -> Result<Self> {
+        let Some(project_dir) = dbt_project_dir(path) else {
+            return Ok(Self::default());
+        };
+
+        let manifest_path = project_dir.join("target").join("manifest.json");
+        if manifest_path.is_file() {
+            let content = fs::read_to_string(&manifest_path)
+                .with_context(|| format!("Failed to read {}", manifest_path.display()))?;
+            let manifest: ManifestSources = serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse {}", manifest_path.display()))?;
+            return Ok(Self {
+                tables: manifest
+                    .sources
+                    .into_values()
+                    .map(|source| {
+                        (
+                            (source.source_name, source.name),
+                            format!("{}.{}", source.schema, source.identifier),
+                        )
+                    })
+                    .collect(),
+            });
+        }
+
+        let mut tables = HashMap::new();
+        let pattern = project_dir.join("**").join("*.yml");
+        for file in glob::glob(&pattern.to_string_lossy())?.flatten() {
+            // Model files and other YAML without sources are skipped
+            let Ok(SourcesFile { sources }) = fs::read_to_string(&file)
+                .map_err(anyhow::Error::from)
+                .and_then(|content| Ok(serde_yaml::from_str::<SourcesFile>(&content)?))
+            else {
+                continue;
+            };
+            for source in sources {
+                let schema = source.schema.as_deref().unwrap_or(&source.name);
+                for table in &source.tables {
+                    let identifier = table.identifier.as_deref().unwrap_or(&table.name);
+                    tables.insert(
+                        (source.name.clone(), table.name.clone()),
+                        format!("{}.{}", schema, identifier),
+                    );
+                }
+            }
+        }
+        Ok(Self { tables })
+    }
+
+    /// The `schema.table` a source table resolves to.
+    pub fn table(&self, source: &str, table: &str) -> Option<&str> {
+        self.tables
+            .get(&(source.to_string(), table.to_string()))
+            .map(String::as_str)
+    }
+}
+
+/// The closest directory at or above `path` with a `dbt_project.yml`.
+fn dbt_project_dir(path: &Path) -> Option<PathBuf> {
+    let path = path.canonicalize().ok()?;
+    path.ancestors()
+        .find(|dir| dir.join("dbt_project.yml").is_file())
+        .map(Path::to_path_buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_dbt_sources() {
+        let dir = tempfile::tempdir().unwrap();
+        let models_dir = dir.path().join("models");
+        fs::create_dir(&models_dir).unwrap();
+        assert!(DbtSources::load(&models_dir).unwrap().table("raw", "regions").is_none());
+
+        fs::write(dir.path().join("dbt_project.yml"), "name: shop\n").unwrap();
+        fs::write(
+            models_dir.join("sources.yml"),
+            r#"
+version: 2
+sources:
+  - name: raw
+    schema: landing
+    tables:
+      - name: regions
+      - name: orders
+        identifier: raw_orders
+  - name: stripe
+    tables:
+      - name: charges
+"#,
+        )
+        .unwrap();
+        fs::write(models_dir.join("orders.yml"), "models:\n  - name: orders\n").unwrap();
+
+        let sources = DbtSources::load(&models_dir).unwrap();
+        assert_eq!(sources.table("raw", "regions"), Some("landing.regions"));
+        assert_eq!(sources.table("raw", "orders"), Some("landing.raw_orders"));
+        assert_eq!(sources.table("stripe", "charges"), Some("stripe.charges"));
+        assert_eq!(sources.table("raw", "charges"), None);
+
+        // A compiled manifest takes precedence over the YAML
+        fs::create_dir(dir.path().join("target")).unwrap();
+        fs::write(
+            dir.path().join("target").join("manifest.json"),
+            r#"{
+                "nodes": {},
+                "sources": {
+                    "source.shop.raw.regions": {
+                        "source_name": "raw", "name": "regions",
+                        "database": "prod", "schema": "raw_prod", "identifier": "regions_v2"
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+        let sources = DbtSources::load(&models_dir).unwrap();
+        assert_eq!(sources.table("raw", "regions"), Some("raw_prod.regions_v2"));
+        assert_eq!(sources.table("raw", "orders"), None);
+    }
+}
@@ -1,6 +1,7 @@
 use anyhow::Result;
 use dirs::home_dir;
 use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
 use tokio::fs;
 
 use crate::{error::BusterError, utils::BusterClient};
@@ -20,6 +21,58 @@ impl Default for BusterCredentials {
     }
 }
 
+/// The top-level `--host` and `--api-key` of this invocation. They're never saved.
+#[derive(Debug, Clone, Default)]
+pub struct CredentialsOverride {
+    pub host: Option<String>,
+    pub api_key: Option<String>,
+}
+
+static CREDENTIALS_OVERRIDE: OnceLock<CredentialsOverride> = OnceLock::new();
+
+/// Makes every command of this invocation that contacts the API use `credentials_override`
+/// over the saved credentials.
+pub fn set_credentials_override(credentials_override: CredentialsOverride) {
+    let _ = CREDENTIALS_OVERRIDE.set(credentials_override);
+}
+
+/// The top-level `--host` of this invocation, if one was given.
+pub fn credentials_override_host() -> Option<&'static str> {
+    CREDENTIALS_OVERRIDE.get().and_then(|o| o.host.as_deref())
+}
+
+/// Applies an override to the saved credentials, if any. Pointed at another host, the key
+/// comes from `--api-key`, then `BUSTER_API_KEY`. The saved key is only used for the host
+/// it was saved for, so it's never sent anywhere else.
+fn override_credentials(
+    saved: Option<BusterCredentials>,
+    credentials_override: &CredentialsOverride,
+    env_api_key: Option<String>,
+) -> Result<BusterCredentials, BusterError> {
+    let url = match (&credentials_override.host, &saved) {
+        (Some(host), _) => host.clone(),
+        (None, Some(saved)) => saved.url.clone(),
+        (None, None) => return Err(BusterError::InvalidCredentials),
+    };
+    let api_key = credentials_override
+        .api_key
+        .clone()
+        .or_else(|| credentials_override.host.as_ref().and(env_api_key))
+        .filter(|api_key| !api_key.is_empty());
+
+    let api_key = match (api_key, saved) {
+        (Some(api_key), _) => api_key,
+        (None, Some(saved)) if same_host(&saved.url, &url) => saved.api_key,
+        (None, _) => return Err(BusterError::MissingApiKey { host: url }),
+    };
+
+    Ok(BusterCredentials { url, api_key })
+}
+
+fn same_host(a: &str, b: &str) -> bool {
+    a.trim_end_matches('/').eq_ignore_ascii_case(b.trim_end_matches('/'))
+}
+
 pub async fn get_buster_credentials() -> Result<BusterCredentials, BusterError> {
     let mut path = home_dir().unwrap_or_default();
     path.push(".buster");
@@ -43,12 +96,45 @@ pub async fn get_buster_credentials() -> Result<BusterCredentials, BusterError>
 }
 
 pub async fn get_and_validate_buster_credentials() -> Result<BusterCredentials, BusterError> {
-    // Get the credentials.
-    let creds = match get_buster_credentials().await {
-        Ok(creds) => creds,
-        Err(e) => return Err(e),
+    // Get the credentials, with this invocation's override applied.
+    let creds = resolve_buster_credentials(CREDENTIALS_OVERRIDE.get()).await?;
+    validate_buster_credentials(creds).await
+}
+
+/// Credentials for `host`, for commands that contact several hosts in one invocation.
+/// The key is `api_key` when given, otherwise it's found as for the top-level `--host`.
+pub async fn get_and_validate_buster_credentials_for_host(
+    host: &str,
+    api_key: Option<String>,
+) -> Result<BusterCredentials, BusterError> {
+    let credentials_override = CredentialsOverride {
+        host: Some(host.to_string()),
+        api_key: api_key.or_else(|| CREDENTIALS_OVERRIDE.get().and_then(|o| o.api_key.clone())),
     };
+    let creds = resolve_buster_credentials(Some(&credentials_override)).await?;
+    validate_buster_credentials(creds).await
+}
 
+async fn resolve_buster_credentials(
+    credentials_override: Option<&CredentialsOverride>,
+) -> Result<BusterCredentials, BusterError> {
+    match (get_buster_credentials().await, credentials_override) {
+        (Ok(creds), None) => Ok(creds),
+        (Err(e), None) => Err(e),
+        (saved, Some(credentials_override)) => {
+            let env_api_key = std::env::var("BUSTER_API_KEY").ok();
+            match saved {
+                Ok(creds) => override_credentials(Some(creds), credentials_override, env_api_key),
+                Err(e) if credentials_override.host.is_none() => Err(e),
+                Err(_) => override_credentials(None, credentials_override, env_api_key),
+            }
+        }
+    }
+}
+
+async fn validate_buster_credentials(
+    creds: BusterCredentials,
+) -> Result<BusterCredentials, BusterError> {
     // Check if the API key is empty.
     if creds.api_key.is_empty() {
         return Err(BusterError::InvalidCredentials);
@@ -60,11 +146,12 @@ pub async fn get_and_validate_buster_credentials() -> Result<BusterCredentials,
         Err(_) => return Err(BusterError::InvalidCredentials),
     };
 
-    if !buster_client.validate_api_key().await? {
-        return Err(BusterError::InvalidCredentials);
+    // Fail before the command does anything when the saved key no longer works
+    match buster_client.validate_api_key().await {
+        Ok(true) => Ok(creds),
+        Ok(false) => Err(BusterError::CredentialsRejected { host: creds.url }),
+        Err(e) => Err(e.downcast::<BusterError>().unwrap_or_else(BusterError::from)),
     }
-
-    Ok(creds)
 }
 
 pub async fn set_buster_credentials(creds: BusterCredentials) -> Result<(), BusterError> {
@@ -100,3 +187,79 @@ pub async fn set_buster_credentials(creds: BusterCredentials) -> Result<(), Bust
         }),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_override_credentials() {
+        let saved = || {
+            Some(BusterCredentials {
+                url: "https://api2.buster.so".to_string(),
+                api_key: "saved-key".to_string(),
+            })
+        };
+        let staging = |api_key: Option<&str>| CredentialsOverride {
+            host: Some("https://staging.buster.so".to_string()),
+            api_key: api_key.map(str::to_string),
+        };
+        let creds = |creds: Result<BusterCredentials, BusterError>| {
+            creds.ok().map(|c| (c.url, c.api_key))
+        };
+
+        assert_eq!(
+            creds(override_credentials(saved(), &staging(None), Some("env-key".to_string()))),
+            Some(("https://staging.buster.so".to_string(), "env-key".to_string()))
+        );
+        assert_eq!(
+            creds(override_credentials(
+                None,
+                &staging(Some("flag-key")),
+                Some("env-key".to_string())
+            )),
+            Some(("https://staging.buster.so".to_string(), "flag-key".to_string()))
+        );
+        assert!(override_credentials(None, &staging(None), None).is_err());
+
+        // Without --host the environment's key doesn't replace the saved one
+        let key_only = CredentialsOverride {
+            host: None,
+            api_key: Some("flag-key".to_string()),
+        };
+        assert_eq!(
+            creds(override_credentials(saved(), &key_only, Some("env-key".to_string()))),
+            Some(("https://api2.buster.so".to_string(), "flag-key".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_override_credentials_keeps_saved_key_on_its_host() {
+        let saved = || {
+            Some(BusterCredentials {
+                url: "https://api2.buster.so".to_string(),
+                api_key: "saved-key".to_string(),
+            })
+        };
+        let host = |url: &str| CredentialsOverride {
+            host: Some(url.to_string()),
+            api_key: None,
+        };
+
+        // The saved key is never sent to a different host
+        let error = override_credentials(saved(), &host("https://staging.buster.so"), None)
+            .err()
+            .expect("a different host needs its own key");
+        assert!(matches!(
+            &error,
+            BusterError::MissingApiKey { host } if host == "https://staging.buster.so"
+        ));
+        assert!(error.to_string().contains("--api-key"));
+
+        // The saved host itself still uses the saved key
+        let creds = override_credentials(saved(), &host("https://api2.buster.so/"), None)
+            .ok()
+            .expect("the saved host keeps its key");
+        assert_eq!(creds.api_key, "saved-key");
+    }
+}
@@ -0,0 +1,147 @@
+use anyhow::Result;
+use glob::{MatchOptions, Pattern};
+use std::path::Path;
+
+use super::utf8_files::read_utf8_file;
+
+/// Name of the file listing model files deploy should skip, in gitignore syntax.
+pub const BUSTER_IGNORE_FILE: &str = ".busterignore";
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// Rules from a `.busterignore` file. Paths are matched relative to the directory holding
+/// the file, the way git matches a `.gitignore`: later rules override earlier ones, `!`
+/// re-includes, a trailing `/` only matches directories, and a pattern without a `/`
+/// (other than a trailing one) matches at any depth.
+#[derive(Debug, Default)]
+pub struct IgnoreRules {
+    rules: Vec<IgnoreRule>,
+}
+
+#[derive(Debug)]
+struct IgnoreRule {
+    pattern: Pattern,
+    negated: bool,
+    dir_only: bool,
+}
+
+impl IgnoreRules {
+    /// Reads the `.busterignore` in `dir`. A directory without one ignores nothing.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(BUSTER_IGNORE_FILE);
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+
+        Self::parse(&read_utf8_file(&path)?)
+            .map_err(|e| anyhow::anyhow!("Invalid {}: {}", path.display(), e))
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut rules = Vec::new();
+
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (negated, line) = match line.strip_prefix('!') {
+                Some(rest) => (true, rest),
+                None => (false, line.strip_prefix('\\').unwrap_or(line)),
+            };
+            let (dir_only, line) = match line.strip_suffix('/') {
+                Some(rest) => (true, rest),
+                None => (false, line),
+            };
+
+            // Patterns with a slash are anchored to the ignore file's directory
+            let glob = match line.strip_prefix('/') {
+                Some(anchored) => anchored.to_string(),
+                None if line.contains('/') => line.to_string(),
+                None => format!("**/{}", line),
+            };
+            let pattern = Pattern::new(&glob)
+                .map_err(|e| anyhow::anyhow!("line {}: '{}': {}", index + 1, line, e))?;
+
+            rules.push(IgnoreRule {
+                pattern,
+                negated,
+                dir_only,
+            });
+        }
+
+        Ok(Self { rules })
+    }
+
+    /// Whether a file, given relative to the ignore file's directory, is ignored. Like git,
+    /// a file in an ignored directory can't be re-included.
+    pub fn is_ignored(&self, relative_path: &Path) -> bool {
+        let ancestors: Vec<&Path> = relative_path
+            .ancestors()
+            .skip(1)
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .collect();
+
+        ancestors
+            .iter()
+            .rev()
+            .any(|dir| self.matches(dir, true) == Some(true))
+            || self.matches(relative_path, false) == Some(true)
+    }
+
+    /// The last rule matching the path decides: `Some(true)` to ignore it, `Some(false)`
+    /// when re-included, `None` when no rule applies.
+    fn matches(&self, path: &Path, is_dir: bool) -> Option<bool> {
+        let path = path.to_string_lossy().replace('\\', "/");
+
+        self.rules
+            .iter()
+            .rev()
+            .filter(|rule| is_dir || !rule.dir_only)
+            .find(|rule| rule.pattern.matches_with(&path, MATCH_OPTIONS))
+            .map(|rule| !rule.negated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ignore_rules() {
+        let rules = IgnoreRules::parse(
+            "# generated by the dbt export\n\
+             *_generated.yml\n\
+             !orders_generated.yml\n\
+             /staging.yml\n\
+             vendor/\n\
+             legacy/**/*.yml\n\
+             \\#notes.yml\n",
+        )
+        .unwrap();
+        let ignored = |path: &str| rules.is_ignored(Path::new(path));
+
+        assert!(ignored("customers_generated.yml"));
+        assert!(ignored("finance/customers_generated.yml"));
+        assert!(!ignored("orders_generated.yml"));
+        assert!(ignored("staging.yml"));
+        assert!(!ignored("finance/staging.yml"));
+        assert!(ignored("vendor/orders.yml"));
+        assert!(ignored("finance/vendor/payments/orders.yml"));
+        assert!(!ignored("vendor.yml"));
+        assert!(ignored("legacy/2023/orders.yml"));
+        assert!(ignored("#notes.yml"));
+        assert!(!ignored("orders.yml"));
+
+        // An ignored directory's files stay ignored
+        let rules = IgnoreRules::parse("vendor/\n!vendor/orders.yml\n").unwrap();
+        assert!(rules.is_ignored(Path::new("vendor/orders.yml")));
+
+        assert!(IgnoreRules::parse("[broken").is_err());
+    }
+}
@@ -0,0 +1,161 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use uuid::Uuid;
+
+use crate::utils::ValidationError;
+
+/// Name of the lock file `buster import --lock` writes next to the imported model files.
+pub const LOCK_FILE: &str = "buster.lock";
+
+/// What `buster import` wrote: each model's dataset id, the file it landed in and that
+/// file's content hash. `buster deploy --lock` compares local files against it.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct BusterLock {
+    pub version: u32,
+    pub models: Vec<LockedModel>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LockedModel {
+    pub name: String,
+    pub id: Uuid,
+    pub data_source_name: String,
+    pub schema: String,
+    /// Model file, relative to the lock file
+    pub file: String,
+    /// SHA-256 of the model file as imported
+    pub hash: String,
+    /// When the dataset was last deployed, as reported by the server
+    pub updated_at: String,
+}
+
+impl BusterLock {
+    const VERSION: u32 = 1;
+
+    pub fn new(mut models: Vec<LockedModel>) -> Self {
+        models.sort_by(|a, b| a.name.cmp(&b.name));
+        Self {
+            version: Self::VERSION,
+            models,
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json + "\n")
+            .map_err(|e| anyhow!("Failed to write lock file {}: {}", path.display(), e))
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read lock file {}: {}", path.display(), e))?;
+        let lock: Self = serde_json::from_str(&json)
+            .map_err(|e| anyhow!("Invalid lock file {}: {}", path.display(), e))?;
+
+        if lock.version != Self::VERSION {
+            return Err(anyhow!(
+                "Unsupported lock file version {} in {} (expected {})",
+                lock.version,
+                path.display(),
+                Self::VERSION
+            ));
+        }
+
+        Ok(lock)
+    }
+
+    /// Warnings for every model whose local file differs from the locked import, every
+    /// locked model no local file defines, and every local model the lock doesn't know.
+    /// `local_hashes` maps each local model name to the hash of the file defining it.
+    pub fn drift(&self, local_hashes: &HashMap<String, String>) -> Vec<(String, ValidationError)> {
+        let mut warnings = Vec::new();
+
+        for locked in &self.models {
+            let reason = match local_hashes.get(&locked.name) {
+                Some(hash) if *hash == locked.hash => continue,
+                Some(_) => format!("{} changed since it was imported", locked.file),
+                None => format!(
+                    "Imported as dataset {} from {} but no local file defines it",
+                    locked.id, locked.file
+                ),
+            };
+            warnings.push((locked.name.clone(), ValidationError::lock_drift(&reason)));
+        }
+
+        let locked_names: BTreeSet<&str> = self.models.iter().map(|m| m.name.as_str()).collect();
+        let mut new_models: Vec<&String> = local_hashes
+            .keys()
+            .filter(|name| !locked_names.contains(name.as_str()))
+            .collect();
+        new_models.sort();
+        for name in new_models {
+            warnings.push((
+                name.clone(),
+                ValidationError::lock_drift("Not part of the locked import"),
+            ));
+        }
+
+        warnings
+    }
+}
+
+/// Hex-encoded SHA-256 of a model file's bytes.
+pub fn content_hash(content: &[u8]) -> String {
+    Sha256::digest(content)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_round_trip_and_drift() {
+        let locked = |name: &str, content: &str| LockedModel {
+            name: name.to_string(),
+            id: Uuid::nil(),
+            data_source_name: "warehouse".to_string(),
+            schema: "analytics".to_string(),
+            file: format!("{}.yml", name),
+            hash: content_hash(content.as_bytes()),
+            updated_at: "2025-02-14T10:00:00Z".to_string(),
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LOCK_FILE);
+        BusterLock::new(vec![locked("orders", "a"), locked("customers", "b")])
+            .write(&path)
+            .unwrap();
+
+        let lock = BusterLock::read(&path).unwrap();
+        assert_eq!(lock.models[0].name, "customers");
+        assert_eq!(
+            content_hash(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+
+        let local = HashMap::from([
+            ("orders".to_string(), content_hash(b"edited")),
+            ("customers".to_string(), content_hash(b"b")),
+            ("refunds".to_string(), content_hash(b"c")),
+        ]);
+        assert_eq!(
+            lock.drift(&local)
+                .iter()
+                .map(|(name, warning)| (name.as_str(), warning.message.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("orders", "orders.yml changed since it was imported"),
+                ("refunds", "Not part of the locked import"),
+            ]
+        );
+
+        let drift = lock.drift(&HashMap::new());
+        assert_eq!(drift.len(), 2);
+        assert!(drift[0].1.message.contains("no local file defines it"));
+    }
+}
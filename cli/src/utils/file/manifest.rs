@@ -0,0 +1,114 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Name of the manifest `buster generate` writes next to the model files it generated.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// The model files `buster generate` produced, in the order they're deployed.
+/// `buster deploy --models-from-manifest` deploys exactly these.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GenerateManifest {
+    pub version: u32,
+    pub models: Vec<ManifestModel>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ManifestModel {
+    pub name: String,
+    /// Model file, relative to the manifest
+    pub file: String,
+}
+
+impl GenerateManifest {
+    const VERSION: u32 = 1;
+
+    pub fn new(mut models: Vec<ManifestModel>) -> Self {
+        models.sort_by(|a, b| a.file.cmp(&b.file));
+        Self {
+            version: Self::VERSION,
+            models,
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json + "\n")
+            .map_err(|e| anyhow!("Failed to write manifest {}: {}", path.display(), e))
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read manifest {}: {}", path.display(), e))?;
+        let manifest: Self = serde_json::from_str(&json)
+            .map_err(|e| anyhow!("Invalid manifest {}: {}", path.display(), e))?;
+
+        if manifest.version != Self::VERSION {
+            return Err(anyhow!(
+                "Unsupported manifest version {} in {} (expected {})",
+                manifest.version,
+                path.display(),
+                Self::VERSION
+            ));
+        }
+
+        Ok(manifest)
+    }
+
+    /// The listed model files in manifest order, resolved against the manifest's directory.
+    /// Fails naming the first file that no longer exists.
+    pub fn model_files(&self, manifest_path: &Path) -> Result<Vec<PathBuf>> {
+        let dir = manifest_path.parent().unwrap_or(Path::new("."));
+        self.models
+            .iter()
+            .map(|model| {
+                let file = dir.join(&model.file);
+                if file.is_file() {
+                    Ok(file)
+                } else {
+                    Err(anyhow!(
+                        "Model file {} of '{}', listed in {}, doesn't exist",
+                        file.display(),
+                        model.name,
+                        manifest_path.display()
+                    ))
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_model_files() -> Result<()> {
+        let dir = TempDir::new()?;
+        std::fs::write(dir.path().join("orders.yml"), "models: []")?;
+        std::fs::write(dir.path().join("customers.yml"), "models: []")?;
+        let manifest_path = dir.path().join(MANIFEST_FILE);
+
+        let model = |name: &str| ManifestModel {
+            name: name.to_string(),
+            file: format!("{}.yml", name),
+        };
+        GenerateManifest::new(vec![model("orders"), model("customers")]).write(&manifest_path)?;
+
+        let manifest = GenerateManifest::read(&manifest_path)?;
+        assert_eq!(
+            manifest.model_files(&manifest_path)?,
+            vec![
+                dir.path().join("customers.yml"),
+                dir.path().join("orders.yml")
+            ]
+        );
+
+        std::fs::remove_file(dir.path().join("orders.yml"))?;
+        let error = manifest.model_files(&manifest_path).unwrap_err();
+        assert!(error.to_string().contains("orders.yml of 'orders'"));
+
+        Ok(())
+    }
+}
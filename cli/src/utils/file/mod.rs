@@ -1,4 +1,8 @@
 pub mod buster_credentials;
+pub mod ignore_files;
+pub mod lock;
+pub mod manifest;
 pub mod model_files;
 pub mod profiles;
 pub mod project_files;
+pub mod utf8_files;
@@ -29,6 +29,8 @@ pub struct BusterModel {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Model {
     pub name: String,
+    #[serde(default)]
+    pub label: Option<String>,
     pub description: String,
     pub model: Option<String>,
     pub schema: Option<String>,
@@ -52,17 +54,23 @@ pub struct Entity {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Dimension {
     pub name: String,
+    #[serde(default)]
+    pub label: Option<String>,
     pub expr: String,
     #[serde(rename = "type")]
     pub dimension_type: String,
     pub description: String,
     #[serde(default = "bool::default")]
     pub searchable: bool,
+    #[serde(default)]
+    pub hidden: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Measure {
     pub name: String,
+    #[serde(default)]
+    pub label: Option<String>,
     pub expr: String,
     pub agg: String,
     pub description: String,
@@ -227,6 +235,11 @@ pub async fn upload_model_files(
                     type_: None,
                     agg: None,
                     searchable: column.searchable,
+                    percentile: None,
+                    filters: None,
+                    hidden: column.hidden,
+                    label: column.label,
+                    ratio: None,
                 });
             }
 
@@ -239,6 +252,11 @@ pub async fn upload_model_files(
                     type_: None,
                     agg: Some(column.agg),
                     searchable: false,
+                    percentile: None,
+                    filters: None,
+                    hidden: false,
+                    label: column.label,
+                    ratio: None,
                 });
             }
 
@@ -254,12 +272,13 @@ pub async fn upload_model_files(
 
             let dataset = DeployDatasetsRequest {
                 data_source_name: profile_name.clone(),
-                env: env.map(String::from).unwrap_or_else(|| {
+                env: Some(env.map(String::from).unwrap_or_else(|| {
                     semantic_model
                         .schema
                         .unwrap_or_else(|| "default".to_string())
-                }),
+                })),
                 name: semantic_model.name,
+                label: semantic_model.label,
                 model: semantic_model.model,
                 schema: schema_name.clone(),
                 description: semantic_model.description,
@@ -270,6 +289,26 @@ pub async fn upload_model_files(
                 id: None,
                 type_: String::from("view"),
                 database: None,
+                dialect_autodetect: false,
+                refresh: None,
+                primary_time_dimension: None,
+                grain: None,
+                verify_grain: false,
+                database_name: None,
+                enabled: true,
+                strict_schema: false,
+                explain_matching: false,
+                fail_on_warning: false,
+                write_comments: false,
+                lint_descriptions: false,
+                fail_on_markdown_issues: false,
+                allowed_dataset_types: None,
+                when_to_use: None,
+                version: model.model_file.version,
+                column_delete_mode: Default::default(),
+                force: false,
+                only_new: false,
+                skipped_columns: Vec::new(),
             };
 
             post_datasets_req_body.push(dataset);
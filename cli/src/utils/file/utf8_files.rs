@@ -0,0 +1,57 @@
+use anyhow::Result;
+use std::path::Path;
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Reads a text file that must be UTF-8, dropping a leading byte order mark if an
+/// editor added one. Invalid UTF-8 is reported with the file path and the byte offset
+/// of the first bad sequence instead of surfacing later as a confusing YAML error.
+pub fn read_utf8_file(path: &Path) -> Result<String> {
+    let bytes = std::fs::read(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+    decode_utf8(&bytes).map_err(|offset| {
+        anyhow::anyhow!(
+            "{} is not valid UTF-8: invalid byte sequence at byte offset {}",
+            path.display(),
+            offset
+        )
+    })
+}
+
+/// Decodes file contents as UTF-8 without a BOM. On failure returns the byte offset
+/// (counted from the start of the file, BOM included) of the first invalid sequence.
+fn decode_utf8(bytes: &[u8]) -> Result<String, usize> {
+    let (skipped, content) = match bytes.strip_prefix(UTF8_BOM) {
+        Some(rest) => (UTF8_BOM.len(), rest),
+        None => (0, bytes),
+    };
+
+    String::from_utf8(content.to_vec()).map_err(|e| skipped + e.utf8_error().valid_up_to())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_utf8() {
+        assert_eq!(
+            decode_utf8("description: Café 東京".as_bytes()).unwrap(),
+            "description: Café 東京"
+        );
+        assert_eq!(decode_utf8(b"\xEF\xBB\xBFversion: 2").unwrap(), "version: 2");
+        assert_eq!(decode_utf8(b"name: caf\xE9"), Err(9));
+        assert_eq!(decode_utf8(b"\xEF\xBB\xBFname: caf\xE9"), Err(12));
+    }
+
+    #[test]
+    fn test_read_utf8_file_reports_path_and_offset() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("orders.yml");
+        std::fs::write(&path, b"name: orders\ndescription: \xFF\n").unwrap();
+
+        let error = read_utf8_file(&path).unwrap_err().to_string();
+        assert!(error.contains(&path.display().to_string()));
+        assert!(error.contains("byte offset 26"));
+    }
+}
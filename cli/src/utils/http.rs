@@ -0,0 +1,119 @@
+use anyhow::Result;
+use reqwest::{Client, Method, RequestBuilder, Response, StatusCode, Url};
+use std::time::Duration;
+
+use crate::error::BusterError;
+
+/// Longest wait for a connection to be established.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Longest wait for the next bytes of a response. Long downloads and streamed responses
+/// are fine as long as data keeps arriving.
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+/// Times a safe request is retried after a connection error or a `5xx` answer.
+const SAFE_METHOD_RETRIES: u32 = 2;
+
+/// `User-Agent` sent with every request, so server logs show which CLI build made it.
+pub fn user_agent() -> String {
+    format!(
+        "buster-cli/{} ({})",
+        env!("CARGO_PKG_VERSION"),
+        env!("GIT_HASH")
+    )
+}
+
+/// The client every command makes its HTTP requests with.
+pub fn http_client() -> Result<Client> {
+    Ok(Client::builder()
+        .use_rustls_tls()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .read_timeout(READ_TIMEOUT)
+        .user_agent(user_agent())
+        .build()?)
+}
+
+/// Sends a request, retrying safe methods (`GET`, `HEAD`, `OPTIONS`) that fail to connect
+/// or get a `5xx` answer, waiting 1s, then 2s. Other methods are sent once, since
+/// repeating them could apply a change twice.
+pub async fn send_with_retry(
+    client: &Client,
+    request: RequestBuilder,
+) -> reqwest::Result<Response> {
+    let request = request.build()?;
+    let mut attempt = 0;
+    loop {
+        let retry = request
+            .try_clone()
+            .filter(|_| is_safe(request.method()) && attempt < SAFE_METHOD_RETRIES);
+        let Some(retry) = retry else {
+            return client.execute(request).await;
+        };
+
+        match client.execute(retry).await {
+            Ok(res) if !res.status().is_server_error() => return Ok(res),
+            Err(e) if !e.is_connect() => return Err(e),
+            outcome => {
+                let reason = match &outcome {
+                    Ok(res) => res.status().to_string(),
+                    Err(e) => e.to_string(),
+                };
+                let wait = Duration::from_secs(1 << attempt);
+                eprintln!(
+                    "⏳ Request to {} failed ({}), retrying in {}s...",
+                    request.url(),
+                    reason,
+                    wait.as_secs()
+                );
+                tokio::time::sleep(wait).await;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// Fails with `BusterError::CredentialsRejected` when Buster answered `401 Unauthorized`,
+/// i.e. the saved API key is invalid or has expired.
+pub fn reject_unauthorized(res: Response) -> Result<Response> {
+    if res.status() == StatusCode::UNAUTHORIZED {
+        return Err(credentials_rejected(res.url()).into());
+    }
+    Ok(res)
+}
+
+fn credentials_rejected(url: &Url) -> BusterError {
+    BusterError::CredentialsRejected {
+        host: url.origin().ascii_serialization(),
+    }
+}
+
+fn is_safe(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_agent_and_safe_methods() {
+        let agent = user_agent();
+        assert!(agent.starts_with(&format!("buster-cli/{} (", env!("CARGO_PKG_VERSION"))));
+        assert!(agent.ends_with(')'));
+
+        assert!(is_safe(&Method::GET));
+        assert!(!is_safe(&Method::POST));
+        assert!(!is_safe(&Method::PUT));
+    }
+
+    #[test]
+    fn test_credentials_rejected_names_host() {
+        let url = Url::parse("https://api2.buster.so/api/v1/datasets/deployed?schema=x").unwrap();
+        let error = credentials_rejected(&url);
+        assert!(matches!(
+            &error,
+            BusterError::CredentialsRejected { host } if host == "https://api2.buster.so"
+        ));
+        assert!(error.to_string().starts_with(
+            "The API key saved for https://api2.buster.so was rejected as invalid or expired"
+        ));
+    }
+}
@@ -2,6 +2,7 @@ mod buster;
 mod dbt;
 mod file;
 mod formatting;
+pub mod http;
 
 pub use buster::*;
 pub use dbt::*;
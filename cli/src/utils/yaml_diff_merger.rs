@@ -6,6 +6,8 @@ use anyhow::{Result, Context};
 use std::fs;
 use colored::*;
 
+use crate::utils::utf8_files::read_utf8_file;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct YamlFile {
     pub models: Vec<Model>,
@@ -107,7 +109,8 @@ pub struct DiffResult {
 
 impl YamlDiffMerger {
     pub fn new(existing_yaml: PathBuf, new_content: String) -> Self {
-        let backup_path = existing_yaml.with_extension("yml.bak");
+        // Not `yml.bak`, which generate's `--overwrite-mode backup` leaves for the user
+        let backup_path = existing_yaml.with_extension("yml.merge.bak");
         Self {
             existing_yaml,
             new_content,
@@ -178,8 +181,7 @@ impl YamlDiffMerger {
 
     pub fn compute_diff(&self) -> Result<DiffResult> {
         // Read and parse existing YAML
-        let existing_content = fs::read_to_string(&self.existing_yaml)
-            .context(format!("Failed to read file: {}", self.existing_yaml.display()))?;
+        let existing_content = read_utf8_file(&self.existing_yaml)?;
         
         let existing_yaml: YamlFile = match serde_yaml::from_str(&existing_content) {
             Ok(yaml) => yaml,
@@ -357,8 +359,7 @@ impl YamlDiffMerger {
             .context("Failed to create backup file")?;
 
         // Read existing YAML preserving style
-        let existing_content = fs::read_to_string(&self.existing_yaml)
-            .context("Failed to read existing YAML file")?;
+        let existing_content = read_utf8_file(&self.existing_yaml)?;
         let mut existing_yaml = Self::parse_yaml_preserving_style(&existing_content)?;
 
         // Parse new content
@@ -0,0 +1,124 @@
+use anyhow::Result;
+use serde_json::json;
+use std::process::Command;
+use tempfile::tempdir;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Answers the requests a deploy makes: API key validation, then the deploy itself, which
+/// is rate limited once so the retry is exercised too.
+async fn serve(listener: TcpListener) -> Result<()> {
+    let mut rate_limited = false;
+    loop {
+        let (mut socket, _) = listener.accept().await?;
+        let mut request = Vec::new();
+        let mut buf = [0; 8192];
+        // Read the headers, then as much body as they announce
+        let body_start = loop {
+            let read = socket.read(&mut buf).await?;
+            request.extend_from_slice(&buf[..read]);
+            if let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                break end + 4;
+            }
+        };
+        let head = String::from_utf8_lossy(&request[..body_start]).to_lowercase();
+        let content_length = head
+            .lines()
+            .find_map(|line| line.strip_prefix("content-length:"))
+            .and_then(|value| value.trim().parse::<usize>().ok())
+            .unwrap_or(0);
+        while request.len() < body_start + content_length {
+            let read = socket.read(&mut buf).await?;
+            request.extend_from_slice(&buf[..read]);
+        }
+
+        let (status, content_type, body) = if head.starts_with("post /api/v1/api_keys/validate") {
+            ("200 OK", "application/json", r#"{"valid":true}"#.to_string())
+        } else if !rate_limited {
+            rate_limited = true;
+            ("429 Too Many Requests\r\nRetry-After: 0", "text/plain", String::new())
+        } else {
+            let result = json!({
+                "type": "result", "success": true, "model_name": "orders",
+                "data_source_name": "warehouse", "schema": "public", "errors": [], "warnings": []
+            });
+            let summary = json!({
+                "type": "summary", "total_models": 1, "successful_models": 1, "failed_models": 0
+            });
+            ("200 OK", "application/x-ndjson", format!("{}\n{}\n", result, summary))
+        };
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            content_type,
+            body.len(),
+            body
+        );
+        socket.write_all(response.as_bytes()).await?;
+        socket.shutdown().await?;
+    }
+}
+
+#[tokio::test]
+async fn test_deploy_ndjson_stdout_is_json_lines() -> Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let url = format!("http://{}", listener.local_addr()?);
+    tokio::spawn(serve(listener));
+
+    let home = tempdir()?;
+    std::fs::create_dir(home.path().join(".buster"))?;
+    std::fs::write(
+        home.path().join(".buster/credentials.yml"),
+        format!("url: {}\napi_key: test-key\n", url),
+    )?;
+
+    let project = tempdir()?;
+    std::fs::write(
+        project.path().join("orders.yml"),
+        r#"
+version: 1
+models:
+  - name: orders
+    description: ""
+    data_source_name: warehouse
+    schema: public
+    dimensions:
+      - name: status
+        expr: status
+        type: string
+        description: Fulfillment state
+      - name: placed_at
+        expr: placed_at
+        type: timestamp
+        description: When the order was placed
+      - name: shipped_at
+        expr: shipped_at
+        type: timestamp
+        description: When the order shipped
+"#,
+    )?;
+
+    let path = project.path().to_str().unwrap().to_string();
+    let output = tokio::task::spawn_blocking(move || {
+        Command::new(env!("CARGO_BIN_EXE_buster-cli"))
+            .args(["deploy", "--output", "ndjson", "--path", &path])
+            .env("HOME", home.path())
+            .env_remove("BUSTER_API_KEY")
+            .output()
+    })
+    .await??;
+
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(output.status.success(), "deploy failed: {}", stdout);
+    let types: Vec<String> = stdout
+        .lines()
+        .map(|line| {
+            let event: serde_json::Value = serde_json::from_str(line)
+                .unwrap_or_else(|e| panic!("stdout line {:?} isn't JSON: {}", line, e));
+            event["type"].as_str().unwrap_or_default().to_string()
+        })
+        .collect();
+    assert_eq!(types, vec!["result", "summary"]);
+
+    Ok(())
+}